@@ -0,0 +1,88 @@
+use burn::backend::{NdArray, ndarray::NdArrayDevice};
+use burn::tensor::backend::Backend;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use neutreeko::{
+    ai::{AI, alphazeutreeko::AlphaZeutreeko, ann::ANNConfig, mcts::MCTS, minmax::MinMax},
+    logic::{Board, Color},
+    platform::NativePlatform,
+};
+
+// Iterations per MCTS search, the time budget is set high enough to never be the limit
+const MCTS_ITERATIONS: usize = 2000;
+const ALPHAZEUTREEKO_ITERATIONS: usize = 200;
+
+// A few plies into the game, so pawns are away from their start squares
+fn middlegame() -> Board {
+    let mut board = Board::default_new();
+    for _ in 0..4 {
+        let game_move = board.legal_moves().next().unwrap();
+        board = board.child(&game_move);
+    }
+    board
+}
+
+fn move_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("move_generation");
+    for (name, board) in [("start", Board::default_new()), ("middlegame", middlegame())] {
+        group.bench_function(BenchmarkId::new("legal_moves", name), |b| b.iter(|| board.legal_moves().count()));
+        group.bench_function(BenchmarkId::new("resulting_boards", name), |b| b.iter(|| board.get_all_valid_directions_and_resulting_boards()));
+    }
+    for depth in [3, 4] {
+        group.bench_function(BenchmarkId::new("perft", depth), |b| b.iter(|| Board::default_new().perft(depth)));
+    }
+    group.finish();
+}
+
+fn winner(c: &mut Criterion) {
+    let board = middlegame();
+    c.bench_function("winner", |b| b.iter(|| board.winner()));
+}
+
+fn minmax(c: &mut Criterion) {
+    let mut group = c.benchmark_group("minmax");
+    group.sample_size(10);
+    let board = Board::default_new();
+    for depth in [3, 4, 5] {
+        group.bench_function(BenchmarkId::from_parameter(depth), |b| b.iter(|| {
+            let mut ai: MinMax<NativePlatform> = MinMax::new(Color::Green, depth);
+            ai.give_all_options(&board, false)
+        }));
+    }
+    group.finish();
+}
+
+// Throughput is reported in iterations per second
+fn mcts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mcts");
+    group.sample_size(10);
+    let board = Board::default_new();
+
+    group.throughput(Throughput::Elements(MCTS_ITERATIONS as u64));
+    group.bench_function("trivial_policy", |b| b.iter(|| {
+        let mut ai: MCTS<NativePlatform> = MCTS::new(Color::Green, 10);
+        ai.set_node_limit(Some(MCTS_ITERATIONS));
+        ai.give_all_options(&board, false)
+    }));
+
+    group.throughput(Throughput::Elements(ALPHAZEUTREEKO_ITERATIONS as u64));
+    group.bench_function("ann_policy", |b| b.iter(|| {
+        let mut ai: AlphaZeutreeko<NdArray<f32>, NativePlatform> = AlphaZeutreeko::new(Color::Green, 10);
+        ai.set_node_limit(Some(ALPHAZEUTREEKO_ITERATIONS));
+        ai.give_all_options(&board, false)
+    }));
+    group.finish();
+}
+
+fn bench_ann_backend<B: Backend>(c: &mut Criterion, backend_name: &str, device: &B::Device) {
+    let ann = ANNConfig::init_from_data::<B>(32, device);
+    let board = middlegame();
+    c.bench_function(&format!("ann_forward/{}", backend_name), |b| b.iter(|| ann.predict(&board)));
+}
+
+// Other backends are added here as they get enabled in Cargo.toml
+fn ann_forward(c: &mut Criterion) {
+    bench_ann_backend::<NdArray<f32>>(c, "ndarray", &NdArrayDevice::default());
+}
+
+criterion_group!(benches, move_generation, winner, minmax, mcts, ann_forward);
+criterion_main!(benches);
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    ai::strength::{STRENGTH_LEVELS, StrengthLevel},
+    gamerecord::GameRecord,
+    gamestore::human_color,
+    logic::{Board, Color},
+    platform::{Platform, load_text, save_text},
+};
+
+// Moves of the player in the longest game still counting as a quick win
+pub const QUICK_WIN_MOVES: usize = 9;
+
+pub const PUZZLES_TO_SOLVE: usize = 10;
+
+// Weakest level whose defeat unlocks BeatExpert, the stronger ones count too
+const EXPERT_LEVEL: &str = "Expert";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement {
+    Initiative,
+    QuickWin,
+    BeatExpert,
+    PuzzleSolver,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [Achievement::Initiative, Achievement::QuickWin, Achievement::BeatExpert, Achievement::PuzzleSolver];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Achievement::Initiative => "Initiative",
+            Achievement::QuickWin => "Quick win",
+            Achievement::BeatExpert => "Expert slayer",
+            Achievement::PuzzleSolver => "Puzzle solver",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::Initiative => "Win a game without ever having to parry a threat",
+            Achievement::QuickWin => "Win a game in under 10 moves",
+            Achievement::BeatExpert => "Beat the AI at the Expert level or above",
+            Achievement::PuzzleSolver => "Solve 10 puzzles without looking at the solution",
+        }
+    }
+
+    // Name in the stored text, kept when labels change
+    fn id(&self) -> &'static str {
+        match self {
+            Achievement::Initiative => "initiative",
+            Achievement::QuickWin => "quick-win",
+            Achievement::BeatExpert => "beat-expert",
+            Achievement::PuzzleSolver => "puzzle-solver",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().find(|achievement| achievement.id() == id).cloned()
+    }
+}
+
+// The opponent never had a winning move on a board the player had to move from, so no move went to blocking one
+fn won_with_initiative(boards: &[Board], human: &Color) -> bool {
+    let opponent = human.other_color();
+    boards.iter()
+        .filter(|board| board.next_player.as_ref() == Some(human))
+        .all(|board| board.winning_moves(&opponent).is_empty())
+}
+
+// Unlocked achievements of the player, with the day of each, and the progress towards the counted ones
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Achievements {
+    pub unlocked: BTreeMap<Achievement, i64>, // day number, see puzzle::day_number
+    pub puzzles_solved: usize,
+}
+
+impl Achievements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains_key(&achievement)
+    }
+
+    // Returns whether the achievement is new
+    fn unlock(&mut self, achievement: Achievement, day: i64) -> bool {
+        if self.is_unlocked(achievement) {
+            return false;
+        }
+        self.unlocked.insert(achievement, day);
+        true
+    }
+
+    // Checks a finished game of the human player against the engine at this level, returns the achievements it unlocked
+    pub fn record_game(&mut self, record: &GameRecord, level: &StrengthLevel, day: i64) -> Vec<Achievement> {
+        let Some(human) = human_color(record) else {
+            return vec![];
+        };
+        if record.result.as_ref().and_then(|result| result.winner()) != Some(human.clone()) {
+            return vec![];
+        }
+        let Some(boards) = record.boards() else {
+            return vec![];
+        };
+        let expert_elo = STRENGTH_LEVELS.iter().find(|level| level.name == EXPERT_LEVEL).map_or(u32::MAX, |level| level.estimated_elo);
+        // from every board but the final one, where the player to move has lost
+        let human_moves = boards[..boards.len() - 1].iter().filter(|board| board.next_player.as_ref() == Some(&human)).count();
+        let mut unlocked = vec![];
+        let checks = [
+            (Achievement::Initiative, won_with_initiative(&boards, &human)),
+            (Achievement::QuickWin, human_moves <= QUICK_WIN_MOVES),
+            (Achievement::BeatExpert, level.estimated_elo >= expert_elo),
+        ];
+        for (achievement, earned) in checks {
+            if earned && self.unlock(achievement, day) {
+                unlocked.push(achievement);
+            }
+        }
+        unlocked
+    }
+
+    // A puzzle or endgame drill solved without the solution, returns the achievements it unlocked
+    pub fn record_puzzle(&mut self, day: i64) -> Vec<Achievement> {
+        self.puzzles_solved += 1;
+        if self.puzzles_solved >= PUZZLES_TO_SOLVE && self.unlock(Achievement::PuzzleSolver, day) {
+            return vec![Achievement::PuzzleSolver];
+        }
+        vec![]
+    }
+
+    // Progress shown next to a locked achievement, None for those earned in one game
+    pub fn progress(&self, achievement: Achievement) -> Option<String> {
+        match achievement {
+            Achievement::PuzzleSolver => Some(format!("{}/{}", self.puzzles_solved.min(PUZZLES_TO_SOLVE), PUZZLES_TO_SOLVE)),
+            _ => None,
+        }
+    }
+
+    // puzzles;<solved> then one id;day line per unlocked achievement
+    pub fn to_text(&self) -> String {
+        let mut text = format!("puzzles;{}\n", self.puzzles_solved);
+        for (achievement, day) in self.unlocked.iter() {
+            text.push_str(&format!("{};{}\n", achievement.id(), day));
+        }
+        text
+    }
+
+    // Unknown ids, from a later version, are skipped
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut achievements = Self::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (name, value) = line.trim().split_once(';')?;
+            if name == "puzzles" {
+                achievements.puzzles_solved = value.parse().ok()?;
+            } else if let Some(achievement) = Achievement::from_id(name) {
+                achievements.unlocked.insert(achievement, value.parse().ok()?);
+            }
+        }
+        Some(achievements)
+    }
+
+    pub async fn load_storage<O: Platform>(key: &str) -> Option<Self> {
+        Self::from_text(&load_text::<O>(key).await?)
+    }
+
+    pub async fn save_storage<O: Platform>(&self, key: &str) -> bool {
+        save_text::<O>(key, &self.to_text()).await
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    gamerecord::GameResult,
+    logic::Color,
+};
+
+// Ends games whose result is no longer in doubt, from the values of the searches playing them. Off by default
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdjudicationConfig {
+    pub resign_threshold: Option<f32>, // a side resigns when its value stays below this, e.g. -0.95
+    pub resign_moves: usize, // own moves in a row below the threshold before resigning
+    pub draw_moves: Option<usize>, // plies in a row without progress before the game is a draw
+    pub draw_value: f32, // a ply makes no progress when the value of the mover is within this of 0
+}
+
+impl Default for AdjudicationConfig {
+    fn default() -> Self {
+        Self {
+            resign_threshold: None,
+            resign_moves: 3,
+            draw_moves: None,
+            draw_value: 0.05,
+        }
+    }
+}
+
+impl AdjudicationConfig {
+    // Used by self-play, where games otherwise run to the move cap once a side has lost its way
+    pub fn self_play() -> Self {
+        Self {
+            resign_threshold: Some(-0.95),
+            resign_moves: 3,
+            draw_moves: Some(40),
+            draw_value: 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Adjudication {
+    Resignation(Color), // color resigning
+    Draw,
+}
+
+impl Adjudication {
+    pub fn result(&self) -> GameResult {
+        match self {
+            Adjudication::Resignation(Color::Green) => GameResult::YellowWins,
+            Adjudication::Resignation(Color::Yellow) => GameResult::GreenWins,
+            Adjudication::Draw => GameResult::Draw,
+        }
+    }
+
+    // Termination tag of the game record
+    pub fn termination(&self) -> &'static str {
+        match self {
+            Adjudication::Resignation(_) => "resignation",
+            Adjudication::Draw => "adjudication",
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        match self {
+            Adjudication::Resignation(color) => format!("{:?} resigns", color),
+            Adjudication::Draw => "draw adjudicated, no progress".to_string(),
+        }
+    }
+}
+
+// Follows one game, fed the value of every search in order
+#[derive(Clone, Debug)]
+pub struct Adjudicator {
+    pub config: AdjudicationConfig,
+    losing_moves: [usize; 2], // green, yellow
+    quiet_plies: usize,
+}
+
+impl Adjudicator {
+    pub fn new(config: AdjudicationConfig) -> Self {
+        Self { config, losing_moves: [0; 2], quiet_plies: 0 }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.resign_threshold.is_some() || self.config.draw_moves.is_some()
+    }
+
+    // Value of the search of the mover, from its point of view. Some when the game should stop here
+    pub fn record(&mut self, mover: &Color, value: f32) -> Option<Adjudication> {
+        let losing_moves = &mut self.losing_moves[match mover {
+            Color::Green => 0,
+            Color::Yellow => 1,
+        }];
+        if let Some(resign_threshold) = self.config.resign_threshold {
+            *losing_moves = if value < resign_threshold { *losing_moves + 1 } else { 0 };
+            if *losing_moves >= self.config.resign_moves.max(1) {
+                return Some(Adjudication::Resignation(mover.clone()));
+            }
+        }
+        if let Some(draw_moves) = self.config.draw_moves {
+            self.quiet_plies = if value.abs() <= self.config.draw_value { self.quiet_plies + 1 } else { 0 };
+            if self.quiet_plies >= draw_moves.max(1) {
+                return Some(Adjudication::Draw);
+            }
+        }
+        None
+    }
+}
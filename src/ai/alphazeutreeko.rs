@@ -1,20 +1,32 @@
 use crate::{
     ai::{
-        ann::{ANN, ANNConfig}, mcts::{MCTSGeneric, Policy}
+        ann::{ANN, ANNConfig, calibration::apply_temperature, weights::DEFAULT_MODEL_ID}, mcts::{MCTSGeneric, Policy}, value::Value,
     },
     logic::{Board, Direction},
 };
 use burn::tensor::backend::Backend;
 
+// Temperature of the values of the default model, fitted by neutreeko-train calibrate. 1 keeps the raw network values
+pub const VALUE_TEMPERATURE: f32 = 1.0;
+
 #[derive(Clone)]
 pub struct ANNPolicy<B: Backend> {
     pub ann: ANN<B>,
+    pub value_temperature: f32, // applied to the network values before MCTS backs them up
 }
 
 impl <B: Backend> ANNPolicy<B> {
     pub fn new_no_data() -> Self {
         Self {
             ann: ANNConfig::init(32, &B::Device::default()),
+            value_temperature: 1.0,
+        }
+    }
+
+    pub fn from_model(model_id: &str) -> Self {
+        Self {
+            ann: ANNConfig::init_from_model(32, model_id, &B::Device::default()),
+            value_temperature: VALUE_TEMPERATURE,
         }
     }
 }
@@ -22,13 +34,16 @@ impl <B: Backend> ANNPolicy<B> {
 impl<B: Backend> Policy for ANNPolicy<B> {
     const IS_TRIVIAL:bool = false;
     fn new() -> Self {
-        Self {
-            ann: ANNConfig::init_from_data(32, &B::Device::default()),
-        }
+        Self::from_model(DEFAULT_MODEL_ID)
+    }
+
+    fn predict(&self, board:&Board) -> (Value, Vec<(f32, usize, Direction, Board)>) {
+        let (board_eval, moves_eval) = self.ann.predict(board);
+        (apply_temperature(board_eval, self.value_temperature), moves_eval)
     }
 
-    fn predict(&self, board:&Board) -> (f32, Vec<(f32, usize, Direction, Board)>) {
-        self.ann.predict(board)
+    fn memory_bytes(&self) -> usize {
+        self.ann.memory_bytes()
     }
 }
 
@@ -4,7 +4,7 @@ use burn::{
         BatchNorm, BatchNormConfig, PaddingConfig2d, Relu, Linear, LinearConfig,
         conv::{Conv2d, Conv2dConfig},
     },
-    tensor::{Device, Tensor, backend::Backend, activation::tanh},
+    tensor::{Device, Tensor, backend::Backend, activation::{sigmoid, tanh}},
 };
 
 // ResNet [basic residual block](https://paperswithcode.com/method/residual-block) implementation.
@@ -146,3 +146,25 @@ impl<B: Backend> PolicyHead<B> {
         out
     }
 }
+
+// Auxiliary head: where the pawns of each player end the game, square by square. Only trained, not used by the engines
+#[derive(Module, Debug)]
+pub struct OwnershipHead<B: Backend> {
+    conv1: Conv2d<B>,
+}
+
+impl<B: Backend> OwnershipHead<B> {
+    pub fn new(channels: usize, device: &Device<B>) -> Self {
+        // conv1x1 to the 2 channels of the input: player to move, opponent
+        let conv1 = Conv2dConfig::new([channels, 2], [1, 1])
+            .with_stride([1, 1])
+            .with_padding(PaddingConfig2d::Same)
+            .with_bias(true)
+            .init(device);
+        Self { conv1 }
+    }
+
+    pub fn forward(&self, input: Tensor<B, 4>) -> Tensor<B, 4> {
+        sigmoid(self.conv1.forward(input)) // [1, 2, 5, 5]
+    }
+}
@@ -0,0 +1,113 @@
+use burn::tensor::backend::Backend;
+
+use crate::{ai::value::Value, gamerecord::GameRecord};
+use super::ANN;
+
+// Equal width bins of the predicted win probability
+pub const CALIBRATION_BINS: usize = 10;
+
+// Temperatures tried by fit_temperature, from sharpening the values to flattening them
+const MIN_TEMPERATURE: f32 = 0.25;
+const MAX_TEMPERATURE: f32 = 4.0;
+const TEMPERATURE_STEP: f32 = 0.05;
+
+// tanh values of exactly 1 have no finite logit
+const MAX_ABS_VALUE: f32 = 1.0 - 1e-6;
+
+// Value of the network for the side to move and the outcome of the game for them: 1 win, -1 loss, 0 draw
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CalibrationSample {
+    pub value: Value,
+    pub outcome: Value,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CalibrationBin {
+    pub samples: usize,
+    pub predicted: f32, // average predicted win probability
+    pub observed: f32, // average score, draws counting half
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CalibrationReport {
+    pub bins: Vec<CalibrationBin>,
+    pub expected_calibration_error: f32, // gap between predicted and observed, weighted by the samples of each bin
+    pub brier_score: f32, // mean squared error of the win probability
+    pub samples: usize,
+}
+
+impl CalibrationReport {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} positions, ECE {:.4}, Brier {:.4}\n", self.samples, self.expected_calibration_error, self.brier_score);
+        for (index, bin) in self.bins.iter().enumerate().filter(|(_, bin)| bin.samples > 0) {
+            let low = index as f32 / self.bins.len() as f32;
+            let high = (index + 1) as f32 / self.bins.len() as f32;
+            text.push_str(&format!("  {:.1}-{:.1}: {} positions, predicted {:.3}, observed {:.3}\n", low, high, bin.samples, bin.predicted, bin.observed));
+        }
+        text
+    }
+}
+
+// The network value is a tanh: its logit is divided by the temperature, above 1 the values get less confident
+pub fn apply_temperature(value: Value, temperature: f32) -> Value {
+    if temperature == 1.0 {
+        return value;
+    }
+    Value::from_network((value.get().clamp(-MAX_ABS_VALUE, MAX_ABS_VALUE).atanh() / temperature).tanh())
+}
+
+pub fn calibration_report(samples: &[CalibrationSample], temperature: f32) -> CalibrationReport {
+    let mut bins = vec![CalibrationBin::default(); CALIBRATION_BINS];
+    let mut squared_error = 0.0;
+    for sample in samples.iter() {
+        let predicted = apply_temperature(sample.value, temperature).win_probability();
+        let observed = sample.outcome.win_probability();
+        squared_error += (predicted - observed).powi(2);
+        let bin = &mut bins[((predicted * CALIBRATION_BINS as f32) as usize).min(CALIBRATION_BINS - 1)];
+        bin.samples += 1;
+        bin.predicted += predicted;
+        bin.observed += observed;
+    }
+    let total = samples.len().max(1) as f32;
+    let mut expected_calibration_error = 0.0;
+    for bin in bins.iter_mut().filter(|bin| bin.samples > 0) {
+        bin.predicted /= bin.samples as f32;
+        bin.observed /= bin.samples as f32;
+        expected_calibration_error += bin.samples as f32 / total * (bin.predicted - bin.observed).abs();
+    }
+    CalibrationReport {
+        bins,
+        expected_calibration_error,
+        brier_score: squared_error / total,
+        samples: samples.len(),
+    }
+}
+
+// Temperature with the lowest Brier score on the samples, 1 when there are none
+pub fn fit_temperature(samples: &[CalibrationSample]) -> f32 {
+    let mut best = (1.0, calibration_report(samples, 1.0).brier_score);
+    let mut temperature = MIN_TEMPERATURE;
+    while temperature <= MAX_TEMPERATURE {
+        let brier_score = calibration_report(samples, temperature).brier_score;
+        if brier_score < best.1 {
+            best = (temperature, brier_score);
+        }
+        temperature += TEMPERATURE_STEP;
+    }
+    best.0
+}
+
+// Network values of every position of the finished games, against how the game ended for the side to move
+pub fn value_samples<B: Backend>(ann: &ANN<B>, games: &[GameRecord]) -> Vec<CalibrationSample> {
+    let mut samples = vec![];
+    for game in games.iter().filter(|game| game.result.is_some()) {
+        let Some(boards) = game.boards() else {
+            continue;
+        };
+        for board in boards.iter().filter(|board| board.winner().is_none()) {
+            let (value, _) = ann.predict(board);
+            samples.push(CalibrationSample { value, outcome: game.result_for(board) });
+        }
+    }
+    samples
+}
@@ -1,12 +1,16 @@
 mod block;
 mod utils;
-#[cfg(feature = "train")]
+pub mod weights;
+pub mod calibration;
+#[cfg(feature = "webgpu")]
+pub mod webgpu;
+#[cfg(any(feature = "train", feature = "web-train"))]
 pub mod train;
 
 use std::marker::PhantomData;
 
-use crate::{logic::{Board, Color, Direction}, platform::Platform};
-use super::AI;
+use crate::{logic::{Board, Color, Direction}, platform::{LogLevel, Platform}, profiling::span};
+use super::{AI, ScoredMove, SearchObserver, SearchResult, Telemetry, value::Value};
 
 use utils::{board_to_input, output_to_moves};
 
@@ -18,15 +22,17 @@ use burn::{
     },
     tensor::{Device, Tensor, backend::Backend},
     config::Config,
+    record::{BinBytesRecorder, HalfPrecisionSettings, Recorder},
 };
 use burn_store::{ModuleSnapshot, BurnpackStore};
 
-use block::{ResidualBlock, ValueHead, PolicyHead};
+use block::{OwnershipHead, ResidualBlock, ValueHead, PolicyHead};
 
 #[derive(Debug)]
 struct PolicyValueOutput<B: Backend> {
     value: Tensor<B, 2>,
     policy: Tensor<B, 4>,
+    ownership: Option<Tensor<B, 4>>,
 }
 
 #[derive(Module, Debug)]
@@ -35,19 +41,36 @@ pub struct ANN<B: Backend> {
     bn1: BatchNorm<B>,
     relu: Relu,
     layer1: ResidualBlock<B>,
-    layer2: ResidualBlock<B>,
-    layer3: ResidualBlock<B>,
-    layer4: ResidualBlock<B>,
+    // optional so smaller networks keep the parameter names of the full one
+    layer2: Option<ResidualBlock<B>>,
+    layer3: Option<ResidualBlock<B>>,
+    layer4: Option<ResidualBlock<B>>,
     value_head: ValueHead<B>,
     policy_head: PolicyHead<B>,
+    // auxiliary target learned during training only, removed before the weights are exported for the engines
+    ownership_head: Option<OwnershipHead<B>>,
 }
 
 impl<B: Backend> ANN<B> {
+    pub fn with_ownership_head(mut self, channels: usize) -> Self {
+        self.ownership_head = Some(OwnershipHead::new(channels, &self.conv1.weight.device()));
+        self
+    }
+
+    pub fn without_auxiliary_heads(mut self) -> Self {
+        self.ownership_head = None;
+        self
+    }
+
+    pub fn has_ownership_head(&self) -> bool {
+        self.ownership_head.is_some()
+    }
+
     fn forward(&self, input: Tensor<B, 4>) -> PolicyValueOutput<B> {
         // Input shape: [1, 2, 5, 5]
 
-        // Subsequent blocks assume 32 channels
-        
+        // Subsequent blocks assume 32 channels, distilled networks may use fewer
+
         // First block
         let out = self.conv1.forward(input); // [1, 32, 5, 5]
         // info!("After conv1 shape: {:?}", out.shape());
@@ -57,35 +80,60 @@ impl<B: Backend> ANN<B> {
         // info!("After first block shape: {:?}", out.shape());
 
         // Residual blocks
-        let out = self.layer1.forward(out); // [1, 32, 5, 5]
-        let out = self.layer2.forward(out); // [1, 32, 5, 5]
-        let out = self.layer3.forward(out); // [1, 32, 5, 5]
-        let out = self.layer4.forward(out); // [1, 32, 5, 5]
+        let mut out = self.layer1.forward(out); // [1, 32, 5, 5]
+        for layer in [&self.layer2, &self.layer3, &self.layer4].into_iter().flatten() {
+            out = layer.forward(out); // [1, 32, 5, 5]
+        }
         let out_copy = out.clone();
         // info!("After residual blocks shape: {:?}", out.shape());
 
+        let ownership = self.ownership_head.as_ref().map(|head| head.forward(out.clone())); // [1, 2, 5, 5]
         let value = self.value_head.forward(out); // [1, 1]
         let policy = self.policy_head.forward(out_copy); // [1, 8, 5, 5]
-        PolicyValueOutput { value, policy }
+        PolicyValueOutput { value, policy, ownership }
+    }
+
+    // Weights held, as f32
+    pub fn memory_bytes(&self) -> usize {
+        self.num_params() * std::mem::size_of::<f32>()
     }
 
-    pub fn predict(&self, board:&Board) -> (f32, Vec<(f32, usize, Direction, Board)>) {
+    // Value for the player to move, the policy over their legal moves
+    pub fn predict(&self, board:&Board) -> (Value, Vec<(f32, usize, Direction, Board)>) {
+        span!(DEBUG, "forward");
         let device = self.conv1.weight.device();
         let input = board_to_input(board, &device);
         let ann_output = self.forward(input);
-        let board_eval: f32 = ann_output.value.to_data().into_vec().unwrap()[0];
+        let board_eval = Value::from_network(ann_output.value.to_data().into_vec().unwrap()[0]);
         let moves_eval = output_to_moves(board, ann_output.policy);
         (board_eval, moves_eval)
     }
 }
 
+pub const MAX_RESIDUAL_BLOCKS: usize = 4;
+
+// Size of the network predicting the moves of the local player, a few hundred games are enough to fit it
+pub const HUMAN_MODEL_CHANNELS: usize = 16;
+pub const HUMAN_MODEL_BLOCKS: usize = 1;
+
 #[derive(Config, Debug)]
 pub struct ANNConfig {
-    channels: usize,
+    pub channels: usize,
+    #[config(default = 4)]
+    pub blocks: usize,
+    // auxiliary ownership head, see ANN::with_ownership_head
+    #[config(default = false)]
+    pub ownership: bool,
 }
 
 impl ANNConfig {
     pub fn init<B: Backend>(channels: usize, device: &Device<B>) -> ANN<B> {
+        ANNConfig::init_with_blocks(channels, MAX_RESIDUAL_BLOCKS, device)
+    }
+
+    // blocks is clamped between 1 and MAX_RESIDUAL_BLOCKS
+    pub fn init_with_blocks<B: Backend>(channels: usize, blocks: usize, device: &Device<B>) -> ANN<B> {
+        let blocks = blocks.clamp(1, MAX_RESIDUAL_BLOCKS);
         let conv1 = Conv2dConfig::new([2, channels], [3, 3])
             .with_stride([1, 1])
             .with_padding(PaddingConfig2d::Same)
@@ -96,9 +144,9 @@ impl ANNConfig {
 
         // Residual blocks
         let layer1 = ResidualBlock::new(channels, device);
-        let layer2 = ResidualBlock::new(channels, device);
-        let layer3 = ResidualBlock::new(channels, device);
-        let layer4 = ResidualBlock::new(channels, device);
+        let layer2 = (blocks >= 2).then(|| ResidualBlock::new(channels, device));
+        let layer3 = (blocks >= 3).then(|| ResidualBlock::new(channels, device));
+        let layer4 = (blocks >= 4).then(|| ResidualBlock::new(channels, device));
 
         let value_head = ValueHead::new(channels, device);
         let policy_head = PolicyHead::new(channels, device);
@@ -113,16 +161,44 @@ impl ANNConfig {
             layer4,
             value_head,
             policy_head,
+            ownership_head: None,
         }
     }
 
     pub fn init_from_data<B: Backend>(channels: usize, device: &Device<B>) -> ANN<B> {
-        let mut ann = ANNConfig::init(channels, device);
-        static DATA: &[u8] = include_bytes!("../../../assets/models/web/model.bpk");
-        let mut store = BurnpackStore::from_static(DATA);
+        ANNConfig::init_from_model(channels, weights::DEFAULT_MODEL_ID, device)
+    }
+
+    pub fn init_from_model<B: Backend>(channels: usize, model_id: &str, device: &Device<B>) -> ANN<B> {
+        ANNConfig::new(channels).init_with_model(model_id, device)
+    }
+
+    pub fn human() -> Self {
+        ANNConfig::new(HUMAN_MODEL_CHANNELS).with_blocks(HUMAN_MODEL_BLOCKS)
+    }
+
+    // Network of this size with the weights of a loaded model
+    pub fn init_with_model<B: Backend>(&self, model_id: &str, device: &Device<B>) -> ANN<B> {
+        let mut ann = ANNConfig::init_with_blocks(self.channels, self.blocks, device);
+        // in the browser, weights::fetch_model or weights::upload_model must have completed first
+        let Some(data) = weights::model_bytes(model_id) else {
+            #[cfg(feature = "web")]
+            log::warn!("Model {} not loaded, using an untrained network", model_id);
+            #[cfg(not(feature = "web"))]
+            eprintln!("Model {} not loaded, using an untrained network", model_id);
+            return ann;
+        };
+        let mut store = BurnpackStore::from_static(data);
         let _ = ann.load_from(&mut store);
         ann
     }
+
+    // Weights written with half precision by the trainer, about half the size of the full precision asset
+    pub fn init_from_half_precision_bytes<B: Backend>(&self, bytes: &[u8], device: &Device<B>) -> Option<ANN<B>> {
+        let ann = ANNConfig::init_with_blocks(self.channels, self.blocks, device);
+        let record = BinBytesRecorder::<HalfPrecisionSettings>::default().load(bytes.to_vec(), device).ok()?;
+        Some(ann.load_record(record))
+    }
 }
 
 
@@ -130,19 +206,36 @@ impl ANNConfig {
 pub struct ANNSolo<B: Backend, O:Platform> {
     color: Color,
     ann: ANN<B>,
+    telemetry: Telemetry,
     _platform: PhantomData<O>,
 }
 
-impl<B: Backend, O: Platform> AI<O> for ANNSolo<B, O> {
-    fn new(color: Color, _difficulty: usize) -> Self {
+impl<B: Backend, O: Platform> ANNSolo<B, O> {
+    pub fn with_model(color: Color, model_id: &str) -> Self {
+        Self::with_config(color, &ANNConfig::new(32), model_id)
+    }
+
+    pub fn with_config(color: Color, config: &ANNConfig, model_id: &str) -> Self {
         let device = B::Device::default();
         Self {
             color,
-            ann: ANNConfig::init_from_data(32, &device),
+            ann: config.init_with_model(model_id, &device),
+            telemetry: Telemetry::default(),
             _platform: PhantomData,
         }
     }
 
+    // Plays like the local player, with the model fitted by train::human on their games
+    pub fn human_like(color: Color) -> Self {
+        Self::with_config(color, &ANNConfig::human(), weights::HUMAN_MODEL_ID)
+    }
+}
+
+impl<B: Backend, O: Platform> AI<O> for ANNSolo<B, O> {
+    fn new(color: Color, _difficulty: usize) -> Self {
+        Self::with_model(color, weights::DEFAULT_MODEL_ID)
+    }
+
     fn color(&self) -> &Color {
         &self.color
     }
@@ -151,11 +244,33 @@ impl<B: Backend, O: Platform> AI<O> for ANNSolo<B, O> {
         self.color = color;
     }
 
-    fn give_all_options(&mut self, board:&Board, verbose: bool) -> (f32, Vec<(f32, usize, Direction)>) {
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, _observer: Option<&dyn SearchObserver>) -> SearchResult {
+        let start_time = O::now();
         let (board_eval, moves_eval) = self.ann.predict(board);
         if verbose {
-            O::print(&format!("ANN board evaluation for color {:?}: {}", self.color(), board_eval));
+            O::log(LogLevel::Info, &format!("ANN board evaluation for color {:?}: {}", self.color(), board_eval));
         }
-        (board_eval, moves_eval.into_iter().map(|x| (x.0, x.1, x.2)).collect())
+        // a single network evaluation, one ply deep
+        let result = SearchResult {
+            value: board_eval.get(),
+            moves: moves_eval.into_iter().map(|x| ScoredMove::new(x.0, x.1, x.2)).collect(),
+            nodes: 1,
+            depth: 1,
+            pv: vec![],
+            time_ms: O::now() - start_time,
+            proven: None,
+        };
+        self.telemetry.record_search(&result);
+        self.telemetry.network_batches += 1;
+        self.telemetry.network_positions += 1;
+        result
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.telemetry
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.telemetry = Telemetry::default();
     }
 }
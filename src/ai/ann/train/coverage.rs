@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use crate::{
+    logic::Board,
+    platform::Platform,
+};
+
+// Random boards drawn for each start position, the rarest of them being the most likely pick
+pub const START_CANDIDATES: usize = 16;
+
+// Times each position was reached in self-play, keyed by its canonical FEN so symmetric positions count together.
+// Training games start from the positions it has seen least, rather than from any random placement
+#[derive(Clone, Debug, Default)]
+pub struct PositionVisits {
+    visits: HashMap<String, usize>,
+}
+
+// A start the side to move wins at once, or cannot keep the opponent from winning, teaches nothing
+fn is_trivial(board: &Board) -> bool {
+    let Some(mover) = &board.next_player else {
+        return true;
+    };
+    !board.winning_moves(mover).is_empty() || board.safe_moves().is_empty()
+}
+
+impl PositionVisits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn visits(&self, board: &Board) -> usize {
+        self.visits.get(&board.canonical_fen()).copied().unwrap_or(0)
+    }
+
+    pub fn record(&mut self, board: &Board) {
+        *self.visits.entry(board.canonical_fen()).or_default() += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.visits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visits.is_empty()
+    }
+
+    // Random boards are drawn until START_CANDIDATES are not trivial, then one is picked with weight 1 / (1 + visits)
+    pub fn sample_start<P: Platform>(&self) -> Board {
+        let mut candidates = vec![];
+        while candidates.len() < START_CANDIDATES {
+            let board = Board::random_board::<P>();
+            if !is_trivial(&board) {
+                let weight = 1.0 / (1 + self.visits(&board)) as f32;
+                candidates.push((board, weight));
+            }
+        }
+        let total: f32 = candidates.iter().map(|(_, weight)| weight).sum();
+        let mut drawn = P::random() * total;
+        for (board, weight) in candidates.iter() {
+            if drawn < *weight {
+                return board.clone();
+            }
+            drawn -= weight;
+        }
+        candidates.pop().unwrap().0
+    }
+
+    // One position per line: visits canonical-fen, the most visited first
+    pub fn to_text(&self) -> String {
+        let mut positions: Vec<(&String, &usize)> = self.visits.iter().collect();
+        positions.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        positions.iter()
+            .map(|(fen, visits)| format!("{} {}\n", visits, fen))
+            .collect()
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut store = Self::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (visits, fen) = line.trim().split_once(' ')?;
+            store.visits.insert(fen.to_string(), visits.parse().ok()?);
+        }
+        Some(store)
+    }
+
+    // Kept next to the model between training runs, a missing file gives an empty store
+    pub fn load_file(path: &str) -> Option<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::from_text(&text),
+            Err(_) => Some(Self::new()),
+        }
+    }
+
+    pub fn save_file(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+}
@@ -0,0 +1,131 @@
+use burn::{
+    module::Module,
+    optim::{Adam, AdamConfig, GradientsParams, Optimizer, adaptor::OptimizerAdaptor},
+    record::{BinFileRecorder, FullPrecisionSettings, HalfPrecisionSettings},
+    tensor::{Device, Tensor, activation::softmax, backend::AutodiffBackend},
+};
+use burn_store::{BurnpackStore, ModuleSnapshot};
+
+use super::{
+    policy_value_loss,
+    utils::{PolicyValueTarget, add_symmetries, illegal_mask},
+};
+use crate::{
+    ai::ann::{ANN, ANNConfig, utils::board_to_input},
+    logic::Board,
+    platform::{NativePlatform, Platform},
+};
+
+// Random moves played from a random board to reach a training position, so positions look like real games
+const MAX_RANDOM_PLIES: usize = 20;
+
+// Trains a smaller network (student) to reproduce the policy and value of a trained one (teacher)
+pub struct Distiller<B: AutodiffBackend> {
+    teacher: ANN<B>,
+    student: ANN<B>,
+    student_config: ANNConfig,
+    optimizer: OptimizerAdaptor<Adam, ANN<B>, B>,
+    learning_rate: f64,
+    device: Device<B>,
+}
+
+impl<B: AutodiffBackend<FloatElem = f32>> Distiller<B> {
+    pub fn new(teacher: ANN<B>, student_config: ANNConfig) -> Self {
+        let device = B::Device::default();
+        let student = ANNConfig::init_with_blocks(student_config.channels, student_config.blocks, &device);
+        Self {
+            teacher,
+            student,
+            student_config,
+            optimizer: AdamConfig::new().init(),
+            learning_rate: 1e-3,
+            device,
+        }
+    }
+
+    // Teacher saved by ANNTrainer::save, with the full 32 channels network
+    pub fn from_file(teacher_filepath: &str, student_config: ANNConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let device = B::Device::default();
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        let teacher = ANNConfig::init(32, &device).load_file(teacher_filepath, &recorder, &device)?;
+        Ok(Self::new(teacher, student_config))
+    }
+
+    fn sample_position(&self) -> Option<Board> {
+        let mut board = Board::random_board::<NativePlatform>();
+        for _ in 0..NativePlatform::random_int(MAX_RANDOM_PLIES + 1) {
+            if board.winner().is_some() {
+                return None;
+            }
+            let possible_moves = board.get_all_valid_directions_and_resulting_boards();
+            board = possible_moves[NativePlatform::random_int(possible_moves.len())].2.clone();
+        }
+        board.winner().is_none().then_some(board)
+    }
+
+    // Teacher output turned into a target: policy probabilities over the legal moves and the value
+    fn teacher_target(&self, input: Tensor<B, 4>, illegal_mask: Tensor<B, 4>) -> PolicyValueTarget<B> {
+        let output = self.teacher.forward(input);
+        let flat_policy: Tensor<B, 2> = (output.policy + illegal_mask).flatten(1, 3);
+        let policy = softmax(flat_policy, 1).reshape([1, 8, 5, 5]).detach();
+        PolicyValueTarget { value: output.value.detach(), policy, ownership: None }
+    }
+
+    fn train_step(&mut self, input: Tensor<B, 4>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> f32 {
+        let output = self.student.forward(input);
+        let loss = policy_value_loss(output, target, illegal_mask);
+        let loss_value = loss.clone().into_scalar();
+        let grads = GradientsParams::from_grads(loss.backward(), &self.student);
+        self.student = self.optimizer.step(self.learning_rate, self.student.clone(), grads);
+        loss_value
+    }
+
+    pub fn distill(&mut self, number_positions: usize) {
+        let mut total_loss = 0.0;
+        let mut steps = 0;
+        let mut positions = 0;
+        while positions < number_positions {
+            let Some(board) = self.sample_position() else {
+                continue;
+            };
+            positions += 1;
+            let input = board_to_input(&board, &self.device);
+            let mask = illegal_mask(&board, &self.device);
+            let target = self.teacher_target(input.clone(), mask.clone());
+            for (input_iter, target_iter, mask_iter) in add_symmetries(input, target, mask).into_iter() {
+                total_loss += self.train_step(input_iter, target_iter, mask_iter);
+                steps += 1;
+            }
+            if positions % 100 == 0 {
+                println!("Position {}/{}, average loss {:.4}", positions, number_positions, total_loss / steps as f32);
+                total_loss = 0.0;
+                steps = 0;
+            }
+        }
+    }
+
+    pub fn student(&self) -> &ANN<B> {
+        &self.student
+    }
+
+    // The student configuration is saved next to the weights, to know which network to create when loading
+    pub fn save(&self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        self.student.clone().save_file(filepath, &recorder)?;
+        self.student_config.save(format!("{}.json", filepath))?;
+        Ok(())
+    }
+
+    pub fn save_for_web(&self, filepath: &str) {
+        let mut store = BurnpackStore::from_file(filepath);
+        let _ = self.student.save_into(&mut store);
+    }
+
+    // Half precision weights, to be loaded with ANNConfig::init_from_half_precision_bytes
+    pub fn save_for_web_half_precision(&self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let recorder = BinFileRecorder::<HalfPrecisionSettings>::new();
+        self.student.clone().save_file(filepath, &recorder)?;
+        self.student_config.save(format!("{}.json", filepath))?;
+        Ok(())
+    }
+}
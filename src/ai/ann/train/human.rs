@@ -0,0 +1,128 @@
+use burn::{
+    module::Module,
+    optim::{Adam, AdamConfig, GradientsParams, Optimizer, adaptor::OptimizerAdaptor},
+    record::{BinFileRecorder, FullPrecisionSettings},
+    tensor::{Device, Tensor, backend::AutodiffBackend},
+};
+use burn_store::{BurnpackStore, ModuleSnapshot};
+
+use super::{
+    policy_value_loss,
+    utils::{PolicyValueTarget, add_symmetries, illegal_mask, moves_and_value_to_target},
+};
+use crate::{
+    ai::{ScoredMove, ann::{ANN, ANNConfig, utils::board_to_input}, value::Value},
+    gamerecord::GameRecord,
+    logic::{Board, Move},
+    platform::{NativePlatform, Platform},
+};
+
+// A position of a recorded game, the move the player chose there and how the game ended for them
+#[derive(Clone, Debug)]
+pub struct HumanExample {
+    pub board: Board,
+    pub played: Move,
+    pub value: Value, // how the game ended for the player, a draw for an unfinished game
+}
+
+// Every move of the named player in the games, games with an illegal move are skipped
+pub fn human_examples(games: &[GameRecord], player: &str) -> Vec<HumanExample> {
+    let mut examples = vec![];
+    for game in games {
+        let Some(moves_played) = game.moves_played_by(player) else {
+            continue;
+        };
+        for (board, played) in moves_played {
+            let value = game.result_for(&board);
+            examples.push(HumanExample { board, played, value });
+        }
+    }
+    examples
+}
+
+// Fits a small network to the moves of one player: the policy target is the move they played,
+// the value target the result of their game
+pub struct HumanMoveTrainer<B: AutodiffBackend> {
+    ann: ANN<B>,
+    config: ANNConfig,
+    optimizer: OptimizerAdaptor<Adam, ANN<B>, B>,
+    learning_rate: f64,
+    device: Device<B>,
+}
+
+impl<B: AutodiffBackend<FloatElem = f32>> HumanMoveTrainer<B> {
+    pub fn new(config: ANNConfig) -> Self {
+        let device = B::Device::default();
+        let ann = ANNConfig::init_with_blocks(config.channels, config.blocks, &device);
+        Self {
+            ann,
+            config,
+            optimizer: AdamConfig::new().init(),
+            learning_rate: 1e-3,
+            device,
+        }
+    }
+
+    fn train_step(&mut self, input: Tensor<B, 4>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> f32 {
+        let output = self.ann.forward(input);
+        let loss = policy_value_loss(output, target, illegal_mask);
+        let loss_value = loss.clone().into_scalar();
+        let grads = GradientsParams::from_grads(loss.backward(), &self.ann);
+        self.ann = self.optimizer.step(self.learning_rate, self.ann.clone(), grads);
+        loss_value
+    }
+
+    // Passes over the examples in a new random order each epoch
+    pub fn fit(&mut self, examples: &[HumanExample], epochs: usize) {
+        let mut order: Vec<usize> = (0..examples.len()).collect();
+        for epoch in 1..=epochs {
+            for index in (1..order.len()).rev() {
+                order.swap(index, NativePlatform::random_int(index + 1));
+            }
+            let mut total_loss = 0.0;
+            let mut steps = 0;
+            for example in order.iter().map(|&index| &examples[index]) {
+                let played = ScoredMove::new(1.0, example.played.pawn_index, example.played.direction.clone());
+                let input = board_to_input(&example.board, &self.device);
+                let target = moves_and_value_to_target(&example.board, example.value, &[played], &self.device);
+                let mask = illegal_mask(&example.board, &self.device);
+                for (input_iter, target_iter, mask_iter) in add_symmetries(input, target, mask).into_iter() {
+                    total_loss += self.train_step(input_iter, target_iter, mask_iter);
+                    steps += 1;
+                }
+            }
+            println!("Epoch {}/{}, average loss {:.4}", epoch, epochs, total_loss / steps.max(1) as f32);
+        }
+    }
+
+    // Share of the examples where the network ranks the move played first
+    pub fn accuracy(&self, examples: &[HumanExample]) -> f32 {
+        let correct = examples.iter()
+            .filter(|example| {
+                let (_, moves_eval) = self.ann.predict(&example.board);
+                moves_eval.iter()
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+                    .is_some_and(|(_, pawn_index, direction, _)| *pawn_index == example.played.pawn_index && *direction == example.played.direction)
+            })
+            .count();
+        correct as f32 / examples.len().max(1) as f32
+    }
+
+    pub fn ann(&self) -> &ANN<B> {
+        &self.ann
+    }
+
+    // The configuration is saved next to the weights, as for distilled networks
+    pub fn save(&self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        self.ann.clone().save_file(filepath, &recorder)?;
+        self.config.save(format!("{}.json", filepath))?;
+        Ok(())
+    }
+
+    // File to load in the web page, registered under weights::HUMAN_MODEL_ID
+    pub fn save_for_web(&self, filepath: &str) {
+        let mut store = BurnpackStore::from_file(filepath);
+        let _ = self.ann.save_into(&mut store);
+    }
+}
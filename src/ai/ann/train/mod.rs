@@ -1,72 +1,199 @@
 mod utils;
+pub mod coverage;
+// file based, native training only
+#[cfg(feature = "train")]
+pub mod distill;
+#[cfg(feature = "train")]
+pub mod human;
+#[cfg(feature = "train")]
+pub mod replay;
+#[cfg(feature = "train")]
 use burn_store::{BurnpackStore, ModuleSnapshot};
-use utils::{moves_and_value_to_target, illegal_mask, opening, PolicyValueTarget, add_symmetries};
+use coverage::PositionVisits;
+use utils::{moves_and_value_to_target, illegal_mask, opening, ownership_target, policy_divergence, symmetric_example, PolicyValueTarget};
 
 use super::{
-    ANN, PolicyValueOutput,
+    ANN, ANNConfig, PolicyValueOutput,
     utils::board_to_input,
 };
 use std::collections::HashSet;
 use burn::{
+    grad_clipping::GradientClippingConfig,
     module::Module,
     nn::loss::{MseLoss, Reduction},
     optim::{Adam, AdamConfig, GradientsParams, Optimizer, adaptor::OptimizerAdaptor, decay::WeightDecayConfig, lr_scheduler::{LrScheduler, cosine::{CosineAnnealingLrScheduler, CosineAnnealingLrSchedulerConfig}}},
-    record::{BinFileRecorder, FullPrecisionSettings},
+    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
     tensor::{Device, Tensor, activation::log_softmax, backend::AutodiffBackend},
 };
 use crate::{
-    ai::{AI, alphazeutreeko::AlphaZeutreeko},
-    logic::{Board, Color},
-    platform::NativePlatform,
+    adjudication::{Adjudication, AdjudicationConfig, Adjudicator},
+    ai::{AI, ScoredMove, SearchResult, alphazeutreeko::AlphaZeutreeko, selector::MoveSelector, value::Value},
+    gamerecord::{GameRecord, GameResult, MoveRecord},
+    logic::{Board, Color, Direction, Move, MoveError},
+    platform::{NativePlatform, Platform},
+    profiling::span,
+    randomstart::{BalancedStartConfig, balanced_start},
+    tactics::{TacticsReport, score_tactics, tactical_positions},
 };
+#[cfg(feature = "train")]
+use crate::gamerecord::{games_to_jsonl, games_to_pgn};
+#[cfg(feature = "train")]
+use burn::record::BinFileRecorder;
+#[cfg(feature = "train")]
+use replay::{ReplayBuffer, append_samples};
 
-pub struct ANNTrainer<B: AutodiffBackend, A: AI<NativePlatform>> {
-    alphazeutreeko: AlphaZeutreeko<B, NativePlatform>,
+// Weight of the ownership error in the loss, the auxiliary target only helps the shared layers learn
+const OWNERSHIP_LOSS_WEIGHT: f32 = 0.25;
+
+// Largest norm of the gradients of a step, larger ones are scaled down to it
+pub const DEFAULT_GRADIENT_CLIP_NORM: f32 = 1.0;
+
+// Positions whose loss was not finite, appended as FEN lines to reproduce a divergence
+#[cfg(feature = "train")]
+const DIVERGED_BOARDS_FILE: &str = "assets/models/diverged_boards.fen";
+
+// Share of self-play games played to the end without adjudication, to see whether resignations were right
+const ADJUDICATION_PLAYTHROUGH: f32 = 0.1;
+
+// Symmetric copies of a position: the position itself, 3 rotations, 2 flips and 2 diagonal flips
+pub const NUMBER_OF_SYMMETRIES: usize = 8;
+
+// Symmetric copies of each self-play position learned from, only the chosen ones are built
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Augmentation {
+    None, // the position as played
+    All, // all NUMBER_OF_SYMMETRIES copies, one after the other
+    Sampled(usize), // this many copies drawn at random for each position, so consecutive updates see different orientations
+}
+
+impl Augmentation {
+    // Indices of the copies for one position, see symmetric_example
+    fn indices<O: Platform>(&self) -> Vec<usize> {
+        match self {
+            Augmentation::None => vec![0],
+            Augmentation::All => (0..NUMBER_OF_SYMMETRIES).collect(),
+            Augmentation::Sampled(count) => {
+                // partial Fisher-Yates shuffle
+                let mut indices: Vec<usize> = (0..NUMBER_OF_SYMMETRIES).collect();
+                let count = (*count).clamp(1, NUMBER_OF_SYMMETRIES);
+                for drawn in 0..count {
+                    let swapped = drawn + O::random_int(NUMBER_OF_SYMMETRIES - drawn);
+                    indices.swap(drawn, swapped);
+                }
+                indices.truncate(count);
+                indices
+            }
+        }
+    }
+}
+
+// Cross-entropy between the policy target and the masked policy output, plus the value error and the ownership error if learned
+fn policy_value_loss<B: AutodiffBackend>(output: PolicyValueOutput<B>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> Tensor<B, 1> {
+    // println!("output value {}", output.value.to_string());
+    // println!("output policy {}", output.policy.to_string());
+    let masked_probabilities = output.policy + illegal_mask;
+    // println!("masked probas {}", masked_probabilities.to_string());
+    let flat_probas: Tensor<B, 2> = masked_probabilities.flatten(1, 3);
+    // println!("flat probas {}", flat_probas.to_string());
+    let log_probabilities = log_softmax(flat_probas, 1);
+    // println!("log probas {}", log_probabilities.to_string());
+    let flat_target = target.policy.flatten(1, 3);
+    // println!("flat target {}", flat_target.to_string());
+    let policy_loss = -(flat_target * log_probabilities).sum_dim(1).mean();
+    // println!("policy loss {}", policy_loss.to_string());
+    let value_loss = MseLoss::new().forward(output.value, target.value, Reduction::Mean);
+    // println!("value loss {}", value_loss.to_string());
+    let loss = policy_loss + value_loss * 0.5;
+    match (output.ownership, target.ownership) {
+        (Some(ownership), Some(ownership_target)) => loss + MseLoss::new().forward(ownership, ownership_target, Reduction::Mean) * OWNERSHIP_LOSS_WEIGHT,
+        _ => loss,
+    }
+}
+
+fn adam<B: AutodiffBackend>(gradient_clip_norm: Option<f32>) -> OptimizerAdaptor<Adam, ANN<B>, B> {
+    AdamConfig::new()
+        .with_weight_decay(Some(WeightDecayConfig::new(1e-6)))
+        .with_grad_clipping(gradient_clip_norm.map(GradientClippingConfig::Norm))
+        .init()
+}
+
+// Native training writes files, the in-browser demo (web-train feature) runs on WasmPlatform and keeps the weights in memory
+pub struct ANNTrainer<B: AutodiffBackend, A: AI<O>, O: Platform = NativePlatform> {
+    alphazeutreeko: AlphaZeutreeko<B, O>,
     pub opponent: Option<A>,
+    pub move_selector: MoveSelector, // picks AlphaZeutreeko moves during self-play, sampling gives more varied games
+    pub games: Vec<GameRecord>, // games played by training_loop, with the engine evaluations
+    pub augmentation: Augmentation,
+    pub losses: Vec<f32>, // average loss of each game learned from by training_loop
+    pub position_visits: Option<PositionVisits>, // when set, games start from rarely reached positions instead of uniform random ones
+    pub balanced_starts: Option<BalancedStartConfig>, // when set without position_visits, games start from positions no side wins soon
+    pub divergence_threshold: Option<f32>, // when set, positions where the search policy is this close to the network one (KL) are not learned from
+    pub replay_file: Option<String>, // with the train feature, self-play positions are also appended there, see replay::ReplayBuffer
+    pub adjudication: AdjudicationConfig, // resignations and draws stopping self-play games early
     optimizer: OptimizerAdaptor<Adam, ANN<B>, B>,
     learning_rate_schedule: CosineAnnealingLrScheduler,
     device: Device<B>,
-    recorder: BinFileRecorder<FullPrecisionSettings>,
 }
 
-impl<B: AutodiffBackend<FloatElem = f32>, A: AI<NativePlatform>> ANNTrainer<B, A> {
+impl<B: AutodiffBackend<FloatElem = f32>, A: AI<O>, O: Platform> ANNTrainer<B, A, O> {
     pub fn new() -> Self {
+        Self::with_network(&ANNConfig::new(32), 6)
+    }
+
+    // Untrained network of the given size, searched by AlphaZeutreeko at this difficulty during self-play
+    pub fn with_network(config: &ANNConfig, difficulty: usize) -> Self {
         let device = B::Device::default();
         let learning_rate_schedule = CosineAnnealingLrSchedulerConfig::new(1e-4, 10000).with_min_lr(1e-5).init().unwrap();
-        let optimizer = AdamConfig::new().with_weight_decay(Some(WeightDecayConfig::new(1e-6))).init();
-        let alphazeutreeko = AlphaZeutreeko::new_no_data(Color::Green, 6);
+        let optimizer = adam(Some(DEFAULT_GRADIENT_CLIP_NORM));
+        let mut alphazeutreeko = AlphaZeutreeko::new_no_data(Color::Green, difficulty);
+        alphazeutreeko.policy.ann = ANNConfig::init_with_blocks(config.channels, config.blocks, &device);
+        if config.ownership {
+            alphazeutreeko.policy.ann = alphazeutreeko.policy.ann.with_ownership_head(config.channels);
+        }
         let opponent = None;
-        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
 
         Self {
             alphazeutreeko,
             opponent,
+            move_selector: MoveSelector::ArgMax,
+            games: vec![],
+            augmentation: Augmentation::All,
+            losses: vec![],
+            position_visits: None,
+            balanced_starts: None,
+            divergence_threshold: None,
+            replay_file: None,
+            adjudication: AdjudicationConfig::self_play(),
             optimizer,
             learning_rate_schedule,
             device,
-            recorder
         }
     }
 
-    fn loss(&self, output: PolicyValueOutput<B>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> Tensor<B, 1> {
-        // println!("output value {}", output.value.to_string());
-        // println!("output policy {}", output.policy.to_string());
-        let masked_probabilities = output.policy + illegal_mask;
-        // println!("masked probas {}", masked_probabilities.to_string());
-        let flat_probas: Tensor<B, 2> = masked_probabilities.flatten(1, 3);
-        // println!("flat probas {}", flat_probas.to_string());
-        let log_probabilities = log_softmax(flat_probas, 1);
-        // println!("log probas {}", log_probabilities.to_string());
-        let flat_target = target.policy.flatten(1, 3);
-        // println!("flat target {}", flat_target.to_string());
-        let policy_loss = -(flat_target * log_probabilities).sum_dim(1).mean();
-        // println!("policy loss {}", policy_loss.to_string());
-        let value_loss = MseLoss::new().forward(output.value, target.value, Reduction::Mean);
-        // println!("value loss {}", value_loss.to_string());
-        policy_loss + value_loss * 0.5
-    }
-
-    fn train_step(&mut self, input:Tensor<B, 4>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> Tensor<B, 1> {
+    // Caps the MCTS iterations of every self-play move, so games take the same time on any device
+    pub fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        self.alphazeutreeko.set_node_limit(node_limit);
+    }
+
+    pub fn ann(&self) -> &ANN<B> {
+        &self.alphazeutreeko.policy.ann
+    }
+
+    // Raw network on the shipped tactical positions, to compare checkpoints without playing games
+    pub fn eval_tactics(&self) -> TacticsReport {
+        score_tactics(&tactical_positions(), |board| {
+            let (value, moves) = self.alphazeutreeko.policy.ann.predict(board);
+            (value, moves.into_iter().map(|(score, pawn_index, direction, _)| ScoredMove::new(score, pawn_index, direction)).collect())
+        })
+    }
+
+    // None trains without clipping. The optimizer starts over, so this is set before training
+    pub fn set_gradient_clipping(&mut self, max_norm: Option<f32>) {
+        self.optimizer = adam(max_norm);
+    }
+
+    // None when the loss is NaN or infinite: the update is skipped so the network is left as it was
+    fn train_step(&mut self, input:Tensor<B, 4>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> Option<f32> {
         // Forward pass
         // println!("input {}", input.to_string());
         // println!("target value {}", target.value.to_string());
@@ -75,92 +202,221 @@ impl<B: AutodiffBackend<FloatElem = f32>, A: AI<NativePlatform>> ANNTrainer<B, A
 
         let output = self.alphazeutreeko.policy.ann.forward(input);
 
-        let loss = self.loss(output, target, illegal_mask);
+        let loss = policy_value_loss(output, target, illegal_mask);
+        let loss_value = loss.clone().into_scalar();
+        if !loss_value.is_finite() {
+            O::print(&format!("loss {}, update skipped", loss_value));
+            return None;
+        }
         let grads = loss.backward();
         let grads = GradientsParams::from_grads(grads, &self.alphazeutreeko.policy.ann);
 
         // Update self.alphazeutreeko.policy.ann parameters
         let lr = self.learning_rate_schedule.step();
         self.alphazeutreeko.policy.ann = self.optimizer.step(lr, self.alphazeutreeko.policy.ann.clone(), grads);
-        println!("loss {}", loss.to_data().to_string());
-        loss
+        O::print(&format!("loss {}", loss_value));
+        Some(loss_value)
+    }
+
+    // Learns the targets of one position with its symmetric copies, returns the summed loss and the steps made
+    // The value is for the player to move, as the searches and the network give it
+    fn learn_position(&mut self, board: &Board, value: Value, moves: &[ScoredMove], final_board: Option<&Board>) -> (f32, usize) {
+        span!(DEBUG, "learn_position");
+        let input = board_to_input(board, &self.device);
+        let mut target = moves_and_value_to_target(board, value, moves, &self.device);
+        target.ownership = final_board.map(|final_board| ownership_target(board, final_board, &self.device));
+        let illegal_mask = illegal_mask(board, &self.device);
+        let mut total_loss = 0.0;
+        let mut steps = 0;
+        for index in self.augmentation.indices::<O>() {
+            let (input_iter, target_iter, illegal_mask_iter) = symmetric_example(&input, &target, &illegal_mask, index);
+            match self.train_step(input_iter, target_iter, illegal_mask_iter) {
+                Some(loss) => {
+                    total_loss += loss;
+                    steps += 1;
+                }
+                None => {
+                    // the other symmetries of the position would fail the same way
+                    self.report_diverged(board);
+                    break;
+                }
+            }
+        }
+        (total_loss, steps)
     }
 
-    pub fn training_loop(&mut self, max_epoch: usize) {
+    fn report_diverged(&self, board: &Board) {
+        O::print(&format!("Not finite loss on {}", board.to_fen()));
+        #[cfg(feature = "train")]
+        {
+            use std::io::Write;
+            let file = std::fs::OpenOptions::new().create(true).append(true).open(DIVERGED_BOARDS_FILE);
+            if let Err(error) = file.and_then(|mut file| writeln!(file, "{}", board.to_fen())) {
+                O::print(&format!("Could not write {}: {}", DIVERGED_BOARDS_FILE, error));
+            }
+        }
+    }
+
+    // Positions the network would not learn much from are dropped, the search agreeing with its raw policy
+    fn surprising_positions(&self, searches: Vec<(Board, SearchResult)>) -> Vec<(Board, SearchResult)> {
+        let Some(threshold) = self.divergence_threshold else {
+            return searches;
+        };
+        let total = searches.len();
+        let kept: Vec<(Board, SearchResult)> = searches.into_iter()
+            .filter(|(board, result)| {
+                let (_, raw_policy) = self.alphazeutreeko.policy.ann.predict(board);
+                policy_divergence(&result.moves, &raw_policy) >= threshold
+            })
+            .collect();
+        println!("Learning from {}/{} positions, the others agree with the network", kept.len(), total);
+        kept
+    }
+
+    fn select_training_move(&self, moves: &[ScoredMove]) -> (usize, Direction) {
+        let selected = self.move_selector.select::<O>(moves).unwrap();
+        (selected.pawn_index, selected.direction.clone())
+    }
+
+    // One game from a random board, with the search of every position played by AlphaZeutreeko or the opponent.
+    // Games stopped by the move limit or a repetition are recorded as draws, adjudicated ones with the adjudicated result
+    fn play_game(&mut self, round: usize) -> Result<(GameRecord, Vec<(Board, SearchResult)>), MoveError> {
+        span!(INFO, "self_play_game", round);
+        let has_opponent = self.opponent.is_some();
+        self.alphazeutreeko.clear_graph();
+        let mut searches = vec![];
+        let mut board = match (&self.position_visits, &self.balanced_starts) {
+            (Some(position_visits), _) => position_visits.sample_start::<O>(),
+            (None, Some(balanced_starts)) => balanced_start::<O>(balanced_starts),
+            (None, None) => Board::random_board::<O>(),
+        };
+        let mut record = GameRecord::new(&board);
+        record.event = "Training".to_string();
+        record.round = Some(round);
+        let mut board_hashes = HashSet::new();
+        board_hashes.insert(board.get_hash());
+        let mut number_moves = 0;
+        let playthrough = O::random() < ADJUDICATION_PLAYTHROUGH;
+        let mut adjudicator = Adjudicator::new(if playthrough { AdjudicationConfig::default() } else { self.adjudication.clone() });
+        // resignation the playthrough games would have had, checked against their result
+        let mut shadow_adjudicator = Adjudicator::new(self.adjudication.clone());
+        let mut shadow_resignation = None;
+        while board.winner().is_none() {
+            if let Some(position_visits) = &mut self.position_visits {
+                position_visits.record(&board);
+            }
+            let alphazeutreeko_color = self.alphazeutreeko.color();
+            println!("Current board");
+            println!("{}", board.str_rep());
+            let possible_moves;
+            let best_move;
+            if board.next_player == Some(alphazeutreeko_color.clone()) {
+                println!("AlphaZeutreeko is playing");
+                possible_moves = self.alphazeutreeko.give_all_options(&board, false);
+                best_move = self.select_training_move(&possible_moves.moves);
+            }
+            else if !has_opponent {
+                println!("AlphaZeutreeko is playing against itself");
+                self.alphazeutreeko.set_color(alphazeutreeko_color.other_color());
+                possible_moves = self.alphazeutreeko.give_all_options(&board, true);
+                best_move = self.select_training_move(&possible_moves.moves);
+            }
+            else {
+                println!("Opponent is playing");
+                possible_moves = self.opponent.as_mut().unwrap().give_all_options(&board, false);
+                best_move = self.opponent.as_ref().unwrap().select_move(&possible_moves.moves, false).unwrap();
+            }
+
+            let mover = board.next_player.clone().unwrap();
+            if let Some(adjudication) = adjudicator.record(&mover, possible_moves.value) {
+                println!("{} after {} moves", adjudication.to_text(), number_moves);
+                record.result = Some(adjudication.result());
+                record.termination = Some(adjudication.termination().to_string());
+                searches.push((board.clone(), possible_moves));
+                break;
+            }
+            if let Some(Adjudication::Resignation(color)) = shadow_adjudicator.record(&mover, possible_moves.value).filter(|_| playthrough) {
+                shadow_resignation.get_or_insert(color);
+            }
+
+            let notation = board.move_to_notation(&Move::new(best_move.0, best_move.1.clone())).unwrap_or_default();
+            let mut move_record = MoveRecord::from_search(notation, &possible_moves);
+            move_record.set_policy(&board, &possible_moves.moves);
+            record.moves.push(move_record);
+            searches.push((board.clone(), possible_moves));
+            board.apply_move(best_move.0, &best_move.1)?;
+            number_moves += 1;
+            if number_moves > 255 {
+                println!("Game taking too long, consider it a draw");
+                record.result = Some(GameResult::Draw);
+                record.termination = Some("move limit".to_string());
+                break;
+            }
+            let new_hash = board.get_hash();
+            if !board_hashes.insert(new_hash){
+                println!("Back to a previous board, break game to avoid loops, consider it a draw");
+                record.result = Some(GameResult::Draw);
+                record.termination = Some("repetition".to_string());
+                break;
+            }
+        }
+        record.finish(&board);
+        if let Some(color) = shadow_resignation {
+            let resignation_right = record.result.as_ref().and_then(GameResult::winner) == Some(color.other_color());
+            println!("Played through a resignation of {:?}, {}", color, if resignation_right { "it was right" } else { "it was wrong" });
+        }
+        println!("Final board");
+        println!("{}", board.str_rep());
+        Ok((record, searches))
+    }
+
+    // Stops with an error if an engine selects an illegal move
+    pub fn training_loop(&mut self, max_epoch: usize) -> Result<(), MoveError> {
         let mut victories = 0.0;
         let mut draws = 0.0;
         let has_opponent = self.opponent.is_some();
         for epoch in 1..=max_epoch {
+            span!(INFO, "epoch", epoch);
             println!("Starting iteration {}/{}", epoch, max_epoch);
-            self.alphazeutreeko.clear_graph();
-            let mut to_feed = vec![];
-            let mut board = Board::random_board::<NativePlatform>();
-            let mut board_hashes = HashSet::new();
-            board_hashes.insert(board.get_hash());
-            let mut number_moves = 0;
-            while board.winner().is_none() {
-                let alphazeutreeko_color = self.alphazeutreeko.color();
-                println!("Current board");
-                println!("{}", board.str_rep());
-                let possible_moves;
-                let best_move;
-                if board.next_player == Some(alphazeutreeko_color.clone()) {
-                    println!("AlphaZeutreeko is playing");
-                    possible_moves = self.alphazeutreeko.give_all_options(&board, false);
-                    best_move = self.alphazeutreeko.best_move_from_vec(&possible_moves.1, false);
-                }
-                else if !has_opponent {
-                    println!("AlphaZeutreeko is playing against itself");
-                    self.alphazeutreeko.set_color(alphazeutreeko_color.other_color());
-                    possible_moves = self.alphazeutreeko.give_all_options(&board, true);
-                    best_move = self.alphazeutreeko.best_move_from_vec(&possible_moves.1, false);
-                }
-                else {
-                    println!("Opponent is playing");
-                    possible_moves = self.opponent.as_mut().unwrap().give_all_options(&board, false);
-                    best_move = self.opponent.as_mut().unwrap().best_move_from_vec(&possible_moves.1, false);
-                }
-                
-                to_feed.push((board.clone(), possible_moves));
-                let moved = board.move_pawn_until_blocked(best_move.0, &best_move.1);
-                if !moved {
-                    panic!("An invalid move was selected!!!");
-                }
-                number_moves += 1;
-                if number_moves > 255 {
-                    println!("Game taking too long, consider it a draw");
-                    draws += 1.0;
-                    break;
-                }
-                let new_hash = board.get_hash();
-                if !board_hashes.insert(new_hash){
-                    println!("Back to a previous board, break game to avoid loops, consider it a draw");
-                    draws += 1.0;
-                    break;
-                }
-            }
+            let (record, to_feed) = self.play_game(epoch)?;
             let alphazeutreeko_color = self.alphazeutreeko.color().clone();
-            if has_opponent && board.winner() == Some(alphazeutreeko_color.clone()) {
-                victories += 1.0;
-                println!("AlphaZeutreeko won!!!");
+            match record.result.as_ref() {
+                Some(GameResult::Draw) => draws += 1.0,
+                Some(result) if has_opponent && result.winner() == Some(alphazeutreeko_color.clone()) => {
+                    victories += 1.0;
+                    println!("AlphaZeutreeko won!!!");
+                }
+                _ => (),
             }
-            println!("Final board");
-            println!("{}", board.str_rep());
+            // positions learn where the pawns ended, when the network has the ownership head
+            let final_board = record.boards()
+                .and_then(|boards| boards.last().cloned())
+                .filter(|_| self.alphazeutreeko.policy.ann.has_ownership_head());
+            self.games.push(record);
             println!("Proceeding to learning");
-            for (board_learn, (board_eval, moves_eval)) in to_feed.into_iter(){
-                let input = board_to_input(&board_learn, &self.device);
-                let target = moves_and_value_to_target(&board_learn, board_eval, &moves_eval, &self.device);
-                let illegal_mask = illegal_mask(&board_learn, &self.device);
-                for (input_iter, target_iter, illegal_mask_iter) in add_symmetries(input, target, illegal_mask).into_iter() {
-                    self.train_step(input_iter, target_iter, illegal_mask_iter);
+            #[cfg(feature = "train")]
+            if let Some(replay_file) = &self.replay_file {
+                if let Err(error) = append_samples(replay_file, &to_feed) {
+                    println!("Could not append to {}: {}", replay_file, error);
                 }
             }
+            let mut total_loss = 0.0;
+            let mut steps = 0;
+            for (board_learn, result) in self.surprising_positions(to_feed).into_iter(){
+                let (loss, position_steps) = self.learn_position(&board_learn, Value::new(result.value), &result.moves, final_board.as_ref());
+                total_loss += loss;
+                steps += position_steps;
+            }
+            if steps > 0 {
+                self.losses.push(total_loss / steps as f32);
+            }
             if has_opponent {
                 self.alphazeutreeko.set_color(alphazeutreeko_color.other_color());
                 self.opponent.as_mut().unwrap().set_color(alphazeutreeko_color);
             }
         }
         println!("Victories: {:.1}%, Draws: {:.1}%", 100.0*victories/max_epoch as f32, 100.0*draws/max_epoch as f32);
+        Ok(())
     }
 
     pub fn train_opening(&mut self, number_passes: usize) {
@@ -173,7 +429,7 @@ impl<B: AutodiffBackend<FloatElem = f32>, A: AI<NativePlatform>> ANNTrainer<B, A
         }
     }
 
-    pub fn evaluate(&mut self, number_games: usize) {
+    pub fn evaluate(&mut self, number_games: usize) -> Result<(), MoveError> {
         let opponent = self.opponent.as_mut().unwrap();
         let mut victories = 0.0;
         let mut draws = 0.0;
@@ -185,15 +441,12 @@ impl<B: AutodiffBackend<FloatElem = f32>, A: AI<NativePlatform>> ANNTrainer<B, A
             while board.winner().is_none() {
                 let best_move;
                 if board.next_player == Some(alphazeutreeko_color.clone()) {
-                    best_move = self.alphazeutreeko.best_move(&board, false);
+                    best_move = self.alphazeutreeko.best_move(&board, false).unwrap();
                 }
                 else {
-                    best_move = opponent.best_move(&board, false);
-                }
-                let moved = board.move_pawn_until_blocked(best_move.0, &best_move.1);
-                if !moved {
-                    panic!("An invalid move was selected!!!");
+                    best_move = opponent.best_move(&board, false).unwrap();
                 }
+                board.apply_move(best_move.0, &best_move.1)?;
                 number_moves += 1;
                 if number_moves > 255 {
                     draws += 1.0;
@@ -207,20 +460,74 @@ impl<B: AutodiffBackend<FloatElem = f32>, A: AI<NativePlatform>> ANNTrainer<B, A
             opponent.set_color(alphazeutreeko_color.clone());
         }
         println!("Victories: {:.1}%, Draws: {:.1}%", 100.0 * victories / number_games as f32, 100.0 * draws / number_games as f32);
+        Ok(())
+    }
+
+    // Weights in memory, for platforms without files. Read back by load_bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+        Ok(recorder.record(self.alphazeutreeko.policy.ann.clone().into_record(), ())?)
+    }
+
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+        let record = recorder.load(bytes.to_vec(), &self.device)?;
+        self.alphazeutreeko.policy.ann = self.alphazeutreeko.policy.ann.clone().load_record(record);
+        Ok(())
     }
+}
 
+#[cfg(feature = "train")]
+impl<B: AutodiffBackend<FloatElem = f32>, A: AI<O>, O: Platform> ANNTrainer<B, A, O> {
     pub fn save(&self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.alphazeutreeko.policy.ann.clone().save_file(filepath, &self.recorder)?;
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        self.alphazeutreeko.policy.ann.clone().save_file(filepath, &recorder)?;
         Ok(())
     }
 
+    pub fn save_games(&self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(filepath, games_to_pgn(&self.games))?;
+        Ok(())
+    }
+
+    // Learns from positions drawn from a replay file of earlier runs, returns the average loss
+    pub fn train_from_replay(&mut self, replay_file: &str, number_positions: usize) -> std::io::Result<f32> {
+        span!(INFO, "train_from_replay", number_positions);
+        let buffer = ReplayBuffer::open(replay_file)?;
+        println!("Learning from {} of the {} positions of {}", number_positions, buffer.len(), replay_file);
+        let mut total_loss = 0.0;
+        let mut steps = 0;
+        for sample in buffer.sample::<O>(number_positions) {
+            let (loss, position_steps) = self.learn_position(&sample.board, Value::new(sample.value), &sample.moves, None);
+            total_loss += loss;
+            steps += position_steps;
+        }
+        Ok(total_loss / steps.max(1) as f32)
+    }
+
     pub fn save_for_web(&self) {
         let mut store = BurnpackStore::from_file("assets/models/web/modelNew");
-        let _ = self.alphazeutreeko.policy.ann.save_into(&mut store);
+        let _ = self.alphazeutreeko.policy.ann.clone().without_auxiliary_heads().save_into(&mut store);
+    }
+
+    // Self-play games written with the search policy of every position, see games_to_jsonl for the format.
+    // The games are also kept in self.games, save_games writes them as PGN. Nothing is learned from them
+    pub fn export_selfplay(&mut self, filepath: &str, number_games: usize) -> Result<(), Box<dyn std::error::Error>> {
+        // the opponent is put back even when a game stops on an illegal move
+        let opponent = self.opponent.take();
+        let records: Result<Vec<GameRecord>, MoveError> = (1..=number_games)
+            .map(|round| self.play_game(round).map(|(record, _)| record))
+            .collect();
+        self.opponent = opponent;
+        let records = records?;
+        std::fs::write(filepath, games_to_jsonl(&records))?;
+        self.games.extend(records);
+        Ok(())
     }
 
     pub fn load(&mut self, filepath: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let loaded_ann = self.alphazeutreeko.policy.ann.clone().load_file(filepath, &self.recorder, &self.device)?;
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        let loaded_ann = self.alphazeutreeko.policy.ann.clone().load_file(filepath, &recorder, &self.device)?;
         self.alphazeutreeko.policy.ann = loaded_ann;
         Ok(())
     }
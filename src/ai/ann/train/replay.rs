@@ -0,0 +1,139 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+
+use memmap2::Mmap;
+
+use crate::{
+    ai::{ScoredMove, SearchResult},
+    logic::{Board, Color, Pawn, Position},
+    platform::Platform,
+};
+
+// Start of every replay file, the version changes with the record layout
+const MAGIC: &[u8; 8] = b"NTREPLY1";
+
+const NUMBER_OF_PAWNS: usize = 6;
+const NUMBER_OF_DIRECTIONS: usize = 8;
+const POLICY_ENTRIES: usize = NUMBER_OF_PAWNS * NUMBER_OF_DIRECTIONS;
+// Policy probabilities are stored as u16, about 1e-5 of precision is plenty for a training target
+const POLICY_SCALE: f32 = u16::MAX as f32;
+const YELLOW_PAWN: u8 = 0x80;
+
+// Pawn bytes (square, YELLOW_PAWN for yellow pawns), player to move, value as f32, then the policy by pawn and direction.
+// Records all have this size, so the index of a record gives its offset in the file
+pub const RECORD_SIZE: usize = NUMBER_OF_PAWNS + 1 + 4 + 2 * POLICY_ENTRIES;
+
+// Position learned from, with the targets of the search that played it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplaySample {
+    pub board: Board,
+    pub value: f32,
+    pub moves: Vec<ScoredMove>,
+}
+
+fn encode(board: &Board, result: &SearchResult) -> Option<[u8; RECORD_SIZE]> {
+    if board.pawns.len() != NUMBER_OF_PAWNS || board.number_of_rows != 5 || board.number_of_columns != 5 {
+        return None;
+    }
+    let mut record = [0; RECORD_SIZE];
+    for (byte, pawn) in record.iter_mut().zip(board.pawns.iter()) {
+        let square = (pawn.position.row * 5 + pawn.position.column) as u8;
+        *byte = if pawn.color == Color::Yellow { square | YELLOW_PAWN } else { square };
+    }
+    record[NUMBER_OF_PAWNS] = match board.next_player.as_ref()? {
+        Color::Green => 0,
+        Color::Yellow => 1,
+    };
+    record[NUMBER_OF_PAWNS + 1..NUMBER_OF_PAWNS + 5].copy_from_slice(&result.value.to_le_bytes());
+    for scored_move in result.moves.iter() {
+        let entry = scored_move.pawn_index * NUMBER_OF_DIRECTIONS + scored_move.direction.clone() as usize;
+        let probability = (scored_move.score.clamp(0.0, 1.0) * POLICY_SCALE).round() as u16;
+        let offset = NUMBER_OF_PAWNS + 5 + 2 * entry;
+        record[offset..offset + 2].copy_from_slice(&probability.to_le_bytes());
+    }
+    Some(record)
+}
+
+fn decode(record: &[u8]) -> Option<ReplaySample> {
+    let pawns = record[..NUMBER_OF_PAWNS].iter()
+        .map(|byte| {
+            let square = (byte & !YELLOW_PAWN) as usize;
+            let color = if byte & YELLOW_PAWN != 0 { Color::Yellow } else { Color::Green };
+            Pawn::new(color, Position { row: square / 5, column: square % 5 })
+        })
+        .collect();
+    let next_player = match record[NUMBER_OF_PAWNS] {
+        0 => Color::Green,
+        1 => Color::Yellow,
+        _ => return None,
+    };
+    let board = Board::new(5, 5, pawns, Some(next_player)).ok()?;
+    let value = f32::from_le_bytes(record[NUMBER_OF_PAWNS + 1..NUMBER_OF_PAWNS + 5].try_into().ok()?);
+    let moves = board.get_all_valid_directions_and_resulting_boards()
+        .into_iter()
+        .map(|(pawn_index, direction, _)| {
+            let offset = NUMBER_OF_PAWNS + 5 + 2 * (pawn_index * NUMBER_OF_DIRECTIONS + direction.clone() as usize);
+            let probability = u16::from_le_bytes([record[offset], record[offset + 1]]) as f32 / POLICY_SCALE;
+            ScoredMove::new(probability, pawn_index, direction)
+        })
+        .collect();
+    Some(ReplaySample { board, value, moves })
+}
+
+// Appends the positions to the replay file, created with its header if missing. Returns the number written,
+// boards that are not the standard 5x5 with 6 pawns are left out
+pub fn append_samples(path: &str, searches: &[(Board, SearchResult)]) -> io::Result<usize> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        file.write_all(MAGIC)?;
+    }
+    let mut bytes = Vec::with_capacity(searches.len() * RECORD_SIZE);
+    for record in searches.iter().filter_map(|(board, result)| encode(board, result)) {
+        bytes.extend_from_slice(&record);
+    }
+    file.write_all(&bytes)?;
+    Ok(bytes.len() / RECORD_SIZE)
+}
+
+// Replay file mapped in memory: the system pages in the records that are read, so it can be larger than the RAM.
+// Positions appended after opening are not seen, the buffer has to be opened again
+pub struct ReplayBuffer {
+    map: Mmap,
+}
+
+impl ReplayBuffer {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // the file is only appended to, the records already mapped do not change
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < MAGIC.len() || &map[..MAGIC.len()] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a replay file", path)));
+        }
+        Ok(Self { map })
+    }
+
+    // A record cut short by an interrupted write is not counted
+    pub fn len(&self) -> usize {
+        (self.map.len() - MAGIC.len()) / RECORD_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<ReplaySample> {
+        if index >= self.len() {
+            return None;
+        }
+        let offset = MAGIC.len() + index * RECORD_SIZE;
+        decode(&self.map[offset..offset + RECORD_SIZE])
+    }
+
+    // Positions drawn uniformly with replacement
+    pub fn sample<O: Platform>(&self, count: usize) -> Vec<ReplaySample> {
+        if self.is_empty() {
+            return vec![];
+        }
+        (0..count).filter_map(|_| self.get(O::random_int(self.len()))).collect()
+    }
+}
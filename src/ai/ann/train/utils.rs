@@ -1,10 +1,9 @@
 use core::f32;
 
 use crate::{
-    ai::ann::utils::board_to_input,
+    ai::{ScoredMove, ann::utils::board_to_input, book::OpeningBook, value::Value},
     logic::{Board, Direction},
 };
-use strum::IntoEnumIterator;
 
 use burn::tensor::{backend::{Backend, AutodiffBackend}, Device, Tensor, s};
 
@@ -12,6 +11,7 @@ use burn::tensor::{backend::{Backend, AutodiffBackend}, Device, Tensor, s};
 pub struct PolicyValueTarget<B: AutodiffBackend> {
     pub value: Tensor<B, 2>,
     pub policy: Tensor<B, 4>,
+    pub ownership: Option<Tensor<B, 4>>, // for networks with the ownership head, see ownership_target
 }
 
 impl<B: AutodiffBackend> PolicyValueTarget<B> {
@@ -19,6 +19,7 @@ impl<B: AutodiffBackend> PolicyValueTarget<B> {
         Self {
             value: self.value.clone(),
             policy: rotate_clockwise(self.policy.clone(), quarter_turns, true),
+            ownership: self.ownership.clone().map(|ownership| rotate_clockwise(ownership, quarter_turns, false)),
         }
     }
 
@@ -26,6 +27,7 @@ impl<B: AutodiffBackend> PolicyValueTarget<B> {
         Self {
             value: self.value.clone(),
             policy: flip(self.policy.clone(), horizontal, vertical, true),
+            ownership: self.ownership.clone().map(|ownership| flip(ownership, horizontal, vertical, false)),
         }
     }
 
@@ -33,10 +35,27 @@ impl<B: AutodiffBackend> PolicyValueTarget<B> {
         Self {
             value: self.value.clone(),
             policy: flip_diagonal(self.policy.clone(), upleft_downright_diag, upright_downleft_diag, true),
+            ownership: self.ownership.clone().map(|ownership| flip_diagonal(ownership, upleft_downright_diag, upright_downleft_diag, false)),
         }
     }
 }
 
+// Probabilities below this count as this, so moves the network rules out do not make the divergence infinite
+const MIN_PROBABILITY: f32 = 1e-6;
+
+// KL divergence of the network policy from the search policy: near 0 when the search only confirms what the network plays
+pub fn policy_divergence(search_moves: &[ScoredMove], raw_policy: &[(f32, usize, Direction, Board)]) -> f32 {
+    search_moves.iter()
+        .filter(|scored_move| scored_move.score > 0.0)
+        .map(|scored_move| {
+            let raw = raw_policy.iter()
+                .find(|(_, pawn_index, direction, _)| *pawn_index == scored_move.pawn_index && *direction == scored_move.direction)
+                .map_or(0.0, |(probability, _, _, _)| *probability);
+            scored_move.score * (scored_move.score / raw.max(MIN_PROBABILITY)).ln()
+        })
+        .sum()
+}
+
 pub fn illegal_mask<B>(board: &Board, device: &Device<B>) -> Tensor<B, 4>
 where B:Backend {
     let possible_moves = board.get_all_valid_directions_and_resulting_boards();
@@ -48,155 +67,67 @@ where B:Backend {
     Tensor::from_data(illegal_mask_array, device)
 }
 
-pub fn moves_and_value_to_target<B>(board: &Board, board_eval: f32, moves_eval: &Vec<(f32, usize, Direction)>, device: &Device<B>) -> PolicyValueTarget<B>
+pub fn moves_and_value_to_target<B>(board: &Board, board_eval: Value, moves_eval: &[ScoredMove], device: &Device<B>) -> PolicyValueTarget<B>
 where B: AutodiffBackend {
-    let value = Tensor::from_floats([[board_eval]], device);
+    let value = Tensor::from_floats([[board_eval.get()]], device);
     let mut policy_floats = [[[[0.0; 5]; 5]; 8]; 1];
-    for (proba, pawn_index, direction) in moves_eval.iter() {
-        let pawn_position = &board.pawns[*pawn_index].position;
-        policy_floats[0][direction.clone() as usize][pawn_position.row as usize][pawn_position.column as usize] = *proba;
+    for scored_move in moves_eval.iter() {
+        let pawn_position = &board.pawns[scored_move.pawn_index].position;
+        policy_floats[0][scored_move.direction.clone() as usize][pawn_position.row as usize][pawn_position.column as usize] = scored_move.score;
     }
     let policy = Tensor::from_floats( policy_floats, device);
-    PolicyValueTarget { value, policy }
+    PolicyValueTarget { value, policy, ownership: None }
 }
 
-pub fn opening<B>(device: &Device<B>) -> Vec<(Tensor<B, 4>, PolicyValueTarget<B>, Tensor<B, 4>)>
+// Squares of the pawns at the end of the game, in the channels of board_to_input: the player to move on board, then the opponent
+pub fn ownership_target<B>(board: &Board, final_board: &Board, device: &Device<B>) -> Tensor<B, 4>
 where B: AutodiffBackend {
-    // initial board
-    let mut board = Board::default_new();
-    let mut opening_moves = vec![(0.5, 0, Direction::Right), (0.5, 1, Direction::Left)];
-    let board_eval = 0.0;
-    let mut to_feed = move_to_learning_input(&board, &opening_moves, board_eval, device);
-    // println!("Initial board");
-    // println!("{}", board.str_rep());
-
-    // 1st move b1-c1
-    board.move_pawn_until_blocked(0, &Direction::Right);
-    opening_moves = vec![(1.0, 3, Direction::Down)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 1");
-    // println!("{}", board.str_rep());
-
-    // 2nd move c2-c3
-    board.move_pawn_until_blocked(3, &Direction::Down);
-    opening_moves = vec![(1.0, 0, Direction::DownLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 2");
-    // println!("{}", board.str_rep());
-
-    // 3rd move c1-a3
-    board.move_pawn_until_blocked(0, &Direction::DownLeft);
-    opening_moves = vec![(1.0, 4, Direction::Right)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 3");
-    // println!("{}", board.str_rep());
-
-    // 4th move b5-c5
-    board.move_pawn_until_blocked(4, &Direction::Right);
-    opening_moves = vec![(1.0, 1, Direction::Down)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 4");
-    // println!("{}", board.str_rep());
-
-    // 5th move d1-d4
-    board.move_pawn_until_blocked(1, &Direction::Down);
-    opening_moves = vec![(1.0, 4, Direction::UpLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 5");
-    // println!("{}", board.str_rep());
-
-    // 6th move c5-b4
-    board.move_pawn_until_blocked(4, &Direction::UpLeft);
-    opening_moves = vec![(1.0, 1, Direction::DownLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 6");
-    // println!("{}", board.str_rep());
-
-    // 7th move d4-c5
-    board.move_pawn_until_blocked(1, &Direction::DownLeft);
-    opening_moves = vec![(1.0, 5, Direction::Up)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 7");
-    // println!("{}", board.str_rep());
-
-    // 8th move d5-d1
-    board.move_pawn_until_blocked(5, &Direction::Up);
-    opening_moves = vec![(1.0, 0, Direction::Down)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 8");
-    // println!("{}", board.str_rep());
-
-    // 9th move a3-a5
-    board.move_pawn_until_blocked(0, &Direction::Down);
-    opening_moves = vec![(1.0, 4, Direction::Down)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 9");
-    // println!("{}", board.str_rep());
-
-    // 10th move b4-b5
-    board.move_pawn_until_blocked(4, &Direction::Down);
-    opening_moves = vec![(1.0, 1, Direction::UpLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 10");
-    // println!("{}", board.str_rep());
-
-    // 11th move c5-a3
-    board.move_pawn_until_blocked(1, &Direction::UpLeft);
-    opening_moves = vec![(1.0, 3, Direction::DownLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 11");
-    // println!("{}", board.str_rep());
-
-    // 12th move c3-b4
-    board.move_pawn_until_blocked(3, &Direction::DownLeft);
-    opening_moves = vec![(1.0, 2, Direction::UpLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 12");
-    // println!("{}", board.str_rep());
-
-    // 13th move c4-a2
-    board.move_pawn_until_blocked(2, &Direction::UpLeft);
-    opening_moves = vec![(1.0, 5, Direction::DownLeft)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 13");
-    // println!("{}", board.str_rep());
-
-    // 14th move d1-a4
-    board.move_pawn_until_blocked(5, &Direction::DownLeft);
-    opening_moves = vec![(1.0, 2, Direction::DownRight)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 14");
-    // println!("{}", board.str_rep());
-
-    // 15th move a2-d5
-    board.move_pawn_until_blocked(2, &Direction::DownRight);
-    opening_moves = vec![(0.2, 5, Direction::UpRight), (0.2, 3, Direction::Up), (0.2, 3, Direction::Right), (0.2, 3, Direction::DownRight), (0.2, 4, Direction::Right)];
-    to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
-    // println!("Move 15");
-    // println!("{}", board.str_rep());
+    let mut ownership = [[[[0.0; 5]; 5]; 2]; 1];
+    for pawn in final_board.pawns.iter() {
+        let channel = if Some(pawn.color.clone()) == board.next_player { 0 } else { 1 };
+        ownership[0][channel][pawn.position.row as usize][pawn.position.column as usize] = 1.0;
+    }
+    Tensor::from_data(ownership, device)
+}
 
+pub fn opening<B>(device: &Device<B>) -> Vec<(Tensor<B, 4>, PolicyValueTarget<B>, Tensor<B, 4>)>
+where B: AutodiffBackend {
+    // same line as the opening book, so the network learns what the engines play from the book
+    let board_eval = Value::DRAW;
+    let mut to_feed = vec![];
+    for (board, opening_moves) in OpeningBook::training_line() {
+        to_feed.append(&mut move_to_learning_input(&board, &opening_moves, board_eval, device));
+    }
     to_feed
 }
 
-fn move_to_learning_input<B>(board: &Board, opening_moves: &Vec<(f32, usize, Direction)>, board_eval: f32, device:&Device<B>) -> Vec<(Tensor<B, 4>, PolicyValueTarget<B>, Tensor<B, 4>)>
+fn move_to_learning_input<B>(board: &Board, opening_moves: &[ScoredMove], board_eval: Value, device:&Device<B>) -> Vec<(Tensor<B, 4>, PolicyValueTarget<B>, Tensor<B, 4>)>
 where B: AutodiffBackend {
     let input = board_to_input(&board, device);
-    let target = moves_and_value_to_target(&board, board_eval, &opening_moves, device);
+    let target = moves_and_value_to_target(&board, board_eval, opening_moves, device);
     let illegal_m = illegal_mask(&board, device);
     add_symmetries(input, target, illegal_m)
 }
 
 pub fn add_symmetries<B>(input:Tensor<B, 4>, target: PolicyValueTarget<B>, illegal_mask: Tensor<B, 4>) -> Vec<(Tensor<B, 4>, PolicyValueTarget<B>, Tensor<B, 4>)>
 where B: AutodiffBackend {
-    let mut with_symmetries = vec![(input.clone(), target.clone(), illegal_mask.clone())];
-    with_symmetries.push((rotate_clockwise(input.clone(), 1, false), target.rotate_clockwise(1), rotate_clockwise(illegal_mask.clone(), 1, true)));
-    with_symmetries.push((rotate_clockwise(input.clone(), 2, false), target.rotate_clockwise(2), rotate_clockwise(illegal_mask.clone(), 2, true)));
-    with_symmetries.push((rotate_clockwise(input.clone(), 3, false), target.rotate_clockwise(3), rotate_clockwise(illegal_mask.clone(), 3, true)));
-    with_symmetries.push((flip(input.clone(), true, false, false), target.flip(true, false), flip(illegal_mask.clone(), true, false, true)));
-    with_symmetries.push((flip(input.clone(), false, true, false), target.flip(false, true), flip(illegal_mask.clone(), false, true, true)));
-    with_symmetries.push((flip_diagonal(input.clone(), true, false, false), target.flip_diagonal(true, false), flip_diagonal(illegal_mask.clone(), true, false, true)));
-    with_symmetries.push((flip_diagonal(input.clone(), false, true, false), target.flip_diagonal(false, true), flip_diagonal(illegal_mask.clone(), false, true, true)));
-    with_symmetries
+    (0..8).map(|index| symmetric_example(&input, &target, &illegal_mask, index)).collect()
+}
+
+// One of the 8 symmetric copies of an example, built on demand: 0 is the example itself, then 3 rotations, 2 flips and 2 diagonal flips
+pub fn symmetric_example<B>(input: &Tensor<B, 4>, target: &PolicyValueTarget<B>, illegal_mask: &Tensor<B, 4>, index: usize) -> (Tensor<B, 4>, PolicyValueTarget<B>, Tensor<B, 4>)
+where B: AutodiffBackend {
+    match index {
+        0 => (input.clone(), target.clone(), illegal_mask.clone()),
+        1..=3 => {
+            let quarter_turns = index as i32;
+            (rotate_clockwise(input.clone(), quarter_turns, false), target.rotate_clockwise(quarter_turns), rotate_clockwise(illegal_mask.clone(), quarter_turns, true))
+        }
+        4 => (flip(input.clone(), true, false, false), target.flip(true, false), flip(illegal_mask.clone(), true, false, true)),
+        5 => (flip(input.clone(), false, true, false), target.flip(false, true), flip(illegal_mask.clone(), false, true, true)),
+        6 => (flip_diagonal(input.clone(), true, false, false), target.flip_diagonal(true, false), flip_diagonal(illegal_mask.clone(), true, false, true)),
+        _ => (flip_diagonal(input.clone(), false, true, false), target.flip_diagonal(false, true), flip_diagonal(illegal_mask.clone(), false, true, true)),
+    }
 }
 
 fn swap_direction_indices<B>(input:Tensor<B, 4>, directions_out: Vec<i32>) -> Tensor<B, 4>
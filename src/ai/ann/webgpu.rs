@@ -0,0 +1,38 @@
+use burn::backend::wgpu::{Wgpu, WgpuDevice, graphics, init_setup_async};
+use js_sys::{Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+// Network inference on the GPU of the browser, the engines search on the CPU as with NdArray
+pub type WebGpuBackend = Wgpu<f32, i32>;
+
+// Whether the browser gives a WebGPU adapter. burn panics without one, so it is asked first
+async fn has_adapter() -> bool {
+    let Ok(gpu) = Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))
+        .and_then(|navigator| Reflect::get(&navigator, &JsValue::from_str("gpu"))) else {
+        return false;
+    };
+    if gpu.is_undefined() || gpu.is_null() {
+        return false;
+    }
+    let Ok(request_adapter) = Reflect::get(&gpu, &JsValue::from_str("requestAdapter")) else {
+        return false;
+    };
+    let Ok(promise) = request_adapter.unchecked_into::<js_sys::Function>().call0(&gpu) else {
+        return false;
+    };
+    match JsFuture::from(Promise::from(promise)).await {
+        Ok(adapter) => !adapter.is_null() && !adapter.is_undefined(),
+        Err(_) => false,
+    }
+}
+
+// Sets up the WebGPU device, which in the browser must be done before the first tensor is created on it.
+// False when the browser has no WebGPU, the page then keeps NdArray
+pub async fn init_webgpu() -> bool {
+    if !has_adapter().await {
+        return false;
+    }
+    init_setup_async::<graphics::WebGpu>(&WgpuDevice::default(), Default::default()).await;
+    true
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::platform::{Platform, load_text, save_text};
+#[cfg(feature = "web")]
+use crate::platform::WasmPlatform;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::JsCast;
+#[cfg(feature = "web")]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "web")]
+use web_sys::{File, Response};
+
+pub const DEFAULT_MODEL_ID: &str = "default";
+
+// Small network fitted to the moves of the local player, only ever uploaded, see ANNSolo::human_like
+pub const HUMAN_MODEL_ID: &str = "human";
+
+// Checkpoint served with the web build, Trunk copies the assets there
+pub struct WebModel {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub url: &'static str,
+}
+
+pub const WEB_MODELS: [WebModel; 1] = [
+    WebModel { id: DEFAULT_MODEL_ID, label: "Latest", url: "models/model.bpk" },
+];
+
+// Uploaded files are registered under this prefix, followed by the file name
+const UPLOAD_PREFIX: &str = "upload:";
+
+// Uploaded weights are also kept in the storage of the platform under this prefix and the model identifier, so they
+// come back on the next visit. Served checkpoints are left to the HTTP cache, a new one may be deployed under the same URL
+const MODEL_STORAGE_PREFIX: &str = "model:";
+
+// Storage entry listing the identifiers of the stored uploads, one per line
+const STORED_UPLOADS_KEY: &str = "models:uploaded";
+
+// Weights downloaded or uploaded once, shared by every network created afterwards.
+// They are leaked so stores can read them without copying, a model is rarely loaded more than once
+static MODEL_CACHE: Mutex<BTreeMap<String, &'static [u8]>> = Mutex::new(BTreeMap::new());
+
+pub fn is_model_loaded(model_id: &str) -> bool {
+    model_bytes(model_id).is_some()
+}
+
+pub fn set_model_bytes(model_id: &str, bytes: Vec<u8>) {
+    let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+    MODEL_CACHE.lock().unwrap().insert(model_id.to_string(), bytes);
+}
+
+// Cached weights, or on native builds the default asset embedded at compile time
+pub fn model_bytes(model_id: &str) -> Option<&'static [u8]> {
+    if let Some(bytes) = MODEL_CACHE.lock().unwrap().get(model_id) {
+        return Some(bytes);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if model_id == DEFAULT_MODEL_ID {
+        static DATA: &[u8] = include_bytes!("../../../assets/models/web/model.bpk");
+        return Some(DATA);
+    }
+    None
+}
+
+// Identifiers and labels of the models to pick from: served checkpoints first, then uploaded files
+pub fn available_models() -> Vec<(String, String)> {
+    let mut models: Vec<(String, String)> = WEB_MODELS.iter()
+        .map(|model| (model.id.to_string(), model.label.to_string()))
+        .collect();
+    for model_id in MODEL_CACHE.lock().unwrap().keys() {
+        if let Some(file_name) = model_id.strip_prefix(UPLOAD_PREFIX) {
+            models.push((model_id.clone(), format!("{} (uploaded)", file_name)));
+        }
+    }
+    models
+}
+
+async fn stored_uploads<O: Platform>() -> Vec<String> {
+    load_text::<O>(STORED_UPLOADS_KEY).await
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+// Keeps loaded weights in storage and lists them for restore_uploaded_models
+pub async fn store_model<O: Platform>(model_id: &str) -> Result<(), String> {
+    let bytes = model_bytes(model_id).ok_or(format!("Model {} is not loaded", model_id))?;
+    O::storage_set(&format!("{}{}", MODEL_STORAGE_PREFIX, model_id), bytes).await?;
+    let mut uploads = stored_uploads::<O>().await;
+    if !uploads.iter().any(|stored| stored == model_id) {
+        uploads.push(model_id.to_string());
+        if !save_text::<O>(STORED_UPLOADS_KEY, &uploads.join("\n")).await {
+            return Err(format!("Could not list model {} in the storage", model_id));
+        }
+    }
+    Ok(())
+}
+
+// Registers the weights stored by earlier visits, returns the identifiers of the models found
+pub async fn restore_uploaded_models<O: Platform>() -> Vec<String> {
+    let mut restored = vec![];
+    for model_id in stored_uploads::<O>().await {
+        if !is_model_loaded(&model_id) {
+            let Some(bytes) = O::storage_get(&format!("{}{}", MODEL_STORAGE_PREFIX, model_id)).await else {
+                continue;
+            };
+            set_model_bytes(&model_id, bytes);
+        }
+        restored.push(model_id);
+    }
+    restored
+}
+
+// Downloads a served checkpoint in the browser, does nothing once it is cached
+#[cfg(feature = "web")]
+pub async fn fetch_model(model_id: &str) -> Result<(), String> {
+    if is_model_loaded(model_id) {
+        return Ok(());
+    }
+    let url = WEB_MODELS.iter()
+        .find(|model| model.id == model_id)
+        .map(|model| model.url)
+        .ok_or(format!("Unknown model {}", model_id))?;
+    let window = web_sys::window().ok_or("No browser window")?;
+    let response = JsFuture::from(window.fetch_with_str(url)).await
+        .map_err(|error| format!("Could not fetch {}: {:?}", url, error))?;
+    let response: Response = response.dyn_into()
+        .map_err(|_| format!("Unexpected answer for {}", url))?;
+    if !response.ok() {
+        return Err(format!("Could not fetch {}: HTTP status {}", url, response.status()));
+    }
+    let buffer = response.array_buffer()
+        .map_err(|error| format!("Could not read {}: {:?}", url, error))?;
+    let buffer = JsFuture::from(buffer).await
+        .map_err(|error| format!("Could not read {}: {:?}", url, error))?;
+    set_model_bytes(model_id, js_sys::Uint8Array::new(&buffer).to_vec());
+    Ok(())
+}
+
+// Registers a .bpk file picked by the user, returns its model identifier
+#[cfg(feature = "web")]
+pub async fn upload_model(file: &File) -> Result<String, String> {
+    let model_id = format!("{}{}", UPLOAD_PREFIX, file.name());
+    upload_model_as(file, &model_id).await?;
+    Ok(model_id)
+}
+
+// Registers a .bpk file under a fixed identifier, e.g. HUMAN_MODEL_ID
+#[cfg(feature = "web")]
+pub async fn upload_model_as(file: &File, model_id: &str) -> Result<(), String> {
+    let buffer = JsFuture::from(file.array_buffer()).await
+        .map_err(|error| format!("Could not read {}: {:?}", file.name(), error))?;
+    set_model_bytes(model_id, js_sys::Uint8Array::new(&buffer).to_vec());
+    // a model the storage has no room for still plays until the page closes
+    if let Err(error) = store_model::<WasmPlatform>(model_id).await {
+        WasmPlatform::print(&format!("Model {} not kept for the next visit: {}", model_id, error));
+    }
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use std::future::Future;
+
+use crate::{
+    analysis::{Analysis, analyze},
+    logic::{Board, Color, Direction},
+    platform::Platform,
+};
+use super::{AI, SearchObserver, SearchResult, Telemetry, alphazeutreeko::AlphaZeutreeko, baseline::{GreedyAI, RandomAI}, committee::CommitteeAI, ann::{ANNSolo, weights::DEFAULT_MODEL_ID}, mcts::MCTS, minmax::{MinMax, MinMaxStats}, oracle::SharedOracle, searchtree::SearchTree, statscache::SharedStatsCache, timemanager::TimeManager};
+
+use burn::tensor::backend::Backend;
+
+pub const ENGINE_NAMES: [&str; 7] = ["minmax", "mcts", "ann", "alphazeutreeko", "committee", "random", "greedy"];
+
+// Any of the engines, so front-ends can pick one at runtime without repeating a match arm per engine
+#[derive(Clone)]
+pub enum AnyAI<B: Backend, O: Platform> {
+    MinMax(MinMax<O>),
+    MCTS(MCTS<O>),
+    ANNSolo(ANNSolo<B, O>),
+    AlphaZeutreeko(AlphaZeutreeko<B, O>),
+    Committee(CommitteeAI<B, O>),
+    Random(RandomAI<O>),
+    Greedy(GreedyAI<O>),
+}
+
+macro_rules! dispatch {
+    ($any_ai:expr, $ai:ident => $body:expr) => {
+        match $any_ai {
+            AnyAI::MinMax($ai) => $body,
+            AnyAI::MCTS($ai) => $body,
+            AnyAI::ANNSolo($ai) => $body,
+            AnyAI::AlphaZeutreeko($ai) => $body,
+            AnyAI::Committee($ai) => $body,
+            AnyAI::Random($ai) => $body,
+            AnyAI::Greedy($ai) => $body,
+        }
+    };
+}
+
+impl<B: Backend, O: Platform> AnyAI<B, O> {
+    pub fn from_name(name: &str, color: Color, difficulty: usize) -> Option<Self> {
+        Self::from_name_with_model(name, color, difficulty, DEFAULT_MODEL_ID)
+    }
+
+    // model_id picks the weights of network engines, other engines ignore it
+    pub fn from_name_with_model(name: &str, color: Color, difficulty: usize, model_id: &str) -> Option<Self> {
+        match name {
+            "minmax" => Some(AnyAI::MinMax(MinMax::new(color, difficulty))),
+            "mcts" => Some(AnyAI::MCTS(MCTS::new(color, difficulty))),
+            "ann" => Some(AnyAI::ANNSolo(ANNSolo::with_model(color, model_id))),
+            "alphazeutreeko" => Some(AnyAI::AlphaZeutreeko(AlphaZeutreeko::with_model(color, difficulty, model_id))),
+            "committee" => Some(AnyAI::Committee(CommitteeAI::with_model(color, difficulty, model_id))),
+            "random" => Some(AnyAI::Random(RandomAI::new(color, difficulty))),
+            "greedy" => Some(AnyAI::Greedy(GreedyAI::new(color, difficulty))),
+            _ => None,
+        }
+    }
+
+    // Engines built on the neural network, which need its weights before being created
+    pub fn uses_network(name: &str) -> bool {
+        matches!(name, "ann" | "alphazeutreeko" | "committee")
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnyAI::MinMax(_) => ENGINE_NAMES[0],
+            AnyAI::MCTS(_) => ENGINE_NAMES[1],
+            AnyAI::ANNSolo(_) => ENGINE_NAMES[2],
+            AnyAI::AlphaZeutreeko(_) => ENGINE_NAMES[3],
+            AnyAI::Committee(_) => ENGINE_NAMES[4],
+            AnyAI::Random(_) => ENGINE_NAMES[5],
+            AnyAI::Greedy(_) => ENGINE_NAMES[6],
+        }
+    }
+
+    pub fn set_limits(&mut self, movetime: Option<f64>, depth: Option<usize>) {
+        match self {
+            AnyAI::MinMax(ai) => {
+                if let Some(depth) = depth {
+                    ai.set_depth(depth);
+                }
+            }
+            AnyAI::MCTS(ai) => {
+                if let Some(movetime) = movetime {
+                    ai.set_time_allowed_ms(movetime);
+                }
+            }
+            AnyAI::ANNSolo(_) | AnyAI::Random(_) | AnyAI::Greedy(_) => (),
+            AnyAI::AlphaZeutreeko(ai) => {
+                if let Some(movetime) = movetime {
+                    ai.set_time_allowed_ms(movetime);
+                }
+            }
+            AnyAI::Committee(ai) => ai.set_limits(movetime, depth),
+        }
+    }
+
+    // Budget for a whole game, shared between the moves by a time manager, MCTS engines only. None goes back to the time per move
+    pub fn set_game_time(&mut self, game_time: Option<f64>) {
+        let time_manager = game_time.map(TimeManager::new);
+        match self {
+            AnyAI::MCTS(ai) => ai.set_time_manager(time_manager),
+            AnyAI::AlphaZeutreeko(ai) => ai.set_time_manager(time_manager),
+            AnyAI::Committee(ai) => ai.set_game_time(game_time),
+            AnyAI::MinMax(_) | AnyAI::ANNSolo(_) | AnyAI::Random(_) | AnyAI::Greedy(_) => (),
+        }
+    }
+
+    // Exploration constant of the MCTS searches, returns whether the engine has one
+    pub fn set_exploration(&mut self, exploration: f32) -> bool {
+        match self {
+            AnyAI::MCTS(ai) => ai.config.exploration = exploration,
+            AnyAI::AlphaZeutreeko(ai) => ai.config.exploration = exploration,
+            AnyAI::Committee(ai) => return ai.set_exploration(exploration),
+            AnyAI::MinMax(_) | AnyAI::ANNSolo(_) | AnyAI::Random(_) | AnyAI::Greedy(_) => return false,
+        }
+        true
+    }
+
+    pub fn analyze(&mut self, board: &Board) -> Analysis {
+        analyze(self, board, false)
+    }
+
+    // Scoring statistics of the last search, MinMax only
+    pub fn minmax_stats(&self) -> Option<MinMaxStats> {
+        match self {
+            AnyAI::MinMax(ai) => Some(ai.stats()),
+            _ => None,
+        }
+    }
+
+    // Plays for the side to move, whatever color the engine was created with
+    pub fn search(&mut self, board: &Board) -> (SearchResult, Option<(usize, Direction)>) {
+        if let Some(color) = &board.next_player {
+            if self.color() != color {
+                self.set_color(color.clone());
+            }
+        }
+        let result = self.give_all_options(board, false);
+        let best_move = self.select_move(&result.moves, false);
+        (result, best_move)
+    }
+}
+
+impl<B: Backend, O: Platform> AI<O> for AnyAI<B, O> {
+    // Defaults to MinMax, use from_name to pick the engine
+    fn new(color: Color, difficulty: usize) -> Self {
+        AnyAI::MinMax(MinMax::new(color, difficulty))
+    }
+
+    fn color(&self) -> &Color {
+        dispatch!(self, ai => ai.color())
+    }
+
+    fn set_color(&mut self, color: Color) {
+        dispatch!(self, ai => ai.set_color(color))
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        dispatch!(self, ai => ai.give_all_options_observed(board, verbose, observer))
+    }
+
+    fn give_all_options_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        async move { dispatch!(self, ai => ai.give_all_options_async(board, verbose, observer).await) }
+    }
+
+    fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        dispatch!(self, ai => ai.set_node_limit(node_limit))
+    }
+
+    fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        dispatch!(self, ai => ai.set_memory_limit(memory_limit))
+    }
+
+    fn set_oracle(&mut self, oracle: Option<SharedOracle>) {
+        dispatch!(self, ai => ai.set_oracle(oracle))
+    }
+
+    fn set_stats_cache(&mut self, stats_cache: Option<SharedStatsCache>) {
+        dispatch!(self, ai => ai.set_stats_cache(stats_cache))
+    }
+
+    fn set_contempt(&mut self, contempt: f32) {
+        dispatch!(self, ai => ai.set_contempt(contempt))
+    }
+
+    fn set_game_history(&mut self, history: &[Board]) {
+        dispatch!(self, ai => ai.set_game_history(history))
+    }
+
+    fn ponder(&mut self, board: &Board, observer: &dyn SearchObserver) {
+        dispatch!(self, ai => ai.ponder(board, observer))
+    }
+
+    fn ponder_async(&mut self, board: &Board, observer: &dyn SearchObserver) -> impl Future<Output = ()> {
+        async move { dispatch!(self, ai => ai.ponder_async(board, observer).await) }
+    }
+
+    fn principal_variation(&self, board: &Board) -> Vec<(usize, Direction)> {
+        dispatch!(self, ai => ai.principal_variation(board))
+    }
+
+    fn search_tree(&self, max_depth: usize) -> Option<SearchTree> {
+        dispatch!(self, ai => ai.search_tree(max_depth))
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        dispatch!(self, ai => ai.telemetry())
+    }
+
+    fn reset_telemetry(&mut self) {
+        dispatch!(self, ai => ai.reset_telemetry())
+    }
+}
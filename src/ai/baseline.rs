@@ -0,0 +1,133 @@
+use std::marker::PhantomData;
+
+use crate::{
+    logic::{Board, Color, Move, ThreatKind},
+    platform::Platform,
+};
+use super::{AI, ScoredMove, SearchObserver, SearchResult, Telemetry};
+
+// Scores given by GreedyAI, normalized to sum to 1 like the other engines
+const WINNING_SCORE: f32 = 1.0;
+const FORCING_SCORE: f32 = 0.75;
+const QUIET_SCORE: f32 = 0.5;
+const LOSING_SCORE: f32 = 0.0;
+
+fn baseline_result<O: Platform>(moves: Vec<ScoredMove>, value: f32, start_time: f64) -> SearchResult {
+    let total: f32 = moves.iter().map(|x| x.score).sum();
+    let moves = if total > 0.0 {
+        moves.into_iter().map(|x| ScoredMove { score: x.score / total, ..x }).collect()
+    } else {
+        let uniform_score = 1.0 / moves.len().max(1) as f32;
+        moves.into_iter().map(|x| ScoredMove { score: uniform_score, ..x }).collect()
+    };
+    SearchResult {
+        value,
+        moves,
+        nodes: 1,
+        depth: 1,
+        pv: vec![],
+        time_ms: O::now() - start_time,
+        proven: None,
+    }
+}
+
+// Plays a legal move at random, the bottom of the strength scale
+#[derive(Clone)]
+pub struct RandomAI<O: Platform> {
+    color: Color,
+    telemetry: Telemetry,
+    _platform: PhantomData<O>,
+}
+
+impl<O: Platform> AI<O> for RandomAI<O> {
+    fn new(color: Color, _difficulty: usize) -> Self {
+        Self { color, telemetry: Telemetry::default(), _platform: PhantomData }
+    }
+
+    fn color(&self) -> &Color {
+        &self.color
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, _verbose: bool, _observer: Option<&dyn SearchObserver>) -> SearchResult {
+        let start_time = O::now();
+        // equal scores make the argmax selector pick uniformly
+        let moves = board.legal_moves().map(|game_move| ScoredMove::new(1.0, game_move.pawn_index, game_move.direction)).collect();
+        let result = baseline_result::<O>(moves, 0.0, start_time);
+        self.telemetry.record_search(&result);
+        result
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.telemetry
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.telemetry = Telemetry::default();
+    }
+}
+
+// One ply of lookahead: wins when it can, then sets up a forced win, avoids moves leaving a win to the opponent,
+// otherwise plays at random
+#[derive(Clone)]
+pub struct GreedyAI<O: Platform> {
+    color: Color,
+    telemetry: Telemetry,
+    _platform: PhantomData<O>,
+}
+
+impl<O: Platform> AI<O> for GreedyAI<O> {
+    fn new(color: Color, _difficulty: usize) -> Self {
+        Self { color, telemetry: Telemetry::default(), _platform: PhantomData }
+    }
+
+    fn color(&self) -> &Color {
+        &self.color
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, _verbose: bool, _observer: Option<&dyn SearchObserver>) -> SearchResult {
+        let start_time = O::now();
+        let opponent = self.color.other_color();
+        let forcing_moves: Vec<Move> = board.threats(&self.color).into_iter()
+            .filter(|threat| threat.kind == ThreatKind::ForcedWin)
+            .map(|threat| threat.game_move)
+            .collect();
+        let moves: Vec<ScoredMove> = board.legal_moves()
+            .map(|game_move| {
+                let child = board.child(&game_move);
+                let score = if child.winner().as_ref() == Some(&self.color) {
+                    WINNING_SCORE
+                } else if forcing_moves.contains(&game_move) {
+                    FORCING_SCORE
+                } else if !child.winning_moves(&opponent).is_empty() {
+                    LOSING_SCORE
+                } else {
+                    QUIET_SCORE
+                };
+                ScoredMove::new(score, game_move.pawn_index, game_move.direction)
+            })
+            .collect();
+        let best_score = moves.iter().map(|x| x.score).fold(LOSING_SCORE, f32::max);
+        let value = if best_score >= FORCING_SCORE { 1.0 } else if best_score == LOSING_SCORE { -1.0 } else { 0.0 };
+        // only the best moves are kept, so the argmax picks among them at random
+        let moves = moves.into_iter().map(|x| ScoredMove { score: if x.score == best_score { 1.0 } else { 0.0 }, ..x }).collect();
+        let result = baseline_result::<O>(moves, value, start_time);
+        self.telemetry.record_search(&result);
+        result
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.telemetry
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.telemetry = Telemetry::default();
+    }
+}
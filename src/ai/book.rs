@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::{
+    logic::{Board, Color, Direction},
+    platform::{LogLevel, Platform},
+};
+use super::{AI, ScoredMove, SearchObserver, SearchResult, Telemetry, oracle::SharedOracle, selector::MoveSelector, searchtree::SearchTree, statscache::SharedStatsCache};
+
+// Opening the ANN is trained on: the moves recommended in each position, the first one being played to reach the next position
+const TRAINING_LINE: [&[(f32, usize, Direction)]; 16] = [
+    &[(0.5, 0, Direction::Right), (0.5, 1, Direction::Left)],
+    // 1st move b1-c1
+    &[(1.0, 3, Direction::Down)],
+    // 2nd move c2-c3
+    &[(1.0, 0, Direction::DownLeft)],
+    // 3rd move c1-a3
+    &[(1.0, 4, Direction::Right)],
+    // 4th move b5-c5
+    &[(1.0, 1, Direction::Down)],
+    // 5th move d1-d4
+    &[(1.0, 4, Direction::UpLeft)],
+    // 6th move c5-b4
+    &[(1.0, 1, Direction::DownLeft)],
+    // 7th move d4-c5
+    &[(1.0, 5, Direction::Up)],
+    // 8th move d5-d1
+    &[(1.0, 0, Direction::Down)],
+    // 9th move a3-a5
+    &[(1.0, 4, Direction::Down)],
+    // 10th move b4-b5
+    &[(1.0, 1, Direction::UpLeft)],
+    // 11th move c5-a3
+    &[(1.0, 3, Direction::DownLeft)],
+    // 12th move c3-b4
+    &[(1.0, 2, Direction::UpLeft)],
+    // 13th move c4-a2
+    &[(1.0, 5, Direction::DownLeft)],
+    // 14th move d1-a4
+    &[(1.0, 2, Direction::DownRight)],
+    // 15th move a2-d5
+    &[(0.2, 5, Direction::UpRight), (0.2, 3, Direction::Up), (0.2, 3, Direction::Right), (0.2, 3, Direction::DownRight), (0.2, 4, Direction::Right)],
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook {
+    positions: HashMap<u64, (Board, Vec<ScoredMove>)>, // keyed by board hash, the board is kept to rule out collisions
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Book built from the opening line used in training
+    pub fn default_book() -> Self {
+        let mut book = Self::new();
+        for (board, moves) in Self::training_line() {
+            book.add(board, moves);
+        }
+        book
+    }
+
+    pub fn training_line() -> Vec<(Board, Vec<ScoredMove>)> {
+        let mut board = Board::default_new();
+        let mut line = vec![];
+        for moves in TRAINING_LINE.iter() {
+            let moves: Vec<ScoredMove> = moves.iter().cloned().map(ScoredMove::from).collect();
+            let next_move = moves[0].clone();
+            line.push((board.clone(), moves));
+            board.move_pawn_until_blocked(next_move.pawn_index, &next_move.direction);
+        }
+        line
+    }
+
+    // Scores are weights, they do not need to sum to 1
+    pub fn add(&mut self, board: Board, moves: Vec<ScoredMove>) {
+        self.positions.insert(board.get_hash(), (board, moves));
+    }
+
+    pub fn lookup(&self, board: &Board) -> Option<&[ScoredMove]> {
+        match self.positions.get(&board.get_hash()) {
+            Some((stored_board, moves)) if stored_board == board => Some(moves.as_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+// Wraps an engine so it plays from the book, when enabled, instead of searching known positions
+#[derive(Clone)]
+pub struct WithBook<A: AI<O>, O: Platform> {
+    inner: A,
+    book: Arc<OpeningBook>,
+    pub enabled: bool,
+    pub randomization: f32, // temperature used to pick among book moves, 0 always plays the main line
+    last_search_from_book: bool,
+    _platform: PhantomData<O>,
+}
+
+impl<A: AI<O>, O: Platform> WithBook<A, O> {
+    pub fn wrap(inner: A, book: Arc<OpeningBook>) -> Self {
+        Self {
+            inner,
+            book,
+            enabled: true,
+            randomization: 0.0,
+            last_search_from_book: false,
+            _platform: PhantomData,
+        }
+    }
+
+    fn book_result(&mut self, board: &Board, verbose: bool) -> Option<SearchResult> {
+        self.last_search_from_book = false;
+        if !self.enabled {
+            return None;
+        }
+        let moves = self.book.lookup(board)?;
+        if verbose {
+            O::log(LogLevel::Info, "Position found in the opening book");
+        }
+        self.last_search_from_book = true;
+        let total: f32 = moves.iter().map(|x| x.score).sum();
+        Some(SearchResult {
+            value: 0.0,
+            moves: moves.iter().map(|x| ScoredMove::new(x.score / total, x.pawn_index, x.direction.clone())).collect(),
+            ..SearchResult::default()
+        })
+    }
+}
+
+impl<A: AI<O>, O: Platform> AI<O> for WithBook<A, O> {
+    fn new(color: Color, difficulty: usize) -> Self {
+        Self::wrap(A::new(color, difficulty), Arc::new(OpeningBook::default_book()))
+    }
+
+    fn color(&self) -> &Color {
+        self.inner.color()
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.inner.set_color(color);
+    }
+
+    fn move_selector(&self) -> MoveSelector {
+        if self.last_search_from_book {
+            MoveSelector::Temperature(self.randomization)
+        } else {
+            self.inner.move_selector()
+        }
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        match self.book_result(board, verbose) {
+            Some(result) => result,
+            None => self.inner.give_all_options_observed(board, verbose, observer),
+        }
+    }
+
+    fn give_all_options_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        async move {
+            match self.book_result(board, verbose) {
+                Some(result) => result,
+                None => self.inner.give_all_options_async(board, verbose, observer).await,
+            }
+        }
+    }
+
+    fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        self.inner.set_node_limit(node_limit);
+    }
+
+    fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.inner.set_memory_limit(memory_limit);
+    }
+
+    fn set_oracle(&mut self, oracle: Option<SharedOracle>) {
+        self.inner.set_oracle(oracle);
+    }
+
+    fn set_stats_cache(&mut self, stats_cache: Option<SharedStatsCache>) {
+        self.inner.set_stats_cache(stats_cache);
+    }
+
+    fn set_contempt(&mut self, contempt: f32) {
+        self.inner.set_contempt(contempt);
+    }
+
+    fn set_game_history(&mut self, history: &[Board]) {
+        self.inner.set_game_history(history);
+    }
+
+    fn ponder(&mut self, board: &Board, observer: &dyn SearchObserver) {
+        self.inner.ponder(board, observer);
+    }
+
+    fn ponder_async(&mut self, board: &Board, observer: &dyn SearchObserver) -> impl Future<Output = ()> {
+        self.inner.ponder_async(board, observer)
+    }
+
+    fn principal_variation(&self, board: &Board) -> Vec<(usize, Direction)> {
+        self.inner.principal_variation(board)
+    }
+
+    fn search_tree(&self, max_depth: usize) -> Option<SearchTree> {
+        self.inner.search_tree(max_depth)
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.inner.telemetry()
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.inner.reset_telemetry();
+    }
+}
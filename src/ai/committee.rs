@@ -0,0 +1,241 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::{
+    logic::{Board, Color, Direction},
+    platform::Platform,
+};
+use super::{AI, ScoredMove, SearchObserver, SearchResult, Telemetry, any::AnyAI, ann::weights::DEFAULT_MODEL_ID, oracle::SharedOracle, searchtree::SearchTree, statscache::SharedStatsCache};
+
+use burn::tensor::backend::Backend;
+
+// MinMax of the default committee searches this many plies more than the difficulty, up to the cap
+const COMMITTEE_EXTRA_DEPTH: usize = 2;
+const COMMITTEE_MAX_MINMAX_DEPTH: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommitteeMode {
+    // Sums the move distributions of the members, each scaled by its weight
+    WeightedVote,
+    // Weighted vote among the moves no MinMax member finds losing, MinMax gives losing moves a score of 0
+    Veto,
+}
+
+#[derive(Clone)]
+pub struct CommitteeMember<B: Backend, O: Platform> {
+    pub engine: AnyAI<B, O>,
+    pub weight: f32,
+}
+
+// Asks every member for its move distribution and plays the combination
+#[derive(Clone)]
+pub struct CommitteeAI<B: Backend, O: Platform> {
+    color: Color,
+    pub members: Vec<CommitteeMember<B, O>>,
+    pub mode: CommitteeMode,
+}
+
+impl<B: Backend, O: Platform> CommitteeAI<B, O> {
+    pub fn with_members(color: Color, members: Vec<CommitteeMember<B, O>>, mode: CommitteeMode) -> Self {
+        let mut committee = Self { color: color.clone(), members, mode };
+        committee.set_color(color);
+        committee
+    }
+
+    // A deeper MinMax keeping AlphaZeutreeko out of the traps it does not see
+    pub fn with_model(color: Color, difficulty: usize, model_id: &str) -> Self {
+        let minmax_depth = (difficulty + COMMITTEE_EXTRA_DEPTH).min(COMMITTEE_MAX_MINMAX_DEPTH);
+        let members = vec![
+            CommitteeMember { engine: AnyAI::from_name("minmax", color.clone(), minmax_depth).unwrap(), weight: 1.0 },
+            CommitteeMember { engine: AnyAI::from_name_with_model("alphazeutreeko", color.clone(), difficulty, model_id).unwrap(), weight: 1.0 },
+        ];
+        Self::with_members(color, members, CommitteeMode::Veto)
+    }
+
+    pub fn set_limits(&mut self, movetime: Option<f64>, depth: Option<usize>) {
+        for member in self.members.iter_mut() {
+            member.engine.set_limits(movetime, depth);
+        }
+    }
+
+    // Every member gets the whole budget, they all search each move
+    pub fn set_game_time(&mut self, game_time: Option<f64>) {
+        for member in self.members.iter_mut() {
+            member.engine.set_game_time(game_time);
+        }
+    }
+
+    // Whether a member has an exploration constant to set
+    pub fn set_exploration(&mut self, exploration: f32) -> bool {
+        self.members.iter_mut().fold(false, |used, member| member.engine.set_exploration(exploration) || used)
+    }
+
+    fn prepare_members(&mut self) {
+        let color = self.color.clone();
+        for member in self.members.iter_mut().filter(|member| *member.engine.color() != color) {
+            member.engine.set_color(color.clone());
+        }
+    }
+
+    // Moves vetoed by a MinMax member, unless every move is, when the position is lost anyway
+    fn vetoed(&self, results: &[SearchResult], moves: &[(usize, Direction)]) -> Vec<bool> {
+        let vetoed: Vec<bool> = moves.iter()
+            .map(|(pawn_index, direction)| {
+                self.members.iter().zip(results).any(|(member, result)| {
+                    matches!(member.engine, AnyAI::MinMax(_)) && result.moves.iter()
+                        .any(|scored| scored.pawn_index == *pawn_index && scored.direction == *direction && scored.score <= 0.0)
+                })
+            })
+            .collect();
+        if vetoed.iter().all(|vetoed| *vetoed) {
+            return vec![false; moves.len()];
+        }
+        vetoed
+    }
+
+    fn combine(&self, results: Vec<SearchResult>) -> SearchResult {
+        let mut moves: Vec<(usize, Direction)> = vec![];
+        for scored in results.iter().flat_map(|result| result.moves.iter()) {
+            if !moves.iter().any(|(pawn_index, direction)| *pawn_index == scored.pawn_index && *direction == scored.direction) {
+                moves.push((scored.pawn_index, scored.direction.clone()));
+            }
+        }
+        let vetoed = match self.mode {
+            CommitteeMode::WeightedVote => vec![false; moves.len()],
+            CommitteeMode::Veto => self.vetoed(&results, &moves),
+        };
+        let total_weight: f32 = self.members.iter().map(|member| member.weight).sum::<f32>().max(f32::MIN_POSITIVE);
+        let mut scored_moves: Vec<ScoredMove> = moves.into_iter().zip(vetoed)
+            .map(|((pawn_index, direction), vetoed)| {
+                let score = if vetoed {
+                    0.0
+                } else {
+                    self.members.iter().zip(results.iter())
+                        .filter_map(|(member, result)| {
+                            result.moves.iter()
+                                .find(|scored| scored.pawn_index == pawn_index && scored.direction == direction)
+                                .map(|scored| member.weight * scored.score)
+                        })
+                        .sum::<f32>() / total_weight
+                };
+                ScoredMove::new(score, pawn_index, direction)
+            })
+            .collect();
+        let score_total: f32 = scored_moves.iter().map(|scored| scored.score).sum();
+        if score_total > 0.0 {
+            scored_moves.iter_mut().for_each(|scored| scored.score /= score_total);
+        }
+        let best_move = scored_moves.iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .map(|scored| (scored.pawn_index, scored.direction.clone()));
+        SearchResult {
+            value: self.members.iter().zip(results.iter()).map(|(member, result)| member.weight * result.value).sum::<f32>() / total_weight,
+            moves: scored_moves,
+            nodes: results.iter().map(|result| result.nodes).sum(),
+            depth: results.iter().map(|result| result.depth).max().unwrap_or_default(),
+            // line of the first member agreeing with the committee
+            pv: results.iter().find(|result| result.pv.first() == best_move.as_ref()).map(|result| result.pv.clone()).unwrap_or_default(),
+            time_ms: results.iter().map(|result| result.time_ms).sum(),
+            proven: results.iter().find_map(|result| result.proven.clone()),
+        }
+    }
+}
+
+impl<B: Backend, O: Platform> AI<O> for CommitteeAI<B, O> {
+    fn new(color: Color, difficulty: usize) -> Self {
+        Self::with_model(color, difficulty, DEFAULT_MODEL_ID)
+    }
+
+    fn color(&self) -> &Color {
+        &self.color
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+        self.prepare_members();
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        self.prepare_members();
+        let results = self.members.iter_mut()
+            .map(|member| member.engine.give_all_options_observed(board, verbose, observer))
+            .collect();
+        self.combine(results)
+    }
+
+    fn give_all_options_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        async move {
+            self.prepare_members();
+            let mut results = vec![];
+            for member in self.members.iter_mut() {
+                // boxed, the members are AnyAI which may themselves hold a committee
+                let search: Pin<Box<dyn Future<Output = SearchResult> + '_>> = Box::pin(member.engine.give_all_options_async(board, verbose, observer));
+                results.push(search.await);
+            }
+            self.combine(results)
+        }
+    }
+
+    fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        for member in self.members.iter_mut() {
+            member.engine.set_node_limit(node_limit);
+        }
+    }
+
+    // Members keep their trees between searches, they share the limit
+    fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        let member_limit = memory_limit.map(|limit| limit / self.members.len().max(1));
+        for member in self.members.iter_mut() {
+            member.engine.set_memory_limit(member_limit);
+        }
+    }
+
+    fn set_oracle(&mut self, oracle: Option<SharedOracle>) {
+        for member in self.members.iter_mut() {
+            member.engine.set_oracle(oracle.clone());
+        }
+    }
+
+    fn set_stats_cache(&mut self, stats_cache: Option<SharedStatsCache>) {
+        for member in self.members.iter_mut() {
+            member.engine.set_stats_cache(stats_cache.clone());
+        }
+    }
+
+    fn set_contempt(&mut self, contempt: f32) {
+        for member in self.members.iter_mut() {
+            member.engine.set_contempt(contempt);
+        }
+    }
+
+    fn set_game_history(&mut self, history: &[Board]) {
+        for member in self.members.iter_mut() {
+            member.engine.set_game_history(history);
+        }
+    }
+
+    fn principal_variation(&self, board: &Board) -> Vec<(usize, Direction)> {
+        self.members.iter()
+            .map(|member| member.engine.principal_variation(board))
+            .find(|principal_variation| !principal_variation.is_empty())
+            .unwrap_or_default()
+    }
+
+    fn search_tree(&self, max_depth: usize) -> Option<SearchTree> {
+        self.members.iter().find_map(|member| member.engine.search_tree(max_depth))
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        let mut telemetry = Telemetry::default();
+        for member in self.members.iter() {
+            telemetry.add(&member.engine.telemetry());
+        }
+        telemetry
+    }
+
+    fn reset_telemetry(&mut self) {
+        for member in self.members.iter_mut() {
+            member.engine.reset_telemetry();
+        }
+    }
+}
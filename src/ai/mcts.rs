@@ -1,10 +1,14 @@
 use std::vec;
+use std::cell::Cell;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::future::Future;
 use std::marker::PhantomData;
 
 use crate::{
-    ai::alphazeutreeko::AlphaZeutreeko, logic::{Board, Color, Direction}, platform::Platform
+    ai::alphazeutreeko::AlphaZeutreeko, logic::{Board, Color, Direction, Move, Symmetry}, platform::{LogLevel, Platform, YIELD_INTERVAL_MS},
+    profiling::{instrument, span},
 };
-use super::{AI, alphazeutreeko::ANNPolicy};
+use super::{AI, BOARD_HEAP_BYTES, GRAPH_EDGE_LINK_BYTES, GRAPH_NODE_LINK_BYTES, ScoredMove, SearchObserver, SearchProgress, SearchResult, Telemetry, alphazeutreeko::ANNPolicy, oracle::{ProvenResult, SharedOracle, proven_search_result}, searchtree::SearchTree, value::Value, timemanager::{SearchStability, TimeManager}, statscache::{MAX_WARM_START_VISITS, PositionStats, SharedStatsCache}};
 
 use petgraph::Graph;
 use petgraph::visit::EdgeRef;
@@ -12,27 +16,92 @@ use petgraph::prelude::NodeIndex;
 use burn::tensor::backend::Backend;
 
 
+// Pondering stops by itself after this many times the normal search time, to bound the tree size
+const MAX_PONDER_FACTOR: f64 = 4.0;
+
+// Rollouts of positions nobody can win (e.g. set up with missing pawns) would never end
+const MAX_ROLLOUT_PLIES: usize = 500;
+
+// Share of the tree capacity kept when a full tree is pruned, the rest is left for the next iterations
+const PRUNE_KEEP_FRACTION: f32 = 0.5;
+
+// Iterations between two samples of the root for the time manager
+const STABILITY_SAMPLE_ITERATIONS: usize = 25;
+
+// Exploration term of best_child
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionFormula {
+    // exploration * prior * sqrt(ln(parent visits) / visits), priors as given
+    UCT,
+    // exploration * prior * sqrt(parent visits) / (1 + visits), priors normalized over the children
+    PUCT,
+}
+
+// Tuning of the child selection in best_child, the default is the historical UCT
+#[derive(Clone, Debug, PartialEq)]
+pub struct MCTSConfig {
+    pub formula: SelectionFormula,
+    pub exploration: f32,
+    // Value given to unvisited children, from the parent point of view. None visits them first with UCT,
+    // whatever their prior, and counts them as draws with PUCT
+    pub first_play_urgency: Option<f32>,
+    // Above 1 flattens the priors, below 1 sharpens them
+    pub prior_temperature: f32,
+    // Weight of prior / (visits + 1), a bonus for likely moves fading as they get visited
+    pub progressive_bias: f32,
+    // Value of a draw for the engine is -contempt, for its opponent contempt. Set through AI::set_contempt,
+    // which starts a new tree
+    pub contempt: f32,
+}
+
+impl Default for MCTSConfig {
+    fn default() -> Self {
+        Self {
+            formula: SelectionFormula::UCT,
+            exploration: 1.414,
+            first_play_urgency: None,
+            prior_temperature: 1.0,
+            progressive_bias: 0.0,
+            contempt: 0.0,
+        }
+    }
+}
+
+// Prior and move in the frame of the parent board, then the symmetry mapping the board reached by the move to the child board
+type MCTSEdge = (f32, usize, Direction, Symmetry);
+
+// Untried moves a node holds on average: a new node keeps all its moves, about 14, and most nodes of a tree are recent leaves
+const UNTRIED_ACTIONS_PER_NODE: usize = 12;
+// Approximate bytes of a node of the tree: the node with its board, its untried moves with their boards, and the edge from its parent
+pub const NODE_BYTES: usize = std::mem::size_of::<MCTSNode>() + BOARD_HEAP_BYTES + GRAPH_NODE_LINK_BYTES
+    + UNTRIED_ACTIONS_PER_NODE * (std::mem::size_of::<(f32, usize, Direction, Board)>() + BOARD_HEAP_BYTES)
+    + std::mem::size_of::<MCTSEdge>() + GRAPH_EDGE_LINK_BYTES;
+
 #[derive(Clone)]
 struct MCTSNode {
-    board_hash: u64,
     board: Board,
+    symmetry: Symmetry, // maps board to its canonical form
+    hash: u64, // of the canonical form, the key of the node in transpositions
     color_next_player: Color, // the color of the next player (if no next player, not the color of the previous player)
     visits: usize,
     wins: f32,
     untried_actions: Vec<(f32, usize, Direction, Board)>,
-    board_eval: f32,
+    board_eval: Value,
+    proven: Option<ProvenResult>, // exact result given by the oracle, the node is not expanded
 }
 
 impl MCTSNode {
-    fn new(board: Board, color_next_player: Color, untried_actions: Vec<(f32, usize, Direction, Board)>, board_eval: f32) -> Self {
+    fn new(board: Board, symmetry: Symmetry, hash: u64, color_next_player: Color, untried_actions: Vec<(f32, usize, Direction, Board)>, board_eval: Value) -> Self {
         Self {
-            board_hash: board.get_hash(),
             board,
+            symmetry,
+            hash,
             color_next_player,
             visits: 0,
             wins: 0.0,
             untried_actions,
             board_eval,
+            proven: None,
         }
     }
 
@@ -40,6 +109,11 @@ impl MCTSNode {
         self.board.winner().is_some()
     }
 
+    // Terminal or proven: rollouts stop here
+    fn is_settled(&self) -> bool {
+        self.is_terminal() || self.proven.is_some()
+    }
+
     fn is_fully_expanded(&self) -> bool {
         self.untried_actions.len() == 0
     }
@@ -47,65 +121,458 @@ impl MCTSNode {
 
 pub trait Policy: Clone {
     const IS_TRIVIAL: bool;
-    fn predict(&self, board:&Board) -> (f32, Vec<(f32, usize, Direction, Board)>) {
-        (0.0, board.get_all_valid_directions_and_resulting_boards().into_iter().map(|(p, dir, b)| (0.0, p, dir, b)).collect())
+    fn predict(&self, board:&Board) -> (Value, Vec<(f32, usize, Direction, Board)>) {
+        (Value::DRAW, board.get_all_valid_directions_and_resulting_boards().into_iter().map(|(p, dir, b)| (0.0, p, dir, b)).collect())
     }
     fn new() -> Self;
+    // Network weights held, counted in the memory of the searches
+    fn memory_bytes(&self) -> usize {
+        0
+    }
 }
 
 #[derive(Clone)]
 pub struct MCTSGeneric<P: Policy, O: Platform> {
     color: Color,
     time_allowed_ms: f64,
-    graph: Graph<MCTSNode, (f32, usize, Direction)>,
+    time_manager: Option<TimeManager>, // replaces time_allowed_ms by a share of a per-game budget
+    node_limit: Option<usize>,
+    max_tree_nodes: Option<usize>, // the tree is pruned when it reaches this size or the memory limit
+    memory_limit: Option<usize>,
+    oracle: Option<SharedOracle>,
+    stats_cache: Option<SharedStatsCache>,
+    graph: Graph<MCTSNode, MCTSEdge>,
+    // symmetric positions share one node, found by their canonical hash
+    transpositions: HashMap<u64, NodeIndex>,
+    // canonical hashes of the positions of the game before the searched one, like the line a symmetric one repeats them
+    history: HashSet<u64>,
+    // searched board, its node and the symmetry mapping it to the node board, for export_tree
+    last_search: Option<(Board, NodeIndex, Symmetry)>,
     pub policy: P,
+    pub config: MCTSConfig,
+    telemetry: Cell<Telemetry>, // a Cell as lookups only borrow the engine
+    pondered: bool, // set by ponder, the next search tells whether it found its position in the tree
     platform: PhantomData<O>,
 }
 
 impl<P: Policy, O: Platform> MCTSGeneric<P, O> {
     pub fn clear_graph(&mut self) {
         self.graph.clear();
+        self.transpositions.clear();
+        self.last_search = None;
+    }
+
+    // Tree of the last search, down to max_depth plies, moves never visited are left out
+    pub fn export_tree(&self, max_depth: usize) -> Option<SearchTree> {
+        let (board, origin, to_origin) = self.last_search.as_ref()?;
+        let node = self.graph.node_weight(*origin)?;
+        Some(SearchTree {
+            notation: None,
+            visits: node.visits,
+            q: if node.visits > 0 { node.wins / node.visits as f32 } else { 0.0 },
+            prior: 0.0,
+            proven: node.proven.clone(),
+            children: self.export_children(*origin, *to_origin, board, max_depth),
+        })
+    }
+
+    // board is the position of the line, to_node maps it to the node board as in principal_variation_from
+    fn export_children(&self, node_index: NodeIndex, to_node: Symmetry, board: &Board, depth_remaining: usize) -> Vec<SearchTree> {
+        if depth_remaining == 0 {
+            return vec![];
+        }
+        let mut children: Vec<SearchTree> = self.graph.edges(node_index)
+            .filter(|edge| self.graph.node_weight(edge.target()).unwrap().visits > 0)
+            .map(|edge| {
+                let (prior, pawn_index, direction, symmetry) = edge.weight();
+                let game_move = Move::new(*pawn_index, to_node.inverse().apply_direction(direction));
+                let child = self.graph.node_weight(edge.target()).unwrap();
+                SearchTree {
+                    notation: board.move_to_notation(&game_move),
+                    visits: child.visits,
+                    // child statistics are for its player to move, the opponent of the player making the move
+                    q: -child.wins / child.visits as f32,
+                    prior: *prior,
+                    proven: child.proven.clone(),
+                    children: self.export_children(edge.target(), to_node.then(symmetry), &board.child(&game_move), depth_remaining - 1),
+                }
+            })
+            .collect();
+        children.sort_by(|a, b| b.visits.cmp(&a.visits));
+        children
+    }
+
+    pub fn set_time_allowed_ms(&mut self, time_allowed_ms: f64) {
+        self.time_allowed_ms = time_allowed_ms;
+    }
+
+    // None goes back to the fixed time allowed per move
+    pub fn set_time_manager(&mut self, time_manager: Option<TimeManager>) {
+        self.time_manager = time_manager;
+    }
+
+    pub fn time_manager(&self) -> Option<&TimeManager> {
+        self.time_manager.as_ref()
+    }
+
+    pub fn set_max_tree_nodes(&mut self, max_tree_nodes: Option<usize>) {
+        self.max_tree_nodes = max_tree_nodes;
+    }
+
+    pub fn tree_size(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn count(&self, update: impl FnOnce(&mut Telemetry)) {
+        let mut telemetry = self.telemetry.get();
+        update(&mut telemetry);
+        self.telemetry.set(telemetry);
+    }
+
+    fn tree_capacity(&self) -> usize {
+        self.max_tree_nodes.unwrap_or(usize::MAX).min(self.memory_node_capacity())
+    }
+
+    // Nodes fitting in the memory limit of the engine and the memory cap of the platform, once the network weights are held
+    fn memory_node_capacity(&self) -> usize {
+        self.memory_limit.unwrap_or(usize::MAX).min(O::max_search_memory()).saturating_sub(self.policy.memory_bytes()) / NODE_BYTES
+    }
+
+    fn record_memory(&self, capped: bool) {
+        let bytes = self.graph.node_count() * NODE_BYTES + self.policy.memory_bytes();
+        self.count(|telemetry| telemetry.record_memory(bytes, capped));
+    }
+
+    // Prunes the tree once it is full, returns the index of origin in the pruned tree
+    fn make_room(&mut self, origin: NodeIndex) -> NodeIndex {
+        if self.graph.node_count() < self.tree_capacity() {
+            return origin;
+        }
+        // the tree is at its largest
+        self.record_memory(self.graph.node_count() >= self.memory_node_capacity());
+        self.prune(origin)
+    }
+
+    // Rebuilds the tree with the most visited nodes below origin, taken best first so they stay connected to it.
+    // Moves whose child is dropped go back to the untried actions of their parent, to be expanded again
+    fn prune(&mut self, origin: NodeIndex) -> NodeIndex {
+        let keep = ((self.tree_capacity() as f32 * PRUNE_KEEP_FRACTION) as usize).max(1);
+        let mut new_indices: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut kept = vec![];
+        let mut frontier = BinaryHeap::from([(self.graph.node_weight(origin).unwrap().visits, origin)]);
+        while let Some((_, node_index)) = frontier.pop() {
+            if kept.len() >= keep {
+                break;
+            }
+            if new_indices.contains_key(&node_index) {
+                continue;
+            }
+            new_indices.insert(node_index, NodeIndex::new(kept.len()));
+            kept.push(node_index);
+            for edge in self.graph.edges(node_index) {
+                if !new_indices.contains_key(&edge.target()) {
+                    frontier.push((self.graph.node_weight(edge.target()).unwrap().visits, edge.target()));
+                }
+            }
+        }
+        let mut graph = Graph::with_capacity(kept.len(), kept.len());
+        for node_index in kept.iter() {
+            graph.add_node(self.graph.node_weight(*node_index).unwrap().clone());
+        }
+        for node_index in kept.iter() {
+            let parent = new_indices[node_index];
+            // added in reverse so the children are listed in the same order as before
+            let edges: Vec<(NodeIndex, MCTSEdge)> = self.graph.edges(*node_index).map(|edge| (edge.target(), edge.weight().clone())).collect();
+            for (target, edge) in edges.into_iter().rev() {
+                match new_indices.get(&target) {
+                    Some(child) => {
+                        graph.add_edge(parent, *child, edge);
+                    }
+                    None => {
+                        let node = graph.node_weight_mut(parent).unwrap();
+                        let board = node.board.child(&Move::new(edge.1, edge.2.clone()));
+                        node.untried_actions.push((edge.0, edge.1, edge.2, board));
+                    }
+                }
+            }
+        }
+        self.graph = graph;
+        self.transpositions = self.graph.node_indices()
+            .map(|node_index| (self.graph.node_weight(node_index).unwrap().board.canonical_hash().0, node_index))
+            .collect();
+        self.last_search = self.last_search.take().and_then(|(board, node_index, to_node)| {
+            new_indices.get(&node_index).map(|new_index| (board, *new_index, to_node))
+        });
+        new_indices[&origin]
+    }
+
+    // Node of this board or of a symmetric one, with the symmetry mapping the board to the node board
+    fn find_node(&self, board: &Board) -> Option<(NodeIndex, Symmetry)> {
+        let (board_hash, symmetry) = board.canonical_hash();
+        let node_index = *self.transpositions.get(&board_hash)?;
+        let node = self.graph.node_weight(node_index).unwrap();
+        let to_node = symmetry.then(&node.symmetry.inverse());
+        (to_node.apply_board(board) == node.board).then_some((node_index, to_node))
+    }
+
+    // find_node for the lookups of the search, counted in the telemetry
+    fn lookup_node(&self, board: &Board) -> Option<(NodeIndex, Symmetry)> {
+        let found = self.find_node(board);
+        self.count(|telemetry| {
+            telemetry.transposition_lookups += 1;
+            telemetry.transposition_hits += found.is_some() as usize;
+        });
+        found
+    }
+
+    // Policy evaluation, network policies evaluate one position per batch
+    fn predict(&self, board: &Board) -> (Value, Vec<(f32, usize, Direction, Board)>) {
+        if !P::IS_TRIVIAL {
+            self.count(|telemetry| {
+                telemetry.network_batches += 1;
+                telemetry.network_positions += 1;
+            });
+        }
+        self.policy.predict(board)
+    }
+
+    fn add_node(&mut self, board: Board, color_next_player: Color, prediction: (Value, Vec<(f32, usize, Direction, Board)>)) -> NodeIndex {
+        let (board_hash, symmetry) = board.canonical_hash();
+        let mut node = MCTSNode::new(board, symmetry, board_hash, color_next_player, prediction.1, prediction.0);
+        node.proven = self.oracle.as_ref().and_then(|oracle| oracle.probe(&node.board));
+        if let Some(stats) = self.cached_stats(board_hash) {
+            node.visits = stats.visits.min(MAX_WARM_START_VISITS);
+            node.wins = stats.wins * node.visits as f32 / stats.visits as f32;
+        }
+        let node_index = self.graph.add_node(node);
+        self.transpositions.insert(board_hash, node_index);
+        node_index
+    }
+
+    fn cached_stats(&self, board_hash: u64) -> Option<PositionStats> {
+        let stats_cache = self.stats_cache.as_ref()?;
+        self.count(|telemetry| telemetry.cache_lookups += 1);
+        let stats = stats_cache.lock().unwrap().get(board_hash).cloned();
+        if stats.is_some() {
+            self.count(|telemetry| telemetry.cache_hits += 1);
+        }
+        stats
+    }
+
+    // Writes the statistics of the tree to the cache, searches from the cache carry its visits so they replace it
+    fn update_stats_cache(&self) {
+        let Some(stats_cache) = &self.stats_cache else {
+            return;
+        };
+        let mut stats_cache = stats_cache.lock().unwrap();
+        for edge in self.graph.edge_references() {
+            let parent = self.graph.node_weight(edge.source()).unwrap();
+            let child = self.graph.node_weight(edge.target()).unwrap();
+            if child.visits == 0 || parent.visits == 0 {
+                continue;
+            }
+            stats_cache.record(child.board.canonical_hash().0, PositionStats {
+                visits: child.visits,
+                wins: child.wins,
+                // shared and warm-started nodes may have more visits than this parent
+                prior: (child.visits as f32 / parent.visits as f32).min(1.0),
+            });
+        }
+    }
+
+    fn prepare_origin(&mut self, board: &Board, verbose: bool) -> (NodeIndex, Symmetry) {
+        // graph is no longer cleared by default, it is pruned once full, see make_room
+        let first_prediction = self.predict(board);
+        if verbose {
+            O::log(LogLevel::Trace, &format!("Policy gives board eval {}", first_prediction.0));
+            for element in first_prediction.1.iter() {
+                O::log(LogLevel::Trace, &format!("Policy gives eval {} to move {:?}", element.0, (element.1, element.2.clone())));
+            }
+        }
+        let found = self.lookup_node(board);
+        if std::mem::take(&mut self.pondered) && found.is_some_and(|(node_index, _)| self.graph.node_weight(node_index).unwrap().visits > 0) {
+            self.count(|telemetry| telemetry.ponder_hits += 1);
+        }
+        found.unwrap_or_else(|| {
+            // the values of the root are for the player to move, even if the engine was set to the other color
+            let color_next_player = board.next_player.clone().unwrap_or_else(|| self.color.clone());
+            (self.add_node(board.clone(), color_next_player, first_prediction), Symmetry::default())
+        })
+    }
+
+    fn ponder_origin(&mut self, board: &Board) -> Option<NodeIndex> {
+        let color_next_player = board.next_player.clone()?;
+        let node_index = match self.lookup_node(board) {
+            Some((node_index, _)) => node_index,
+            None => {
+                let prediction = self.predict(board);
+                self.add_node(board.clone(), color_next_player, prediction)
+            }
+        };
+        self.pondered = true;
+        self.count(|telemetry| telemetry.ponders += 1);
+        Some(node_index)
+    }
+
+    fn most_visited_child(&self, node_index: NodeIndex) -> Option<(NodeIndex, MCTSEdge)> {
+        self.graph.edges(node_index)
+            .filter(|edge| self.graph.node_weight(edge.target()).unwrap().visits > 0)
+            .max_by_key(|edge| self.graph.node_weight(edge.target()).unwrap().visits)
+            .map(|edge| (edge.target(), edge.weight().clone()))
+    }
+
+    fn move_time_ms(&self, board: &Board) -> f64 {
+        self.time_manager.as_ref().map_or(self.time_allowed_ms, |time_manager| time_manager.move_time_ms(board))
+    }
+
+    // Samples the root every STABILITY_SAMPLE_ITERATIONS, the extended time of the move once the search looks unstable
+    fn extended_time_ms(&self, origin: NodeIndex, iterations: usize, stability: &mut SearchStability, move_time_ms: f64) -> Option<f64> {
+        let time_manager = self.time_manager.as_ref()?;
+        if iterations % STABILITY_SAMPLE_ITERATIONS != 0 {
+            return None;
+        }
+        let origin_node = self.graph.node_weight(origin).unwrap();
+        let best_move = self.most_visited_child(origin).map(|(_, edge)| Move::new(edge.1, edge.2));
+        stability.record(best_move, origin_node.wins / origin_node.visits.max(1) as f32);
+        stability.is_unstable().then(|| time_manager.extended_time_ms(move_time_ms))
+    }
+
+    fn record_move_time(&mut self, start_time: f64) {
+        if let Some(time_manager) = self.time_manager.as_mut() {
+            time_manager.record_move(O::now() - start_time);
+        }
+    }
+
+    fn budget_spent(&self, start_time: f64, iterations: usize, allotted_ms: f64) -> f32 {
+        let time_spent = ((O::now() - start_time) / allotted_ms) as f32;
+        let iterations_spent = self.node_limit.map_or(0.0, |node_limit| iterations as f32 / node_limit as f32);
+        time_spent.max(iterations_spent).clamp(0.0, 1.0)
+    }
+
+    // Reports elapsed time and iterations, returns true if the observer asked to stop
+    fn report_progress(&self, observer: Option<&dyn SearchObserver>, origin: NodeIndex, to_origin: Symmetry, start_time: f64, iterations: usize, allotted_ms: f64) -> bool {
+        let Some(observer) = observer else {
+            return false;
+        };
+        observer.on_progress(&SearchProgress {
+            fraction: self.budget_spent(start_time, iterations, allotted_ms),
+            nodes: iterations,
+            best_move: self.most_visited_child(origin).map(|(_, edge)| (edge.1, to_origin.inverse().apply_direction(&edge.2))),
+        });
+        observer.is_cancelled()
     }
 
     fn iterate(&mut self, origin: NodeIndex) {
+        span!(TRACE, "mcts_iteration");
+        // shared nodes make a graph, the path is kept for backpropagation
+        let mut path = vec![origin];
         let mut node_index = origin;
         let mut node = self.graph.node_weight(node_index).unwrap();
-        while !node.is_terminal() && node.is_fully_expanded() {
-            node_index = self.best_child(node_index);
+        while !node.is_settled() && node.is_fully_expanded() {
+            let Some(child_index) = self.best_child(node_index) else {
+                // no legal move in a position set up by hand, scored as a draw
+                self.backpropagate(&path, self.draw_value_at(&path));
+                return;
+            };
+            node_index = child_index;
+            if path.contains(&node_index) {
+                // back to a position of the line, scored as a draw
+                self.backpropagate(&path, self.draw_value_at(&path));
+                return;
+            }
+            path.push(node_index);
+            if self.repeats_game(node_index) {
+                // back to a position of the game, the node is visited so its move is scored as a draw
+                self.backpropagate(&path, self.draw_value_at(&path));
+                return;
+            }
             node = self.graph.node_weight(node_index).unwrap();
         }
-        if !node.is_terminal() && !node.is_fully_expanded() {
-            node_index = self.expand(node_index);
+        if !node.is_settled() {
+            if let Some(child_index) = self.expand(node_index) {
+                if path.contains(&child_index) {
+                    self.backpropagate(&path, self.draw_value_at(&path));
+                    return;
+                }
+                node_index = child_index;
+                path.push(node_index);
+                if self.repeats_game(node_index) {
+                    self.backpropagate(&path, self.draw_value_at(&path));
+                    return;
+                }
+            }
         }
 
-        let winner = self.rollout(node_index);
-        self.backpropagate(node_index, winner);
+        let value = self.rollout(node_index);
+        self.backpropagate(&path, value);
     }
 
-    fn expand(&mut self, node_index: NodeIndex) -> NodeIndex {
-        let node = self.graph.node_weight_mut(node_index).unwrap();
-        let action = node.untried_actions.pop().unwrap();
+    // None when every move of the node already has its child
+    fn expand(&mut self, node_index: NodeIndex) -> Option<NodeIndex> {
+        let number_moves = self.graph.edges(node_index).count() + self.graph.node_weight(node_index)?.untried_actions.len();
+        let node = self.graph.node_weight_mut(node_index)?;
+        let mut action = node.untried_actions.pop()?;
         let child_color = node.color_next_player.other_color();
-        let prediction = self.policy.predict(&action.3);
-        let child = self.graph.add_node(MCTSNode::new(action.3, child_color, prediction.1, prediction.0));
-        self.graph.add_edge(node_index, child, (action.0, action.1, action.2));
-        child
+        if P::IS_TRIVIAL {
+            // the cached visit share replaces the uniform prior, 1 for a move visited as often as the others
+            if let Some(stats) = self.cached_stats(action.3.canonical_hash().0) {
+                action.0 = stats.prior * number_moves as f32;
+            }
+        }
+        let (child, symmetry) = match self.lookup_node(&action.3) {
+            Some(found) => found,
+            None => {
+                let prediction = self.predict(&action.3);
+                (self.add_node(action.3, child_color, prediction), Symmetry::default())
+            }
+        };
+        self.graph.add_edge(node_index, child, (action.0, action.1, action.2, symmetry));
+        Some(child)
     }
 
-    fn random_rollout(&self, node: &MCTSNode) -> f32 {
+    // For the player to move at the node, as every value of the tree
+    fn random_rollout(&self, node: &MCTSNode) -> Value {
         let mut current_board = node.board.clone();
-        while current_board.next_player.is_some() {
+        let mut plies = 0;
+        while current_board.next_player.is_some() && plies < MAX_ROLLOUT_PLIES {
+            plies += 1;
             let all_possible_moves = current_board.get_all_valid_directions_and_resulting_boards();
+            if all_possible_moves.is_empty() {
+                break;
+            }
             let random_move_index = O::random_int(all_possible_moves.len());
             current_board = all_possible_moves[random_move_index].2.clone();
         }
-        if current_board.winner().unwrap() == node.color_next_player {1.0} else {-1.0}
+        // a blocked player or a rollout too long ends the game without a winner
+        match current_board.winner() {
+            Some(winner) => Value::from_outcome(Some(&winner), &node.color_next_player),
+            None => self.draw_value(&node.color_next_player),
+        }
+    }
+
+    // A draw for the player to move, worth less than an unclear position to the engine when it has contempt for draws
+    fn draw_value(&self, player: &Color) -> Value {
+        let contempt = if *player == self.color { -self.config.contempt } else { self.config.contempt };
+        Value::new(contempt)
+    }
+
+    // Draw of the line at its last node, for its player to move
+    fn draw_value_at(&self, path: &[NodeIndex]) -> Value {
+        let node_index = *path.last().unwrap();
+        self.draw_value(&self.graph.node_weight(node_index).unwrap().color_next_player)
     }
 
-    fn rollout(&self, node_index: NodeIndex) -> f32 {
+    fn repeats_game(&self, node_index: NodeIndex) -> bool {
+        self.history.contains(&self.graph.node_weight(node_index).unwrap().hash)
+    }
+
+    fn rollout(&self, node_index: NodeIndex) -> Value {
         let node = self.graph.node_weight(node_index).unwrap();
         if node.board.winner().is_some() {
-            -1.0 // cannot win because opponent made a move, this is a loss
+            Value::LOSS // cannot win because opponent made a move, this is a loss
+        }
+        else if let Some(proven) = &node.proven {
+            Value::from_proven(proven)
         }
         else if P::IS_TRIVIAL {
             self.random_rollout(node)
@@ -115,59 +582,114 @@ impl<P: Policy, O: Platform> MCTSGeneric<P, O> {
         }
     }
 
-    fn backpropagate(&mut self, node_index:NodeIndex, winner:f32) {
-        let mut current_node_index = node_index;
-        let mut to_add = winner;
-        loop {
-            let current_node = self.graph.node_weight_mut(current_node_index).unwrap();
+    // wins of a node sum the values for its player to move, flipped at every ply up the path
+    fn backpropagate(&mut self, path: &[NodeIndex], value: Value) {
+        let mut to_add = value;
+        for node_index in path.iter().rev() {
+            let current_node = self.graph.node_weight_mut(*node_index).unwrap();
             current_node.visits += 1;
-            current_node.wins += to_add;
-            to_add = -to_add;
-            match self.graph.edges_directed(current_node_index, petgraph::Direction::Incoming).next() {
-                None => break,
-                Some(edge) => current_node_index = edge.source(),
-            }
+            current_node.wins += to_add.get();
+            to_add = to_add.for_opponent();
         }
     }
 
-    fn best_child(&mut self, node_index: NodeIndex) -> NodeIndex {
-        let mut best_score = f32::MIN;
-        let mut best_child = self.graph.edges_directed(node_index, petgraph::Direction::Outgoing).next().unwrap().target();
-        let parent_visits = self.graph.node_weight(node_index).unwrap().visits as f32;
+    // Moves of the trivial policy have no prior, they all count as 1
+    // Negative or NaN priors of a broken network count as 0
+    fn edge_prior(edge: &MCTSEdge) -> f32 {
+        if P::IS_TRIVIAL && edge.0 <= 0.0 {
+            1.0
+        } else if edge.0 >= 0.0 {
+            edge.0
+        } else {
+            0.0
+        }
+    }
+
+    // None when the node has no child
+    fn best_child(&mut self, node_index: NodeIndex) -> Option<NodeIndex> {
+        // NaN scores are never picked, and a node whose scores are all NaN or -inf still goes down its first child
+        let mut best_score = f32::NEG_INFINITY;
+        let mut best_child = self.graph.edges_directed(node_index, petgraph::Direction::Outgoing).next()?.target();
+        let parent_visits = self.graph.node_weight(node_index)?.visits as f32;
+        let config = &self.config;
+        let inverse_temperature = 1.0 / config.prior_temperature;
+        let temper = |prior: f32| if config.prior_temperature != 1.0 { prior.powf(inverse_temperature) } else { prior };
+        // UCT keeps the scale of the priors, of 1 each without a network
+        let prior_total: f32 = if config.prior_temperature != 1.0 || config.formula == SelectionFormula::PUCT {
+            self.graph.edges(node_index).map(|edge| temper(Self::edge_prior(edge.weight()))).sum()
+        } else {
+            1.0
+        };
+        let children = self.graph.edges(node_index).count() as f32;
         for edge in self.graph.edges_directed(node_index, petgraph::Direction::Outgoing) {
             let child_index = edge.target();
             let child = self.graph.node_weight(child_index).unwrap();
-            if child.visits == 0 {
-                return child_index;
-            }
-            let mut prior = edge.weight().0;
-            if P::IS_TRIVIAL {
-                prior = 1.0;
-            }
-            let exploit = -child.wins / child.visits as f32;
-            let explore = prior * 1.414 * (parent_visits.ln() / child.visits as f32).sqrt();
-            let score = exploit + explore;
+            // uniform when no child has a usable prior
+            let prior = if prior_total.is_finite() && prior_total > 0.0 {
+                temper(Self::edge_prior(edge.weight())) / prior_total
+            } else {
+                1.0 / children
+            };
+            let exploit = match (child.visits, config.first_play_urgency, config.formula) {
+                (0, None, SelectionFormula::UCT) => return Some(child_index),
+                (0, None, SelectionFormula::PUCT) => 0.0,
+                (0, Some(first_play_urgency), _) => first_play_urgency,
+                (visits, _, _) => -child.wins / visits as f32,
+            };
+            let explore = match config.formula {
+                SelectionFormula::UCT => prior * config.exploration * (parent_visits.ln() / child.visits.max(1) as f32).sqrt(),
+                SelectionFormula::PUCT => prior * config.exploration * parent_visits.sqrt() / (child.visits as f32 + 1.0),
+            };
+            let bias = config.progressive_bias * prior / (child.visits as f32 + 1.0);
+            let score = exploit + explore + bias;
             if score > best_score {
                 best_child = child_index;
                 best_score = score;
             }
         }
-        best_child
+        Some(best_child)
+    }
+
+    // to_node maps the board of the line to the node board, moves are mapped back along the way
+    fn principal_variation_from(&self, mut node_index: NodeIndex, mut to_node: Symmetry) -> Vec<(usize, Direction)> {
+        let mut principal_variation = vec![];
+        let mut line_nodes = vec![node_index];
+        while let Some((child_index, edge)) = self.most_visited_child(node_index) {
+            if line_nodes.contains(&child_index) {
+                break;
+            }
+            principal_variation.push((edge.1, to_node.inverse().apply_direction(&edge.2)));
+            to_node = to_node.then(&edge.3);
+            node_index = child_index;
+            line_nodes.push(node_index);
+        }
+        principal_variation
     }
 
-    fn choose_final_move_give_all_options(&self, origin: NodeIndex) -> (f32, Vec<(f32, usize, Direction)>) {
+    fn choose_final_move_give_all_options(&self, origin: NodeIndex, to_origin: Symmetry, iterations: usize, start_time: f64) -> SearchResult {
         let origin_node = self.graph.node_weight(origin).unwrap();
         let mut moves_found = vec![];
         let mut total_visits = 0.0;
         for edge in self.graph.edges(origin) {
             let target_node_index = edge.target();
             let visits = self.graph.node_weight(target_node_index).unwrap().visits as f32;
-            let move_with_policy = edge.weight().clone();
-            moves_found.push((visits, move_with_policy.1, move_with_policy.2));
+            let move_with_policy = edge.weight();
+            moves_found.push(ScoredMove::new(visits, move_with_policy.1, to_origin.inverse().apply_direction(&move_with_policy.2)));
             total_visits += visits;
         }
-        moves_found.iter_mut().for_each(|x| x.0 /= total_visits);
-        (origin_node.wins / origin_node.visits as f32 , moves_found)
+        moves_found.iter_mut().for_each(|x| x.score /= total_visits);
+        let pv = self.principal_variation_from(origin, to_origin);
+        let result = SearchResult {
+            value: origin_node.wins / origin_node.visits as f32,
+            moves: moves_found,
+            nodes: iterations,
+            depth: pv.len(),
+            pv,
+            time_ms: O::now() - start_time,
+            proven: None,
+        };
+        self.count(|telemetry| telemetry.record_search(&result));
+        result
     }
 }
 
@@ -183,12 +705,24 @@ impl Policy for TrivialPolicy {
 
 impl<P: Policy, O: Platform> AI<O> for MCTSGeneric<P, O> {
     fn new(color: Color, difficulty: usize) -> Self {
-        O::print(&format!("Creating MCTS AI with trivial policy? {}", P::IS_TRIVIAL));
+        O::log(LogLevel::Trace, &format!("Creating MCTS AI with trivial policy? {}", P::IS_TRIVIAL));
         Self {
             color,
             time_allowed_ms: (difficulty.pow(3)) as f64 * 0.05 * 1000.0,
-            graph: Graph::<MCTSNode, (f32, usize, Direction)>::new(),
+            time_manager: None,
+            node_limit: None,
+            max_tree_nodes: None,
+            memory_limit: None,
+            oracle: None,
+            stats_cache: None,
+            graph: Graph::<MCTSNode, MCTSEdge>::new(),
+            transpositions: HashMap::new(),
+            history: HashSet::new(),
+            last_search: None,
             policy: P::new(),
+            config: MCTSConfig::default(),
+            telemetry: Cell::new(Telemetry::default()),
+            pondered: false,
             platform: PhantomData,
         }
     }
@@ -199,33 +733,161 @@ impl<P: Policy, O: Platform> AI<O> for MCTSGeneric<P, O> {
 
     fn set_color(&mut self, color:Color){
         self.color = color;
-        self.graph.clear();
+        self.clear_graph();
     }
 
-    fn give_all_options(&mut self, board:&Board, verbose: bool) -> (f32, Vec<(f32, usize, Direction)>) {
-        // graph is no longer cleared by default, risk of high memory usage
-        // self.graph.clear();
-        let first_prediction = self.policy.predict(board);
-        if verbose {
-            O::print(&format!("Policy gives board eval {}", first_prediction.0));
-            for element in first_prediction.1.iter() {
-                O::print(&format!("Policy gives eval {} to move {:?}", element.0, (element.1, element.2.clone())));
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        span!(INFO, "mcts_search");
+        let start_time = O::now();
+        if let Some(result) = self.oracle.as_ref().and_then(|oracle| proven_search_result::<O>(oracle.as_ref(), board, start_time)) {
+            self.count(|telemetry| telemetry.record_search(&result));
+            self.record_move_time(start_time);
+            return result;
+        }
+        let (mut origin, to_origin) = self.prepare_origin(board, verbose);
+        self.last_search = Some((board.clone(), origin, to_origin));
+        let move_time_ms = self.move_time_ms(board);
+        let mut allotted_ms = move_time_ms;
+        let mut stability = SearchStability::new();
+        let mut last_report = start_time;
+        let mut iterations = 0;
+        while self.budget_spent(start_time, iterations, allotted_ms) < 1.0 {
+            origin = self.make_room(origin);
+            self.iterate(origin);
+            iterations += 1;
+            if let Some(extended_ms) = self.extended_time_ms(origin, iterations, &mut stability, move_time_ms) {
+                allotted_ms = extended_ms;
+            }
+            if O::now() - last_report > YIELD_INTERVAL_MS {
+                last_report = O::now();
+                if self.report_progress(observer, origin, to_origin, start_time, iterations, allotted_ms) {
+                    break;
+                }
             }
         }
-        let board_hash = board.get_hash();
-        let possible_origin = self.graph.node_indices().find(|index| {
-            let index_hash = self.graph.node_weight(*index).unwrap().board_hash;
-            board_hash == index_hash
-        });
-        let origin = possible_origin.unwrap_or_else(|| {
-            self.graph.add_node(MCTSNode::new(board.clone(), self.color.clone(), first_prediction.1, first_prediction.0))
-        });
+        self.record_move_time(start_time);
+        self.record_memory(false);
+        self.update_stats_cache();
+        self.choose_final_move_give_all_options(origin, to_origin, iterations, start_time)
+    }
+
+    fn give_all_options_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        let search = async move {
+            let start_time = O::now();
+            if let Some(result) = self.oracle.as_ref().and_then(|oracle| proven_search_result::<O>(oracle.as_ref(), board, start_time)) {
+                self.count(|telemetry| telemetry.record_search(&result));
+                self.record_move_time(start_time);
+                return result;
+            }
+            let (mut origin, to_origin) = self.prepare_origin(board, verbose);
+            self.last_search = Some((board.clone(), origin, to_origin));
+            let move_time_ms = self.move_time_ms(board);
+            let mut allotted_ms = move_time_ms;
+            let mut stability = SearchStability::new();
+            let mut last_yield = start_time;
+            let mut iterations = 0;
+            while self.budget_spent(start_time, iterations, allotted_ms) < 1.0 {
+                origin = self.make_room(origin);
+                self.iterate(origin);
+                iterations += 1;
+                if let Some(extended_ms) = self.extended_time_ms(origin, iterations, &mut stability, move_time_ms) {
+                    allotted_ms = extended_ms;
+                }
+                if O::now() - last_yield > YIELD_INTERVAL_MS {
+                    self.report_progress(observer, origin, to_origin, start_time, iterations, allotted_ms);
+                    O::yield_now().await;
+                    last_yield = O::now();
+                    if observer.is_some_and(|observer| observer.is_cancelled()) {
+                        break;
+                    }
+                }
+            }
+            self.record_move_time(start_time);
+            self.record_memory(false);
+            self.update_stats_cache();
+            self.choose_final_move_give_all_options(origin, to_origin, iterations, start_time)
+        };
+        instrument!(search, INFO, "mcts_search")
+    }
+
+    fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        self.node_limit = node_limit;
+    }
+
+    fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.memory_limit = memory_limit;
+    }
+
+    fn set_oracle(&mut self, oracle: Option<SharedOracle>) {
+        self.oracle = oracle;
+    }
+
+    fn set_stats_cache(&mut self, stats_cache: Option<SharedStatsCache>) {
+        self.stats_cache = stats_cache;
+    }
+
+    // The statistics of the tree were backpropagated with the previous value of draws
+    fn set_contempt(&mut self, contempt: f32) {
+        let contempt = contempt.clamp(-1.0, 1.0);
+        if contempt != self.config.contempt {
+            self.config.contempt = contempt;
+            self.clear_graph();
+        }
+    }
+
+    fn set_game_history(&mut self, history: &[Board]) {
+        self.history = history.iter().map(|board| board.canonical_hash().0).collect();
+    }
 
+    fn ponder(&mut self, board: &Board, observer: &dyn SearchObserver) {
+        let Some(mut origin) = self.ponder_origin(board) else {
+            return;
+        };
         let start_time = O::now();
-        while O::now() - start_time < self.time_allowed_ms {
+        while !observer.is_cancelled() && O::now() - start_time < MAX_PONDER_FACTOR * self.time_allowed_ms {
+            origin = self.make_room(origin);
             self.iterate(origin);
         }
-        self.choose_final_move_give_all_options(origin)
+    }
+
+    fn ponder_async(&mut self, board: &Board, observer: &dyn SearchObserver) -> impl Future<Output = ()> {
+        async move {
+            let Some(mut origin) = self.ponder_origin(board) else {
+                return;
+            };
+            let start_time = O::now();
+            let mut last_yield = start_time;
+            while O::now() - start_time < MAX_PONDER_FACTOR * self.time_allowed_ms {
+                origin = self.make_room(origin);
+                self.iterate(origin);
+                if O::now() - last_yield > YIELD_INTERVAL_MS {
+                    O::yield_now().await;
+                    last_yield = O::now();
+                    if observer.is_cancelled() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn search_tree(&self, max_depth: usize) -> Option<SearchTree> {
+        self.export_tree(max_depth)
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.telemetry.get()
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.telemetry.set(Telemetry::default());
+    }
+
+    fn principal_variation(&self, board: &Board) -> Vec<(usize, Direction)> {
+        match self.find_node(board) {
+            Some((node_index, to_node)) => self.principal_variation_from(node_index, to_node),
+            None => vec![],
+        }
     }
 }
 
@@ -233,13 +895,33 @@ pub type MCTS<O> = MCTSGeneric<TrivialPolicy, O>;
 
 impl<B: Backend, O: Platform> AlphaZeutreeko<B, O> {
     pub fn new_no_data(color: Color, difficulty: usize) -> Self {
-        O::print(&format!("Creating MCTS AI with trivial policy? {}", false));
+        O::log(LogLevel::Trace, &format!("Creating MCTS AI with trivial policy? {}", false));
         Self {
             color,
             time_allowed_ms: (difficulty.pow(3)) as f64 * 0.05 * 1000.0,
-            graph: Graph::<MCTSNode, (f32, usize, Direction)>::new(),
+            time_manager: None,
+            node_limit: None,
+            max_tree_nodes: None,
+            memory_limit: None,
+            oracle: None,
+            stats_cache: None,
+            graph: Graph::<MCTSNode, MCTSEdge>::new(),
+            transpositions: HashMap::new(),
+            history: HashSet::new(),
+            last_search: None,
             policy: ANNPolicy::new_no_data(),
+            config: MCTSConfig::default(),
+            telemetry: Cell::new(Telemetry::default()),
+            pondered: false,
             platform: PhantomData,
         }
     }
+
+    // Uses the weights registered under model_id in ann::weights
+    pub fn with_model(color: Color, difficulty: usize, model_id: &str) -> Self {
+        Self {
+            policy: ANNPolicy::from_model(model_id),
+            ..Self::new_no_data(color, difficulty)
+        }
+    }
 }
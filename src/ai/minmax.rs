@@ -1,15 +1,105 @@
 use std::vec;
+use std::cell::Cell;
+use std::future::Future;
 use std::marker::PhantomData;
 
 use crate::{
     logic::{Board, Color, Direction},
-    platform::Platform,
+    platform::{Platform, YIELD_INTERVAL_MS},
+    profiling::{instrument, span},
 };
-use super::AI;
+use super::{AI, BOARD_HEAP_BYTES, GRAPH_EDGE_LINK_BYTES, GRAPH_NODE_LINK_BYTES, ScoredMove, SearchObserver, SearchProgress, SearchResult, Telemetry, oracle::{ProvenResult, SharedOracle, proven_search_result}, value::Value};
 
 use petgraph::Graph;
 use petgraph::visit::EdgeRef;
 use petgraph::prelude::NodeIndex;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "web-threads")]
+use std::sync::{Arc, Mutex};
+
+// Score of a won game, less the plies from the root to the win so faster wins score higher.
+// Every other score is 0 or the score of a draw, under half of it, so any score far from 0 is a proven result
+const WIN_SCORE: isize = 100;
+// Plies searched below a leaf while the side to move has to answer a threat
+const MAX_THREAT_EXTENSION: usize = 6;
+// Root moves scoring below this lose, score_root gives them all 0 so their exact score is not needed.
+// Lowered under the score of a draw when the engine avoids them, see loss_bound
+const LOSS_BOUND: isize = -1;
+// Half width of the window around the previous iteration value, scores of a pass only move by whole wins
+const ASPIRATION_WINDOW: isize = 1;
+// Approximate bytes of a node of the tree: its evaluation with the pawns of its board, and the edge from its parent
+pub const NODE_BYTES: usize = std::mem::size_of::<BoardEvaluation>() + BOARD_HEAP_BYTES + GRAPH_NODE_LINK_BYTES
+    + std::mem::size_of::<(usize, Direction)>() + GRAPH_EDGE_LINK_BYTES;
+
+// Work done scoring the tree, to compare the node counts of search changes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MinMaxStats {
+    pub scored_nodes: usize, // calls to minmax_score, a node can be scored once per pass
+    pub extended_leaves: usize, // leaves given the threat extension
+    pub researches: usize, // null window searches that had to be searched again in full
+    pub aspiration_failures: usize, // root searches outside the aspiration window
+}
+
+impl MinMaxStats {
+    fn add(&mut self, other: &MinMaxStats) {
+        self.scored_nodes += other.scored_nodes;
+        self.extended_leaves += other.extended_leaves;
+        self.researches += other.researches;
+        self.aspiration_failures += other.aspiration_failures;
+    }
+}
+
+// Progress of the subtrees of a root split, shared with the thread waiting for them, and its request to stop
+#[cfg(feature = "parallel")]
+#[derive(Default)]
+struct SplitProgress {
+    cancelled: AtomicBool,
+    nodes: AtomicUsize, // of all the subtrees
+    depth: AtomicUsize, // deepest layer a subtree is building
+}
+
+// Observer of the layers of one subtree, adds its nodes to the shared progress
+#[cfg(feature = "parallel")]
+struct SubtreeObserver<'a> {
+    shared: &'a SplitProgress,
+    depth: usize,
+    reported_nodes: Cell<usize>,
+}
+
+#[cfg(feature = "parallel")]
+impl SearchObserver for SubtreeObserver<'_> {
+    fn on_progress(&self, progress: &SearchProgress) {
+        let new_nodes = progress.nodes.saturating_sub(self.reported_nodes.replace(progress.nodes));
+        self.shared.nodes.fetch_add(new_nodes, Ordering::Relaxed);
+        self.shared.depth.fetch_max((progress.fraction * self.depth as f32).round() as usize, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+fn win_score(depth: usize) -> isize {
+    WIN_SCORE - depth as isize
+}
+
+fn loss_score(depth: usize) -> isize {
+    -WIN_SCORE + depth as isize
+}
+
+// Score for the side to move turned into the distance from the root to the end of the game
+fn proven_from_score(score: isize) -> Option<ProvenResult> {
+    // isize::MIN is the score of a root without moves
+    let magnitude = score.saturating_abs();
+    if !(WIN_SCORE / 2..=WIN_SCORE).contains(&magnitude) {
+        return None;
+    }
+    let distance = (WIN_SCORE - magnitude) as usize;
+    Some(if score > 0 { ProvenResult::Win(distance) } else { ProvenResult::Loss(distance) })
+}
 
 #[derive(Clone)]
 struct BoardEvaluation {
@@ -17,44 +107,525 @@ struct BoardEvaluation {
     color: Color,
     score: isize,
     depth: usize,
+    proven: bool, // exact score given by the oracle or a draw by repetition, the node is not expanded
+    extended_score: Cell<Option<isize>>, // score after the threat extension, set once the node is scored as a leaf
 }
 
 impl BoardEvaluation {
     fn new(board: Board, color: Color, depth: usize) -> Self {
-        let mut a = Self { board: board, color: color, score: 0, depth: depth};
+        let mut a = Self { board: board, color: color, score: 0, depth: depth, proven: false, extended_score: Cell::new(None)};
         a.score_board();
         a
     }
 
     fn score_board(&mut self) {
         match self.board.winner() {
-            Some(winner_color) if winner_color == self.color => self.score = 100 - self.depth as isize,
-            Some(_) => self.score = -100 + self.depth as isize,
+            Some(winner_color) if winner_color == self.color => self.score = win_score(self.depth),
+            Some(_) => self.score = loss_score(self.depth),
             None => self.score = 0,
         }
     }
+
+    fn set_proven(&mut self, result: &ProvenResult) {
+        let Some(next_player) = &self.board.next_player else {
+            // finished games are already scored
+            return;
+        };
+        let winner_color = match result {
+            ProvenResult::Win(_) => next_player.clone(),
+            ProvenResult::Loss(_) => next_player.other_color(),
+        };
+        let end_depth = self.depth + result.distance();
+        self.score = if winner_color == self.color { win_score(end_depth) } else { loss_score(end_depth) };
+        self.proven = true;
+    }
+
+    fn set_drawn(&mut self, draw_score: isize) {
+        self.score = draw_score;
+        self.proven = true;
+    }
 }
 
 #[derive(Clone)]
 pub struct MinMax<O: Platform> {
     color: Color,
     depth: usize,
+    node_limit: Option<usize>,
+    memory_limit: Option<usize>,
+    oracle: Option<SharedOracle>,
+    contempt: f32,
+    history: Vec<Board>, // positions of the game before the searched one, going back to one of them is a draw
     graph: Graph<BoardEvaluation, (usize, Direction)>,
-    _platform: PhantomData<O>,
+    stats: Cell<MinMaxStats>, // of the last search
+    telemetry: Telemetry,
+    _platform: PhantomData<fn() -> O>, // keeps MinMax Send for the parallel root search
 }
 
 impl <O: Platform> MinMax<O> {
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    pub fn stats(&self) -> MinMaxStats {
+        self.stats.get()
+    }
+
+    // Score of a draw for the color of the search, -contempt in wins. Below the 0 of unclear positions when the engine
+    // has contempt for draws, and always short of the scores of proven results
+    fn draw_score(&self) -> isize {
+        let limit = WIN_SCORE / 2 - 1;
+        (-(self.contempt * WIN_SCORE as f32).round() as isize).clamp(-limit, limit)
+    }
+
+    // Root moves scoring below this lose, drawing moves are scored exactly so score_root can weigh them
+    fn loss_bound(&self) -> isize {
+        LOSS_BOUND.min(self.draw_score() - 1)
+    }
+
+    // Boards of the game and of the line down to node_index, the children of the node repeating one are draws
+    fn earlier_boards(&self, node_index: NodeIndex) -> Vec<&Board> {
+        let mut boards: Vec<&Board> = self.history.iter().collect();
+        let mut line_node = Some(node_index);
+        while let Some(line_index) = line_node {
+            boards.push(&self.graph.node_weight(line_index).unwrap().board);
+            line_node = self.graph.neighbors_directed(line_index, petgraph::Direction::Incoming).next();
+        }
+        boards
+    }
+
+    fn count(&self, update: impl FnOnce(&mut MinMaxStats)) {
+        let mut stats = self.stats.get();
+        update(&mut stats);
+        self.stats.set(stats);
+    }
+
+    fn evaluate(&self, board: Board, depth: usize) -> BoardEvaluation {
+        let mut evaluation = BoardEvaluation::new(board, self.color.clone(), depth);
+        if let Some(result) = self.oracle.as_ref().and_then(|oracle| oracle.probe(&evaluation.board)) {
+            evaluation.set_proven(&result);
+        }
+        evaluation
+    }
+
+    fn expand_node(&mut self, node_index: NodeIndex, current_depth: usize) -> Vec<NodeIndex> {
+        let color_at_this_depth = if current_depth % 2 == 0 {
+            self.color.clone()
+        } else {
+            self.color.other_color()
+        };
+        let mut new_nodes = Vec::new();
+        let considered_node = self.graph.node_weight(node_index).unwrap();
+        if considered_node.proven || considered_node.board.winner().is_some() {
+            return new_nodes;
+        }
+        let considered_board = considered_node.board.clone();
+        let mut children = vec![];
+        for (pawn_index, pawn) in considered_board.pawns.iter().enumerate() {
+            if pawn.color != color_at_this_depth {
+                continue;
+            }
+            children.extend(considered_board.get_valid_directions_and_resulting_boards(pawn_index).into_iter()
+                .map(|(direction, new_board)| (pawn_index, direction, new_board)));
+        }
+        // Move ordering for alpha-beta: wins, then moves leaving a winning move, are searched first.
+        // petgraph walks the edges of a node from the last added, so they are added last.
+        // The last layer, most of the tree, is left unordered: its leaves look for winning moves when scored
+        if current_depth + 1 < self.depth {
+            children.sort_by_cached_key(|(_, _, new_board)| {
+                if new_board.winner().is_some() {
+                    2
+                } else if !new_board.winning_moves(&color_at_this_depth).is_empty() {
+                    1
+                } else {
+                    0
+                }
+            });
+        }
+        let earlier_boards = self.earlier_boards(node_index);
+        let repeated: Vec<bool> = children.iter().map(|(_, _, new_board)| earlier_boards.contains(&new_board)).collect();
+        for ((pawn_index, direction, new_board), repeated) in children.into_iter().zip(repeated) {
+            let mut evaluation = self.evaluate(new_board, current_depth + 1);
+            if repeated {
+                evaluation.set_drawn(self.draw_score());
+            }
+            let new_node_index = self.graph.add_node(evaluation);
+            self.graph.add_edge(node_index, new_node_index, (pawn_index, direction));
+            new_nodes.push(new_node_index);
+        }
+        new_nodes
+    }
+
+    // The node limit of the engine, within its memory limit and the memory cap of the platform
+    fn effective_node_limit(&self) -> usize {
+        self.node_limit.unwrap_or(usize::MAX).min(self.memory_node_capacity())
+    }
+
+    fn memory_node_capacity(&self) -> usize {
+        self.memory_limit.unwrap_or(usize::MAX).min(O::max_search_memory()) / NODE_BYTES
+    }
+
+    // Memory of the tree of the last search, and whether the memory rather than the depth stopped it
+    fn record_memory(&mut self) {
+        let nodes = self.graph.node_count();
+        self.telemetry.record_memory(nodes * NODE_BYTES, nodes >= self.memory_node_capacity());
+    }
+
+    fn node_limit_reached(&self) -> bool {
+        self.graph.node_count() >= self.effective_node_limit()
+    }
+
+    // Reports how many layers are built, returns true if the observer asked to stop
+    fn report_progress(&self, observer: Option<&dyn SearchObserver>, current_depth: usize) -> bool {
+        let Some(observer) = observer else {
+            return false;
+        };
+        observer.on_progress(&SearchProgress {
+            fraction: current_depth as f32 / self.depth.max(1) as f32,
+            nodes: self.graph.node_count(),
+            best_move: None,
+        });
+        observer.is_cancelled()
+    }
+
+    // Expands the tree breadth-first below origin, a node at first_depth, returns the depth fully built
+    fn build_layers(&mut self, origin: NodeIndex, first_depth: usize, observer: Option<&dyn SearchObserver>, start_time: f64) -> usize {
+        let mut to_explore = vec![origin];
+        let mut last_report = start_time;
+        let mut depth_searched = first_depth;
+        'search: for current_depth in first_depth..self.depth {
+            span!(DEBUG, "minmax_layer", depth = current_depth, nodes = to_explore.len());
+            let mut to_explore_next = Vec::new();
+            for considered_node_index in to_explore.iter() {
+                if O::now() - last_report > YIELD_INTERVAL_MS {
+                    last_report = O::now();
+                    if self.report_progress(observer, current_depth) {
+                        // unexpanded nodes are scored statically, so the partial tree is still usable
+                        break 'search;
+                    }
+                }
+                if self.node_limit_reached() {
+                    break 'search;
+                }
+                to_explore_next.append(&mut self.expand_node(*considered_node_index, current_depth));
+            }
+            to_explore = to_explore_next;
+            depth_searched += 1;
+        }
+        depth_searched
+    }
+
+    // Leaves where a player threatens to win are searched further, so the horizon does not hide forced losses.
+    // Computed when alpha-beta first reaches the leaf, pruned leaves never pay for it
+    fn leaf_score(&self, node_index: NodeIndex) -> isize {
+        let evaluation = self.graph.node_weight(node_index).unwrap();
+        // expanded nodes are only leaves of the shallow passes of root_scores
+        if evaluation.proven || evaluation.board.next_player.is_none() || self.graph.edges(node_index).next().is_some() {
+            return evaluation.score;
+        }
+        if let Some(score) = evaluation.extended_score.get() {
+            return score;
+        }
+        self.count(|stats| stats.extended_leaves += 1);
+        let score = self.threat_score(&evaluation.board, evaluation.depth, MAX_THREAT_EXTENSION);
+        evaluation.extended_score.set(Some(score));
+        score
+    }
+
+    // Score of an unfinished position: the side to move wins if it has a threat, and has to parry the threats
+    // of its opponent, which is searched one ply further until the position is quiet
+    fn threat_score(&self, board: &Board, depth: usize, extension_left: usize) -> isize {
+        let Some(mover) = &board.next_player else {
+            return BoardEvaluation::new(board.clone(), self.color.clone(), depth).score;
+        };
+        if !board.winning_moves(mover).is_empty() {
+            return if *mover == self.color { win_score(depth + 1) } else { loss_score(depth + 1) };
+        }
+        let opponent_threatens = !board.winning_moves(&mover.other_color()).is_empty();
+        // forced wins of a quiet position are left to the search, Board::threats is too slow for every leaf
+        if extension_left == 0 || !opponent_threatens {
+            return 0;
+        }
+        let maximizing = *mover == self.color;
+        let mut best = if maximizing { isize::MIN } else { isize::MAX };
+        for game_move in board.legal_moves() {
+            let score = self.threat_score(&board.child(&game_move), depth + 1, extension_left - 1);
+            // a parry reaching a quiet position is enough, the extension only looks for forced wins
+            if (maximizing && score >= 0) || (!maximizing && score <= 0) {
+                return score;
+            }
+            best = if maximizing { best.max(score) } else { best.min(score) };
+        }
+        best
+    }
+
+    // Minmax score of each root move, in the order of the root edges.
+    // Iterative deepening over the built tree: each pass searches first the best move of the previous pass,
+    // within an aspiration window around its value. The other moves are only told apart from losses
+    fn root_scores(&self, origin: NodeIndex) -> Vec<isize> {
+        let children: Vec<NodeIndex> = self.graph.edges(origin).map(|edge| edge.target()).collect();
+        let mut scores = vec![0; children.len()];
+        let mut previous_value = None;
+        for depth in 1..=self.depth {
+            let mut order: Vec<usize> = (0..children.len()).collect();
+            order.sort_by_key(|index| std::cmp::Reverse(scores[*index]));
+            for (rank, index) in order.into_iter().enumerate() {
+                scores[index] = match previous_value {
+                    Some(previous_value) if rank == 0 => self.aspiration_score(children[index], depth - 1, previous_value),
+                    _ => self.minmax_score(children[index], depth - 1, self.loss_bound(), isize::MAX, false),
+                };
+            }
+            previous_value = scores.iter().max().copied();
+        }
+        self.exact_losses(&children, &mut scores);
+        scores
+    }
+
+    fn aspiration_score(&self, node_index: NodeIndex, depth_remaining: usize, previous_value: isize) -> isize {
+        let alpha = previous_value - ASPIRATION_WINDOW;
+        let beta = previous_value + ASPIRATION_WINDOW;
+        let score = self.minmax_score(node_index, depth_remaining, alpha, beta, false);
+        if score > alpha && score < beta {
+            return score;
+        }
+        self.count(|stats| stats.aspiration_failures += 1);
+        self.minmax_score(node_index, depth_remaining, self.loss_bound(), isize::MAX, false)
+    }
+
+    // When every root move loses, their scores are only bounds: searched again in full so the value tells how far the loss is
+    fn exact_losses(&self, children: &[NodeIndex], scores: &mut [isize]) {
+        if scores.iter().any(|score| *score > self.loss_bound()) {
+            return;
+        }
+        for (child, score) in children.iter().zip(scores.iter_mut()) {
+            *score = self.minmax_score(*child, self.depth - 1, isize::MIN, isize::MAX, false);
+        }
+    }
+
+    // Root split: each root move gets its own tree, built and scored with a full alpha-beta window on a rayon thread.
+    // The subtrees are then grafted under the root so the graph is the one the sequential search builds.
+    // With a shared progress, the subtrees report to it and stop building their layers once it is cancelled
+    #[cfg(feature = "parallel")]
+    fn give_all_options_parallel(&mut self, board: &Board, start_time: f64, progress: Option<&SplitProgress>) -> SearchResult {
+        let origin = self.graph.add_node(BoardEvaluation::new(board.clone(), self.color.clone(), 0));
+        let children = self.expand_node(origin, 0);
+        let mut searcher = self.clone();
+        searcher.graph.clear();
+        // the subtrees start below the root, which the lines may go back to
+        searcher.history.push(board.clone());
+        searcher.node_limit = Some(self.effective_node_limit() / children.len().max(1));
+        let subtrees: Vec<(NodeIndex, MinMax<O>, isize, usize)> = children.iter()
+            .map(|child| (*child, searcher.clone(), self.graph.node_weight(*child).unwrap().clone()))
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|(child, mut subtree, evaluation)| {
+                let subtree_origin = subtree.graph.add_node(evaluation);
+                let observer = progress.map(|shared| SubtreeObserver { shared, depth: subtree.depth, reported_nodes: Cell::new(0) });
+                let observer = observer.as_ref().map(|observer| observer as &dyn SearchObserver);
+                let depth_searched = subtree.build_layers(subtree_origin, 1, observer, start_time);
+                subtree.report_progress(observer, depth_searched);
+                let score = subtree.minmax_score(subtree_origin, subtree.depth - 1, subtree.loss_bound(), isize::MAX, false);
+                (child, subtree, score, depth_searched)
+            })
+            .collect();
+        let mut depth_searched = self.depth;
+        let mut child_scores = vec![];
+        let mut stats = self.stats();
+        for (child, subtree, score, subtree_depth) in subtrees {
+            depth_searched = depth_searched.min(subtree_depth);
+            child_scores.push((child, score));
+            stats.add(&subtree.stats());
+            self.graft(child, subtree.graph);
+        }
+        self.stats.set(stats);
+        // same move order as root_scores
+        let root_children: Vec<NodeIndex> = self.graph.edges(origin).map(|edge| edge.target()).collect();
+        let mut root_scores: Vec<isize> = root_children.iter()
+            .filter_map(|target| child_scores.iter().find(|(child, _)| child == target).map(|(_, score)| *score))
+            .collect();
+        self.exact_losses(&root_children, &mut root_scores);
+        self.score_root(origin, &root_scores, depth_searched, start_time)
+    }
+
+    // Moves a subtree under node_index, which stands for the subtree root (its first node)
+    #[cfg(feature = "parallel")]
+    fn graft(&mut self, node_index: NodeIndex, subtree: Graph<BoardEvaluation, (usize, Direction)>) {
+        let (nodes, edges) = subtree.into_nodes_edges();
+        let mut new_indices = vec![node_index];
+        for node in nodes.into_iter().skip(1) {
+            new_indices.push(self.graph.add_node(node.weight));
+        }
+        for edge in edges {
+            self.graph.add_edge(new_indices[edge.source().index()], new_indices[edge.target().index()], edge.weight);
+        }
+    }
+
+    fn score_root(&self, origin: NodeIndex, root_scores: &[isize], depth_searched: usize, start_time: f64) -> SearchResult {
+        let mut total = 0.0;
+        let mut best_minmax = isize::MIN;
+        let mut all_moves_found = vec![];
+        for (edge, minmax) in self.graph.edges(origin).zip(root_scores.iter().copied()) {
+            let mut minmax_to_push = minmax;
+            if minmax_to_push == 0 {
+                // small positive value to unclear positions, and draws without contempt
+                minmax_to_push = 1;
+            }
+            // don't allow below 0
+            minmax_to_push = minmax_to_push.max(0);
+            total += minmax_to_push as f32;
+            let move_found = edge.weight().clone();
+            all_moves_found.push(ScoredMove::new(minmax_to_push as f32, move_found.0, move_found.1));
+            if minmax > best_minmax {best_minmax = minmax;}
+        }
+        // handle case of certain loss, or of draws the engine has contempt for: the best moves share the choice,
+        // the draw over a loss and the longest loss
+        if total < 0.5 {
+            let n_items = root_scores.iter().filter(|minmax| **minmax == best_minmax).count() as f32;
+            for (move_found, minmax) in all_moves_found.iter_mut().zip(root_scores.iter()) {
+                if *minmax == best_minmax {
+                    move_found.score += 1.0 / n_items;
+                }
+            }
+        }
+        else {
+            all_moves_found.iter_mut().for_each(|x| x.score /= total);
+        }
+
+        let board = &self.graph.node_weight(origin).unwrap().board;
+        // the tree scores for the engine color, the value and the proven result are for the side to move
+        let mover_score = if board.next_player.as_ref() == Some(&self.color) { best_minmax } else { best_minmax.saturating_neg() };
+        SearchResult {
+            value: Value::from_minmax(mover_score, WIN_SCORE).get(),
+            moves: all_moves_found,
+            nodes: self.graph.node_count(),
+            depth: depth_searched,
+            pv: self.principal_variation(board),
+            time_ms: O::now() - start_time,
+            // root scores are exact once a move wins or all lose, exact_losses searches the losses again in full
+            proven: proven_from_score(mover_score),
+        }
+    }
+
+    fn search(&mut self, board: &Board, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        span!(INFO, "minmax_search", depth = self.depth);
+        self.graph.clear();
+        self.stats.set(MinMaxStats::default());
+        let start_time = O::now();
+        if let Some(result) = self.oracle.as_ref().and_then(|oracle| proven_search_result::<O>(oracle.as_ref(), board, start_time)) {
+            return result;
+        }
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if observer.is_none() && self.depth > 1 {
+            return self.give_all_options_parallel(board, start_time, None);
+        }
+        let origin = self.graph.add_node(BoardEvaluation::new(board.clone(), self.color.clone(), 0));
+        let depth_searched = self.build_layers(origin, 0, observer, start_time);
+        let root_scores = self.root_scores(origin);
+        self.score_root(origin, &root_scores, depth_searched, start_time)
+    }
+
+    async fn search_async(&mut self, board: &Board, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        self.graph.clear();
+        self.stats.set(MinMaxStats::default());
+        let start_time = O::now();
+        if let Some(result) = self.oracle.as_ref().and_then(|oracle| proven_search_result::<O>(oracle.as_ref(), board, start_time)) {
+            return result;
+        }
+        #[cfg(feature = "web-threads")]
+        if crate::platform::threads_ready() && self.depth > 1 {
+            return self.search_on_workers(board, observer, start_time).await;
+        }
+        let origin = self.graph.add_node(BoardEvaluation::new(board.clone(), self.color.clone(), 0));
+        let mut to_explore = vec![origin];
+        let mut last_yield = start_time;
+        let mut depth_searched = 0;
+        'search: for current_depth in 0..self.depth {
+            let mut to_explore_next = Vec::new();
+            for considered_node_index in to_explore.iter() {
+                if O::now() - last_yield > YIELD_INTERVAL_MS {
+                    self.report_progress(observer, current_depth);
+                    O::yield_now().await;
+                    last_yield = O::now();
+                    if observer.is_some_and(|observer| observer.is_cancelled()) {
+                        break 'search;
+                    }
+                }
+                if self.node_limit_reached() {
+                    break 'search;
+                }
+                to_explore_next.append(&mut self.expand_node(*considered_node_index, current_depth));
+            }
+            to_explore = to_explore_next;
+            depth_searched += 1;
+        }
+        let root_scores = self.root_scores(origin);
+        self.score_root(origin, &root_scores, depth_searched, start_time)
+    }
+
+    // Root split on the web workers of the rayon pool. The browser main thread must not block,
+    // so it hands the whole search to the pool and polls for the result between yields.
+    // The workers share their progress with the main thread, which passes it on to the observer and a stop back
+    #[cfg(feature = "web-threads")]
+    async fn search_on_workers(&mut self, board: &Board, observer: Option<&dyn SearchObserver>, start_time: f64) -> SearchResult {
+        let finished = Arc::new(Mutex::new(None));
+        let progress = Arc::new(SplitProgress::default());
+        let sender = finished.clone();
+        let worker_progress = progress.clone();
+        let mut searcher = self.clone();
+        let board = board.clone();
+        rayon::spawn(move || {
+            // each worker has its own clock, time_ms is measured again on the main thread
+            let result = searcher.give_all_options_parallel(&board, O::now(), Some(&worker_progress));
+            *sender.lock().unwrap() = Some((searcher, result));
+        });
+        let mut last_report = start_time;
+        loop {
+            // try_lock, waiting on a lock is not allowed on the main thread
+            if let Some((searcher, mut result)) = finished.try_lock().ok().and_then(|mut finished| finished.take()) {
+                *self = searcher;
+                result.time_ms = O::now() - start_time;
+                return result;
+            }
+            if let Some(observer) = observer.filter(|_| O::now() - last_report > YIELD_INTERVAL_MS) {
+                last_report = O::now();
+                observer.on_progress(&SearchProgress {
+                    fraction: progress.depth.load(Ordering::Relaxed) as f32 / self.depth.max(1) as f32,
+                    nodes: progress.nodes.load(Ordering::Relaxed),
+                    best_move: None,
+                });
+                // the subtrees stop building and score what they have
+                if observer.is_cancelled() {
+                    progress.cancelled.store(true, Ordering::Relaxed);
+                }
+            }
+            O::yield_now().await;
+        }
+    }
+
+    // Alpha-beta with principal variation search: the first child gets the full window,
+    // the others a null window proving they are no better, searched again when they are
     fn minmax_score(&self, node_index: NodeIndex, depth_remaining: usize, mut alpha: isize, mut beta: isize, maximizing_player: bool) -> isize {
+        self.count(|stats| stats.scored_nodes += 1);
         if depth_remaining == 0 {
-            return self.graph.node_weight(node_index).unwrap().score;
+            return self.leaf_score(node_index);
         }
 
         let mut value = if maximizing_player { isize::MIN } else { isize::MAX };
         let mut at_least_one_edge = false;
         for edge in self.graph.edges(node_index) {
-            at_least_one_edge = true;
             let target_node_index = edge.target();
-            let score = self.minmax_score(target_node_index, depth_remaining - 1, alpha, beta, !maximizing_player);
+            let score = if !at_least_one_edge {
+                self.minmax_score(target_node_index, depth_remaining - 1, alpha, beta, !maximizing_player)
+            } else {
+                let (null_alpha, null_beta) = if maximizing_player { (alpha, alpha.saturating_add(1)) } else { (beta.saturating_sub(1), beta) };
+                let score = self.minmax_score(target_node_index, depth_remaining - 1, null_alpha, null_beta, !maximizing_player);
+                if score > alpha && score < beta {
+                    self.count(|stats| stats.researches += 1);
+                    self.minmax_score(target_node_index, depth_remaining - 1, alpha, beta, !maximizing_player)
+                } else {
+                    score
+                }
+            };
+            at_least_one_edge = true;
 
             if maximizing_player {
                 value = value.max(score);
@@ -69,7 +640,7 @@ impl <O: Platform> MinMax<O> {
             }
         }
         if !at_least_one_edge {
-            value = self.graph.node_weight(node_index).unwrap().score;
+            value = self.leaf_score(node_index);
         }
         value
     }
@@ -80,7 +651,14 @@ impl <O: Platform> AI<O> for MinMax<O> {
         Self {
             color,
             depth,
+            node_limit: None,
+            memory_limit: None,
+            oracle: None,
+            contempt: 0.0,
+            history: vec![],
             graph: Graph::<BoardEvaluation, (usize, Direction)>::new(),
+            stats: Cell::new(MinMaxStats::default()),
+            telemetry: Telemetry::default(),
             _platform: PhantomData,
         }
     }
@@ -93,64 +671,80 @@ impl <O: Platform> AI<O> for MinMax<O> {
         self.color = color;
     }
 
-    fn give_all_options(&mut self, board:&Board, _verbose: bool) -> (f32, Vec<(f32, usize, Direction)>) {
-        self.graph.clear();
-        let origin = self.graph.add_node(BoardEvaluation::new(board.clone(), self.color.clone(), 0));
-        let mut to_explore = vec![origin];
-        for current_depth in 0..self.depth {
-            let color_at_this_depth = if current_depth % 2 == 0 {
-                &self.color
-            } else {
-                &self.color.other_color()
-            };
-            let mut to_explore_next = Vec::new();
-            for considered_node_index in to_explore.iter() {
-                let considered_board = self.graph.node_weight(*considered_node_index).unwrap().board.clone();
-                if considered_board.winner().is_some() {
-                    continue;
-                }
-                for (pawn_index, pawn) in considered_board.pawns.iter().enumerate() {
-                    if &pawn.color != color_at_this_depth {
-                        continue;
-                    }
-                    let directions = considered_board.get_valid_directions_and_resulting_boards(pawn_index);
-                    for (direction, new_board) in directions {
-                        let new_node_index = self.graph.add_node(BoardEvaluation::new(new_board, self.color.clone(), current_depth + 1));
-                        self.graph.add_edge(*considered_node_index, new_node_index, (pawn_index, direction));
-                        to_explore_next.push(new_node_index);
-                    }
+    fn give_all_options_observed(&mut self, board: &Board, _verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        let result = self.search(board, observer);
+        self.telemetry.record_search(&result);
+        self.record_memory();
+        result
+    }
+
+    fn give_all_options_async(&mut self, board: &Board, _verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        async move {
+            let result = instrument!(self.search_async(board, observer), INFO, "minmax_search", depth = self.depth).await;
+            self.telemetry.record_search(&result);
+            self.record_memory();
+            result
+        }
+    }
+
+    fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        self.node_limit = node_limit;
+    }
+
+    fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.memory_limit = memory_limit;
+    }
+
+    fn set_oracle(&mut self, oracle: Option<SharedOracle>) {
+        self.oracle = oracle;
+    }
+
+    fn set_contempt(&mut self, contempt: f32) {
+        self.contempt = contempt.clamp(-1.0, 1.0);
+    }
+
+    fn set_game_history(&mut self, history: &[Board]) {
+        self.history = history.to_vec();
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.telemetry
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.telemetry = Telemetry::default();
+    }
+
+    fn principal_variation(&self, board: &Board) -> Vec<(usize, Direction)> {
+        let mut principal_variation = vec![];
+        let Some(mut node_index) = self.graph.node_indices().next() else {
+            return principal_variation;
+        };
+        if &self.graph.node_weight(node_index).unwrap().board != board {
+            return principal_variation;
+        }
+        let mut maximizing_player = true;
+        for depth_remaining in (1..=self.depth).rev() {
+            let mut best: Option<(isize, NodeIndex, (usize, Direction))> = None;
+            for edge in self.graph.edges(node_index) {
+                let score = self.minmax_score(edge.target(), depth_remaining - 1, isize::MIN, isize::MAX, !maximizing_player);
+                let is_better = match &best {
+                    None => true,
+                    Some((best_score, _, _)) => if maximizing_player { score > *best_score } else { score < *best_score },
+                };
+                if is_better {
+                    best = Some((score, edge.target(), edge.weight().clone()));
                 }
             }
-            to_explore = to_explore_next;
-        }
-        let mut total = 0.0;
-        let mut best_minmax = isize::MIN;
-        let mut all_moves_found = vec![];
-        for edge in self.graph.edges(origin) {
-            let target_node_index = edge.target();
-            let minmax = self.minmax_score(target_node_index, self.depth - 1, isize::MIN, isize::MAX, false);
-            let mut minmax_to_push = minmax;
-            if minmax_to_push == 0 {
-                // small positive value to draw
-                minmax_to_push = 1;
+            match best {
+                None => break,
+                Some((_, target_node_index, best_move)) => {
+                    principal_variation.push(best_move);
+                    node_index = target_node_index;
+                }
             }
-            // don't allow below 0
-            minmax_to_push = minmax_to_push.max(0);
-            total += minmax_to_push as f32;
-            let move_found = edge.weight().clone();
-            all_moves_found.push((minmax_to_push as f32, move_found.0, move_found.1));
-            if minmax > best_minmax {best_minmax = minmax;}
-        }
-        // handle case of certain loss
-        if total < 0.5 {
-            let n_items = all_moves_found.len() as f32;
-            all_moves_found.iter_mut().for_each(|x| x.0 += 1.0 / n_items);
+            maximizing_player = !maximizing_player;
         }
-        else {
-            all_moves_found.iter_mut().for_each(|x| x.0 /= total);
-        }
-
-        let board_eval = best_minmax as f32 / 100.0;
-        (board_eval, all_moves_found)
+        principal_variation
     }
 }
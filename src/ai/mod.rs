@@ -2,7 +2,201 @@ pub mod minmax;
 pub mod mcts;
 pub mod ann;
 pub mod alphazeutreeko;
-use crate::{logic::{Board, Color, Direction}, platform::Platform};
+pub mod strength;
+pub mod any;
+pub mod selector;
+pub mod book;
+pub mod oracle;
+pub mod statscache;
+pub mod searchtree;
+pub mod committee;
+pub mod baseline;
+pub mod timemanager;
+pub mod value;
+use std::future::Future;
+
+use crate::{logic::{Board, Color, Direction, Move, Pawn}, platform::{LogLevel, Platform, SearchHandle}};
+use oracle::{ProvenResult, SharedOracle};
+use statscache::SharedStatsCache;
+use searchtree::SearchTree;
+use selector::MoveSelector;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoredMove {
+    pub score: f32,
+    pub pawn_index: usize,
+    pub direction: Direction,
+}
+
+impl ScoredMove {
+    pub fn new(score: f32, pawn_index: usize, direction: Direction) -> Self {
+        Self { score, pawn_index, direction }
+    }
+
+    pub fn to_move(&self) -> Move {
+        Move::new(self.pawn_index, self.direction.clone())
+    }
+}
+
+impl From<(f32, usize, Direction)> for ScoredMove {
+    fn from((score, pawn_index, direction): (f32, usize, Direction)) -> Self {
+        Self::new(score, pawn_index, direction)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult {
+    pub value: f32, // Value of the board for the player to move, from -1 to 1
+    pub moves: Vec<ScoredMove>, // scores sum to 1 for MinMax and MCTS
+    pub nodes: usize, // nodes created (MinMax) or iterations run (MCTS)
+    pub depth: usize, // plies fully searched (MinMax) or length of the best line (MCTS)
+    pub pv: Vec<(usize, Direction)>,
+    pub time_ms: f64,
+    pub proven: Option<ProvenResult>, // set when the oracle or the search proves the result, for the side to move
+}
+
+impl SearchResult {
+    // "Green wins in 7: b1-c1 ...", the proven result with its winning line. None unless the result is proven
+    pub fn proven_text(&self, board: &Board) -> Option<String> {
+        let proven = self.proven.as_ref()?;
+        let mover = board.next_player.as_ref()?;
+        let line = board.line_to_notation(&self.pv[..self.pv.len().min(proven.distance())]);
+        if line.is_empty() {
+            return Some(proven.to_text(mover));
+        }
+        Some(format!("{}: {}", proven.to_text(mover), line.join(" ")))
+    }
+}
+
+// Approximate memory of the search trees: petgraph links of a node and of an edge, and the pawns a board keeps on the heap
+pub(crate) const GRAPH_NODE_LINK_BYTES: usize = 8;
+pub(crate) const GRAPH_EDGE_LINK_BYTES: usize = 16;
+pub(crate) const BOARD_HEAP_BYTES: usize = 6 * std::mem::size_of::<Pawn>();
+
+// Counters summed over the searches of an engine, shown by debug panels and the CLI stats command
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Telemetry {
+    pub searches: usize,
+    pub iterations: usize, // nodes created (MinMax) or iterations run (MCTS), as in SearchResult
+    pub total_depth: usize,
+    pub time_ms: f64,
+    pub transposition_lookups: usize,
+    pub transposition_hits: usize,
+    pub cache_lookups: usize, // statistics cache kept across games
+    pub cache_hits: usize,
+    pub ponders: usize,
+    pub ponder_hits: usize, // searches starting from a position already explored while pondering
+    pub network_batches: usize,
+    pub network_positions: usize,
+    pub peak_memory_bytes: usize, // largest approximate memory of a search, its tree and the network weights held. Not summed
+    pub memory_capped: usize, // searches stopped or pruned by the memory cap
+}
+
+impl Telemetry {
+    pub fn record_search(&mut self, result: &SearchResult) {
+        self.searches += 1;
+        self.iterations += result.nodes;
+        self.total_depth += result.depth;
+        self.time_ms += result.time_ms;
+    }
+
+    pub fn record_memory(&mut self, bytes: usize, capped: bool) {
+        self.peak_memory_bytes = self.peak_memory_bytes.max(bytes);
+        self.memory_capped += capped as usize;
+    }
+
+    pub fn add(&mut self, other: &Telemetry) {
+        self.searches += other.searches;
+        self.iterations += other.iterations;
+        self.total_depth += other.total_depth;
+        self.time_ms += other.time_ms;
+        self.transposition_lookups += other.transposition_lookups;
+        self.transposition_hits += other.transposition_hits;
+        self.cache_lookups += other.cache_lookups;
+        self.cache_hits += other.cache_hits;
+        self.ponders += other.ponders;
+        self.ponder_hits += other.ponder_hits;
+        self.network_batches += other.network_batches;
+        self.network_positions += other.network_positions;
+        self.peak_memory_bytes = self.peak_memory_bytes.max(other.peak_memory_bytes);
+        self.memory_capped += other.memory_capped;
+    }
+
+    fn ratio(numerator: usize, denominator: usize) -> Option<f32> {
+        (denominator > 0).then(|| numerator as f32 / denominator as f32)
+    }
+
+    pub fn average_depth(&self) -> Option<f32> {
+        Self::ratio(self.total_depth, self.searches)
+    }
+
+    pub fn transposition_hit_rate(&self) -> Option<f32> {
+        Self::ratio(self.transposition_hits, self.transposition_lookups)
+    }
+
+    pub fn cache_hit_rate(&self) -> Option<f32> {
+        Self::ratio(self.cache_hits, self.cache_lookups)
+    }
+
+    pub fn ponder_hit_rate(&self) -> Option<f32> {
+        Self::ratio(self.ponder_hits, self.ponders)
+    }
+
+    pub fn average_batch_size(&self) -> Option<f32> {
+        Self::ratio(self.network_positions, self.network_batches)
+    }
+
+    // One line per counter, counters the engine never used are left out
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Searches: {}\n", self.searches);
+        if self.searches > 0 {
+            text.push_str(&format!("Iterations: {} ({:.0} per search)\n", self.iterations, self.iterations as f64 / self.searches as f64));
+            text.push_str(&format!("Average depth: {:.1}\n", self.average_depth().unwrap_or_default()));
+            text.push_str(&format!("Search time: {:.0} ms ({:.0} ms per search)\n", self.time_ms, self.time_ms / self.searches as f64));
+        }
+        if let Some(rate) = self.transposition_hit_rate() {
+            text.push_str(&format!("Transposition hits: {} of {} ({:.1}%)\n", self.transposition_hits, self.transposition_lookups, rate * 100.0));
+        }
+        if let Some(rate) = self.cache_hit_rate() {
+            text.push_str(&format!("Statistics cache hits: {} of {} ({:.1}%)\n", self.cache_hits, self.cache_lookups, rate * 100.0));
+        }
+        if let Some(rate) = self.ponder_hit_rate() {
+            text.push_str(&format!("Ponder hits: {} of {} ({:.1}%)\n", self.ponder_hits, self.ponders, rate * 100.0));
+        }
+        if let Some(batch_size) = self.average_batch_size() {
+            text.push_str(&format!("Network batches: {} ({:.1} positions per batch)\n", self.network_batches, batch_size));
+        }
+        if self.peak_memory_bytes > 0 {
+            text.push_str(&format!("Search memory: {:.1} MB at most, cap reached in {} searches\n",
+                self.peak_memory_bytes as f64 / (1024.0 * 1024.0), self.memory_capped));
+        }
+        text
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchProgress {
+    pub fraction: f32, // estimated share of the search budget already spent, from 0 to 1
+    pub nodes: usize, // nodes created or iterations run so far
+    pub best_move: Option<(usize, Direction)>,
+}
+
+// Receives progress reports from a running search and can ask it to stop early
+pub trait SearchObserver {
+    fn on_progress(&self, _progress: &SearchProgress) {}
+
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl SearchObserver for SearchHandle {
+    fn is_cancelled(&self) -> bool {
+        SearchHandle::is_cancelled(self)
+    }
+}
 
 pub trait AI<O: Platform>: Clone {
     fn color(&self) -> &Color;
@@ -13,37 +207,120 @@ pub trait AI<O: Platform>: Clone {
         if board.next_player != Some(self.color().clone()) {
             return None;
         }
-        Some(self.best_move(board, verbose))
+        self.best_move(board, verbose)
+    }
+
+    fn ai_play_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> Option<(usize, Direction)> {
+        if board.next_player != Some(self.color().clone()) {
+            return None;
+        }
+        let result = self.give_all_options_observed(board, verbose, observer);
+        if verbose {
+            O::log(LogLevel::Info, &format!("==Board eval: {}==", result.value));
+        }
+        self.select_move(&result.moves, verbose)
     }
 
-    fn best_move_from_vec(&mut self, moves: &Vec<(f32, usize, Direction)>, verbose: bool) -> (usize, Direction) {
-        let mut best_moves_found = vec![];
-        let mut best_score = 0.0;
-        for option in moves {
+    fn ai_play_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = Option<(usize, Direction)>> {
+        async move {
+            if board.next_player != Some(self.color().clone()) {
+                return None;
+            }
+            let result = self.give_all_options_async(board, verbose, observer).await;
             if verbose {
-                O::print(&format!("Considering move {:?} with score {}", (option.1, option.2.clone()), option.0));
+                O::log(LogLevel::Info, &format!("==Board eval: {}==", result.value));
             }
-            if option.0 > best_score {
-                best_score = option.0;
-                best_moves_found = vec![(option.1, &option.2)];
-            } else if option.0 == best_score {
-                best_moves_found.push((option.1, &option.2));
+            self.select_move(&result.moves, verbose)
+        }
+    }
+
+    // How select_move picks among the scored moves
+    fn move_selector(&self) -> MoveSelector {
+        MoveSelector::ArgMax
+    }
+
+    fn select_move(&self, moves: &[ScoredMove], verbose: bool) -> Option<(usize, Direction)> {
+        if verbose {
+            for option in moves {
+                O::log(LogLevel::Debug, &format!("Considering move {:?} with score {}", (option.pawn_index, option.direction.clone()), option.score));
             }
         }
-        let best_move_found = best_moves_found[O::random_int(best_moves_found.len())];
+        let selected = self.move_selector().select::<O>(moves)?;
         if verbose {
-            O::print(&format!("==Best move found: {:?} with score {}==", best_move_found, best_score));
+            O::log(LogLevel::Info, &format!("==Best move found: {:?} with score {}==", (selected.pawn_index, selected.direction.clone()), selected.score));
         }
-        (best_move_found.0, best_move_found.1.clone())
+        Some((selected.pawn_index, selected.direction.clone()))
     }
 
-    fn best_move(&mut self, board:&Board, verbose: bool) -> (usize, Direction) {
-        let all_options = self.give_all_options(board, verbose);
+    fn best_move(&mut self, board:&Board, verbose: bool) -> Option<(usize, Direction)> {
+        let result = self.give_all_options(board, verbose);
         if verbose {
-            O::print(&format!("==Board eval: {}==", all_options.0));
+            O::log(LogLevel::Info, &format!("==Board eval: {}==", result.value));
         }
-        self.best_move_from_vec(&all_options.1, verbose)
+        self.select_move(&result.moves, verbose)
+    }
+
+    fn give_all_options(&mut self, board:&Board, verbose: bool) -> SearchResult {
+        self.give_all_options_observed(board, verbose, None)
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult;
+
+    // Engines with long searches override this to yield regularly and stop when the observer cancels
+    fn give_all_options_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        async move { self.give_all_options_observed(board, verbose, observer) }
+    }
+
+    // Swap rule: asked after the first move, with the engine holding the color to move.
+    // Swapping gives the engine the position of the opponent, worth it when its own side is losing
+    fn wants_swap(&mut self, board: &Board) -> bool {
+        self.give_all_options(board, false).value < 0.0
+    }
+
+    fn wants_swap_async(&mut self, board: &Board, observer: Option<&dyn SearchObserver>) -> impl Future<Output = bool> {
+        async move { self.give_all_options_async(board, false, observer).await.value < 0.0 }
+    }
+
+    // Caps the nodes (or iterations) a search may create, so strength does not depend on the device speed
+    fn set_node_limit(&mut self, _node_limit: Option<usize>) {}
+
+    // Caps the approximate memory of a search in bytes, below the cap of the platform. Trees stop growing or get pruned there
+    fn set_memory_limit(&mut self, _memory_limit: Option<usize>) {}
+
+    // Exact solver consulted on every new node, proven positions are not searched further
+    fn set_oracle(&mut self, _oracle: Option<SharedOracle>) {}
+
+    // Statistics kept across games, searches start from them and write back what they learned
+    fn set_stats_cache(&mut self, _stats_cache: Option<SharedStatsCache>) {}
+
+    // How the engine values a draw against playing on, from -1 to 1: above 0 it avoids draws as the stronger side should,
+    // below 0 it seeks them. Engines without search ignore it
+    fn set_contempt(&mut self, _contempt: f32) {}
+
+    // Positions of the game before the one searched, oldest first. Going back to one of them is a draw by repetition
+    fn set_game_history(&mut self, _history: &[Board]) {}
+
+    // Keeps searching the position left to the opponent until the observer cancels, so the next search can reuse the tree
+    fn ponder(&mut self, _board: &Board, _observer: &dyn SearchObserver) {}
+
+    fn ponder_async(&mut self, _board: &Board, _observer: &dyn SearchObserver) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    // Best line found by the last search from this board, empty if the engine does not keep a tree
+    fn principal_variation(&self, _board: &Board) -> Vec<(usize, Direction)> {
+        vec![]
+    }
+
+    // Statistics of the last search, None if the engine does not keep a tree
+    fn search_tree(&self, _max_depth: usize) -> Option<SearchTree> {
+        None
+    }
+
+    // Counters of every search since the engine was created or the telemetry reset
+    fn telemetry(&self) -> Telemetry {
+        Telemetry::default()
     }
 
-    fn give_all_options(&mut self, board:&Board, verbose: bool) -> (f32, Vec<(f32, usize, Direction)>);
+    fn reset_telemetry(&mut self) {}
 }
\ No newline at end of file
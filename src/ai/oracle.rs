@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use crate::{
+    logic::{Board, Color, Direction},
+    platform::Platform,
+};
+use super::{ScoredMove, SearchResult, value::Value};
+
+// Proven outcome for the side to move, with the number of plies until the game ends with best play
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProvenResult {
+    Win(usize),
+    Loss(usize),
+}
+
+impl ProvenResult {
+    pub fn distance(&self) -> usize {
+        match self {
+            ProvenResult::Win(distance) | ProvenResult::Loss(distance) => *distance,
+        }
+    }
+
+    // "Green wins in 7" for the given side to move, the distance counted in plies
+    pub fn to_text(&self, mover: &Color) -> String {
+        let winner = match self {
+            ProvenResult::Win(_) => mover.clone(),
+            ProvenResult::Loss(_) => mover.other_color(),
+        };
+        format!("{:?} wins in {}", winner, self.distance())
+    }
+
+    // Result of the position after the best move, for the next side to move
+    pub fn after_best_move(&self) -> Self {
+        match self {
+            ProvenResult::Win(distance) => ProvenResult::Loss(distance.saturating_sub(1)),
+            ProvenResult::Loss(distance) => ProvenResult::Win(distance.saturating_sub(1)),
+        }
+    }
+
+    // Result for the player who made the move leading to this position
+    pub fn for_previous_player(&self) -> Self {
+        match self {
+            ProvenResult::Win(distance) => ProvenResult::Loss(distance + 1),
+            ProvenResult::Loss(distance) => ProvenResult::Win(distance + 1),
+        }
+    }
+}
+
+// Anything that can prove positions exactly, consulted by the engines when they create a node
+pub trait ExactOracle {
+    // None when the position is not proven
+    fn probe(&self, board: &Board) -> Option<ProvenResult>;
+}
+
+pub type SharedOracle = Arc<dyn ExactOracle + Send + Sync>;
+
+// Brute force search of all lines up to a few plies, only proves short wins and losses
+#[derive(Clone, Debug)]
+pub struct ShallowSolver {
+    depth: usize,
+}
+
+impl ShallowSolver {
+    pub fn new(depth: usize) -> Self {
+        Self { depth }
+    }
+
+    fn solve(&self, board: &Board, depth_remaining: usize) -> Option<ProvenResult> {
+        if board.winner().is_some() {
+            // the previous player aligned their pawns
+            return Some(ProvenResult::Loss(0));
+        }
+        if depth_remaining == 0 {
+            return None;
+        }
+        let mut fastest_win: Option<usize> = None;
+        let mut slowest_loss = Some(0);
+        for (_, _, new_board) in board.get_all_valid_directions_and_resulting_boards() {
+            match self.solve(&new_board, depth_remaining - 1).map(|result| result.for_previous_player()) {
+                Some(ProvenResult::Win(distance)) => {
+                    fastest_win = Some(fastest_win.map_or(distance, |fastest| fastest.min(distance)));
+                    if distance == 1 {
+                        break;
+                    }
+                }
+                Some(ProvenResult::Loss(distance)) => slowest_loss = slowest_loss.map(|slowest| distance.max(slowest)),
+                None => slowest_loss = None,
+            }
+        }
+        match (fastest_win, slowest_loss) {
+            (Some(distance), _) => Some(ProvenResult::Win(distance)),
+            // without any move the game cannot end, so a position is lost only if some move was found
+            (None, Some(distance)) if distance > 0 => Some(ProvenResult::Loss(distance)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ShallowSolver {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl ExactOracle for ShallowSolver {
+    fn probe(&self, board: &Board) -> Option<ProvenResult> {
+        self.solve(board, self.depth)
+    }
+}
+
+// Best move in a proven position: fastest win, or slowest loss. None if the oracle does not prove a consistent line
+fn proven_move(oracle: &dyn ExactOracle, board: &Board, result: &ProvenResult) -> Option<(usize, Direction)> {
+    board.get_all_valid_directions_and_resulting_boards()
+        .into_iter()
+        .find(|(_, _, new_board)| {
+            oracle.probe(new_board).is_some_and(|child_result| &child_result.for_previous_player() == result)
+        })
+        .map(|(pawn_index, direction, _)| (pawn_index, direction))
+}
+
+// Search result of a proven position, so the engines can answer without searching
+pub fn proven_search_result<O: Platform>(oracle: &dyn ExactOracle, board: &Board, start_time: f64) -> Option<SearchResult> {
+    let result = oracle.probe(board)?;
+    let best_move = proven_move(oracle, board, &result)?;
+    let moves = board.get_all_valid_directions_and_resulting_boards()
+        .into_iter()
+        .map(|(pawn_index, direction, _)| {
+            let score = if (pawn_index, direction.clone()) == best_move { 1.0 } else { 0.0 };
+            ScoredMove::new(score, pawn_index, direction)
+        })
+        .collect();
+
+    let mut pv = vec![];
+    let mut line_board = board.clone();
+    let mut line_result = result.clone();
+    while line_result.distance() > 0 {
+        let Some((pawn_index, direction)) = proven_move(oracle, &line_board, &line_result) else {
+            break;
+        };
+        line_board.move_pawn_until_blocked(pawn_index, &direction);
+        pv.push((pawn_index, direction));
+        line_result = line_result.after_best_move();
+    }
+
+    Some(SearchResult {
+        value: Value::from_proven(&result).get(),
+        moves,
+        nodes: 0,
+        depth: result.distance(),
+        pv,
+        time_ms: O::now() - start_time,
+        proven: Some(result),
+    })
+}
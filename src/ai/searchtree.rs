@@ -0,0 +1,56 @@
+use super::oracle::ProvenResult;
+
+// Snapshot of a search tree, moves are given in the frame of the searched board
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchTree {
+    pub notation: Option<String>, // move leading to this node, None for the root
+    pub visits: usize,
+    pub q: f32, // mean value for the player who made the move (for the player to move at the root), from -1 to 1
+    pub prior: f32, // policy prior of the move, 0 for the root
+    pub proven: Option<ProvenResult>,
+    pub children: Vec<SearchTree>, // most visited first
+}
+
+impl SearchTree {
+    pub fn number_of_nodes(&self) -> usize {
+        1 + self.children.iter().map(|child| child.number_of_nodes()).sum::<usize>()
+    }
+
+    pub fn to_json(&self) -> String {
+        let notation = match &self.notation {
+            Some(notation) => format!("\"{}\"", notation),
+            None => "null".to_string(),
+        };
+        let proven = match &self.proven {
+            Some(ProvenResult::Win(distance)) => format!("\"win in {}\"", distance),
+            Some(ProvenResult::Loss(distance)) => format!("\"loss in {}\"", distance),
+            None => "null".to_string(),
+        };
+        let children: Vec<String> = self.children.iter().map(|child| child.to_json()).collect();
+        format!(
+            "{{\"move\":{},\"visits\":{},\"q\":{:.4},\"prior\":{:.4},\"proven\":{},\"children\":[{}]}}",
+            notation, self.visits, self.q, self.prior, proven, children.join(",")
+        )
+    }
+
+    // Graphviz source, e.g. `dot -Tsvg tree.dot -o tree.svg`
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph search {".to_string(), "    node [shape=box];".to_string()];
+        let mut next_id = 0;
+        self.write_dot(&mut lines, &mut next_id);
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn write_dot(&self, lines: &mut Vec<String>, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+        lines.push(format!("    n{} [label=\"{}\\nN={} Q={:.3}\"];", id, self.notation.as_deref().unwrap_or("root"), self.visits, self.q));
+        for child in self.children.iter() {
+            let child_id = child.write_dot(lines, next_id);
+            lines.push(format!("    n{} -> n{} [label=\"P={:.3}\"];", id, child_id, child.prior));
+        }
+        id
+    }
+}
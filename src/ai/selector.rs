@@ -0,0 +1,58 @@
+use crate::platform::Platform;
+use super::ScoredMove;
+
+// How a move is picked among the scored moves of a search result
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveSelector {
+    // highest score, ties broken at random
+    #[default]
+    ArgMax,
+    // random move with probability proportional to its score
+    Proportional,
+    // random move with probability proportional to score^(1/temperature), argmax when the temperature is 0
+    Temperature(f32),
+}
+
+impl MoveSelector {
+    // None only when there is no move. Negative scores count as 0, and all-zero scores give a uniform choice
+    pub fn select<'a, O: Platform>(&self, moves: &'a [ScoredMove]) -> Option<&'a ScoredMove> {
+        match self {
+            MoveSelector::ArgMax => select_argmax::<O>(moves),
+            MoveSelector::Proportional => sample::<O>(moves, 1.0),
+            MoveSelector::Temperature(temperature) if *temperature <= 0.0 => select_argmax::<O>(moves),
+            MoveSelector::Temperature(temperature) => sample::<O>(moves, 1.0 / temperature),
+        }
+    }
+}
+
+fn select_argmax<'a, O: Platform>(moves: &'a [ScoredMove]) -> Option<&'a ScoredMove> {
+    let best_score = moves.iter().map(|x| x.score).fold(f32::NEG_INFINITY, f32::max);
+    let best_moves: Vec<&ScoredMove> = moves.iter().filter(|x| x.score == best_score).collect();
+    if best_moves.is_empty() {
+        // only NaN scores
+        return moves.get(O::random_int(moves.len()));
+    }
+    Some(best_moves[O::random_int(best_moves.len())])
+}
+
+fn sample<'a, O: Platform>(moves: &'a [ScoredMove], exponent: f32) -> Option<&'a ScoredMove> {
+    // scaling by the best score keeps high exponents from overflowing or underflowing
+    let best_score = moves.iter().map(|x| x.score).fold(0.0, f32::max);
+    let weights: Vec<f32> = moves.iter()
+        .map(|x| if x.score > 0.0 { (x.score / best_score).powf(exponent) } else { 0.0 })
+        .collect();
+    let total: f32 = weights.iter().sum();
+    if !(total > 0.0 && total.is_finite()) {
+        return moves.get(O::random_int(moves.len()));
+    }
+    let mut threshold = O::random() * total;
+    for (scored_move, weight) in moves.iter().zip(weights.iter()) {
+        if threshold < *weight {
+            return Some(scored_move);
+        }
+        threshold -= weight;
+    }
+    // rounding errors can leave a tiny remainder
+    moves.iter().zip(weights.iter()).rev().find(|(_, weight)| **weight > 0.0).map(|(scored_move, _)| scored_move)
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::platform::{Platform, load_text, save_text};
+
+// Positions searched less than this are not worth keeping
+pub const MIN_VISITS_STORED: usize = 20;
+
+// Warm-started nodes count at most this many visits, so a new search can still change its mind
+pub const MAX_WARM_START_VISITS: usize = 1000;
+
+// Keeps the file and the browser storage small, the most visited positions are kept
+pub const MAX_POSITIONS_STORED: usize = 20000;
+
+// Statistics of one position from the point of view of the player to move
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionStats {
+    pub visits: usize,
+    pub wins: f32,
+    pub prior: f32, // share of the parent visits that went to this position
+}
+
+// MCTS statistics kept across games, keyed by the canonical hash of the position.
+// Hashes come from the standard hasher, files may need to be rebuilt after a compiler update
+#[derive(Clone, Debug, Default)]
+pub struct StatsCache {
+    positions: HashMap<u64, PositionStats>,
+}
+
+pub type SharedStatsCache = Arc<Mutex<StatsCache>>;
+
+impl StatsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shared(self) -> SharedStatsCache {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn get(&self, board_hash: u64) -> Option<&PositionStats> {
+        self.positions.get(&board_hash)
+    }
+
+    // Search trees start from the cached statistics, so the most visited record replaces the others
+    pub fn record(&mut self, board_hash: u64, stats: PositionStats) {
+        if stats.visits < MIN_VISITS_STORED {
+            return;
+        }
+        let stored = self.positions.entry(board_hash).or_default();
+        if stats.visits > stored.visits {
+            *stored = stats;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // One position per line: hash visits wins prior
+    pub fn to_text(&self) -> String {
+        let mut positions: Vec<(&u64, &PositionStats)> = self.positions.iter().collect();
+        positions.sort_by(|a, b| b.1.visits.cmp(&a.1.visits).then(a.0.cmp(b.0)));
+        positions.iter()
+            .take(MAX_POSITIONS_STORED)
+            .map(|(board_hash, stats)| format!("{} {} {} {}\n", board_hash, stats.visits, stats.wins, stats.prior))
+            .collect()
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut cache = Self::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split_whitespace();
+            let board_hash = fields.next()?.parse().ok()?;
+            let stats = PositionStats {
+                visits: fields.next()?.parse().ok()?,
+                wins: fields.next()?.parse().ok()?,
+                prior: fields.next()?.parse().ok()?,
+            };
+            cache.positions.insert(board_hash, stats);
+        }
+        Some(cache)
+    }
+
+    // Native front-ends keep the cache in a file, a missing file gives an empty cache
+    pub fn load_file(path: &str) -> Option<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::from_text(&text),
+            Err(_) => Some(Self::new()),
+        }
+    }
+
+    pub fn save_file(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    // The web front-end keeps the cache in the storage of the platform, a missing entry gives an empty cache
+    pub async fn load_storage<O: Platform>(key: &str) -> Option<Self> {
+        match load_text::<O>(key).await {
+            Some(text) => Self::from_text(&text),
+            None => Some(Self::new()),
+        }
+    }
+
+    pub async fn save_storage<O: Platform>(&self, key: &str) -> bool {
+        save_text::<O>(key, &self.to_text()).await
+    }
+}
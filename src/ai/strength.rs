@@ -0,0 +1,228 @@
+use std::future::Future;
+use std::marker::PhantomData;
+
+use crate::{
+    logic::{Board, Color, Direction},
+    platform::{LogLevel, Platform},
+};
+use super::{AI, SearchObserver, SearchResult, Telemetry, oracle::SharedOracle, selector::MoveSelector, searchtree::SearchTree, statscache::SharedStatsCache};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineKind {
+    Random,
+    Greedy,
+    MinMax,
+    MCTS,
+    AlphaZeutreeko,
+}
+
+impl EngineKind {
+    // Name understood by AnyAI::from_name
+    pub fn engine_name(&self) -> &'static str {
+        match self {
+            EngineKind::Random => "random",
+            EngineKind::Greedy => "greedy",
+            EngineKind::MinMax => "minmax",
+            EngineKind::MCTS => "mcts",
+            EngineKind::AlphaZeutreeko => "alphazeutreeko",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StrengthLevel {
+    pub name: &'static str,
+    pub estimated_elo: u32, // rough estimate, to be refined with engine matches. Random play is the 0 anchor
+    pub engine: EngineKind, // engine used when the player lets the level choose
+    pub difficulty: usize, // passed to the engine constructor, depth for MinMax and time for MCTS
+    pub node_limit: Option<usize>,
+    pub blunder_probability: f32, // chance to play a random legal move instead of the engine choice
+}
+
+pub const STRENGTH_LEVELS: [StrengthLevel; 8] = [
+    StrengthLevel {
+        name: "Random",
+        estimated_elo: 0,
+        engine: EngineKind::Random,
+        difficulty: 1,
+        node_limit: None,
+        blunder_probability: 0.0,
+    },
+    StrengthLevel {
+        name: "First steps",
+        estimated_elo: 300,
+        engine: EngineKind::Greedy,
+        difficulty: 1,
+        node_limit: None,
+        blunder_probability: 0.0,
+    },
+    StrengthLevel {
+        name: "Beginner",
+        estimated_elo: 600,
+        engine: EngineKind::MCTS,
+        difficulty: 1,
+        node_limit: Some(200),
+        blunder_probability: 0.35,
+    },
+    StrengthLevel {
+        name: "Novice",
+        estimated_elo: 900,
+        engine: EngineKind::MCTS,
+        difficulty: 2,
+        node_limit: Some(1000),
+        blunder_probability: 0.2,
+    },
+    StrengthLevel {
+        name: "Casual",
+        estimated_elo: 1200,
+        engine: EngineKind::MinMax,
+        difficulty: 3,
+        node_limit: None,
+        blunder_probability: 0.1,
+    },
+    StrengthLevel {
+        name: "Club",
+        estimated_elo: 1500,
+        engine: EngineKind::MinMax,
+        difficulty: 5,
+        node_limit: None,
+        blunder_probability: 0.03,
+    },
+    StrengthLevel {
+        name: "Expert",
+        estimated_elo: 1800,
+        engine: EngineKind::AlphaZeutreeko,
+        difficulty: 4,
+        node_limit: None,
+        blunder_probability: 0.0,
+    },
+    StrengthLevel {
+        name: "Master",
+        estimated_elo: 2100,
+        engine: EngineKind::AlphaZeutreeko,
+        difficulty: 6,
+        node_limit: None,
+        blunder_probability: 0.0,
+    },
+];
+
+// Levels are numbered from 1, out of range values are clamped
+pub fn strength_level(level: usize) -> &'static StrengthLevel {
+    &STRENGTH_LEVELS[level.clamp(1, STRENGTH_LEVELS.len()) - 1]
+}
+
+// Wraps an engine to play at a strength level: limited search and occasional random moves
+#[derive(Clone)]
+pub struct Calibrated<A: AI<O>, O: Platform> {
+    inner: A,
+    blunder_probability: f32,
+    _platform: PhantomData<O>,
+}
+
+impl<A: AI<O>, O: Platform> Calibrated<A, O> {
+    pub fn with_level(color: Color, level: &StrengthLevel) -> Self {
+        Self::wrap(A::new(color, level.difficulty), level)
+    }
+
+    // The engine should have been created with level.difficulty
+    pub fn wrap(mut inner: A, level: &StrengthLevel) -> Self {
+        inner.set_node_limit(level.node_limit);
+        Self {
+            inner,
+            blunder_probability: level.blunder_probability,
+            _platform: PhantomData,
+        }
+    }
+
+    fn maybe_blunder(&self, mut result: SearchResult, verbose: bool) -> SearchResult {
+        if result.moves.is_empty() || O::random() >= self.blunder_probability {
+            return result;
+        }
+        if verbose {
+            O::log(LogLevel::Info, "Calibrated AI picks a random move");
+        }
+        // equal scores make the argmax selector pick uniformly
+        let uniform_score = 1.0 / result.moves.len() as f32;
+        result.moves.iter_mut().for_each(|x| x.score = uniform_score);
+        result
+    }
+}
+
+impl<A: AI<O>, O: Platform> AI<O> for Calibrated<A, O> {
+    // difficulty is the strength level, from 1 to STRENGTH_LEVELS.len(), see strength_level for the clamping
+    fn new(color: Color, difficulty: usize) -> Self {
+        Self::with_level(color, strength_level(difficulty))
+    }
+
+    fn color(&self) -> &Color {
+        self.inner.color()
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.inner.set_color(color);
+    }
+
+    fn move_selector(&self) -> MoveSelector {
+        self.inner.move_selector()
+    }
+
+    fn give_all_options_observed(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> SearchResult {
+        let result = self.inner.give_all_options_observed(board, verbose, observer);
+        self.maybe_blunder(result, verbose)
+    }
+
+    fn give_all_options_async(&mut self, board: &Board, verbose: bool, observer: Option<&dyn SearchObserver>) -> impl Future<Output = SearchResult> {
+        async move {
+            let result = self.inner.give_all_options_async(board, verbose, observer).await;
+            self.maybe_blunder(result, verbose)
+        }
+    }
+
+    fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        self.inner.set_node_limit(node_limit);
+    }
+
+    fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.inner.set_memory_limit(memory_limit);
+    }
+
+    fn set_oracle(&mut self, oracle: Option<SharedOracle>) {
+        self.inner.set_oracle(oracle);
+    }
+
+    fn set_stats_cache(&mut self, stats_cache: Option<SharedStatsCache>) {
+        self.inner.set_stats_cache(stats_cache);
+    }
+
+    fn set_contempt(&mut self, contempt: f32) {
+        self.inner.set_contempt(contempt);
+    }
+
+    fn set_game_history(&mut self, history: &[Board]) {
+        self.inner.set_game_history(history);
+    }
+
+    fn ponder(&mut self, board: &Board, observer: &dyn SearchObserver) {
+        self.inner.ponder(board, observer);
+    }
+
+    fn ponder_async(&mut self, board: &Board, observer: &dyn SearchObserver) -> impl Future<Output = ()> {
+        self.inner.ponder_async(board, observer)
+    }
+
+    fn principal_variation(&self, board: &Board) -> Vec<(usize, Direction)> {
+        self.inner.principal_variation(board)
+    }
+
+    fn search_tree(&self, max_depth: usize) -> Option<SearchTree> {
+        self.inner.search_tree(max_depth)
+    }
+
+    fn telemetry(&self) -> Telemetry {
+        self.inner.telemetry()
+    }
+
+    fn reset_telemetry(&mut self) {
+        self.inner.reset_telemetry();
+    }
+}
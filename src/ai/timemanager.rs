@@ -0,0 +1,128 @@
+use crate::logic::{Board, Move};
+
+// Own moves a game is expected to last, the budget is spread over them
+pub const DEFAULT_EXPECTED_MOVES: usize = 30;
+
+// Moves still expected once a game outlasts expected_moves, the remaining budget keeps being shared
+const MIN_MOVES_LEFT: usize = 8;
+
+// Share of the base time given to a move with a single sensible choice
+const FORCED_MOVE_FRACTION: f64 = 0.1;
+
+// An unstable search gets up to this many times its move time, but never more than this share of what is left
+const MAX_EXTENSION_FACTOR: f64 = 2.5;
+const MAX_REMAINING_SHARE: f64 = 0.25;
+
+const MIN_MOVE_TIME_MS: f64 = 5.0;
+
+// Root samples of SearchStability, only the last STABILITY_WINDOW are looked at
+const STABILITY_WINDOW: usize = 8;
+const MIN_STABILITY_SAMPLES: usize = 4;
+const MAX_FLIP_RATE: f32 = 0.25;
+const MAX_VALUE_DEVIATION: f32 = 0.08;
+
+// Spreads a per-game budget over the moves of an engine instead of a fixed time per move:
+// forced moves are played almost at once, searches whose best move or value keeps changing get extended
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeManager {
+    pub game_budget_ms: f64,
+    pub expected_moves: usize,
+    spent_ms: f64,
+    moves: usize,
+}
+
+impl TimeManager {
+    pub fn new(game_budget_ms: f64) -> Self {
+        Self {
+            game_budget_ms,
+            expected_moves: DEFAULT_EXPECTED_MOVES,
+            spent_ms: 0.0,
+            moves: 0,
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        self.spent_ms = 0.0;
+        self.moves = 0;
+    }
+
+    pub fn remaining_ms(&self) -> f64 {
+        (self.game_budget_ms - self.spent_ms).max(0.0)
+    }
+
+    // Even share of the remaining budget between the moves still expected
+    pub fn base_time_ms(&self) -> f64 {
+        let moves_left = self.expected_moves.saturating_sub(self.moves).max(MIN_MOVES_LEFT);
+        (self.remaining_ms() / moves_left as f64).max(MIN_MOVE_TIME_MS)
+    }
+
+    // Time to start the search of this position with
+    pub fn move_time_ms(&self, board: &Board) -> f64 {
+        if is_forced(board) {
+            (self.base_time_ms() * FORCED_MOVE_FRACTION).max(MIN_MOVE_TIME_MS)
+        } else {
+            self.base_time_ms()
+        }
+    }
+
+    // Time of a search found unstable after starting with move_time_ms
+    pub fn extended_time_ms(&self, move_time_ms: f64) -> f64 {
+        (move_time_ms * MAX_EXTENSION_FACTOR).min(self.remaining_ms() * MAX_REMAINING_SHARE).max(move_time_ms)
+    }
+
+    pub fn record_move(&mut self, time_ms: f64) {
+        self.spent_ms += time_ms;
+        self.moves += 1;
+    }
+}
+
+// A single legal move, a win in one, or a single move that does not let the opponent win at once
+pub fn is_forced(board: &Board) -> bool {
+    let Some(mover) = &board.next_player else {
+        return true;
+    };
+    board.legal_moves().count() <= 1 || !board.winning_moves(mover).is_empty() || board.safe_moves().len() == 1
+}
+
+// Best move and value of the root sampled during a search
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchStability {
+    best_moves: Vec<Option<Move>>,
+    values: Vec<f32>,
+}
+
+impl SearchStability {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, best_move: Option<Move>, value: f32) {
+        self.best_moves.push(best_move);
+        self.values.push(value);
+    }
+
+    // Share of the recent samples where the best move changed
+    pub fn flip_rate(&self) -> f32 {
+        let recent = &self.best_moves[self.best_moves.len().saturating_sub(STABILITY_WINDOW)..];
+        if recent.len() < 2 {
+            return 0.0;
+        }
+        let flips = recent.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        flips as f32 / (recent.len() - 1) as f32
+    }
+
+    // Standard deviation of the recent root values
+    pub fn value_deviation(&self) -> f32 {
+        let recent = &self.values[self.values.len().saturating_sub(STABILITY_WINDOW)..];
+        if recent.is_empty() {
+            return 0.0;
+        }
+        let mean = recent.iter().sum::<f32>() / recent.len() as f32;
+        (recent.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / recent.len() as f32).sqrt()
+    }
+
+    pub fn is_unstable(&self) -> bool {
+        self.values.len() >= MIN_STABILITY_SAMPLES
+            && (self.flip_rate() > MAX_FLIP_RATE || self.value_deviation() > MAX_VALUE_DEVIATION)
+    }
+}
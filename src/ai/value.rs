@@ -0,0 +1,68 @@
+use std::fmt;
+
+use crate::logic::Color;
+use super::oracle::ProvenResult;
+
+// Evaluation of a board for the player to move: 1 a win, -1 a loss, 0 a draw or an even position.
+// Engines, the network and the training targets all work in this perspective, a position is worth the opposite
+// for the player who moved into it. SearchResult::value is the f32 of the value of the searched board, engines search
+// for the player to move
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Value(f32);
+
+impl Value {
+    pub const WIN: Value = Value(1.0);
+    pub const DRAW: Value = Value(0.0);
+    pub const LOSS: Value = Value(-1.0);
+
+    // Out of range values are clamped and NaN counts as a draw, so one broken evaluation cannot poison a tree
+    pub fn new(value: f32) -> Self {
+        if value.is_nan() { Self::DRAW } else { Self(value.clamp(-1.0, 1.0)) }
+    }
+
+    // The value head ends with a tanh, already in range
+    pub fn from_network(output: f32) -> Self {
+        Self::new(output)
+    }
+
+    // Score of MinMax, win_score for a win on the board
+    pub fn from_minmax(score: isize, win_score: isize) -> Self {
+        Self::new(score as f32 / win_score as f32)
+    }
+
+    // How the game ended for the player, no winner is a draw
+    pub fn from_outcome(winner: Option<&Color>, player: &Color) -> Self {
+        match winner {
+            Some(winner) if winner == player => Self::WIN,
+            Some(_) => Self::LOSS,
+            None => Self::DRAW,
+        }
+    }
+
+    pub fn from_proven(proven: &ProvenResult) -> Self {
+        match proven {
+            ProvenResult::Win(_) => Self::WIN,
+            ProvenResult::Loss(_) => Self::LOSS,
+        }
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+
+    // The same position for the other player, the flip made at every ply of a backpropagation
+    pub fn for_opponent(self) -> Self {
+        Self(-self.0)
+    }
+
+    // Expected score from 0 to 1, a draw counting half
+    pub fn win_probability(self) -> f32 {
+        (self.0 + 1.0) / 2.0
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
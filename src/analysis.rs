@@ -0,0 +1,317 @@
+use crate::{
+    ai::{AI, ScoredMove, SearchResult, oracle::ProvenResult},
+    gamerecord::GameRecord,
+    logic::{Board, Color, Direction, Move, Position},
+    platform::Platform,
+};
+
+// Evaluation drop, for the player who moved, above which a move is reported as a blunder
+pub const DEFAULT_BLUNDER_THRESHOLD: f32 = 0.3;
+
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    pub board_eval: f32,
+    pub moves: Vec<ScoredMove>,
+    pub principal_variation: Vec<(usize, Direction)>,
+    pub nodes: usize,
+    pub depth: usize,
+    pub time_ms: f64,
+    pub proven: Option<ProvenResult>, // for the side to move, when the engine proved the result
+}
+
+impl Analysis {
+    pub fn principal_variation_notation(&self, board: &Board) -> Vec<String> {
+        board.line_to_notation(&self.principal_variation)
+    }
+
+    pub fn to_text(&self, board: &Board) -> String {
+        let mut text = format!("Evaluation: {:.3}\n", self.board_eval);
+        if let (Some(proven), Some(mover)) = (&self.proven, &board.next_player) {
+            text.push_str(&format!("Proven: {}\n", proven.to_text(mover)));
+        }
+        text.push_str(&format!("Searched {} nodes, depth {}, in {:.0} ms\n", self.nodes, self.depth, self.time_ms));
+        text.push_str(&format!("Best line: {}\n", self.principal_variation_notation(board).join(" ")));
+        text.push_str("Moves:\n");
+        for scored_move in self.moves.iter() {
+            let notation = board.move_to_notation(&scored_move.to_move()).unwrap_or_default();
+            text.push_str(&format!("  {} {:.3}\n", notation, scored_move.score));
+        }
+        let arrows = self.arrows(board);
+        if !arrows.is_empty() {
+            text.push_str(&format!("Arrows: {}\n", arrows_to_text(&arrows)));
+        }
+        text
+    }
+
+    // Best move of the engine, then the wins the opponent threatens if the side to move ignores them
+    pub fn arrows(&self, board: &Board) -> Vec<Arrow> {
+        let mut arrows = vec![];
+        if let Some(best_move) = self.moves.first() {
+            arrows.extend(Arrow::from_move(board, &best_move.to_move(), ArrowKind::BestMove));
+        }
+        if let Some(mover) = &board.next_player {
+            for threat in board.winning_moves(&mover.other_color()) {
+                arrows.extend(Arrow::from_move(board, &threat, ArrowKind::Threat));
+            }
+        }
+        arrows
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArrowKind {
+    BestMove,
+    Threat,
+    User,
+}
+
+impl ArrowKind {
+    pub const ALL: [ArrowKind; 3] = [ArrowKind::BestMove, ArrowKind::Threat, ArrowKind::User];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ArrowKind::BestMove => "Best move",
+            ArrowKind::Threat => "Threat",
+            ArrowKind::User => "Note",
+        }
+    }
+
+    // Colour letter of the PGN arrow command, as chess sites write it
+    fn letter(&self) -> char {
+        match self {
+            ArrowKind::BestMove => 'G',
+            ArrowKind::Threat => 'R',
+            ArrowKind::User => 'B',
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.letter() == letter)
+    }
+}
+
+// Arrow drawn over the board from the square a pawn leaves to the square it stops on
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arrow {
+    pub from: Position,
+    pub to: Position,
+    pub kind: ArrowKind,
+}
+
+impl Arrow {
+    // The move may belong to either color, whoever is to move. None if the pawn cannot move that way
+    pub fn from_move(board: &Board, game_move: &Move, kind: ArrowKind) -> Option<Self> {
+        let mut turn = board.clone();
+        turn.next_player = Some(board.pawns.get(game_move.pawn_index)?.color.clone());
+        let outcome = turn.apply_move(game_move.pawn_index, &game_move.direction).ok()?;
+        Some(Self { from: outcome.from, to: outcome.to, kind })
+    }
+
+    // Colour letter then both squares, Gb1b4
+    pub fn to_text(&self) -> String {
+        format!("{}{}{}", self.kind.letter(), self.from.to_notation(), self.to.to_notation())
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut chars = text.chars();
+        let kind = ArrowKind::from_letter(chars.next()?)?;
+        let squares = chars.as_str();
+        // the row of the first square ends before the second column letter
+        let split = squares.char_indices().skip(1).find(|(_, c)| c.is_ascii_alphabetic())?.0;
+        Some(Self { from: Position::from_notation(&squares[..split])?, to: Position::from_notation(&squares[split..])?, kind })
+    }
+}
+
+pub fn arrows_to_text(arrows: &[Arrow]) -> String {
+    arrows.iter().map(Arrow::to_text).collect::<Vec<_>>().join(",")
+}
+
+pub fn arrows_from_text(text: &str) -> Vec<Arrow> {
+    text.split(',').filter_map(|arrow| Arrow::from_text(arrow.trim())).collect()
+}
+
+// Runs the engine for the side to move and collects its evaluation, per-move scores and best line
+pub fn analyze<O: Platform, A: AI<O>>(ai: &mut A, board: &Board, verbose: bool) -> Analysis {
+    if let Some(color) = &board.next_player {
+        if ai.color() != color {
+            ai.set_color(color.clone());
+        }
+    }
+    let result = ai.give_all_options(board, verbose);
+    to_analysis(result)
+}
+
+pub async fn analyze_async<O: Platform, A: AI<O>>(ai: &mut A, board: &Board) -> Analysis {
+    if let Some(color) = &board.next_player {
+        if ai.color() != color {
+            ai.set_color(color.clone());
+        }
+    }
+    let result = ai.give_all_options_async(board, false, None).await;
+    to_analysis(result)
+}
+
+// Score mass of the moves landing on each square, row by row, scaled so the hottest square is 1.
+// Works for any scores summing to 1, the policy of the network or the MinMax move scores
+pub fn landing_heat_map(board: &Board, moves: &[ScoredMove]) -> Vec<f32> {
+    let mut heat_map = vec![0.0; board.number_of_rows * board.number_of_columns];
+    for scored_move in moves.iter() {
+        let mut new_board = board.clone();
+        let Ok(outcome) = new_board.apply_move(scored_move.pawn_index, &scored_move.direction) else {
+            continue;
+        };
+        heat_map[outcome.to.row * board.number_of_columns + outcome.to.column] += scored_move.score.max(0.0);
+    }
+    let hottest = heat_map.iter().cloned().fold(0.0, f32::max);
+    if hottest > 0.0 {
+        heat_map.iter_mut().for_each(|heat| *heat /= hottest);
+    }
+    heat_map
+}
+
+fn to_analysis(mut result: SearchResult) -> Analysis {
+    result.moves.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Analysis {
+        board_eval: result.value,
+        moves: result.moves,
+        principal_variation: result.pv,
+        nodes: result.nodes,
+        depth: result.depth,
+        time_ms: result.time_ms,
+        proven: result.proven,
+    }
+}
+
+// Evaluates every move of the game that has no evaluation yet, and notes the engine choice when the game move differs.
+// Returns false if the record contains an illegal move
+pub fn annotate_game<O: Platform, A: AI<O>>(ai: &mut A, record: &mut GameRecord) -> bool {
+    let Some(boards) = record.boards() else {
+        return false;
+    };
+    for (move_record, board) in record.moves.iter_mut().zip(boards.iter()) {
+        if move_record.evaluation.is_some() {
+            continue;
+        }
+        let analysis = analyze(ai, board, false);
+        move_record.evaluation = Some(analysis.board_eval);
+        move_record.depth = Some(analysis.depth);
+        move_record.time_ms = Some(analysis.time_ms);
+        let best_notation = analysis.moves.first().and_then(|best_move| board.move_to_notation(&best_move.to_move()));
+        if let Some(best_notation) = best_notation.filter(|notation| *notation != move_record.notation) {
+            if move_record.comment.is_none() {
+                move_record.comment = Some(format!("engine prefers {}", best_notation));
+            }
+        }
+        if move_record.arrows.is_empty() {
+            move_record.arrows = analysis.arrows(board);
+        }
+    }
+    true
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveReview {
+    pub ply: usize, // index of the move in the game, from 0
+    pub color: Color,
+    pub notation: String,
+    pub best_evaluation: f32, // evaluation before the move, assuming the best move is played
+    pub played_evaluation: f32, // evaluation after the move, for the same player
+    pub best_move: Option<String>, // engine choice, when it differs from the played move
+    pub is_blunder: bool,
+}
+
+impl MoveReview {
+    pub fn evaluation_drop(&self) -> f32 {
+        (self.best_evaluation - self.played_evaluation).max(0.0)
+    }
+}
+
+// Post-game report: every move of the game re-evaluated, with blunders flagged
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameReview {
+    pub moves: Vec<MoveReview>,
+    pub threshold: f32,
+}
+
+impl GameReview {
+    // analyses[i] is the analysis of boards[i], None for finished games
+    fn from_analyses(record: &GameRecord, boards: &[Board], analyses: &[Option<Analysis>], threshold: f32) -> Self {
+        let mut moves = vec![];
+        for (ply, move_record) in record.moves.iter().enumerate() {
+            let (Some(color), Some(analysis)) = (&boards[ply].next_player, &analyses[ply]) else {
+                continue;
+            };
+            let played_evaluation = match (&boards[ply + 1].winner(), &analyses[ply + 1]) {
+                (Some(winner), _) => if winner == color { 1.0 } else { -1.0 },
+                (None, Some(next_analysis)) => -next_analysis.board_eval,
+                (None, None) => continue,
+            };
+            let best_move = analysis.moves.first()
+                .and_then(|best_move| boards[ply].move_to_notation(&best_move.to_move()))
+                .filter(|notation| *notation != move_record.notation);
+            let best_evaluation = analysis.board_eval.max(played_evaluation);
+            moves.push(MoveReview {
+                ply,
+                color: color.clone(),
+                notation: move_record.notation.clone(),
+                best_evaluation,
+                played_evaluation,
+                is_blunder: best_move.is_some() && best_evaluation - played_evaluation > threshold,
+                best_move,
+            });
+        }
+        Self { moves, threshold }
+    }
+
+    pub fn blunders(&self) -> impl Iterator<Item = &MoveReview> {
+        self.moves.iter().filter(|move_review| move_review.is_blunder)
+    }
+
+    pub fn blunder_count(&self, color: &Color) -> usize {
+        self.blunders().filter(|move_review| &move_review.color == color).count()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Blunders: Green {}, Yellow {}\n",
+            self.blunder_count(&Color::Green), self.blunder_count(&Color::Yellow)
+        );
+        for move_review in self.moves.iter() {
+            text.push_str(&format!(
+                "{:>3}. {:?} {} {:.2}",
+                move_review.ply + 1, move_review.color, move_review.notation, move_review.played_evaluation
+            ));
+            if move_review.is_blunder {
+                text.push_str(&format!(" ?? (-{:.2})", move_review.evaluation_drop()));
+                if let Some(best_move) = &move_review.best_move {
+                    text.push_str(&format!(" better {}", best_move));
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// Re-evaluates every position of the game, None if the record contains an illegal move
+pub fn review_game<O: Platform, A: AI<O>>(ai: &mut A, record: &GameRecord, threshold: f32) -> Option<GameReview> {
+    let boards = record.boards()?;
+    let analyses: Vec<Option<Analysis>> = boards.iter()
+        .map(|board| board.next_player.as_ref().map(|_| analyze(ai, board, false)))
+        .collect();
+    Some(GameReview::from_analyses(record, &boards, &analyses, threshold))
+}
+
+pub async fn review_game_async<O: Platform, A: AI<O>>(ai: &mut A, record: &GameRecord, threshold: f32) -> Option<GameReview> {
+    let boards = record.boards()?;
+    let mut analyses = vec![];
+    for board in boards.iter() {
+        analyses.push(match board.next_player {
+            Some(_) => Some(analyze_async(ai, board).await),
+            None => None,
+        });
+    }
+    Some(GameReview::from_analyses(record, &boards, &analyses, threshold))
+}
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use wasm_bindgen::prelude::*;
+use burn::backend::ndarray::NdArray;
+
+use crate::{
+    ai::{AI, any::AnyAI},
+    logic::{Board, Direction, Move, StartPosition},
+    platform::WasmPlatform,
+};
+
+// Headless entry point for JS applications embedding the engine without the Yew UI.
+// Every query returns a JSON string so it can be consumed with JSON.parse on the JS side.
+#[wasm_bindgen]
+pub struct NeutreekoEngine {
+    board: Board,
+}
+
+impl Default for NeutreekoEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl NeutreekoEngine {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            board: Board::default_new(),
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        self.board = Board::default_new();
+    }
+
+    // Handicap start, by StartPosition name, false if the name is unknown
+    pub fn new_game_from(&mut self, start: &str) -> bool {
+        let Some(start) = StartPosition::from_name(start) else {
+            return false;
+        };
+        self.board = Board::from_start(&start);
+        true
+    }
+
+    pub fn board(&self) -> String {
+        self.board.to_json()
+    }
+
+    pub fn legal_moves(&self) -> String {
+        let moves: Vec<String> = self.board.get_all_valid_directions_and_resulting_boards()
+            .into_iter()
+            .map(|(pawn_index, direction, _)| Move::new(pawn_index, direction).to_json())
+            .collect();
+        format!("[{}]", moves.join(","))
+    }
+
+    pub fn play(&mut self, pawn_index: usize, direction: &str) -> bool {
+        self.try_play(pawn_index, direction).is_none()
+    }
+
+    // Same as play, returns why the move was rejected or nothing if it was played
+    pub fn try_play(&mut self, pawn_index: usize, direction: &str) -> Option<String> {
+        let Ok(direction) = Direction::from_str(direction) else {
+            return Some(format!("unknown direction {}", direction));
+        };
+        self.board.apply_move(pawn_index, &direction).err().map(|error| error.to_string())
+    }
+
+    pub fn best_move(&self, ai_type: &str, difficulty: usize) -> Option<String> {
+        let color = self.board.next_player.clone()?;
+        let mut ai: AnyAI<NdArray<f32, i32>, WasmPlatform> = AnyAI::from_name(ai_type, color, difficulty)?;
+        let ai_move = ai.ai_play(&self.board, false);
+        ai_move.map(|(pawn_index, direction)| Move::new(pawn_index, direction).to_json())
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::{
+    adjudication::{AdjudicationConfig, Adjudicator},
+    ai::{AI, any::AnyAI},
+    gamerecord::{GameRecord, GameResult, MoveRecord, games_to_pgn},
+    logic::{Board, Color, Move, StartPosition},
+    platform::Platform,
+};
+
+use burn::tensor::backend::Backend;
+
+#[derive(Clone, Debug)]
+pub struct MatchConfig {
+    pub number_games: usize,
+    pub movetime_ms: Option<f64>,
+    pub game_time_ms: Option<f64>, // budget of each engine for a whole game, replaces movetime_ms for MCTS engines
+    pub max_moves: usize,
+    pub max_repetitions: usize,
+    pub start: StartPosition,
+    pub adjudication: AdjudicationConfig,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            number_games: 10,
+            movetime_ms: None,
+            game_time_ms: None,
+            max_moves: 200,
+            max_repetitions: 3,
+            start: StartPosition::Standard,
+            adjudication: AdjudicationConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MatchSummary {
+    pub first_wins: usize,
+    pub second_wins: usize,
+    pub draws: usize,
+    pub games: Vec<GameRecord>,
+}
+
+impl MatchSummary {
+    pub fn to_pgn(&self) -> String {
+        games_to_pgn(&self.games)
+    }
+}
+
+pub fn play_game<B: Backend, O: Platform>(
+    green: &mut AnyAI<B, O>,
+    yellow: &mut AnyAI<B, O>,
+    names: (&str, &str),
+    start: &Board,
+    config: &MatchConfig,
+) -> GameRecord {
+    let mut board = start.clone();
+    let mut record = GameRecord::new(start);
+    record.event = "Engine match".to_string();
+    record.green = names.0.to_string();
+    record.yellow = names.1.to_string();
+    let mut repetitions: HashMap<u64, usize> = HashMap::new();
+    repetitions.insert(board.get_hash(), 1);
+    // boards before the current one, for the engines to see repetitions coming
+    let mut history: Vec<Board> = vec![];
+    let mut termination = "normal".to_string();
+    let mut adjudicated = None;
+    let mut adjudicator = Adjudicator::new(config.adjudication.clone());
+    if config.game_time_ms.is_some() {
+        // a fresh budget for each game
+        green.set_game_time(config.game_time_ms);
+        yellow.set_game_time(config.game_time_ms);
+    }
+
+    while board.winner().is_none() {
+        if record.moves.len() >= config.max_moves {
+            termination = "move limit".to_string();
+            break;
+        }
+        let Some(playing_color) = board.next_player.clone() else {
+            break;
+        };
+        // a blocked player, only possible from a position set up by hand, draws like in the engines' searches
+        if board.legal_moves().next().is_none() {
+            termination = "no legal move".to_string();
+            break;
+        }
+        let engine = if playing_color == Color::Green { &mut *green } else { &mut *yellow };
+        engine.set_limits(config.movetime_ms, None);
+        engine.set_game_history(&history);
+        let (search_result, best_move) = engine.search(&board);
+        if let Some(adjudication) = adjudicator.record(&playing_color, search_result.value) {
+            O::print(&format!("{} after {} moves", adjudication.to_text(), record.moves.len()));
+            termination = adjudication.termination().to_string();
+            adjudicated = Some(adjudication.result());
+            break;
+        }
+        let game_move = best_move.map(|(pawn_index, direction)| Move::new(pawn_index, direction));
+        let Some((game_move, notation)) = game_move.and_then(|game_move| {
+            board.move_to_notation(&game_move).map(|notation| (game_move, notation))
+        }) else {
+            termination = "illegal move".to_string();
+            adjudicated = Some(match playing_color {
+                Color::Green => GameResult::YellowWins,
+                Color::Yellow => GameResult::GreenWins,
+            });
+            break;
+        };
+        history.push(board.clone());
+        board.move_pawn_until_blocked(game_move.pawn_index, &game_move.direction);
+        record.moves.push(MoveRecord::from_search(notation, &search_result));
+
+        let count = repetitions.entry(board.get_hash()).or_insert(0);
+        *count += 1;
+        if *count >= config.max_repetitions {
+            termination = "repetition".to_string();
+            break;
+        }
+    }
+
+    record.result = Some(adjudicated.unwrap_or(match board.winner() {
+        Some(Color::Green) => GameResult::GreenWins,
+        Some(Color::Yellow) => GameResult::YellowWins,
+        None => GameResult::Draw,
+    }));
+    record.termination = Some(termination);
+    record
+}
+
+// Plays config.number_games games, the first engine taking Green in even games and Yellow in odd ones
+pub fn run_match<B: Backend, O: Platform>(
+    first: &mut AnyAI<B, O>,
+    second: &mut AnyAI<B, O>,
+    names: (&str, &str),
+    config: &MatchConfig,
+) -> MatchSummary {
+    let mut summary = MatchSummary::default();
+    let start = Board::from_start(&config.start);
+    for game_index in 0..config.number_games {
+        let first_is_green = game_index % 2 == 0;
+        let mut game = if first_is_green {
+            play_game(first, second, names, &start, config)
+        } else {
+            play_game(second, first, (names.1, names.0), &start, config)
+        };
+        game.round = Some(game_index + 1);
+        match (&game.result, first_is_green) {
+            (Some(GameResult::Draw), _) => summary.draws += 1,
+            (Some(GameResult::GreenWins), true) | (Some(GameResult::YellowWins), false) => summary.first_wins += 1,
+            _ => summary.second_wins += 1,
+        }
+        O::print(&format!(
+            "Game {}/{}: {} vs {} {} ({}), score {}-{}-{}",
+            game_index + 1, config.number_games, game.green, game.yellow,
+            game.result.as_ref().map_or("*", |result| result.to_pgn()), game.termination.as_deref().unwrap_or_default(),
+            summary.first_wins, summary.draws, summary.second_wins
+        ));
+        summary.games.push(game);
+    }
+    summary
+}
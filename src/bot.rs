@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use burn::tensor::backend::Backend;
+
+use crate::{
+    ai::{AI, any::AnyAI},
+    game::Game,
+    logic::{Board, BoardSymbols, Color, Move, StartPosition},
+    platform::Platform,
+};
+
+// Chat front-end for Discord-like servers: the host forwards each message with its channel and author,
+// the bot answers with text. Users play against an engine, one game per user and channel
+pub const DEFAULT_PREFIX: &str = "!neutreeko";
+pub const DEFAULT_ENGINE: &str = "mcts";
+pub const DEFAULT_DIFFICULTY: usize = 3;
+// Higher difficulties think for more than a minute, too long for a chat answer
+pub const MAX_DIFFICULTY: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GameKey {
+    pub channel: String,
+    pub user: String,
+}
+
+impl GameKey {
+    pub fn new(channel: &str, user: &str) -> Self {
+        Self { channel: channel.to_string(), user: user.to_string() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BotCommand {
+    NewGame { engine: String, difficulty: usize, color: Color, start: StartPosition },
+    Move(String),
+    Board,
+    Resign,
+    Help,
+}
+
+impl BotCommand {
+    // Words after the prefix, e.g. "new mcts:4 yellow" or "move b1-b4". None if the command is unknown
+    pub fn parse(arguments: &str) -> Option<Self> {
+        let mut words = arguments.split_whitespace();
+        let command = match words.next()?.to_ascii_lowercase().as_str() {
+            "new" | "play" | "start" => {
+                let mut engine = DEFAULT_ENGINE.to_string();
+                let mut difficulty = DEFAULT_DIFFICULTY;
+                let mut color = Color::Green;
+                let mut start = StartPosition::Standard;
+                for word in words {
+                    let word = word.to_ascii_lowercase();
+                    match word.as_str() {
+                        "green" | "g" => color = Color::Green,
+                        "yellow" | "y" => color = Color::Yellow,
+                        _ => if let Some(start_position) = StartPosition::from_name(&word) {
+                            start = start_position;
+                        } else {
+                            let (name, level) = word.split_once(':').unwrap_or((word.as_str(), ""));
+                            engine = name.to_string();
+                            if !level.is_empty() {
+                                difficulty = level.parse().ok()?;
+                            }
+                        },
+                    }
+                }
+                BotCommand::NewGame { engine, difficulty, color, start }
+            }
+            "move" | "m" => BotCommand::Move(words.collect::<Vec<_>>().join(" ")),
+            "board" | "show" => BotCommand::Board,
+            "resign" | "quit" => BotCommand::Resign,
+            "help" => BotCommand::Help,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+// First legal move written in the message, as "b1-b4", "b1 b4" or "b1b4"
+pub fn find_move(board: &Board, message: &str) -> Option<Move> {
+    let words: Vec<String> = message.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphanumeric()).to_ascii_lowercase())
+        .collect();
+    for (index, word) in words.iter().enumerate() {
+        let mut candidates = vec![word.clone()];
+        if word.len() == 4 && word.is_ascii() {
+            candidates.push(format!("{}-{}", &word[..2], &word[2..]));
+        }
+        if let Some(next_word) = words.get(index + 1) {
+            candidates.push(format!("{}-{}", word, next_word));
+        }
+        if let Some(found) = candidates.iter().find_map(|candidate| board.move_from_notation(candidate)) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+struct ChatGame<B: Backend, O: Platform> {
+    game: Game,
+    human: Color,
+    engine: AnyAI<B, O>,
+    engine_label: String, // e.g. "mcts:3"
+}
+
+pub struct ChatBot<B: Backend, O: Platform> {
+    pub prefix: String,
+    pub symbols: BoardSymbols,
+    // each game has its own lock so engines of different games search at the same time
+    games: Mutex<HashMap<GameKey, Arc<Mutex<ChatGame<B, O>>>>>,
+}
+
+impl<B: Backend, O: Platform> Default for ChatBot<B, O> {
+    fn default() -> Self {
+        Self::new(DEFAULT_PREFIX, BoardSymbols::EMOJI)
+    }
+}
+
+impl<B: Backend, O: Platform> ChatBot<B, O> {
+    pub fn new(prefix: &str, symbols: BoardSymbols) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            symbols,
+            games: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn number_of_games(&self) -> usize {
+        self.games.lock().unwrap().len()
+    }
+
+    // Answer to a message, None when the message is not meant for the bot
+    pub fn handle_message(&self, key: &GameKey, message: &str) -> Option<String> {
+        let message = message.trim();
+        let Some(arguments) = message.strip_prefix(self.prefix.as_str()) else {
+            // outside commands, only moves of a game in progress are answered
+            let chat_game = self.game(key)?;
+            let mut chat_game = chat_game.lock().unwrap();
+            let human_move = find_move(&chat_game.game.board, message)?;
+            return Some(self.play_turn(key, &mut chat_game, &human_move));
+        };
+        let Some(command) = BotCommand::parse(arguments) else {
+            return Some(self.help());
+        };
+        let reply = match command {
+            BotCommand::NewGame { engine, difficulty, color, start } => self.new_game(key, &engine, difficulty, color, &start),
+            BotCommand::Move(text) => match self.game(key) {
+                Some(chat_game) => {
+                    let mut chat_game = chat_game.lock().unwrap();
+                    match find_move(&chat_game.game.board, &text) {
+                        Some(human_move) => self.play_turn(key, &mut chat_game, &human_move),
+                        None => format!("{} is not a legal move here", text),
+                    }
+                }
+                None => self.no_game(),
+            },
+            BotCommand::Board => match self.game(key) {
+                Some(chat_game) => {
+                    let chat_game = chat_game.lock().unwrap();
+                    self.render(&chat_game.game.board)
+                }
+                None => self.no_game(),
+            },
+            BotCommand::Resign => {
+                // the map lock is released before the game lock is taken, as in play_turn
+                let removed = self.games.lock().unwrap().remove(key);
+                match removed {
+                    Some(chat_game) => format!("You resigned, {} wins.", chat_game.lock().unwrap().engine_label),
+                    None => self.no_game(),
+                }
+            }
+            BotCommand::Help => self.help(),
+        };
+        Some(reply)
+    }
+
+    fn game(&self, key: &GameKey) -> Option<Arc<Mutex<ChatGame<B, O>>>> {
+        self.games.lock().unwrap().get(key).cloned()
+    }
+
+    fn new_game(&self, key: &GameKey, engine_name: &str, difficulty: usize, human: Color, start: &StartPosition) -> String {
+        if difficulty == 0 || difficulty > MAX_DIFFICULTY {
+            return format!("The difficulty goes from 1 to {}", MAX_DIFFICULTY);
+        }
+        let Some(engine) = AnyAI::from_name(engine_name, human.other_color(), difficulty) else {
+            return format!("Unknown engine {}", engine_name);
+        };
+        let mut chat_game = ChatGame {
+            game: Game::new(&Board::from_start(start)),
+            human: human.clone(),
+            engine,
+            engine_label: format!("{}:{}", engine_name, difficulty),
+        };
+        let mut reply = format!("New game against {}, you play {:?}.\n", chat_game.engine_label, human);
+        if chat_game.game.board.next_player.as_ref() != Some(&human) {
+            reply.push_str(&self.engine_turn(&mut chat_game));
+        } else {
+            reply.push_str(&self.render(&chat_game.game.board));
+        }
+        self.games.lock().unwrap().insert(key.clone(), Arc::new(Mutex::new(chat_game)));
+        reply
+    }
+
+    // Plays the human move then the engine answer, the game is dropped once won
+    fn play_turn(&self, key: &GameKey, chat_game: &mut ChatGame<B, O>, human_move: &Move) -> String {
+        if chat_game.game.board.next_player.as_ref() != Some(&chat_game.human) {
+            return "It is not your turn".to_string();
+        }
+        if let Err(error) = chat_game.game.play(human_move) {
+            return format!("Illegal move: {}", error);
+        }
+        let reply = match chat_game.game.board.winner() {
+            Some(_) => format!("{}You win!", self.render(&chat_game.game.board)),
+            None => self.engine_turn(chat_game),
+        };
+        if chat_game.game.board.winner().is_some() {
+            self.games.lock().unwrap().remove(key);
+        }
+        reply
+    }
+
+    fn engine_turn(&self, chat_game: &mut ChatGame<B, O>) -> String {
+        let board = chat_game.game.board.clone();
+        let Some((pawn_index, direction)) = chat_game.engine.ai_play(&board, false) else {
+            return self.render(&board);
+        };
+        let engine_move = Move::new(pawn_index, direction);
+        let notation = board.move_to_notation(&engine_move).unwrap_or_default();
+        if let Err(error) = chat_game.game.play(&engine_move) {
+            return format!("The engine played an illegal move: {}", error);
+        }
+        let mut reply = format!("I play {}\n{}", notation, self.render(&chat_game.game.board));
+        if chat_game.game.board.winner().is_some() {
+            reply.push_str("I win!");
+        }
+        reply
+    }
+
+    // ASCII grids go in a code block so chat clients keep the alignment
+    fn render(&self, board: &Board) -> String {
+        let grid = board.str_rep_with(&self.symbols);
+        let grid = if self.symbols.coordinates { format!("```\n{}```\n", grid) } else { grid };
+        match &board.next_player {
+            Some(color) if board.winner().is_none() => format!("{}{:?} to move\n", grid, color),
+            _ => grid,
+        }
+    }
+
+    fn no_game(&self) -> String {
+        format!("You have no game here, start one with {} new", self.prefix)
+    }
+
+    pub fn help(&self) -> String {
+        [
+            format!("{} new [engine[:difficulty]] [green|yellow] [start position]: start a game, e.g. {} new minmax:4 yellow", self.prefix, self.prefix),
+            "Then write your moves as from-to squares, e.g. b1-b4. Columns a-e go left to right, rows 1-5 top to bottom".to_string(),
+            format!("{} board: show the board", self.prefix),
+            format!("{} resign: abandon the game", self.prefix),
+        ].join("\n")
+    }
+}
@@ -0,0 +1,695 @@
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use burn::backend::NdArray;
+use neutreeko::{
+    adjudication::AdjudicationConfig,
+    analysis::{DEFAULT_BLUNDER_THRESHOLD, annotate_game, review_game},
+    explain::explain_best_move,
+    render::{RenderOptions, board_to_svg},
+    arena::{MatchConfig, run_match},
+    selfcheck::{SelfCheckConfig, run_self_check},
+    tuning::{TunableParameter, TuningConfig, run_tuning},
+    gamerecord::{games_from_pgn, games_to_pgn},
+    logic::{Board, Color, Move, StartPosition},
+    platform::{NativePlatform, SearchHandle},
+    ai::{
+        AI,
+        SearchObserver,
+        SearchProgress,
+        any::AnyAI,
+        book::{OpeningBook, WithBook},
+        oracle::{SharedOracle, ShallowSolver},
+        statscache::{SharedStatsCache, StatsCache},
+    }
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum EngineType {
+    None,
+    #[value(name = "minmax")]
+    MinMax,
+    Mcts,
+    Ann,
+    #[value(name = "alphazeutreeko")]
+    AlphaZeutreeko,
+    Committee,
+    Random,
+    Greedy,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PlayerColor {
+    Green,
+    Yellow,
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "neutreeko-cli", about = "Play Neutreeko in the terminal")]
+struct Args {
+    /// Engine to play against, "none" for two human players
+    #[arg(short, long, value_enum, default_value_t = EngineType::MinMax)]
+    engine: EngineType,
+    /// Engine difficulty, from 1 (very easy) to 6 (expert)
+    #[arg(short, long, default_value_t = 4)]
+    difficulty: usize,
+    /// Color played by the human
+    #[arg(short, long, value_enum, default_value_t = PlayerColor::Green)]
+    color: PlayerColor,
+    /// Print the engine reasoning
+    #[arg(short, long)]
+    verbose: bool,
+    /// Let the engine keep thinking while waiting for your move
+    #[arg(long)]
+    ponder: bool,
+    /// Search every position, even those in the opening book
+    #[arg(long)]
+    no_book: bool,
+    /// Temperature used to pick among book moves, 0 always plays the main line
+    #[arg(long, default_value_t = 0.0)]
+    book_randomization: f32,
+    /// Solve positions exactly up to this many plies before searching, 0 to disable
+    #[arg(long, default_value_t = 0)]
+    solver_depth: usize,
+    /// File keeping MCTS statistics across games, read at start and written when the game ends
+    #[arg(long)]
+    stats_cache: Option<String>,
+    /// Approximate memory a search may take in megabytes, MinMax stops and MCTS prunes its tree there
+    #[arg(long)]
+    memory_mb: Option<usize>,
+    /// Start position: standard, yellow-first, green-center or yellow-center for handicap games
+    #[arg(long, default_value = "standard")]
+    start: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play two engines against each other
+    Match(MatchArgs),
+    /// Evaluate a position and print the best line, or annotate the games of a PGN file
+    Analyze(AnalyzeArgs),
+    /// Play an engine against itself and print its search telemetry
+    Stats(StatsArgs),
+    /// Compare the moves of several engines on random positions and flag the provably losing ones
+    Selfcheck(SelfCheckArgs),
+    /// Tune search parameters of an engine with SPSA self-play and print the tuned values
+    Tune(TuneArgs),
+    /// Write a game of a PGN file as an animated GIF, needs the image feature
+    Animate(AnimateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct AnalyzeArgs {
+    /// Position to analyze, defaults to the standard start
+    #[arg(short, long)]
+    fen: Option<String>,
+    /// Engine, as <engine>:<difficulty>
+    #[arg(short, long, default_value = "minmax:4")]
+    engine: String,
+    /// Search time in milliseconds, for time-based engines
+    #[arg(long)]
+    movetime: Option<f64>,
+    /// Search depth in plies, for MinMax
+    #[arg(long)]
+    depth: Option<usize>,
+    /// Games to annotate with the engine evaluations, printed back as PGN
+    #[arg(long)]
+    pgn: Option<String>,
+    /// With --pgn, print a review of each game with its blunders instead of the annotated PGN
+    #[arg(long)]
+    review: bool,
+    /// Evaluation drop above which a move is reported as a blunder
+    #[arg(long, default_value_t = DEFAULT_BLUNDER_THRESHOLD)]
+    blunder_threshold: f32,
+    /// Write the search tree of MCTS engines to this file, as Graphviz if it ends in .dot, as JSON otherwise
+    #[arg(long)]
+    tree: Option<String>,
+    /// Plies of the search tree written with --tree
+    #[arg(long, default_value_t = 2)]
+    tree_depth: usize,
+    /// Write a diagram of the position with the best move and threat arrows to this file, as PNG if it ends in .png
+    /// (needs the image feature), as SVG otherwise
+    #[arg(long)]
+    diagram: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct MatchArgs {
+    /// First engine, as <engine>:<difficulty>
+    #[arg(long, default_value = "minmax:4")]
+    first: String,
+    /// Second engine, as <engine>:<difficulty>
+    #[arg(long, default_value = "mcts:4")]
+    second: String,
+    /// Number of games, colours alternate between games
+    #[arg(short = 'n', long, default_value_t = 10)]
+    games: usize,
+    /// Time allowed per move in milliseconds, for time-based engines
+    #[arg(long)]
+    movetime: Option<f64>,
+    /// Time budget of each engine for a whole game in milliseconds, spent unevenly between the moves by MCTS engines
+    #[arg(long)]
+    game_time: Option<f64>,
+    /// Number of moves after which the game is adjudicated a draw
+    #[arg(long, default_value_t = 200)]
+    max_moves: usize,
+    /// A side resigns when its evaluation stays below this, e.g. -0.95
+    #[arg(long, allow_hyphen_values = true)]
+    resign_threshold: Option<f32>,
+    /// Own moves in a row below --resign-threshold before resigning
+    #[arg(long, default_value_t = 3)]
+    resign_moves: usize,
+    /// Plies in a row with evaluations close to 0 after which the game is adjudicated a draw
+    #[arg(long)]
+    draw_moves: Option<usize>,
+    /// File where the games are written
+    #[arg(short, long)]
+    output: Option<String>,
+    /// Start position: standard, yellow-first, green-center or yellow-center
+    #[arg(long, default_value = "standard")]
+    start: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    /// Engine, as <engine>:<difficulty>
+    #[arg(short, long, default_value = "mcts:3")]
+    engine: String,
+    /// Number of self-play games
+    #[arg(short = 'n', long, default_value_t = 2)]
+    games: usize,
+    /// Time allowed per move in milliseconds, for time-based engines
+    #[arg(long)]
+    movetime: Option<f64>,
+    /// Number of moves after which the game is adjudicated a draw
+    #[arg(long, default_value_t = 200)]
+    max_moves: usize,
+    /// Start position: standard, yellow-first, green-center or yellow-center
+    #[arg(long, default_value = "standard")]
+    start: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct SelfCheckArgs {
+    /// Engines to compare, as <engine>:<difficulty> separated by commas
+    #[arg(short, long, default_value = "minmax:6,mcts:4,alphazeutreeko:4")]
+    engines: String,
+    /// Number of random positions
+    #[arg(short = 'n', long, default_value_t = 50)]
+    positions: usize,
+    /// Time allowed per move in milliseconds, for time-based engines
+    #[arg(long)]
+    movetime: Option<f64>,
+    /// Search depth in plies of the MinMax engines
+    #[arg(long)]
+    depth: Option<usize>,
+    /// Plies the solver searches to prove a move loses
+    #[arg(long, default_value_t = 4)]
+    proof_depth: usize,
+    /// File where the report is written, printed otherwise
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct TuneArgs {
+    /// Engine to tune, mcts or alphazeutreeko
+    #[arg(short, long, default_value = "mcts")]
+    engine: String,
+    /// Difficulty of the engine
+    #[arg(short, long, default_value_t = 2)]
+    difficulty: usize,
+    /// Parameters to tune, separated by commas: exploration, fpu, prior-temperature, progressive-bias, expected-moves
+    #[arg(short, long, default_value = "exploration")]
+    parameters: String,
+    /// Number of SPSA iterations
+    #[arg(short = 'n', long, default_value_t = 100)]
+    iterations: usize,
+    /// Games per iteration, rounded up to an even number
+    #[arg(short, long, default_value_t = 2)]
+    games: usize,
+    /// Time allowed per move in milliseconds
+    #[arg(long, default_value_t = 100.0)]
+    movetime: f64,
+    /// Budget of each engine for a whole game in milliseconds, needed to tune expected-moves
+    #[arg(long)]
+    game_time: Option<f64>,
+    /// Gain of the updates, in squared steps of each parameter
+    #[arg(long, default_value_t = 4.0)]
+    learning_rate: f64,
+    /// Play every game from the standard start instead of balanced random starts
+    #[arg(long)]
+    standard_start: bool,
+    /// File where the report is written, printed otherwise
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct AnimateArgs {
+    /// PGN file holding the game
+    pgn: String,
+    /// Game of the file to animate, from 1
+    #[arg(short, long, default_value_t = 1)]
+    game: usize,
+    /// File where the GIF is written
+    #[arg(short, long, default_value = "game.gif")]
+    output: String,
+    /// Time each move stays on screen in milliseconds
+    #[arg(long, default_value_t = 800)]
+    frame_ms: u32,
+    /// Size of a square in pixels
+    #[arg(long, default_value_t = 60)]
+    square_size: u32,
+}
+
+type Engine = AnyAI<NdArray<f32>, NativePlatform>;
+type BookEngine = WithBook<Engine, NativePlatform>;
+
+impl EngineType {
+    fn name(&self) -> Option<&'static str> {
+        match self {
+            EngineType::None => None,
+            EngineType::MinMax => Some("minmax"),
+            EngineType::Mcts => Some("mcts"),
+            EngineType::Ann => Some("ann"),
+            EngineType::AlphaZeutreeko => Some("alphazeutreeko"),
+            EngineType::Committee => Some("committee"),
+            EngineType::Random => Some("random"),
+            EngineType::Greedy => Some("greedy"),
+        }
+    }
+}
+
+fn engine_plays(engine: &Option<BookEngine>, board: &Board) -> bool {
+    engine.as_ref().is_some_and(|engine| board.next_player.as_ref() == Some(engine.color()))
+}
+
+// Keeps a single status line updated while the engine searches
+struct CliSearchObserver {
+    board: Board,
+}
+
+impl SearchObserver for CliSearchObserver {
+    fn on_progress(&self, progress: &SearchProgress) {
+        let best_move = progress.best_move.as_ref()
+            .and_then(|(pawn_index, direction)| self.board.move_to_notation(&Move::new(*pawn_index, direction.clone())))
+            .unwrap_or_else(|| "-".to_string());
+        print!("\rThinking... {:>3.0}% {} nodes, best {}   ", progress.fraction * 100.0, progress.nodes, best_move);
+        let _ = io::stdout().flush();
+    }
+}
+
+fn print_help() {
+    println!("Commands:");
+    println!("  <from>-<to>  play a move in algebraic notation, e.g. b1-c1");
+    println!("  moves        list the legal moves");
+    println!("  undo         take back your last move");
+    println!("  help         show this help");
+    println!("  quit         leave the game");
+}
+
+fn parse_engine(spec: &str) -> Engine {
+    let (name, difficulty) = spec.split_once(':').unwrap_or((spec, "4"));
+    let difficulty = difficulty.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid difficulty in {}", spec);
+        std::process::exit(1);
+    });
+    Engine::from_name(name, Color::Green, difficulty).unwrap_or_else(|| {
+        eprintln!("Unknown engine {}", name);
+        std::process::exit(1);
+    })
+}
+
+fn parse_start(name: &str) -> StartPosition {
+    StartPosition::from_name(name).unwrap_or_else(|| {
+        eprintln!("Unknown start position {}", name);
+        std::process::exit(1);
+    })
+}
+
+fn play_match(match_args: MatchArgs) {
+    let mut first = parse_engine(&match_args.first);
+    let mut second = parse_engine(&match_args.second);
+    let config = MatchConfig {
+        number_games: match_args.games,
+        movetime_ms: match_args.movetime,
+        game_time_ms: match_args.game_time,
+        max_moves: match_args.max_moves,
+        start: parse_start(&match_args.start),
+        adjudication: AdjudicationConfig {
+            resign_threshold: match_args.resign_threshold,
+            resign_moves: match_args.resign_moves,
+            draw_moves: match_args.draw_moves,
+            ..AdjudicationConfig::default()
+        },
+        ..MatchConfig::default()
+    };
+    let summary = run_match(&mut first, &mut second, (&match_args.first, &match_args.second), &config);
+    println!(
+        "{} vs {}: +{} ={} -{}",
+        match_args.first, match_args.second, summary.first_wins, summary.draws, summary.second_wins
+    );
+    if let Some(output) = match_args.output {
+        if let Err(error) = std::fs::write(&output, summary.to_pgn()) {
+            eprintln!("Could not write {}: {}", output, error);
+        }
+    }
+}
+
+fn print_stats(stats_args: StatsArgs) {
+    let mut first = parse_engine(&stats_args.engine);
+    let mut second = parse_engine(&stats_args.engine);
+    let config = MatchConfig {
+        number_games: stats_args.games,
+        movetime_ms: stats_args.movetime,
+        max_moves: stats_args.max_moves,
+        start: parse_start(&stats_args.start),
+        ..MatchConfig::default()
+    };
+    let summary = run_match(&mut first, &mut second, (&stats_args.engine, &stats_args.engine), &config);
+    println!("{} self-play: +{} ={} -{}", stats_args.engine, summary.first_wins, summary.draws, summary.second_wins);
+    let mut telemetry = first.telemetry();
+    telemetry.add(&second.telemetry());
+    print!("{}", telemetry.to_text());
+}
+
+fn self_check(self_check_args: SelfCheckArgs) {
+    let mut engines: Vec<(String, Engine)> = self_check_args.engines.split(',')
+        .map(|spec| (spec.to_string(), parse_engine(spec)))
+        .collect();
+    let config = SelfCheckConfig {
+        positions: self_check_args.positions,
+        movetime_ms: self_check_args.movetime,
+        depth: self_check_args.depth,
+    };
+    let oracle = ShallowSolver::new(self_check_args.proof_depth);
+    let report = run_self_check(&mut engines, &oracle, &config);
+    match self_check_args.output {
+        Some(output) => {
+            if let Err(error) = std::fs::write(&output, report.to_text()) {
+                eprintln!("Could not write {}: {}", output, error);
+            }
+        }
+        None => print!("{}", report.to_text()),
+    }
+}
+
+fn tune(tune_args: TuneArgs) {
+    let parameters: Option<Vec<TunableParameter>> = tune_args.parameters.split(',')
+        .map(|name| TunableParameter::from_name(name.trim()))
+        .collect();
+    let Some(parameters) = parameters else {
+        eprintln!("Unknown parameter in {}", tune_args.parameters);
+        std::process::exit(1);
+    };
+    let config = TuningConfig {
+        engine: tune_args.engine,
+        difficulty: tune_args.difficulty,
+        parameters,
+        iterations: tune_args.iterations,
+        games_per_iteration: tune_args.games,
+        movetime_ms: Some(tune_args.movetime),
+        game_time_ms: tune_args.game_time,
+        random_starts: !tune_args.standard_start,
+        learning_rate: tune_args.learning_rate,
+        ..TuningConfig::default()
+    };
+    let report = match run_tuning::<NdArray<f32>, NativePlatform>(&config) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    match tune_args.output {
+        Some(output) => {
+            if let Err(error) = std::fs::write(&output, report.to_text()) {
+                eprintln!("Could not write {}: {}", output, error);
+            }
+        }
+        None => print!("{}", report.to_text()),
+    }
+}
+
+fn animate_game(animate_args: AnimateArgs) {
+    let text = std::fs::read_to_string(&animate_args.pgn).unwrap_or_else(|error| {
+        eprintln!("Could not read {}: {}", animate_args.pgn, error);
+        std::process::exit(1);
+    });
+    let Some(games) = games_from_pgn(&text) else {
+        eprintln!("Invalid game record in {}", animate_args.pgn);
+        std::process::exit(1);
+    };
+    let Some(game) = animate_args.game.checked_sub(1).and_then(|index| games.get(index)) else {
+        eprintln!("{} holds {} games", animate_args.pgn, games.len());
+        std::process::exit(1);
+    };
+    let options = RenderOptions { square_size: animate_args.square_size, ..RenderOptions::default() };
+    #[cfg(feature = "image")]
+    let image = neutreeko::render::game_to_gif(game, &options, animate_args.frame_ms);
+    #[cfg(not(feature = "image"))]
+    let image: Result<Vec<u8>, String> = Err(format!(
+        "{} frames of {} ms need the image feature, build with --features image",
+        neutreeko::render::game_frames(game, &options).map_or(0, |frames| frames.len()), animate_args.frame_ms,
+    ));
+    let image = image.unwrap_or_else(|error| {
+        eprintln!("Could not draw the game: {}", error);
+        std::process::exit(1);
+    });
+    if let Err(error) = std::fs::write(&animate_args.output, image) {
+        eprintln!("Could not write {}: {}", animate_args.output, error);
+        std::process::exit(1);
+    }
+}
+
+fn annotate_games(analyze_args: &AnalyzeArgs, path: &str) {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|error| {
+        eprintln!("Could not read {}: {}", path, error);
+        std::process::exit(1);
+    });
+    let Some(mut games) = games_from_pgn(&text) else {
+        eprintln!("Invalid game record in {}", path);
+        std::process::exit(1);
+    };
+    let mut engine = parse_engine(&analyze_args.engine);
+    engine.set_limits(analyze_args.movetime, analyze_args.depth);
+    if analyze_args.review {
+        for game in games.iter() {
+            println!("{} - {}", game.green, game.yellow);
+            match review_game(&mut engine, game, analyze_args.blunder_threshold) {
+                Some(review) => print!("{}", review.to_text()),
+                None => println!("Illegal move in the game"),
+            }
+        }
+        return;
+    }
+    for game in games.iter_mut() {
+        annotate_game(&mut engine, game);
+    }
+    print!("{}", games_to_pgn(&games));
+}
+
+fn analyze_position(analyze_args: AnalyzeArgs) {
+    if let Some(path) = &analyze_args.pgn {
+        return annotate_games(&analyze_args, path);
+    }
+    let board = match &analyze_args.fen {
+        Some(fen) => Board::from_fen(fen).unwrap_or_else(|| {
+            eprintln!("Invalid FEN {}", fen);
+            std::process::exit(1);
+        }),
+        None => Board::default_new(),
+    };
+    println!("{}", board.str_rep());
+    if let Some(winner) = board.winner() {
+        println!("{:?} has won", winner);
+        return;
+    }
+    let mut engine = parse_engine(&analyze_args.engine);
+    engine.set_limits(analyze_args.movetime, analyze_args.depth);
+    let analysis = engine.analyze(&board);
+    print!("{}", analysis.to_text(&board));
+    if let Some(explanation) = explain_best_move(&board, &analysis) {
+        println!("Why {}", explanation.to_text());
+    }
+    if let Some(stats) = engine.minmax_stats() {
+        println!(
+            "Scored {} nodes, {} leaves extended, {} re-searches, {} aspiration failures",
+            stats.scored_nodes, stats.extended_leaves, stats.researches, stats.aspiration_failures
+        );
+    }
+    if let Some(path) = &analyze_args.tree {
+        let Some(tree) = engine.search_tree(analyze_args.tree_depth) else {
+            eprintln!("The {} engine does not keep a search tree", engine.name());
+            std::process::exit(1);
+        };
+        let text = if path.ends_with(".dot") { tree.to_dot() } else { tree.to_json() };
+        if let Err(error) = std::fs::write(path, text) {
+            eprintln!("Could not write {}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+    if let Some(path) = &analyze_args.diagram {
+        let caption = format!("{} {:+.2}", board.to_fen(), analysis.board_eval);
+        let options = RenderOptions { arrows: analysis.arrows(&board), caption: Some(caption), ..RenderOptions::default() };
+        write_diagram(path, &board, &options);
+    }
+}
+
+#[cfg(feature = "image")]
+fn write_diagram(path: &str, board: &Board, options: &RenderOptions) {
+    let image = if path.ends_with(".png") {
+        neutreeko::render::board_to_png(board, options).unwrap_or_else(|error| {
+            eprintln!("Could not draw the diagram: {}", error);
+            std::process::exit(1);
+        })
+    } else {
+        board_to_svg(board, options).into_bytes()
+    };
+    if let Err(error) = std::fs::write(path, image) {
+        eprintln!("Could not write {}: {}", path, error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "image"))]
+fn write_diagram(path: &str, board: &Board, options: &RenderOptions) {
+    if path.ends_with(".png") {
+        eprintln!("PNG diagrams need the image feature, write an .svg file or build with --features image");
+        std::process::exit(1);
+    }
+    if let Err(error) = std::fs::write(path, board_to_svg(board, options)) {
+        eprintln!("Could not write {}: {}", path, error);
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    #[cfg(feature = "tracing")]
+    let _flame = neutreeko::profiling::init_tracing();
+    let args = Args::parse();
+    match args.command {
+        Some(Command::Match(match_args)) => return play_match(match_args),
+        Some(Command::Analyze(analyze_args)) => return analyze_position(analyze_args),
+        Some(Command::Stats(stats_args)) => return print_stats(stats_args),
+        Some(Command::Selfcheck(self_check_args)) => return self_check(self_check_args),
+        Some(Command::Tune(tune_args)) => return tune(tune_args),
+        Some(Command::Animate(animate_args)) => return animate_game(animate_args),
+        None => (),
+    }
+    let human_color = match args.color {
+        PlayerColor::Green => Color::Green,
+        PlayerColor::Yellow => Color::Yellow,
+    };
+    let book = Arc::new(OpeningBook::default_book());
+    let stats_cache: Option<SharedStatsCache> = args.stats_cache.as_ref().map(|path| {
+        StatsCache::load_file(path).unwrap_or_else(|| {
+            eprintln!("Invalid statistics file {}", path);
+            std::process::exit(1);
+        }).shared()
+    });
+    let mut engine = args.engine.name()
+        .and_then(|name| Engine::from_name(name, human_color.other_color(), args.difficulty))
+        .map(|engine| {
+            let mut engine = WithBook::wrap(engine, book.clone());
+            engine.enabled = !args.no_book;
+            engine.randomization = args.book_randomization;
+            if args.solver_depth > 0 {
+                let oracle: SharedOracle = Arc::new(ShallowSolver::new(args.solver_depth));
+                engine.set_oracle(Some(oracle));
+            }
+            engine.set_stats_cache(stats_cache.clone());
+            engine.set_memory_limit(args.memory_mb.map(|memory_mb| memory_mb * 1024 * 1024));
+            engine
+        });
+    let mut board = Board::from_start(&parse_start(&args.start));
+    let mut history: Vec<Board> = vec![];
+    let stdin = io::stdin();
+
+    print_help();
+    loop {
+        println!();
+        println!("{}", board.str_rep());
+        if let Some(winner) = board.winner() {
+            println!("{:?} wins!", winner);
+            break;
+        }
+
+        if let Some(ai) = engine.as_mut().filter(|ai| board.next_player.as_ref() == Some(ai.color())) {
+            let observer = CliSearchObserver { board: board.clone() };
+            let ai_move = ai.ai_play_observed(&board, args.verbose, Some(&observer));
+            // clear the progress line
+            print!("\r{}\r", " ".repeat(60));
+            match ai_move {
+                Some((pawn_index, direction)) => {
+                    let ai_move = Move::new(pawn_index, direction);
+                    println!("AI plays {}", board.move_to_notation(&ai_move).unwrap_or_default());
+                    history.push(board.clone());
+                    board.move_pawn_until_blocked(ai_move.pawn_index, &ai_move.direction);
+                }
+                None => break,
+            }
+            continue;
+        }
+
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        let ponder_handle = SearchHandle::new();
+        let bytes_read = std::thread::scope(|scope| {
+            if let Some(ai) = engine.as_mut().filter(|_| args.ponder) {
+                scope.spawn(|| ai.ponder(&board, &ponder_handle));
+            }
+            let bytes_read = stdin.lock().read_line(&mut line).unwrap_or(0);
+            ponder_handle.cancel();
+            bytes_read
+        });
+        if bytes_read == 0 {
+            break;
+        }
+        match line.trim() {
+            "" => (),
+            "quit" | "exit" => break,
+            "help" => print_help(),
+            "moves" => {
+                let notations: Vec<String> = board.get_all_valid_directions_and_resulting_boards()
+                    .into_iter()
+                    .filter_map(|(pawn_index, direction, _)| board.move_to_notation(&Move::new(pawn_index, direction)))
+                    .collect();
+                println!("{}", notations.join(" "));
+            }
+            "undo" => {
+                let mut undone = false;
+                while let Some(previous_board) = history.pop() {
+                    board = previous_board;
+                    undone = true;
+                    if !engine_plays(&engine, &board) {
+                        break;
+                    }
+                }
+                if !undone {
+                    println!("Nothing to undo");
+                }
+            }
+            input => match board.move_from_notation(input) {
+                Some(human_move) => {
+                    history.push(board.clone());
+                    board.move_pawn_until_blocked(human_move.pawn_index, &human_move.direction);
+                }
+                None => println!("Invalid move: {}", input),
+            },
+        }
+    }
+
+    if let (Some(path), Some(stats_cache)) = (&args.stats_cache, &stats_cache) {
+        let stats_cache = stats_cache.lock().unwrap();
+        match stats_cache.save_file(path) {
+            Ok(()) => println!("Saved statistics of {} positions to {}", stats_cache.len(), path),
+            Err(error) => eprintln!("Could not write {}: {}", path, error),
+        }
+    }
+}
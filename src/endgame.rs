@@ -0,0 +1,104 @@
+use crate::{
+    ai::{AI, SearchResult, minmax::MinMax, oracle::ProvenResult},
+    logic::{Board, Color},
+    platform::Platform,
+};
+
+// Won positions for the endgame trainer, see generate_endgames
+pub const ENDGAMES: &str = include_str!("../assets/endgames.txt");
+
+// MinMax depth proving the drills and choosing the defence, its proven wins are short enough to find over the board
+pub const ENDGAME_DEPTH: usize = 4;
+
+// Position the side to move wins, and the plies of its fastest win against the best defence, both sides counted
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndgameDrill {
+    pub board: Board,
+    pub plies: usize,
+}
+
+impl EndgameDrill {
+    // Moves of the attacker in the fastest win, the winning move included
+    pub fn moves_to_win(&self) -> usize {
+        self.plies.div_ceil(2)
+    }
+
+    // fen;plies
+    pub fn to_line(&self) -> String {
+        format!("{};{}", self.board.to_fen(), self.plies)
+    }
+
+    pub fn from_line(line: &str) -> Option<Self> {
+        let (fen, plies) = line.split_once(';')?;
+        Some(Self { board: Board::from_fen(fen.trim())?, plies: plies.trim().parse().ok()? })
+    }
+}
+
+// Lines starting with # are comments. None if a line cannot be read
+pub fn endgames_from_text(text: &str) -> Option<Vec<EndgameDrill>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(EndgameDrill::from_line)
+        .collect()
+}
+
+pub fn endgame_drills() -> Vec<EndgameDrill> {
+    endgames_from_text(ENDGAMES).unwrap_or_default()
+}
+
+// MinMax search of the side to move, its proven result and principal variation give the best play of both sides
+pub fn solve_endgame<O: Platform>(board: &Board) -> SearchResult {
+    let mut solver: MinMax<O> = MinMax::new(board.next_player.clone().unwrap_or(Color::Green), ENDGAME_DEPTH);
+    solver.give_all_options(board, false)
+}
+
+pub async fn solve_endgame_async<O: Platform>(board: &Board) -> SearchResult {
+    let mut solver: MinMax<O> = MinMax::new(board.next_player.clone().unwrap_or(Color::Green), ENDGAME_DEPTH);
+    solver.give_all_options_async(board, false, None).await
+}
+
+// Best line of both sides from the drill position, in notation
+pub fn solution_line(drill: &EndgameDrill, solution: &SearchResult) -> Vec<String> {
+    drill.board.line_to_notation(&solution.pv[..solution.pv.len().min(drill.plies)])
+}
+
+// Where an attempt stands once the attacker has moved
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrillProgress {
+    Won,
+    OnTrack,
+    TooSlow(usize), // still won, but the defender now holds out this many plies, more than the optimal line leaves
+    Lost, // the win is no longer proven
+}
+
+// board is the position after the move of the attacker, defence the solve_endgame search of the defender on it,
+// plies_left what the optimal line has left after this move
+pub fn drill_progress(board: &Board, defence: &SearchResult, plies_left: usize) -> DrillProgress {
+    if board.winner().is_some() {
+        return DrillProgress::Won;
+    }
+    match defence.proven {
+        Some(ProvenResult::Loss(distance)) if distance <= plies_left => DrillProgress::OnTrack,
+        Some(ProvenResult::Loss(distance)) => DrillProgress::TooSlow(distance),
+        _ => DrillProgress::Lost,
+    }
+}
+
+// Random positions won in more than one ply, without two of them symmetric
+pub fn generate_endgames<P: Platform>(count: usize) -> Vec<EndgameDrill> {
+    let mut drills: Vec<EndgameDrill> = vec![];
+    while drills.len() < count {
+        let mut board = Board::random_board::<P>();
+        if P::random_int(2) == 1 {
+            board.next_player = board.next_player.map(|color| color.other_color());
+        }
+        let Some(ProvenResult::Win(plies)) = solve_endgame::<P>(&board).proven else {
+            continue;
+        };
+        if plies > 1 && drills.iter().all(|drill| drill.board.canonical_fen() != board.canonical_fen()) {
+            drills.push(EndgameDrill { board, plies });
+        }
+    }
+    drills
+}
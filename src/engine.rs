@@ -0,0 +1,52 @@
+use std::io::{self, BufRead, Write};
+
+use clap::Parser;
+use burn::backend::NdArray;
+use neutreeko::{
+    ai::any::AnyAI,
+    logic::Color,
+    platform::NativePlatform,
+    protocol::ProtocolHandler,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "neutreeko-engine", about = "Serve a Neutreeko engine over a text protocol on stdin/stdout")]
+struct Args {
+    /// Engine to serve: minmax, mcts, ann or alphazeutreeko
+    #[arg(short, long, default_value = "minmax")]
+    engine: String,
+    /// Engine difficulty, from 1 (very easy) to 6 (expert)
+    #[arg(short, long, default_value_t = 4)]
+    difficulty: usize,
+    /// How much the engine dislikes draws, from -1 (seeks them) to 1 (avoids them), setoption changes it
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    contempt: f32,
+}
+
+fn main() {
+    #[cfg(feature = "tracing")]
+    let _flame = neutreeko::profiling::init_tracing();
+    let args = Args::parse();
+    let Some(engine) = AnyAI::<NdArray<f32>, NativePlatform>::from_name(&args.engine, Color::Green, args.difficulty) else {
+        eprintln!("Unknown engine {}", args.engine);
+        std::process::exit(1);
+    };
+    let mut handler = ProtocolHandler::new(engine, args.difficulty);
+    // through the option, so the engine keeps it when the model is changed
+    for response in handler.handle_line(&format!("setoption name contempt value {}", args.contempt)) {
+        eprintln!("{}", response);
+    }
+    let mut stdout = io::stdout();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        for response in handler.handle_line(&line) {
+            let _ = writeln!(stdout, "{}", response);
+        }
+        let _ = stdout.flush();
+        if !handler.is_running() {
+            break;
+        }
+    }
+}
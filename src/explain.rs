@@ -0,0 +1,110 @@
+use crate::{
+    ai::{ScoredMove, SearchResult},
+    analysis::Analysis,
+    logic::{Board, Color, Direction, Move, Position, ThreatKind},
+};
+
+// Plies of the expected continuation given after the move
+const EXPECTED_LINE_LENGTH: usize = 4;
+
+// Moves below this share of the scores are lost for the search, MinMax gives them 0
+const LOSING_SCORE: f32 = 1e-3;
+
+// Reasons for a move, from the tactics of the position and the search that chose it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub notation: String,
+    pub reasons: Vec<String>,
+}
+
+impl Explanation {
+    pub fn to_text(&self) -> String {
+        format!("{}: {}", self.notation, self.reasons.join(", "))
+    }
+}
+
+fn line_notation(squares: &[Position]) -> String {
+    squares.iter().map(Position::to_notation).collect::<Vec<_>>().join("-")
+}
+
+// Lines this color would complete with one move, whoever is to move
+fn threatened_lines(board: &Board, color: &Color) -> Vec<String> {
+    let mut turn = board.clone();
+    turn.next_player = Some(color.clone());
+    let mut lines = vec![];
+    for threat in turn.winning_moves(color) {
+        let mut completed = turn.clone();
+        if completed.apply_move(threat.pawn_index, &threat.direction).is_err() {
+            continue;
+        }
+        let line = line_notation(&completed.pawn_squares(color));
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+// Why the player to move would play this move, None if it is not legal here
+pub fn explain_move(board: &Board, played: &Move, result: &SearchResult) -> Option<Explanation> {
+    explain(board, played, &result.moves, &result.pv, result.value)
+}
+
+// Why the engine of the analysis prefers its best move, the first of its moves sorted by score
+pub fn explain_best_move(board: &Board, analysis: &Analysis) -> Option<Explanation> {
+    let best_move = analysis.moves.first()?;
+    explain(board, &best_move.to_move(), &analysis.moves, &analysis.principal_variation, analysis.board_eval)
+}
+
+fn explain(board: &Board, played: &Move, moves: &[ScoredMove], pv: &[(usize, Direction)], value: f32) -> Option<Explanation> {
+    let mover = board.next_player.clone()?;
+    let opponent = mover.other_color();
+    let notation = board.move_to_notation(played)?;
+    let child = board.child(played);
+    let threat = board.threats(&mover).into_iter().find(|threat| threat.game_move == *played);
+    if let Some(threat) = threat.as_ref().filter(|threat| threat.kind == ThreatKind::Win) {
+        let reasons = vec![format!("completes the line {}", line_notation(&threat.squares))];
+        return Some(Explanation { notation, reasons });
+    }
+
+    let mut reasons = vec![];
+    let safe_moves = board.safe_moves();
+    let played_score = moves.iter()
+        .find(|scored_move| scored_move.to_move() == *played)
+        .map_or(0.0, |scored_move| scored_move.score);
+    if board.legal_moves().count() > 1 && safe_moves.len() == 1 && safe_moves[0] == *played {
+        reasons.push("only move avoiding an immediate loss".to_string());
+    } else if moves.len() > 1 && played_score > LOSING_SCORE
+        && moves.iter().filter(|scored_move| scored_move.score > LOSING_SCORE).count() == 1 {
+        reasons.push("only move the search does not find losing".to_string());
+    }
+
+    let lines_after = threatened_lines(&child, &opponent);
+    for line in threatened_lines(board, &opponent) {
+        if !lines_after.contains(&line) {
+            reasons.push(format!("blocks {:?}'s line {}", opponent, line));
+        }
+    }
+
+    let threat_squares: Vec<String> = child.threat_squares(&mover).iter().map(Position::to_notation).collect();
+    match threat.map(|threat| threat.kind) {
+        Some(ThreatKind::ForcedWin) => reasons.push(format!("forces a win, no reply covers {}", threat_squares.join("/"))),
+        Some(ThreatKind::DoubleThreat) => reasons.push(format!("creates a double threat on {}", threat_squares.join("/"))),
+        _ if threat_squares.len() == 1 => reasons.push(format!("threatens to complete a line on {}", threat_squares[0])),
+        _ => (),
+    }
+
+    // the principal variation starts with the move chosen by the search, which may not be the one played
+    let pv_follows = pv.first().is_some_and(|(pawn_index, direction)| Move::new(*pawn_index, direction.clone()) == *played);
+    if pv_follows && pv.len() > 1 {
+        let expected = child.line_to_notation(&pv[1..pv.len().min(EXPECTED_LINE_LENGTH + 1)]);
+        if !expected.is_empty() {
+            reasons.push(format!("expects {}", expected.join(" ")));
+        }
+    }
+
+    if reasons.is_empty() {
+        reasons.push(format!("best evaluation found ({:+.2})", value));
+    }
+    Some(Explanation { notation, reasons })
+}
@@ -0,0 +1,184 @@
+use crate::logic::{Board, Color, Move, MoveError, MoveOutcome};
+#[cfg(feature = "engines")]
+use crate::{
+    ai::{AI, SearchResult},
+    gamerecord::{GameRecord, MoveRecord},
+    platform::Platform,
+};
+
+// The two seats of a game: the first player starts with the color to move, the swap rule may exchange the colors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Player {
+    First,
+    Second,
+}
+
+impl Player {
+    pub fn other(&self) -> Player {
+        match self {
+            Player::First => Player::Second,
+            Player::Second => Player::First,
+        }
+    }
+}
+
+// A game in progress with the rules in use, the board alone does not know who plays which color
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    pub board: Board,
+    pub swap_rule: bool, // pie rule: after the first move, the second player may take the first player's color
+    start: Board,
+    moves: Vec<Move>,
+    swapped: bool,
+}
+
+impl Game {
+    pub fn new(start: &Board) -> Self {
+        Self {
+            board: start.clone(),
+            swap_rule: false,
+            start: start.clone(),
+            moves: vec![],
+            swapped: false,
+        }
+    }
+
+    pub fn with_swap_rule(start: &Board) -> Self {
+        Self {
+            swap_rule: true,
+            ..Self::new(start)
+        }
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn is_swapped(&self) -> bool {
+        self.swapped
+    }
+
+    pub fn play(&mut self, game_move: &Move) -> Result<MoveOutcome, MoveError> {
+        let outcome = self.board.apply_move(game_move.pawn_index, &game_move.direction)?;
+        self.moves.push(game_move.clone());
+        Ok(outcome)
+    }
+
+    // Only right after the first move, and only once
+    pub fn can_swap(&self) -> bool {
+        self.swap_rule && !self.swapped && self.moves.len() == 1 && self.board.winner().is_none()
+    }
+
+    // The board does not change: the second player now owns the first move and the first player replies
+    pub fn swap(&mut self) -> bool {
+        if !self.can_swap() {
+            return false;
+        }
+        self.swapped = true;
+        true
+    }
+
+    pub fn color_of(&self, player: Player) -> Color {
+        let first_color = self.start.next_player.clone().unwrap_or(Color::Green);
+        match (player, self.swapped) {
+            (Player::First, false) | (Player::Second, true) => first_color,
+            (Player::First, true) | (Player::Second, false) => first_color.other_color(),
+        }
+    }
+
+    pub fn player_of(&self, color: &Color) -> Player {
+        if self.color_of(Player::First) == *color {
+            Player::First
+        } else {
+            Player::Second
+        }
+    }
+
+    pub fn player_to_move(&self) -> Option<Player> {
+        self.board.next_player.as_ref().map(|color| self.player_of(color))
+    }
+}
+
+// A position of a replayed game: the move played there and the search of the engine on it
+#[cfg(feature = "engines")]
+#[derive(Clone, Debug)]
+pub struct ReplayedPly {
+    pub board: Board,
+    pub played: Move,
+    pub search: SearchResult,
+    pub engine_move: Option<Move>, // highest scored move of the search, the first one on ties
+}
+
+#[cfg(feature = "engines")]
+impl ReplayedPly {
+    pub fn agrees(&self) -> bool {
+        self.engine_move.as_ref() == Some(&self.played)
+    }
+}
+
+// Every ply of a game seen by an engine, with the game record annotated by its searches
+#[cfg(feature = "engines")]
+#[derive(Clone, Debug)]
+pub struct ReplayedGame {
+    pub plies: Vec<ReplayedPly>,
+    pub record: GameRecord,
+}
+
+#[cfg(feature = "engines")]
+impl ReplayedGame {
+    // Share of the moves played that the engine would have played
+    pub fn agreement(&self) -> f32 {
+        self.plies.iter().filter(|ply| ply.agrees()).count() as f32 / self.plies.len().max(1) as f32
+    }
+}
+
+#[cfg(feature = "engines")]
+impl Game {
+    // Game of a record, None if one of its moves is illegal
+    pub fn from_record(record: &GameRecord) -> Option<Self> {
+        let mut game = Self::new(&record.start_board()?);
+        for move_record in record.moves.iter() {
+            let game_move = game.board.move_from_notation(&move_record.notation)?;
+            game.play(&game_move).ok()?;
+        }
+        Some(game)
+    }
+
+    // Searches every position of the game from the start, whatever the game board is now. The engine move is
+    // the best scored one rather than the engine's own selection, so replays of a deterministic engine
+    // (MinMax, or MCTS with a node limit on SeededPlatform) give the same plies every time
+    pub fn replay_with_engine<O: Platform, A: AI<O>>(&self, engine: &mut A) -> Result<ReplayedGame, MoveError> {
+        let mut board = self.start.clone();
+        let mut record = GameRecord::new(&board);
+        let mut plies = vec![];
+        for played in self.moves.iter() {
+            let position = board.clone();
+            board.apply_move(played.pawn_index, &played.direction)?;
+            if let Some(color) = position.next_player.as_ref().filter(|color| engine.color() != *color) {
+                engine.set_color(color.clone());
+            }
+            let search = engine.give_all_options(&position, false);
+            let engine_move = search.moves.iter()
+                .reduce(|best, scored_move| if scored_move.score > best.score { scored_move } else { best })
+                .map(|scored_move| scored_move.to_move());
+            let mut move_record = MoveRecord::from_search(position.move_to_notation(played).unwrap_or_default(), &search);
+            move_record.set_policy(&position, &search.moves);
+            let engine_notation = engine_move.as_ref()
+                .filter(|engine_move| *engine_move != played)
+                .and_then(|engine_move| position.move_to_notation(engine_move));
+            if let Some(engine_notation) = engine_notation {
+                move_record.comment = Some(format!("engine prefers {}", engine_notation));
+            }
+            record.moves.push(move_record);
+            plies.push(ReplayedPly { board: position, played: played.clone(), search, engine_move });
+        }
+        record.finish(&board);
+        Ok(ReplayedGame { plies, record })
+    }
+}
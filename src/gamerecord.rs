@@ -0,0 +1,386 @@
+use crate::{
+    ai::{ScoredMove, SearchResult, value::Value},
+    analysis::{Arrow, arrows_from_text, arrows_to_text},
+    logic::{Board, Color, Move},
+};
+
+// Comment of the first move when the second player took over its color with the swap rule
+pub const SWAP_COMMENT: &str = "colors swapped";
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    GreenWins,
+    YellowWins,
+    Draw,
+}
+
+impl GameResult {
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::GreenWins => "1-0",
+            GameResult::YellowWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+
+    pub fn from_pgn(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(GameResult::GreenWins),
+            "0-1" => Some(GameResult::YellowWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameResult::GreenWins => Some(Color::Green),
+            GameResult::YellowWins => Some(Color::Yellow),
+            GameResult::Draw => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveRecord {
+    pub notation: String,
+    pub evaluation: Option<f32>, // engine evaluation for the player making the move, from -1 to 1
+    pub depth: Option<usize>,
+    pub time_ms: Option<f64>, // time spent on the move
+    pub clock_ms: Option<f64>, // time left on the clock after the move
+    pub policy: Vec<(String, f32)>, // move scores of the search, kept for training data, empty if not recorded
+    pub arrows: Vec<Arrow>, // drawn on the board before the move, from an analysis or by the user
+    pub comment: Option<String>,
+}
+
+impl MoveRecord {
+    pub fn new(notation: String) -> Self {
+        Self {
+            notation,
+            ..Self::default()
+        }
+    }
+
+    // Move played by an engine, annotated with its search
+    pub fn from_search(notation: String, result: &SearchResult) -> Self {
+        Self {
+            notation,
+            evaluation: Some(result.value),
+            depth: Some(result.depth),
+            time_ms: Some(result.time_ms),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_policy(&mut self, board: &Board, moves: &[ScoredMove]) {
+        self.policy = moves.iter()
+            .filter_map(|scored_move| board.move_to_notation(&scored_move.to_move()).map(|notation| (notation, scored_move.score)))
+            .collect();
+    }
+
+    // Annotations are written as a PGN comment: {[%eval 0.25] [%depth 4] [%emt 120.0] [%clk 59000.0] [%policy b1-c1:0.5,d1-c1:0.5] [%cal Gb1c1,Rd5d2] free text}
+    fn annotation(&self) -> Option<String> {
+        let mut parts = vec![];
+        if let Some(evaluation) = self.evaluation {
+            parts.push(format!("[%eval {:.3}]", evaluation));
+        }
+        if let Some(depth) = self.depth {
+            parts.push(format!("[%depth {}]", depth));
+        }
+        if let Some(time_ms) = self.time_ms {
+            parts.push(format!("[%emt {:.1}]", time_ms));
+        }
+        if let Some(clock_ms) = self.clock_ms {
+            parts.push(format!("[%clk {:.1}]", clock_ms));
+        }
+        if !self.policy.is_empty() {
+            let policy: Vec<String> = self.policy.iter().map(|(notation, score)| format!("{}:{:.4}", notation, score)).collect();
+            parts.push(format!("[%policy {}]", policy.join(",")));
+        }
+        if !self.arrows.is_empty() {
+            parts.push(format!("[%cal {}]", arrows_to_text(&self.arrows)));
+        }
+        if let Some(comment) = &self.comment {
+            // braces would end the comment early
+            parts.push(comment.replace(['{', '}'], ""));
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("{{{}}}", parts.join(" ")))
+        }
+    }
+
+    fn parse_annotation(&mut self, annotation: &str) {
+        let mut rest = annotation.trim();
+        let mut comment = vec![];
+        while !rest.is_empty() {
+            if let Some(command) = rest.strip_prefix("[%") {
+                let Some(end) = command.find(']') else {
+                    break;
+                };
+                let mut words = command[..end].split_whitespace();
+                let name = words.next();
+                let value = words.next().unwrap_or_default();
+                match name {
+                    Some("eval") => self.evaluation = value.parse().ok(),
+                    Some("depth") => self.depth = value.parse().ok(),
+                    Some("emt") => self.time_ms = value.parse().ok(),
+                    Some("clk") => self.clock_ms = value.parse().ok(),
+                    Some("policy") => {
+                        self.policy = value.split(',')
+                            .filter_map(|entry| entry.split_once(':'))
+                            .filter_map(|(notation, score)| score.parse().ok().map(|score| (notation.to_string(), score)))
+                            .collect();
+                    }
+                    Some("cal") => self.arrows = arrows_from_text(value),
+                    _ => (),
+                }
+                rest = command[end + 1..].trim_start();
+            } else {
+                let end = rest.find("[%").unwrap_or(rest.len());
+                comment.push(rest[..end].trim());
+                rest = &rest[end..];
+            }
+        }
+        let comment = comment.join(" ");
+        if !comment.is_empty() {
+            self.comment = Some(comment);
+        }
+    }
+}
+
+// One game: start position, moves with their annotations and outcome
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    pub event: String,
+    pub round: Option<usize>,
+    pub green: String,
+    pub yellow: String,
+    pub start_fen: String,
+    pub moves: Vec<MoveRecord>,
+    pub result: Option<GameResult>, // None while the game is in progress
+    pub termination: Option<String>,
+}
+
+impl GameRecord {
+    pub fn new(start: &Board) -> Self {
+        Self {
+            event: "Casual game".to_string(),
+            round: None,
+            green: "?".to_string(),
+            yellow: "?".to_string(),
+            start_fen: start.to_fen(),
+            moves: vec![],
+            result: None,
+            termination: None,
+        }
+    }
+
+    pub fn start_board(&self) -> Option<Board> {
+        Board::from_fen(&self.start_fen)
+    }
+
+    // Every position of the game, from the start to the final one. None if a move is illegal
+    pub fn boards(&self) -> Option<Vec<Board>> {
+        let mut board = self.start_board()?;
+        let mut boards = vec![board.clone()];
+        for move_record in self.moves.iter() {
+            let game_move = board.move_from_notation(&move_record.notation)?;
+            board.move_pawn_until_blocked(game_move.pawn_index, &game_move.direction);
+            boards.push(board.clone());
+        }
+        Some(boards)
+    }
+
+    // Positions where the named player was to move, with the move they played. None if a move is illegal
+    pub fn moves_played_by(&self, player: &str) -> Option<Vec<(Board, Move)>> {
+        let boards = self.boards()?;
+        let mut played = vec![];
+        for (index, (board, move_record)) in boards.iter().zip(self.moves.iter()).enumerate() {
+            let mover = match board.next_player {
+                Some(Color::Green) => &self.green,
+                Some(Color::Yellow) => &self.yellow,
+                None => continue,
+            };
+            // after a swap the names follow the new colors, the first move was made by the other player
+            let swapped_away = index == 0 && move_record.comment.as_deref() == Some(SWAP_COMMENT);
+            if mover == player && !swapped_away {
+                played.push((board.clone(), board.move_from_notation(&move_record.notation)?));
+            }
+        }
+        Some(played)
+    }
+
+    // Sets the result from the final position, for games that ended by alignment
+    pub fn finish(&mut self, board: &Board) {
+        self.result = match board.winner() {
+            Some(Color::Green) => Some(GameResult::GreenWins),
+            Some(Color::Yellow) => Some(GameResult::YellowWins),
+            None => self.result.clone(),
+        };
+    }
+
+    // Outcome for the player to move on this board: 1 for a win, -1 for a loss, 0 for a draw or an unfinished game
+    pub fn result_for(&self, board: &Board) -> Value {
+        match &board.next_player {
+            Some(mover) => Value::from_outcome(self.result.as_ref().and_then(|result| result.winner()).as_ref(), mover),
+            None => Value::DRAW,
+        }
+    }
+
+    // One JSON object per move, see games_to_jsonl. None if a move is illegal
+    pub fn to_jsonl(&self, game: usize) -> Option<String> {
+        let boards = self.boards()?;
+        let mut lines = String::new();
+        for (ply, (board, move_record)) in boards.iter().zip(self.moves.iter()).enumerate() {
+            let value = move_record.evaluation.map_or("null".to_string(), |evaluation| format!("{:.4}", evaluation));
+            let policy: Vec<String> = move_record.policy.iter()
+                .map(|(notation, score)| format!("\"{}\":{:.4}", notation, score))
+                .collect();
+            let mut planes = [0.0; 8 * 5 * 5];
+            for (notation, score) in move_record.policy.iter() {
+                let Some(game_move) = board.move_from_notation(notation) else {
+                    continue;
+                };
+                let position = &board.pawns[game_move.pawn_index].position;
+                planes[(game_move.direction as usize * 5 + position.row as usize) * 5 + position.column as usize] = *score;
+            }
+            // most scores are 0, written without decimals to keep the lines short
+            let planes: Vec<String> = planes.iter().map(|score| format!("{:.4}", score).trim_end_matches('0').trim_end_matches('.').to_string()).collect();
+            lines.push_str(&format!(
+                "{{\"game\":{},\"ply\":{},\"fen\":\"{}\",\"move\":\"{}\",\"value\":{},\"result\":{},\"policy\":{{{}}},\"policy_planes\":[{}]}}\n",
+                game, ply, board.to_fen(), move_record.notation, value, self.result_for(board), policy.join(","), planes.join(",")
+            ));
+        }
+        Some(lines)
+    }
+
+    fn result_token(&self) -> &'static str {
+        self.result.as_ref().map_or("*", |result| result.to_pgn())
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        pgn.push_str(&format!("[Event \"{}\"]\n", self.event));
+        if let Some(round) = self.round {
+            pgn.push_str(&format!("[Round \"{}\"]\n", round));
+        }
+        pgn.push_str(&format!("[Green \"{}\"]\n", self.green));
+        pgn.push_str(&format!("[Yellow \"{}\"]\n", self.yellow));
+        pgn.push_str(&format!("[FEN \"{}\"]\n", self.start_fen));
+        pgn.push_str(&format!("[Result \"{}\"]\n", self.result_token()));
+        if let Some(termination) = &self.termination {
+            pgn.push_str(&format!("[Termination \"{}\"]\n", termination));
+        }
+        pgn.push('\n');
+        let mut movetext = vec![];
+        for (index, move_record) in self.moves.iter().enumerate() {
+            if index % 2 == 0 {
+                movetext.push(format!("{}.", index / 2 + 1));
+            }
+            movetext.push(move_record.notation.clone());
+            if let Some(annotation) = move_record.annotation() {
+                movetext.push(annotation);
+            }
+        }
+        movetext.push(self.result_token().to_string());
+        pgn.push_str(&movetext.join(" "));
+        pgn.push('\n');
+        pgn
+    }
+
+    // Reads a single game, None if the text is not a game written by to_pgn
+    pub fn from_pgn(pgn: &str) -> Option<Self> {
+        let mut record = GameRecord::new(&Board::default_new());
+        let mut movetext = String::new();
+        for line in pgn.lines() {
+            let line = line.trim();
+            if let Some(tag) = line.strip_prefix('[').and_then(|tag| tag.strip_suffix(']')) {
+                let (name, value) = tag.split_once(' ')?;
+                let value = value.trim().trim_matches('"').to_string();
+                match name {
+                    "Event" => record.event = value,
+                    "Round" => record.round = value.parse().ok(),
+                    "Green" => record.green = value,
+                    "Yellow" => record.yellow = value,
+                    "FEN" => record.start_fen = value,
+                    "Result" => record.result = GameResult::from_pgn(&value),
+                    "Termination" => record.termination = Some(value),
+                    _ => (),
+                }
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+        Board::from_fen(&record.start_fen)?;
+
+        let mut rest = movetext.trim();
+        while !rest.is_empty() {
+            if let Some(annotation) = rest.strip_prefix('{') {
+                let end = annotation.find('}')?;
+                record.moves.last_mut()?.parse_annotation(&annotation[..end]);
+                rest = annotation[end + 1..].trim_start();
+                continue;
+            }
+            let end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = rest[end..].trim_start();
+            if token == "*" || GameResult::from_pgn(token).is_some() || token.ends_with('.') {
+                continue;
+            }
+            record.moves.push(MoveRecord::new(token.to_string()));
+        }
+        record.boards()?;
+        Some(record)
+    }
+}
+
+pub fn games_to_pgn(records: &[GameRecord]) -> String {
+    records.iter()
+        .map(|record| record.to_pgn())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Positions of the games for analysis outside Rust, one JSON object per line:
+// {"game":0,"ply":0,"fen":"...","move":"b1-b4","value":0.1250,"result":1,"policy":{"b1-b4":0.5000,...},"policy_planes":[...]}
+// - fen: board before the move, the player to move is its last field
+// - value: search evaluation for the player to move, from -1 to 1, null if the move was not searched
+// - result: outcome of the game for the player to move, 1 for a win, -1 for a loss, 0 for a draw
+// - policy: search scores of the legal moves, empty if not recorded
+// - policy_planes: the same scores in the network output layout, 200 numbers indexed by (direction * 5 + row) * 5 + column
+//   of the moving pawn, directions in the order of Direction::ALL
+// Games with an illegal move are left out
+pub fn games_to_jsonl(records: &[GameRecord]) -> String {
+    records.iter()
+        .enumerate()
+        .filter_map(|(game, record)| record.to_jsonl(game))
+        .collect()
+}
+
+// Text of every game of a file, games are separated by their Event tag
+pub fn split_pgn(pgn: &str) -> Vec<String> {
+    let mut games = vec![];
+    let mut current = String::new();
+    for line in pgn.lines() {
+        if line.trim_start().starts_with("[Event ") && !current.trim().is_empty() {
+            games.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        games.push(current);
+    }
+    games
+}
+
+// Reads every game of a file
+pub fn games_from_pgn(pgn: &str) -> Option<Vec<GameRecord>> {
+    split_pgn(pgn).iter().map(|game| GameRecord::from_pgn(game)).collect()
+}
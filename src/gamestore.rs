@@ -0,0 +1,190 @@
+use crate::{
+    gamerecord::{GameRecord, games_from_pgn, games_to_pgn, split_pgn},
+    logic::Color,
+    platform::{Platform, load_text, save_text},
+};
+
+// Name the front-ends give the person at the keyboard in game records
+pub const HUMAN_PLAYER: &str = "Human";
+
+// Color of the human player in a game against an engine, None if both or neither side is human
+pub fn human_color(record: &GameRecord) -> Option<Color> {
+    match (record.green == HUMAN_PLAYER, record.yellow == HUMAN_PLAYER) {
+        (true, false) => Some(Color::Green),
+        (false, true) => Some(Color::Yellow),
+        _ => None,
+    }
+}
+
+// The browser storage holds a few megabytes, the oldest games are dropped past this
+pub const MAX_GAMES_STORED: usize = 500;
+
+// Named saves are kept until deleted, saving under a new name is refused past this
+pub const MAX_SAVED_GAMES: usize = 50;
+
+// Finished games of the local player, to fit a model playing like them.
+// Only kept on the device when the player opted in, nothing is sent anywhere: the trainer reads an exported file
+#[derive(Clone, Debug, Default)]
+pub struct GameStore {
+    games: Vec<GameRecord>,
+}
+
+impl GameStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn games(&self) -> &[GameRecord] {
+        &self.games
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    // Keeps finished games with the human player, returns whether the game was kept
+    pub fn add(&mut self, record: GameRecord) -> bool {
+        if record.result.is_none() || (record.green != HUMAN_PLAYER && record.yellow != HUMAN_PLAYER) {
+            return false;
+        }
+        self.games.push(record);
+        if self.games.len() > MAX_GAMES_STORED {
+            self.games.drain(..self.games.len() - MAX_GAMES_STORED);
+        }
+        true
+    }
+
+    pub fn to_pgn(&self) -> String {
+        games_to_pgn(&self.games)
+    }
+
+    pub fn from_pgn(pgn: &str) -> Option<Self> {
+        Some(Self { games: games_from_pgn(pgn)? })
+    }
+
+    // A missing file gives an empty store
+    pub fn load_file(path: &str) -> Option<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::from_pgn(&text),
+            Err(_) => Some(Self::new()),
+        }
+    }
+
+    pub fn save_file(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_pgn())
+    }
+
+    // The web front-end keeps the games in the storage of the platform.
+    // Unlike the statistics cache, a missing entry means the player did not opt in and gives None
+    pub async fn load_storage<O: Platform>(key: &str) -> Option<Self> {
+        Self::from_pgn(&load_text::<O>(key).await?)
+    }
+
+    pub async fn save_storage<O: Platform>(&self, key: &str) -> bool {
+        save_text::<O>(key, &self.to_pgn()).await
+    }
+
+    // Opting out deletes the stored games
+    pub async fn remove_storage<O: Platform>(key: &str) -> bool {
+        O::storage_delete(key).await.is_ok()
+    }
+}
+
+// Game saved under a name to come back to, finished or not, with the time of the save in ms since the epoch
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedGame {
+    pub name: String,
+    pub saved_ms: f64,
+    pub record: GameRecord,
+}
+
+impl SavedGame {
+    // The game record with Slot and Saved tags after its Event tag, other PGN readers skip them
+    pub fn to_pgn(&self) -> String {
+        let pgn = self.record.to_pgn();
+        let (event, rest) = pgn.split_once('\n').unwrap_or((&pgn, ""));
+        format!("{}\n[Slot \"{}\"]\n[Saved \"{}\"]\n{}", event, self.name, self.saved_ms.round(), rest)
+    }
+
+    pub fn from_pgn(pgn: &str) -> Option<Self> {
+        let tag = |name: &str| pgn.lines()
+            .find_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']')?.strip_prefix(name)?.strip_prefix(' '))
+            .map(|value| value.trim().trim_matches('"').to_string());
+        Some(Self { name: tag("Slot")?, saved_ms: tag("Saved")?.parse().ok()?, record: GameRecord::from_pgn(pgn)? })
+    }
+}
+
+// Games saved by name in the web front-end, the last saved first
+#[derive(Clone, Debug, Default)]
+pub struct SavedGames {
+    games: Vec<SavedGame>,
+}
+
+impl SavedGames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn games(&self) -> &[SavedGame] {
+        &self.games
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SavedGame> {
+        self.games.iter().find(|game| game.name == name)
+    }
+
+    // Saving under an existing name replaces that game. Quotes and brackets would break the PGN tag and are dropped,
+    // false for an empty name or when every slot is taken
+    pub fn save(&mut self, name: &str, record: GameRecord, saved_ms: f64) -> bool {
+        let name: String = name.chars().filter(|c| !matches!(c, '"' | '[' | ']')).collect::<String>().trim().to_string();
+        if name.is_empty() || (self.get(&name).is_none() && self.games.len() >= MAX_SAVED_GAMES) {
+            return false;
+        }
+        self.games.retain(|game| game.name != name);
+        self.games.insert(0, SavedGame { name, saved_ms, record });
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.games.len();
+        self.games.retain(|game| game.name != name);
+        self.games.len() < count
+    }
+
+    // Every saved game, readable as a plain PGN file
+    pub fn to_pgn(&self) -> String {
+        self.games.iter()
+            .map(SavedGame::to_pgn)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn from_pgn(pgn: &str) -> Option<Self> {
+        let games = split_pgn(pgn).iter().map(|game| SavedGame::from_pgn(game)).collect::<Option<Vec<SavedGame>>>()?;
+        Some(Self { games })
+    }
+
+    // A missing entry gives no saved games
+    pub async fn load_storage<O: Platform>(key: &str) -> Option<Self> {
+        match load_text::<O>(key).await {
+            Some(pgn) => Self::from_pgn(&pgn),
+            None => Some(Self::new()),
+        }
+    }
+
+    pub async fn save_storage<O: Platform>(&self, key: &str) -> bool {
+        save_text::<O>(key, &self.to_pgn()).await
+    }
+}
@@ -0,0 +1,308 @@
+use std::time::Duration;
+
+use burn::backend::NdArray;
+use eframe::egui;
+use neutreeko::{
+    ai::{AI, any::{AnyAI, ENGINE_NAMES}, ann::weights::{DEFAULT_MODEL_ID, set_model_bytes}, minmax::MinMax},
+    analysis::{Analysis, analyze},
+    game::Game,
+    logic::{Board, Color, Direction, Move, Position, StartPosition},
+    platform::{NativePlatform, Platform, Receiver, Sender, channel},
+};
+
+type Engine = AnyAI<NdArray<f32>, NativePlatform>;
+
+const SQUARE_SIZE: f32 = 90.0;
+const ANALYSIS_DEPTH: usize = 5;
+// Model identifier of the checkpoint loaded from disk
+const CHECKPOINT_MODEL_ID: &str = "checkpoint";
+
+const LIGHT_SQUARE: egui::Color32 = egui::Color32::from_rgb(240, 217, 181);
+const DARK_SQUARE: egui::Color32 = egui::Color32::from_rgb(181, 136, 99);
+const GREEN_PAWN: egui::Color32 = egui::Color32::from_rgb(46, 204, 113);
+const YELLOW_PAWN: egui::Color32 = egui::Color32::from_rgb(241, 196, 15);
+
+// Results sent back by the search threads, with the board they were computed for
+enum Reply {
+    Move(Engine, Board, Option<(usize, Direction)>),
+    Analysis(Board, Analysis),
+}
+
+struct GuiApp {
+    game: Game,
+    start_position: StartPosition,
+    engine_selected: Option<usize>, // index in ENGINE_NAMES, None for two human players
+    difficulty: usize,
+    human_color: Color,
+    model_id: String,
+    checkpoint_path: String,
+    engine: Option<Engine>, // kept between moves so tree search engines reuse their tree, None while it searches
+    selected_pawn: Option<usize>,
+    thinking: bool,
+    analyzing: bool,
+    analysis: Option<(Board, Analysis)>,
+    message: Option<String>,
+    replies: Receiver<Reply>,
+    reply_sender: Sender<Reply>,
+}
+
+impl GuiApp {
+    fn new() -> Self {
+        let (reply_sender, replies) = channel();
+        Self {
+            game: Game::new(&Board::default_new()),
+            start_position: StartPosition::Standard,
+            engine_selected: Some(0),
+            difficulty: 4,
+            human_color: Color::Green,
+            model_id: DEFAULT_MODEL_ID.to_string(),
+            checkpoint_path: String::new(),
+            engine: None,
+            selected_pawn: None,
+            thinking: false,
+            analyzing: false,
+            analysis: None,
+            message: None,
+            replies,
+            reply_sender,
+        }
+    }
+
+    fn new_game(&mut self) {
+        self.game = Game::new(&Board::from_start(&self.start_position));
+        self.engine = None;
+        self.selected_pawn = None;
+        self.analysis = None;
+        self.message = None;
+    }
+
+    fn engine_to_move(&self) -> bool {
+        self.engine_selected.is_some()
+            && self.game.board.next_player.as_ref().is_some_and(|color| *color != self.human_color)
+    }
+
+    // Starts the engine search in a thread, the reply is picked up by poll_replies
+    fn start_engine_move(&mut self) {
+        let Some(engine_index) = self.engine_selected else {
+            return;
+        };
+        let Some(color) = self.game.board.next_player.clone() else {
+            return;
+        };
+        let mut engine = match self.engine.take() {
+            Some(engine) if engine.name() == ENGINE_NAMES[engine_index] => engine,
+            _ => match Engine::from_name_with_model(ENGINE_NAMES[engine_index], color.clone(), self.difficulty, &self.model_id) {
+                Some(engine) => engine,
+                None => return,
+            },
+        };
+        if *engine.color() != color {
+            engine.set_color(color);
+        }
+        let board = self.game.board.clone();
+        let sender = self.reply_sender.clone();
+        self.thinking = true;
+        NativePlatform::spawn(move || async move {
+            let best_move = engine.ai_play(&board, false);
+            sender.send(Reply::Move(engine, board, best_move));
+        });
+    }
+
+    fn start_analysis(&mut self) {
+        let board = self.game.board.clone();
+        let sender = self.reply_sender.clone();
+        self.analyzing = true;
+        NativePlatform::spawn(move || async move {
+            let mut analyzer: MinMax<NativePlatform> = MinMax::new(Color::Green, ANALYSIS_DEPTH);
+            let analysis = analyze(&mut analyzer, &board, false);
+            sender.send(Reply::Analysis(board, analysis));
+        });
+    }
+
+    fn poll_replies(&mut self) {
+        while let Some(reply) = self.replies.try_recv() {
+            match reply {
+                Reply::Move(engine, board, best_move) => {
+                    self.thinking = false;
+                    self.engine = Some(engine);
+                    // the game was restarted while the engine was searching
+                    if board != self.game.board {
+                        continue;
+                    }
+                    if let Some((pawn_index, direction)) = best_move {
+                        if let Err(error) = self.game.play(&Move::new(pawn_index, direction)) {
+                            self.message = Some(format!("Engine move rejected: {}", error));
+                        }
+                    }
+                }
+                Reply::Analysis(board, analysis) => {
+                    self.analyzing = false;
+                    self.analysis = Some((board, analysis));
+                }
+            }
+        }
+    }
+
+    fn load_checkpoint(&mut self) {
+        match std::fs::read(self.checkpoint_path.trim()) {
+            Ok(bytes) => {
+                set_model_bytes(CHECKPOINT_MODEL_ID, bytes);
+                self.model_id = CHECKPOINT_MODEL_ID.to_string();
+                self.engine = None;
+                self.message = Some(format!("Loaded {}", self.checkpoint_path.trim()));
+            }
+            Err(error) => self.message = Some(format!("Could not read {}: {}", self.checkpoint_path.trim(), error)),
+        }
+    }
+
+    // Selects a pawn of the player to move, or plays the selected pawn to the clicked square
+    fn square_clicked(&mut self, position: Position) {
+        let board = &self.game.board;
+        if let Some(pawn_index) = board.pawn_at(&position) {
+            if board.next_player.as_ref() == Some(&board.pawns[pawn_index].color) {
+                self.selected_pawn = Some(pawn_index);
+            }
+            return;
+        }
+        let Some(pawn_index) = self.selected_pawn else {
+            return;
+        };
+        let destination = board.get_valid_directions_and_resulting_boards(pawn_index)
+            .into_iter()
+            .find(|(_, new_board)| new_board.pawns[pawn_index].position == position);
+        if let Some((direction, _)) = destination {
+            if let Err(error) = self.game.play(&Move::new(pawn_index, direction)) {
+                self.message = Some(error.to_string());
+            }
+            self.selected_pawn = None;
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        if ui.button("New game").clicked() {
+            self.new_game();
+        }
+        ui.separator();
+        egui::ComboBox::from_label("Start position")
+            .selected_text(self.start_position.description())
+            .show_ui(ui, |ui| {
+                for start in StartPosition::all() {
+                    let description = start.description();
+                    ui.selectable_value(&mut self.start_position, start, description);
+                }
+            });
+        egui::ComboBox::from_label("Opponent")
+            .selected_text(self.engine_selected.map_or("Human", |index| ENGINE_NAMES[index]))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.engine_selected, None, "Human");
+                for (index, name) in ENGINE_NAMES.iter().enumerate() {
+                    ui.selectable_value(&mut self.engine_selected, Some(index), *name);
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.difficulty, 1..=6).text("Difficulty"));
+        ui.horizontal(|ui| {
+            ui.label("You play");
+            ui.radio_value(&mut self.human_color, Color::Green, "Green");
+            ui.radio_value(&mut self.human_color, Color::Yellow, "Yellow");
+        });
+        ui.separator();
+        ui.label(format!("Network weights: {}", self.model_id));
+        ui.text_edit_singleline(&mut self.checkpoint_path);
+        if ui.button("Load checkpoint (.bpk)").clicked() {
+            self.load_checkpoint();
+        }
+        ui.separator();
+        if ui.add_enabled(!self.analyzing, egui::Button::new(if self.analyzing { "Analyzing..." } else { "Analyze" })).clicked() {
+            self.start_analysis();
+        }
+        if let Some((board, analysis)) = &self.analysis {
+            ui.monospace(analysis.to_text(board));
+        }
+    }
+
+    fn status(&self) -> String {
+        if let Some(winner) = self.game.board.winner() {
+            return format!("{:?} wins", winner);
+        }
+        match &self.game.board.next_player {
+            Some(color) if self.thinking => format!("{:?} is thinking...", color),
+            Some(color) => format!("{:?} to move", color),
+            None => "Game over".to_string(),
+        }
+    }
+
+    fn board_view(&mut self, ui: &mut egui::Ui) {
+        let board = self.game.board.clone();
+        let size = egui::vec2(board.number_of_columns as f32 * SQUARE_SIZE, board.number_of_rows as f32 * SQUARE_SIZE);
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+        let origin = response.rect.min;
+        let square_rect = |row: usize, column: usize| egui::Rect::from_min_size(
+            origin + egui::vec2(column as f32 * SQUARE_SIZE, row as f32 * SQUARE_SIZE),
+            egui::vec2(SQUARE_SIZE, SQUARE_SIZE),
+        );
+        for row in 0..board.number_of_rows {
+            for column in 0..board.number_of_columns {
+                let color = if (row + column) % 2 == 0 { LIGHT_SQUARE } else { DARK_SQUARE };
+                painter.rect_filled(square_rect(row, column), 0.0, color);
+            }
+        }
+        for (index, pawn) in board.pawns.iter().enumerate() {
+            let center = square_rect(pawn.position.row, pawn.position.column).center();
+            let color = match pawn.color {
+                Color::Green => GREEN_PAWN,
+                Color::Yellow => YELLOW_PAWN,
+            };
+            painter.circle_filled(center, SQUARE_SIZE * 0.35, color);
+            if self.selected_pawn == Some(index) {
+                painter.circle_stroke(center, SQUARE_SIZE * 0.4, egui::Stroke::new(3.0, egui::Color32::BLACK));
+            }
+        }
+        // destinations of the selected pawn
+        if let Some(pawn_index) = self.selected_pawn {
+            for (_, new_board) in board.get_valid_directions_and_resulting_boards(pawn_index) {
+                let position = &new_board.pawns[pawn_index].position;
+                painter.circle_filled(square_rect(position.row, position.column).center(), SQUARE_SIZE * 0.1, egui::Color32::DARK_GRAY);
+            }
+        }
+        if self.thinking || !response.clicked() {
+            return;
+        }
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let offset = pointer - origin;
+            let position = Position { row: (offset.y / SQUARE_SIZE) as usize, column: (offset.x / SQUARE_SIZE) as usize };
+            if position.row < board.number_of_rows && position.column < board.number_of_columns {
+                self.square_clicked(position);
+            }
+        }
+    }
+}
+
+impl eframe::App for GuiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_replies();
+        if !self.thinking && self.engine_to_move() && self.game.board.winner().is_none() {
+            self.start_engine_move();
+        }
+        if self.thinking || self.analyzing {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        egui::SidePanel::left("controls").min_width(260.0).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| self.controls(ui));
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(self.status());
+            if let Some(message) = &self.message {
+                ui.colored_label(egui::Color32::RED, message);
+            }
+            self.board_view(ui);
+        });
+    }
+}
+
+fn main() -> eframe::Result<()> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 560.0]),
+        ..Default::default()
+    };
+    eframe::run_native("Neutreeko", options, Box::new(|_creation_context| Ok(Box::new(GuiApp::new()))))
+}
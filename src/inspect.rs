@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::Parser;
+use neutreeko::gamerecord::{GameRecord, GameResult, games_from_pgn};
+
+#[derive(Parser, Debug)]
+#[command(name = "neutreeko-inspect", about = "Print statistics about recorded self-play games used for training")]
+struct Args {
+    /// PGN files written by the trainer
+    #[arg(required = true)]
+    files: Vec<String>,
+    /// Number of plies that make an opening
+    #[arg(long, default_value_t = 4)]
+    opening_plies: usize,
+    /// Number of openings listed
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+// Value targets are counted in bins of this width, from -1 to 1
+const VALUE_BIN_WIDTH: f32 = 0.2;
+
+fn entropy(policy: &[(String, f32)]) -> f32 {
+    let total: f32 = policy.iter().map(|(_, score)| score.max(0.0)).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    policy.iter()
+        .map(|(_, score)| score.max(0.0) / total)
+        .filter(|probability| *probability > 0.0)
+        .map(|probability| -probability * probability.ln())
+        .sum()
+}
+
+fn print_duplication(games: &[GameRecord]) {
+    let mut positions = 0;
+    let mut unique = HashSet::new();
+    let mut unique_canonical = HashSet::new();
+    for game in games.iter() {
+        let Some(boards) = game.boards() else {
+            continue;
+        };
+        // the final position has no target
+        for board in boards.iter().take(game.moves.len()) {
+            positions += 1;
+            unique.insert(board.to_fen());
+            unique_canonical.insert(board.canonical_fen());
+        }
+    }
+    println!("Positions: {}", positions);
+    if positions == 0 {
+        return;
+    }
+    let duplication_rate = |unique_count: usize| 100.0 * (1.0 - unique_count as f32 / positions as f32);
+    println!("  unique: {} ({:.1}% duplicates)", unique.len(), duplication_rate(unique.len()));
+    println!("  unique up to symmetry: {} ({:.1}% duplicates)", unique_canonical.len(), duplication_rate(unique_canonical.len()));
+}
+
+fn print_policy_entropy(games: &[GameRecord]) {
+    let mut entropies: Vec<(f32, usize)> = games.iter()
+        .flat_map(|game| game.moves.iter())
+        .filter(|move_record| !move_record.policy.is_empty())
+        .map(|move_record| (entropy(&move_record.policy), move_record.policy.len()))
+        .collect();
+    println!("Policy targets: {}", entropies.len());
+    if entropies.is_empty() {
+        return;
+    }
+    entropies.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let count = entropies.len() as f32;
+    let mean = entropies.iter().map(|(entropy, _)| entropy).sum::<f32>() / count;
+    // 1 means a uniform target over the legal moves
+    let mean_normalized = entropies.iter()
+        .map(|(entropy, number_moves)| if *number_moves > 1 { entropy / (*number_moves as f32).ln() } else { 0.0 })
+        .sum::<f32>() / count;
+    let one_hot = entropies.iter().filter(|(entropy, _)| *entropy < 0.01).count();
+    println!("  mean entropy: {:.3} nats, median {:.3}", mean, entropies[entropies.len() / 2].0);
+    println!("  mean entropy relative to uniform: {:.3}", mean_normalized);
+    println!("  one-hot targets: {:.1}%", 100.0 * one_hot as f32 / count);
+}
+
+fn print_value_distribution(games: &[GameRecord]) {
+    let values: Vec<f32> = games.iter()
+        .flat_map(|game| game.moves.iter())
+        .filter_map(|move_record| move_record.evaluation)
+        .collect();
+    println!("Value targets: {}", values.len());
+    if values.is_empty() {
+        return;
+    }
+    let number_bins = (2.0 / VALUE_BIN_WIDTH).round() as usize;
+    let mut bins = vec![0; number_bins];
+    for value in values.iter() {
+        let bin = ((value.clamp(-1.0, 1.0) + 1.0) / VALUE_BIN_WIDTH) as usize;
+        bins[bin.min(number_bins - 1)] += 1;
+    }
+    let largest_bin = *bins.iter().max().unwrap();
+    for (index, count) in bins.iter().enumerate() {
+        let low = -1.0 + index as f32 * VALUE_BIN_WIDTH;
+        let bar = "#".repeat(count * 40 / largest_bin.max(1));
+        println!("  [{:+.1}, {:+.1}) {:>6} {}", low, low + VALUE_BIN_WIDTH, count, bar);
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    println!("  mean: {:.3}", mean);
+}
+
+fn print_results(games: &[GameRecord]) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for game in games.iter() {
+        let result = match &game.result {
+            Some(GameResult::GreenWins) => "Green wins",
+            Some(GameResult::YellowWins) => "Yellow wins",
+            Some(GameResult::Draw) => "Draws",
+            None => "Unfinished",
+        };
+        *counts.entry(result).or_insert(0) += 1;
+    }
+    println!("Games: {}", games.len());
+    for result in ["Green wins", "Yellow wins", "Draws", "Unfinished"] {
+        if let Some(count) = counts.get(result) {
+            println!("  {}: {}", result, count);
+        }
+    }
+}
+
+fn print_openings(games: &[GameRecord], opening_plies: usize, top: usize) {
+    let mut openings: HashMap<String, usize> = HashMap::new();
+    for game in games.iter().filter(|game| game.moves.len() >= opening_plies) {
+        let opening: Vec<&str> = game.moves.iter().take(opening_plies).map(|move_record| move_record.notation.as_str()).collect();
+        // games starting from other positions are told apart by their start
+        let key = format!("{} | {}", game.start_fen, opening.join(" "));
+        *openings.entry(key).or_insert(0) += 1;
+    }
+    let mut openings: Vec<(String, usize)> = openings.into_iter().collect();
+    openings.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    println!("Most common openings ({} plies): {} distinct", opening_plies, openings.len());
+    for (opening, count) in openings.iter().take(top) {
+        println!("  {:>5} {}", count, opening);
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut games = vec![];
+    for file in args.files.iter() {
+        let text = std::fs::read_to_string(file).unwrap_or_else(|error| {
+            eprintln!("Could not read {}: {}", file, error);
+            std::process::exit(1);
+        });
+        match games_from_pgn(&text) {
+            Some(mut file_games) => games.append(&mut file_games),
+            None => {
+                eprintln!("Invalid game record in {}", file);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    print_results(&games);
+    print_duplication(&games);
+    print_policy_entropy(&games);
+    print_value_distribution(&games);
+    print_openings(&games, args.opening_plies, args.top);
+}
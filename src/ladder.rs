@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use crate::{
+    gamerecord::{GameRecord, GameResult, MoveRecord, games_to_pgn},
+    logic::{Board, Color},
+    platform::Platform,
+};
+
+// Rated games between the accounts of a game server, bots and people alike: participants ask for a game,
+// the ladder pairs those of close ratings and updates the Elo ratings with each result
+pub const INITIAL_RATING: f64 = 1500.0;
+
+const K_FACTOR: f64 = 32.0;
+
+// Largest rating difference of a pairing, widened for every second the longest waiting participant has waited
+pub const BASE_RATING_GAP: f64 = 100.0;
+pub const RATING_GAP_PER_SECOND: f64 = 10.0;
+
+// Same limits as engine matches, a game reaching them is a draw
+pub const MAX_PLIES: usize = 200;
+pub const MAX_REPETITIONS: usize = 3;
+
+// Longest a participant may take over a move, past it the game is lost on time. Keeps a vanished opponent from
+// holding the other side in its game
+pub const MOVE_TIME_LIMIT_MS: f64 = 5.0 * 60.0 * 1000.0;
+
+const TOKEN_LENGTH: usize = 32;
+const MAX_NAME_LENGTH: usize = 30;
+
+// Expected score against the opponent, from 0 to 1
+pub fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticipantKind {
+    Bot,
+    Human,
+}
+
+impl ParticipantKind {
+    pub const ALL: [ParticipantKind; 2] = [ParticipantKind::Bot, ParticipantKind::Human];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ParticipantKind::Bot => "bot",
+            ParticipantKind::Human => "human",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|kind| kind.label() == name.to_ascii_lowercase()).cloned()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub kind: ParticipantKind,
+    pub rating: f64,
+    pub games: usize,
+    token: String,
+}
+
+// Game between two participants, the record names them
+#[derive(Clone, Debug)]
+pub struct LadderGame {
+    pub board: Board,
+    pub record: GameRecord,
+    pub last_move_ms: f64, // time of the pairing or of the last move
+    repetitions: HashMap<u64, usize>,
+}
+
+impl LadderGame {
+    fn new(green: &str, yellow: &str, round: usize, now_ms: f64) -> Self {
+        let board = Board::default_new();
+        let mut record = GameRecord::new(&board);
+        record.event = "Ladder game".to_string();
+        record.round = Some(round);
+        record.green = green.to_string();
+        record.yellow = yellow.to_string();
+        let repetitions = HashMap::from([(board.get_hash(), 1)]);
+        Self { board, record, last_move_ms: now_ms, repetitions }
+    }
+
+    // The side to move let its time for the move run out
+    pub fn is_overdue(&self, now_ms: f64) -> bool {
+        self.record.result.is_none() && now_ms - self.last_move_ms > MOVE_TIME_LIMIT_MS
+    }
+
+    pub fn color_of(&self, name: &str) -> Option<Color> {
+        if self.record.green == name {
+            Some(Color::Green)
+        } else if self.record.yellow == name {
+            Some(Color::Yellow)
+        } else {
+            None
+        }
+    }
+
+    pub fn player_to_move(&self) -> Option<&str> {
+        match self.board.next_player.as_ref()? {
+            Color::Green => Some(&self.record.green),
+            Color::Yellow => Some(&self.record.yellow),
+        }
+    }
+
+    // Plays the move and returns the result once the game is over
+    fn play(&mut self, notation: &str) -> Result<Option<GameResult>, String> {
+        let game_move = self.board.move_from_notation(notation).ok_or(format!("illegal move {}", notation))?;
+        let notation = self.board.move_to_notation(&game_move).ok_or(format!("illegal move {}", notation))?;
+        self.board.move_pawn_until_blocked(game_move.pawn_index, &game_move.direction);
+        self.record.moves.push(MoveRecord::new(notation));
+        self.record.finish(&self.board);
+        if self.record.result.is_some() {
+            self.record.termination = Some("normal".to_string());
+            return Ok(self.record.result.clone());
+        }
+        let count = self.repetitions.entry(self.board.get_hash()).or_insert(0);
+        *count += 1;
+        let termination = if *count >= MAX_REPETITIONS {
+            "repetition"
+        } else if self.record.moves.len() >= MAX_PLIES {
+            "move limit"
+        } else {
+            return Ok(None);
+        };
+        self.record.termination = Some(termination.to_string());
+        self.record.result = Some(GameResult::Draw);
+        Ok(self.record.result.clone())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Ladder {
+    participants: HashMap<String, Participant>, // by name
+    seeking: Vec<(String, f64)>, // names waiting for a game, with the time they asked in ms
+    games: HashMap<usize, LadderGame>,
+    next_id: usize,
+    finished: Vec<(usize, GameRecord)>,
+}
+
+impl Ladder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Creates the account and returns its token, the only way to act for it
+    pub fn register<O: Platform>(&mut self, name: &str, kind: ParticipantKind) -> Result<String, String> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LENGTH
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("names have 1 to {} letters, digits, - or _", MAX_NAME_LENGTH));
+        }
+        if self.participants.contains_key(name) {
+            return Err(format!("{} is taken", name));
+        }
+        let token: String = (0..TOKEN_LENGTH)
+            .map(|_| char::from_digit(O::random_int(16) as u32, 16).unwrap_or('0'))
+            .collect();
+        let participant = Participant { name: name.to_string(), kind, rating: INITIAL_RATING, games: 0, token: token.clone() };
+        self.participants.insert(name.to_string(), participant);
+        Ok(token)
+    }
+
+    // Name of the account holding the token
+    pub fn authenticate(&self, token: &str) -> Option<&str> {
+        self.participants.values().find(|participant| participant.token == token).map(|participant| participant.name.as_str())
+    }
+
+    pub fn participant(&self, name: &str) -> Option<&Participant> {
+        self.participants.get(name)
+    }
+
+    // Best rating first
+    pub fn standings(&self) -> Vec<&Participant> {
+        let mut participants: Vec<&Participant> = self.participants.values().collect();
+        participants.sort_by(|a, b| b.rating.total_cmp(&a.rating).then(a.name.cmp(&b.name)));
+        participants
+    }
+
+    pub fn game(&self, id: usize) -> Option<&LadderGame> {
+        self.games.get(&id)
+    }
+
+    // Record of the game, in progress or finished
+    pub fn record(&self, id: usize) -> Option<&GameRecord> {
+        match self.games.get(&id) {
+            Some(game) => Some(&game.record),
+            None => self.finished.iter().find(|(finished_id, _)| *finished_id == id).map(|(_, record)| record),
+        }
+    }
+
+    // Game in progress of the participant, one at a time
+    pub fn current_game(&self, name: &str) -> Option<(usize, &LadderGame)> {
+        self.games.iter().find(|(_, game)| game.color_of(name).is_some()).map(|(id, game)| (*id, game))
+    }
+
+    pub fn is_seeking(&self, name: &str) -> bool {
+        self.seeking.iter().any(|(seeker, _)| seeker == name)
+    }
+
+    pub fn seek(&mut self, name: &str, now_ms: f64) -> Result<(), String> {
+        if !self.participants.contains_key(name) {
+            return Err(format!("no account {}", name));
+        }
+        if self.current_game(name).is_some() {
+            return Err("finish the current game first".to_string());
+        }
+        if !self.is_seeking(name) {
+            self.seeking.push((name.to_string(), now_ms));
+        }
+        Ok(())
+    }
+
+    pub fn cancel_seek(&mut self, name: &str) {
+        self.seeking.retain(|(seeker, _)| seeker != name);
+    }
+
+    // Pairs the waiting participants, the longest waiting first with the closest rating in reach.
+    // Colours are drawn at random, returns the ids of the new games
+    pub fn pair<O: Platform>(&mut self, now_ms: f64) -> Vec<usize> {
+        let rating = |ladder: &Self, name: &str| ladder.participants.get(name).map_or(INITIAL_RATING, |participant| participant.rating);
+        let mut started = vec![];
+        let mut index = 0;
+        while index < self.seeking.len() {
+            let (name, since) = self.seeking[index].clone();
+            let gap = BASE_RATING_GAP + RATING_GAP_PER_SECOND * ((now_ms - since) / 1000.0).max(0.0);
+            let opponent = self.seeking.iter().enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(other, (opponent, _))| (other, (rating(self, opponent) - rating(self, &name)).abs()))
+                .filter(|(_, difference)| *difference <= gap)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(other, _)| other);
+            let Some(opponent_index) = opponent else {
+                index += 1;
+                continue;
+            };
+            let (opponent, _) = self.seeking[opponent_index].clone();
+            self.seeking.retain(|(seeker, _)| *seeker != name && *seeker != opponent);
+            let (green, yellow) = if O::random_int(2) == 0 { (&name, &opponent) } else { (&opponent, &name) };
+            let id = self.next_id;
+            self.next_id += 1;
+            self.games.insert(id, LadderGame::new(green, yellow, id + 1, now_ms));
+            started.push(id);
+        }
+        started
+    }
+
+    // Plays the move for the participant, returns the record once the game is over.
+    // A move coming after the time limit loses the game on time instead
+    pub fn play(&mut self, name: &str, id: usize, notation: &str, now_ms: f64) -> Result<Option<GameRecord>, String> {
+        let game = self.games.get_mut(&id).ok_or(format!("no game {}", id))?;
+        if game.player_to_move() != Some(name) {
+            return Err("not your turn".to_string());
+        }
+        if game.is_overdue(now_ms) {
+            return Ok(self.forfeit(id));
+        }
+        match game.play(notation)? {
+            Some(_) => Ok(self.finish(id)),
+            None => {
+                game.last_move_ms = now_ms;
+                Ok(None)
+            }
+        }
+    }
+
+    // Finishes the games whose side to move ran out of time, as losses for it, and returns their records
+    pub fn forfeit_overdue(&mut self, now_ms: f64) -> Vec<GameRecord> {
+        let mut overdue: Vec<usize> = self.games.iter()
+            .filter(|(_, game)| game.is_overdue(now_ms))
+            .map(|(id, _)| *id)
+            .collect();
+        overdue.sort();
+        overdue.into_iter().filter_map(|id| self.forfeit(id)).collect()
+    }
+
+    fn forfeit(&mut self, id: usize) -> Option<GameRecord> {
+        let game = self.games.get_mut(&id)?;
+        let result = match game.board.next_player.as_ref()? {
+            Color::Green => GameResult::YellowWins,
+            Color::Yellow => GameResult::GreenWins,
+        };
+        game.record.result = Some(result);
+        game.record.termination = Some("time forfeit".to_string());
+        self.finish(id)
+    }
+
+    pub fn resign(&mut self, name: &str, id: usize) -> Result<GameRecord, String> {
+        let game = self.games.get_mut(&id).ok_or(format!("no game {}", id))?;
+        let result = match game.color_of(name) {
+            Some(Color::Green) => GameResult::YellowWins,
+            Some(Color::Yellow) => GameResult::GreenWins,
+            None => return Err("not your game".to_string()),
+        };
+        game.record.result = Some(result);
+        game.record.termination = Some("resignation".to_string());
+        self.finish(id).ok_or(format!("no game {}", id))
+    }
+
+    // Moves the finished game to the records and updates the ratings of both sides
+    fn finish(&mut self, id: usize) -> Option<GameRecord> {
+        let game = self.games.remove(&id)?;
+        let result = game.record.result.clone()?;
+        let green_score = match result {
+            GameResult::GreenWins => 1.0,
+            GameResult::YellowWins => 0.0,
+            GameResult::Draw => 0.5,
+        };
+        let green_rating = self.participants.get(&game.record.green)?.rating;
+        let yellow_rating = self.participants.get(&game.record.yellow)?.rating;
+        let change = K_FACTOR * (green_score - expected_score(green_rating, yellow_rating));
+        for (name, change) in [(&game.record.green, change), (&game.record.yellow, -change)] {
+            if let Some(participant) = self.participants.get_mut(name) {
+                participant.rating += change;
+                participant.games += 1;
+            }
+        }
+        self.finished.push((id, game.record.clone()));
+        Some(game.record)
+    }
+
+    pub fn finished_pgn(&self) -> String {
+        let records: Vec<GameRecord> = self.finished.iter().map(|(_, record)| record.clone()).collect();
+        games_to_pgn(&records)
+    }
+
+    // One account per line: name;kind;rating;games;token. Games in progress are not kept
+    pub fn accounts_to_text(&self) -> String {
+        self.standings().iter()
+            .map(|participant| format!(
+                "{};{};{:.1};{};{}\n",
+                participant.name, participant.kind.label(), participant.rating, participant.games, participant.token,
+            ))
+            .collect()
+    }
+
+    pub fn from_accounts_text(text: &str) -> Option<Self> {
+        let mut ladder = Self::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.trim().split(';');
+            let name = fields.next()?.to_string();
+            let participant = Participant {
+                name: name.clone(),
+                kind: ParticipantKind::from_name(fields.next()?)?,
+                rating: fields.next()?.parse().ok()?,
+                games: fields.next()?.parse().ok()?,
+                token: fields.next()?.to_string(),
+            };
+            ladder.participants.insert(name, participant);
+        }
+        Some(ladder)
+    }
+}
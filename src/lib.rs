@@ -1,4 +1,97 @@
+//! Rules and engines for the Neutreeko board game.
+//!
+//! The [`prelude`] gathers the types most programs need. Play moves by hand:
+//!
+//! ```
+//! use neutreeko::prelude::*;
+//!
+//! let mut game = Game::new(&Board::default_new());
+//! let opening = game.board.move_from_notation("b1-b4").unwrap();
+//! game.play(&opening).unwrap();
+//! assert_eq!(game.board.next_player, Some(Color::Yellow));
+//! ```
+//!
+//! Evaluate a position (engines need the `engines` feature, on by default):
+//!
+//! ```
+//! # #[cfg(feature = "engines")] {
+//! use neutreeko::prelude::*;
+//!
+//! // Yellow wins at once with b1-a2
+//! let board = Board::from_fen("1yg1g/2g2/y4/y4/5 y").unwrap();
+//! let mut engine: MinMax<NativePlatform> = MinMax::new(Color::Yellow, 3);
+//! let analysis = analyze(&mut engine, &board, false);
+//! assert!(analysis.board_eval > 0.0);
+//! println!("{}", analysis.to_text(&board));
+//! # }
+//! ```
+//!
+//! Let an engine play:
+//!
+//! ```
+//! # #[cfg(feature = "engines")] {
+//! use neutreeko::prelude::*;
+//!
+//! let mut game = Game::new(&Board::default_new());
+//! let mut engine: MCTS<NativePlatform> = MCTS::new(Color::Green, 1);
+//! let (pawn_index, direction) = engine.ai_play(&game.board, false).unwrap();
+//! game.play(&Move::new(pawn_index, direction)).unwrap();
+//! assert_eq!(game.moves().len(), 1);
+//! # }
+//! ```
+
+// Rules, always built
 pub mod logic;
-pub mod ui;
-pub mod ai;
+pub mod game;
 pub mod platform;
+pub mod prelude;
+// Engines and the tools built on them
+#[cfg(feature = "engines")]
+pub mod ai;
+#[cfg(feature = "engines")]
+pub mod profiling;
+#[cfg(feature = "engines")]
+pub mod protocol;
+#[cfg(feature = "engines")]
+pub mod arena;
+#[cfg(feature = "engines")]
+pub mod selfcheck;
+#[cfg(feature = "engines")]
+pub mod adjudication;
+#[cfg(feature = "engines")]
+pub mod analysis;
+#[cfg(feature = "engines")]
+pub mod explain;
+#[cfg(feature = "engines")]
+pub mod tactics;
+#[cfg(feature = "engines")]
+pub mod endgame;
+#[cfg(feature = "engines")]
+pub mod puzzle;
+#[cfg(feature = "engines")]
+pub mod randomstart;
+#[cfg(feature = "engines")]
+pub mod render;
+#[cfg(feature = "engines")]
+pub mod gamerecord;
+#[cfg(feature = "engines")]
+pub mod gamestore;
+#[cfg(feature = "engines")]
+pub mod profile;
+#[cfg(feature = "engines")]
+pub mod achievements;
+#[cfg(feature = "engines")]
+pub mod bot;
+#[cfg(feature = "engines")]
+pub mod ladder;
+#[cfg(feature = "engines")]
+pub mod tuning;
+// Browser front-end and JS bindings
+#[cfg(feature = "web")]
+pub mod ui;
+#[cfg(feature = "web")]
+pub mod api;
+#[cfg(feature = "web")]
+mod webstorage;
+#[cfg(feature = "web-train")]
+pub mod traindemo;
@@ -1,11 +1,12 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::hash::{Hash, Hasher, DefaultHasher};
-use strum_macros::EnumIter;
-use strum::IntoEnumIterator;
+use std::str::FromStr;
 
 use crate::platform::Platform;
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     Yellow,
     Green,
@@ -21,6 +22,7 @@ impl Color {
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pawn {
     pub color: Color,
     pub position: Position,
@@ -33,14 +35,18 @@ impl Pawn {
 }
 
 #[derive(Clone, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
     pub number_of_rows: usize,
     pub number_of_columns: usize,
     pub pawns: Vec<Pawn>,
     pub next_player: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub win_condition: WinConditionRef,
 }
 
-#[derive(EnumIter, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Up = 0,
     Down = 1,
@@ -53,6 +59,36 @@ pub enum Direction {
 }
 
 impl Direction {
+    // In the order of their values, as the policy head of the ANN expects them
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Direction> {
+        Self::ALL.into_iter()
+    }
+
+    // Row and column steps of one square in this direction, rows grow downwards
+    pub fn increments(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (-1, 1),
+            Direction::DownLeft => (1, -1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
     pub fn flip(&self, horizontal: bool, vertical: bool) -> &Self {
         let mut after_horizontal_flip = self;
         if horizontal {
@@ -119,15 +155,233 @@ impl Direction {
     }
 }
 
+// Parses the variant name, e.g. "UpLeft"
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|direction| format!("{:?}", direction) == name)
+            .ok_or(format!("Unknown direction {}", name))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub pawn_index: usize,
+    pub direction: Direction,
+}
+
+impl Move {
+    pub fn new(pawn_index: usize, direction: Direction) -> Self {
+        Self { pawn_index, direction }
+    }
+
+    pub fn to_json(&self) -> String {
+        format!("{{\"pawn\":{},\"direction\":\"{:?}\"}}", self.pawn_index, self.direction)
+    }
+}
+
+// One of the 8 symmetries of the square board: quarter turns clockwise, then a horizontal flip if flip is set.
+// Pawns keep their index, only their positions and the move directions change
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    pub quarter_turns: i32,
+    pub flip: bool,
+}
+
+impl Symmetry {
+    // Same order as Board::symmetries
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        [false, true].into_iter().flat_map(|flip| (0..4).map(move |quarter_turns| Symmetry { quarter_turns, flip }))
+    }
+
+    pub fn apply_board(&self, board: &Board) -> Board {
+        let rotated = board.rotate_clockwise(self.quarter_turns);
+        if self.flip { rotated.flip(true, false) } else { rotated }
+    }
+
+    pub fn apply_direction(&self, direction: &Direction) -> Direction {
+        direction.rotate_clockwise(self.quarter_turns).flip(self.flip, false).clone()
+    }
+
+    // Flipping reverses the rotation: a flipped symmetry is its own inverse
+    pub fn inverse(&self) -> Symmetry {
+        if self.flip {
+            *self
+        } else {
+            Symmetry { quarter_turns: (4 - self.quarter_turns).rem_euclid(4), flip: false }
+        }
+    }
+
+    // Symmetry applying self first, then other
+    pub fn then(&self, other: &Symmetry) -> Symmetry {
+        let other_turns = if self.flip { -other.quarter_turns } else { other.quarter_turns };
+        Symmetry {
+            quarter_turns: (self.quarter_turns + other_turns).rem_euclid(4),
+            flip: self.flip != other.flip,
+        }
+    }
+}
+
+// Start positions for handicap games, the handicap goes to the weaker player
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StartPosition {
+    #[default]
+    Standard,
+    YellowFirst, // standard layout, Yellow gets the first move
+    CenterPawn(Color), // the pawn of this color closest to the center starts on it
+}
+
+impl StartPosition {
+    pub fn all() -> Vec<StartPosition> {
+        vec![
+            StartPosition::Standard,
+            StartPosition::YellowFirst,
+            StartPosition::CenterPawn(Color::Green),
+            StartPosition::CenterPawn(Color::Yellow),
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StartPosition::Standard => "standard",
+            StartPosition::YellowFirst => "yellow-first",
+            StartPosition::CenterPawn(Color::Green) => "green-center",
+            StartPosition::CenterPawn(Color::Yellow) => "yellow-center",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|start| start.name() == name)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            StartPosition::Standard => "Standard",
+            StartPosition::YellowFirst => "Yellow moves first",
+            StartPosition::CenterPawn(Color::Green) => "Green pawn on the center",
+            StartPosition::CenterPawn(Color::Yellow) => "Yellow pawn on the center",
+        }
+    }
+}
+
+// Why a move was rejected by Board::apply_move
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveError {
+    GameOver,
+    NotYourTurn,
+    PawnBlocked,
+    PawnIndexOutOfRange,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            MoveError::GameOver => "the game is over",
+            MoveError::NotYourTurn => "this pawn belongs to the player not to move",
+            MoveError::PawnBlocked => "the pawn is blocked in this direction",
+            MoveError::PawnIndexOutOfRange => "there is no pawn with this index",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+// Why a position was rejected when building or setting up a board
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoardError {
+    PawnOutOfBounds(Position),
+    PawnsOnSameSquare(Position),
+    WrongPawnCount(Color, usize),
+    AlreadyWon(Color),
+    NoPlayerToMove,
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardError::PawnOutOfBounds(position) => write!(f, "a pawn is outside the board at row {} column {}", position.row, position.column),
+            BoardError::PawnsOnSameSquare(position) => write!(f, "two pawns are on {}", position.to_notation()),
+            BoardError::WrongPawnCount(color, count) => write!(f, "{:?} has {} pawns instead of 3", color, count),
+            BoardError::AlreadyWon(color) => write!(f, "{:?} has already aligned three pawns", color),
+            BoardError::NoPlayerToMove => write!(f, "no player to move"),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+// What a legal move did to the board
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveOutcome {
+    pub from: Position,
+    pub to: Position,
+    pub winner: Option<Color>,
+}
+
+// How a threat wins, from the most to the least direct
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ThreatKind {
+    Win, // the move completes a line
+    ForcedWin, // every reply leaves a line to complete, the color wins with its next move
+    DoubleThreat, // the move leaves two squares completing a line, the opponent has no win to answer with but may cover both
+}
+
+// A move of Board::threats. squares is the completed line for a win, the squares left completing a line otherwise
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Threat {
+    pub kind: ThreatKind,
+    pub game_move: Move,
+    pub squares: Vec<Position>,
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub row: usize,
     pub column: usize,
 }
 
+impl Position {
+    // Algebraic notation: columns a-e from left to right, rows 1-5 from top to bottom
+    pub fn to_notation(&self) -> String {
+        format!("{}{}", Self::column_name(self.column), Self::row_name(self.row))
+    }
+
+    // Labels written along the board edges, as in the notation
+    pub fn column_name(column: usize) -> char {
+        (b'a' + column as u8) as char
+    }
+
+    pub fn row_name(row: usize) -> String {
+        (row + 1).to_string()
+    }
+
+    pub fn from_notation(notation: &str) -> Option<Self> {
+        let mut chars = notation.chars();
+        let column_char = chars.next()?.to_ascii_lowercase();
+        if !column_char.is_ascii_lowercase() {
+            return None;
+        }
+        let row: usize = chars.as_str().parse().ok()?;
+        if row == 0 {
+            return None;
+        }
+        Some(Self { row: row - 1, column: (column_char as u8 - b'a') as usize })
+    }
+}
+
+// Three adjacent positions on a line, any other number of positions is never aligned
 fn aligned_positions(positions_in: &Vec<&Position>) -> bool {
     if positions_in.len() != 3 {
-        panic!("aligned_positions function requires exactly 3 positions");
+        return false;
     }
     let mut positions = positions_in.clone();
 
@@ -151,14 +405,178 @@ fn aligned_positions(positions_in: &Vec<&Position>) -> bool {
     (same_row && adjacent_columns) || (same_column && adjacent_rows) || diagonal
 }
 
+// Decides whether a position is won, Board::winner asks the condition the board holds.
+// Variants implement it instead of changing aligned_positions
+pub trait WinCondition: Send + Sync {
+    // Identifies the condition when boards are compared or hashed
+    fn name(&self) -> &'static str;
+
+    fn outcome(&self, board: &Board) -> Option<Color>;
+}
+
+// Standard rule: three pawns adjacent in a row, a column or a diagonal
+pub struct ThreeInARow;
+
+impl WinCondition for ThreeInARow {
+    fn name(&self) -> &'static str {
+        "three-in-a-row"
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Color> {
+        let mut yellow_positions = Vec::new();
+        let mut green_positions = Vec::new();
+
+        for pawn in board.pawns.iter() {
+            match pawn.color {
+                Color::Green => green_positions.push(&pawn.position),
+                Color::Yellow => yellow_positions.push(&pawn.position),
+            }
+        }
+        if aligned_positions(&green_positions) {
+            return Some(Color::Green);
+        }
+        if aligned_positions(&yellow_positions) {
+            return Some(Color::Yellow);
+        }
+        None
+    }
+}
+
+// Generic rule for variants: `length` pawns of a color on one row, column or diagonal,
+// next to each other when `adjacent`, anywhere on the line otherwise
+pub struct LineCondition {
+    pub name: &'static str,
+    pub length: usize,
+    pub adjacent: bool,
+}
+
+impl LineCondition {
+    fn has_line(&self, board: &Board, color: &Color) -> bool {
+        let own_square = |row: isize, column: isize| board.pawns.iter()
+            .any(|pawn| pawn.color == *color && pawn.position.row as isize == row && pawn.position.column as isize == column);
+        let on_board = |row: isize, column: isize| row >= 0 && column >= 0
+            && row < board.number_of_rows as isize && column < board.number_of_columns as isize;
+        board.pawns.iter().filter(|pawn| pawn.color == *color).any(|pawn| {
+            [(0, 1), (1, 0), (1, 1), (1, -1)].iter().any(|(row_increment, column_increment)| {
+                let mut row = pawn.position.row as isize;
+                let mut column = pawn.position.column as isize;
+                let mut count = 0;
+                while on_board(row, column) {
+                    if own_square(row, column) {
+                        count += 1;
+                    } else if self.adjacent {
+                        break;
+                    }
+                    row += row_increment;
+                    column += column_increment;
+                }
+                count >= self.length
+            })
+        })
+    }
+}
+
+impl WinCondition for LineCondition {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Color> {
+        [Color::Green, Color::Yellow].into_iter().find(|color| self.has_line(board, color))
+    }
+}
+
+pub static THREE_IN_A_ROW: ThreeInARow = ThreeInARow;
+
+// Variant with four pawns per side
+pub static FOUR_IN_A_ROW: LineCondition = LineCondition { name: "four-in-a-row", length: 4, adjacent: true };
+
+// Lines with gaps count, e.g. a1 c1 e1
+pub static THREE_ON_A_LINE: LineCondition = LineCondition { name: "three-on-a-line", length: 3, adjacent: false };
+
+// Boards keep a static condition so they stay cheap to clone, compare and hash
+#[derive(Clone, Copy)]
+pub struct WinConditionRef(pub &'static dyn WinCondition);
+
+impl Default for WinConditionRef {
+    fn default() -> Self {
+        Self(&THREE_IN_A_ROW)
+    }
+}
+
+impl fmt::Debug for WinConditionRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.name())
+    }
+}
+
+impl PartialEq for WinConditionRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name() == other.0.name()
+    }
+}
+
+impl Hash for WinConditionRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.name().hash(state);
+    }
+}
+
+// Symbols of Board::str_rep_with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoardSymbols {
+    pub green: &'static str,
+    pub yellow: &'static str,
+    pub empty: &'static str,
+    pub coordinates: bool, // row numbers and column letters around the grid, for symbols one character wide
+}
+
+impl BoardSymbols {
+    pub const ASCII: BoardSymbols = BoardSymbols { green: "g", yellow: "y", empty: ".", coordinates: true };
+    pub const EMOJI: BoardSymbols = BoardSymbols { green: "🟢", yellow: "🟡", empty: "⬜", coordinates: false };
+}
+
 impl Board {
-    pub fn new(number_of_rows: usize, number_of_columns: usize, pawns: Vec<Pawn>, next_player: Option<Color>) -> Self {
-        let board = Self { number_of_rows, number_of_columns, pawns, next_player};
-        if board.is_valid() {
-            board
-        } else {
-            panic!("Invalid board, pawns are on the same position or out of bounds")
+    pub fn new(number_of_rows: usize, number_of_columns: usize, pawns: Vec<Pawn>, next_player: Option<Color>) -> Result<Self, BoardError> {
+        let board = Self { number_of_rows, number_of_columns, pawns, next_player, win_condition: WinConditionRef::default() };
+        board.validate()?;
+        Ok(board)
+    }
+
+    // Pawns inside the board and on distinct squares, the same check as is_valid with the reason
+    pub fn validate(&self) -> Result<(), BoardError> {
+        let mut occupied_positions = HashSet::new();
+        for pawn in self.pawns.iter() {
+            if pawn.position.row >= self.number_of_rows || pawn.position.column >= self.number_of_columns {
+                return Err(BoardError::PawnOutOfBounds(pawn.position.clone()));
+            }
+            if !occupied_positions.insert(&pawn.position) {
+                return Err(BoardError::PawnsOnSameSquare(pawn.position.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // Stricter check for positions set up by hand: playable by the engines, with a player to move and no winner yet
+    pub fn validate_setup(&self) -> Result<(), BoardError> {
+        self.validate()?;
+        for color in [Color::Green, Color::Yellow] {
+            let count = self.pawns.iter().filter(|pawn| pawn.color == color).count();
+            if count != 3 {
+                return Err(BoardError::WrongPawnCount(color, count));
+            }
         }
+        if let Some(winner) = self.winner() {
+            return Err(BoardError::AlreadyWon(winner));
+        }
+        if self.next_player.is_none() {
+            return Err(BoardError::NoPlayerToMove);
+        }
+        Ok(())
+    }
+
+    pub fn pawn_at(&self, position: &Position) -> Option<usize> {
+        self.pawns.iter().position(|pawn| pawn.position == *position)
     }
 
     pub fn default_new() -> Self {
@@ -169,7 +587,19 @@ impl Board {
         pawns.push(Pawn::new(Color::Yellow, Position { row: 1, column: 2 }));
         pawns.push(Pawn::new(Color::Yellow, Position { row: 4, column: 1 }));
         pawns.push(Pawn::new(Color::Yellow, Position { row: 4, column: 3 }));
-        Self::new(5, 5, pawns, Some(Color::Green))
+        Self { number_of_rows: 5, number_of_columns: 5, pawns, next_player: Some(Color::Green), win_condition: WinConditionRef::default() }
+    }
+
+    pub fn from_start(start: &StartPosition) -> Self {
+        let mut board = Self::default_new();
+        match start {
+            StartPosition::Standard => (),
+            StartPosition::YellowFirst => board.next_player = Some(Color::Yellow),
+            // c4 for Green and c2 for Yellow, one square from the center
+            StartPosition::CenterPawn(Color::Green) => board.pawns[2].position = Position { row: 2, column: 2 },
+            StartPosition::CenterPawn(Color::Yellow) => board.pawns[3].position = Position { row: 2, column: 2 },
+        }
+        board
     }
 
     pub fn random_board<P: Platform>() -> Self {
@@ -186,19 +616,131 @@ impl Board {
                 number_of_rows: 5,
                 number_of_columns: 5,
                 pawns,
-                next_player: Some(Color::Green)
+                next_player: Some(Color::Green),
+                win_condition: WinConditionRef::default(),
             };
             if board.is_valid() && board.winner().is_none() {break ;}
         }
         board
     }
 
+    // Number of move sequences of the given length, finished games having no continuation
+    pub fn perft(&self, depth: usize) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        // leaves are counted without building their boards
+        if depth == 1 {
+            return self.legal_moves().count();
+        }
+        self.legal_moves()
+            .map(|game_move| self.child(&game_move).perft(depth - 1))
+            .sum()
+    }
+
+    // Rotations assume a square board
+    pub fn rotate_clockwise(&self, quarter_turns: i32) -> Board {
+        let mut rotated = self.clone();
+        for _ in 0..quarter_turns.rem_euclid(4) {
+            for pawn in rotated.pawns.iter_mut() {
+                pawn.position = Position {
+                    row: pawn.position.column,
+                    column: rotated.number_of_rows - 1 - pawn.position.row,
+                };
+            }
+            std::mem::swap(&mut rotated.number_of_rows, &mut rotated.number_of_columns);
+        }
+        rotated
+    }
+
+    pub fn flip(&self, horizontal: bool, vertical: bool) -> Board {
+        let mut flipped = self.clone();
+        for pawn in flipped.pawns.iter_mut() {
+            if horizontal {
+                pawn.position.row = flipped.number_of_rows - 1 - pawn.position.row;
+            }
+            if vertical {
+                pawn.position.column = flipped.number_of_columns - 1 - pawn.position.column;
+            }
+        }
+        flipped
+    }
+
+    // The 8 symmetries of the square board, the identity first
+    pub fn symmetries(&self) -> Vec<Board> {
+        Symmetry::all().map(|symmetry| symmetry.apply_board(self)).collect()
+    }
+
+    // Same hash for all symmetric positions with the same pawn order, with the symmetry mapping this board to the hashed one
+    pub fn canonical_hash(&self) -> (u64, Symmetry) {
+        Symmetry::all()
+            .map(|symmetry| (symmetry.apply_board(self).get_hash(), symmetry))
+            .min_by_key(|(hash, _)| *hash)
+            .unwrap()
+    }
+
+    // Same string for all symmetric positions, whatever the order of the pawns
+    pub fn canonical_fen(&self) -> String {
+        self.symmetries().iter().map(|board| board.to_fen()).min().unwrap()
+    }
+
     pub fn get_hash(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
         hasher.finish()
     }
 
+    // Standard board in the low 32 bits of a u64: the squares (row * 5 + column) of the green pawns then of the yellow
+    // pawns, 5 bits each and ascending within a color, then a bit set when Yellow is to move and one when nobody is.
+    // The order of the pawns does not matter, unlike get_hash the key can be decoded. None for other sizes, pawn counts
+    // or win conditions, which the code does not keep
+    pub fn encode_u64(&self) -> Option<u64> {
+        if self.number_of_rows != 5 || self.number_of_columns != 5 || self.win_condition != WinConditionRef::default() {
+            return None;
+        }
+        let mut code = 0;
+        for (index, color) in [Color::Green, Color::Yellow].iter().enumerate() {
+            let mut squares: Vec<u64> = self.pawns.iter()
+                .filter(|pawn| pawn.color == *color)
+                .map(|pawn| (pawn.position.row * 5 + pawn.position.column) as u64)
+                .collect();
+            if squares.len() != 3 {
+                return None;
+            }
+            squares.sort();
+            for (rank, square) in squares.iter().enumerate() {
+                code |= square << (5 * (3 * index + rank));
+            }
+        }
+        match self.next_player {
+            Some(Color::Green) => (),
+            Some(Color::Yellow) => code |= 1 << 30,
+            None => code |= 1 << 31,
+        }
+        Some(code)
+    }
+
+    // Board of encode_u64, its pawns green first and by square. None if the code has a square off the board,
+    // two pawns on one square or bits above the 32 used
+    pub fn decode_u64(code: u64) -> Option<Self> {
+        if code >> 32 != 0 || code & (3 << 30) == 3 << 30 {
+            return None;
+        }
+        let pawns = (0..6)
+            .map(|index| {
+                let square = ((code >> (5 * index)) & 0x1f) as usize;
+                let color = if index < 3 { Color::Green } else { Color::Yellow };
+                (square < 25).then(|| Pawn::new(color, Position { row: square / 5, column: square % 5 }))
+            })
+            .collect::<Option<Vec<Pawn>>>()?;
+        let next_player = match code >> 30 {
+            0 => Some(Color::Green),
+            1 => Some(Color::Yellow),
+            _ => None,
+        };
+        Self::new(5, 5, pawns, next_player).ok()
+    }
+
     pub fn str_rep(&self) -> String {
         let mut result = String::new();
         let mut grid = vec![vec![". ".to_string(); self.number_of_columns as usize]; self.number_of_rows as usize];
@@ -226,6 +768,138 @@ impl Board {
         result
     }
 
+    // Grid drawn with the given symbols, one line per row, for chat messages and terminals without the pawn indices of str_rep
+    pub fn str_rep_with(&self, symbols: &BoardSymbols) -> String {
+        let mut result = String::new();
+        for row in 0..self.number_of_rows {
+            if symbols.coordinates {
+                result.push_str(&format!("{} ", row + 1));
+            }
+            for column in 0..self.number_of_columns {
+                let symbol = match self.pawn_at(&Position { row, column }) {
+                    Some(pawn_index) => match self.pawns[pawn_index].color {
+                        Color::Green => symbols.green,
+                        Color::Yellow => symbols.yellow,
+                    },
+                    None => symbols.empty,
+                };
+                result.push_str(symbol);
+            }
+            result.push('\n');
+        }
+        if symbols.coordinates {
+            result.push_str("  ");
+            for column in 0..self.number_of_columns {
+                result.push((b'a' + column as u8) as char);
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    // FEN-like notation: rows from top to bottom separated by '/', 'g' and 'y' for pawns,
+    // digits for runs of empty squares, then the next player ('g', 'y' or '-' once the game is over)
+    pub fn to_fen(&self) -> String {
+        let mut rows = Vec::with_capacity(self.number_of_rows);
+        for row in 0..self.number_of_rows {
+            let mut row_str = String::new();
+            let mut empty_squares = 0;
+            for column in 0..self.number_of_columns {
+                let position = Position { row, column };
+                match self.pawns.iter().find(|pawn| pawn.position == position) {
+                    Some(pawn) => {
+                        if empty_squares > 0 {
+                            row_str.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+                        row_str.push(match pawn.color {
+                            Color::Green => 'g',
+                            Color::Yellow => 'y',
+                        });
+                    }
+                    None => empty_squares += 1,
+                }
+            }
+            if empty_squares > 0 {
+                row_str.push_str(&empty_squares.to_string());
+            }
+            rows.push(row_str);
+        }
+        let next_player = match &self.next_player {
+            Some(Color::Green) => "g",
+            Some(Color::Yellow) => "y",
+            None => "-",
+        };
+        format!("{} {}", rows.join("/"), next_player)
+    }
+
+    // Pawns, player to move and winner, for JS and HTTP clients
+    pub fn to_json(&self) -> String {
+        let pawns: Vec<String> = self.pawns.iter()
+            .map(|pawn| format!(
+                "{{\"color\":\"{:?}\",\"row\":{},\"column\":{}}}",
+                pawn.color, pawn.position.row, pawn.position.column
+            ))
+            .collect();
+        let next_player = match &self.next_player {
+            Some(color) => format!("\"{:?}\"", color),
+            None => "null".to_string(),
+        };
+        let winner = match self.winner() {
+            Some(color) => format!("\"{:?}\"", color),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"rows\":{},\"columns\":{},\"pawns\":[{}],\"next_player\":{},\"winner\":{}}}",
+            self.number_of_rows, self.number_of_columns, pawns.join(","), next_player, winner
+        )
+    }
+
+    pub fn from_fen(fen: &str) -> Option<Self> {
+        let mut parts = fen.split_whitespace();
+        let rows: Vec<&str> = parts.next()?.split('/').collect();
+        let next_player = match parts.next().unwrap_or("g") {
+            "g" => Some(Color::Green),
+            "y" => Some(Color::Yellow),
+            "-" => None,
+            _ => return None,
+        };
+        let number_of_rows = rows.len();
+        let mut number_of_columns = None;
+        let mut green_pawns = vec![];
+        let mut yellow_pawns = vec![];
+        for (row, row_str) in rows.iter().enumerate() {
+            let mut column = 0;
+            for character in row_str.chars() {
+                match character {
+                    'g' | 'G' => green_pawns.push(Pawn::new(Color::Green, Position { row, column })),
+                    'y' | 'Y' => yellow_pawns.push(Pawn::new(Color::Yellow, Position { row, column })),
+                    _ => {
+                        column += character.to_digit(10)? as usize;
+                        continue;
+                    }
+                }
+                column += 1;
+            }
+            if *number_of_columns.get_or_insert(column) != column {
+                return None;
+            }
+        }
+        if green_pawns.len() != 3 || yellow_pawns.len() != 3 {
+            return None;
+        }
+        // green pawns first, as in the default board, so that pawn indices round-trip
+        green_pawns.append(&mut yellow_pawns);
+        let board = Self {
+            number_of_rows,
+            number_of_columns: number_of_columns?,
+            pawns: green_pawns,
+            next_player,
+            win_condition: WinConditionRef::default(),
+        };
+        if board.is_valid() { Some(board) } else { None }
+    }
+
     fn is_valid(&self) -> bool {
         let mut occupied_positions_values = HashSet::new();
         for pawn in self.pawns.iter() {
@@ -243,137 +917,266 @@ impl Board {
         if !self.is_valid() {
             return None;
         }
+        self.win_condition.0.outcome(self)
+    }
 
-        let mut yellow_positions = Vec::new();
-        let mut green_positions = Vec::new();
+    // Same pawns under another rule, e.g. Board::default_new().with_win_condition(&THREE_ON_A_LINE)
+    pub fn with_win_condition(mut self, win_condition: &'static dyn WinCondition) -> Self {
+        self.win_condition = WinConditionRef(win_condition);
+        self
+    }
 
-        for pawn in self.pawns.iter() {
-            match pawn.color {
-                Color::Green => green_positions.push(&pawn.position),
-                Color::Yellow => yellow_positions.push(&pawn.position),
+    // Square where the pawn stops when sliding in this direction, None if it cannot move at all
+    fn destination(&self, pawn_index: usize, direction: &Direction) -> Option<Position> {
+        let (row_increment, column_increment) = direction.increments();
+        let mut row = self.pawns[pawn_index].position.row as isize;
+        let mut column = self.pawns[pawn_index].position.column as isize;
+        loop {
+            let next_row = row + row_increment;
+            let next_column = column + column_increment;
+            if next_row < 0 || next_row >= self.number_of_rows as isize
+                || next_column < 0 || next_column >= self.number_of_columns as isize {
+                break;
             }
+            if self.pawns.iter().any(|pawn| pawn.position.row as isize == next_row && pawn.position.column as isize == next_column) {
+                break;
+            }
+            row = next_row;
+            column = next_column;
         }
-        if aligned_positions(&mut green_positions) {
-            return Some(Color::Green);
-        }
-        if aligned_positions(&mut yellow_positions) {
-            return Some(Color::Yellow);
-        }
-        None
+        let destination = Position { row: row as usize, column: column as usize };
+        (destination != self.pawns[pawn_index].position).then_some(destination)
     }
 
-    fn move_pawn(&mut self, pawn_index: usize, row_increment: isize, column_increment: isize) -> bool {
-        let init_position = self.pawns[pawn_index].position.clone();
-        
-        let final_row = isize::try_from(init_position.row).unwrap() + row_increment;
-        let final_column = isize::try_from(init_position.column).unwrap() + column_increment;
+    // Thin wrapper for callers that only need to know whether the move was legal
+    pub fn move_pawn_until_blocked(&mut self, pawn_index: usize, direction: &Direction) -> bool {
+        self.apply_move(pawn_index, direction).is_ok()
+    }
 
-        if final_row < 0 || final_row >= isize::try_from(self.number_of_rows).unwrap()
-            || final_column < 0 || final_column >= isize::try_from(self.number_of_columns).unwrap() {
-            return false;
-        }
-        let final_position = Position{
-            row: usize::try_from(final_row).unwrap(),
-            column: usize::try_from(final_column).unwrap()
+    // Slides the pawn until it is blocked and passes the turn, the board is left untouched on error
+    pub fn apply_move(&mut self, pawn_index: usize, direction: &Direction) -> Result<MoveOutcome, MoveError> {
+        let Some(color) = &self.next_player else {
+            return Err(MoveError::GameOver);
         };
-        
-        self.pawns[pawn_index].position = final_position;
-        if self.is_valid() {
-            true
-        } else {
-            self.pawns[pawn_index].position = init_position;
-            false
+        let Some(pawn) = self.pawns.get(pawn_index) else {
+            return Err(MoveError::PawnIndexOutOfRange);
+        };
+        if pawn.color != *color {
+            return Err(MoveError::NotYourTurn);
         }
+        let from = pawn.position.clone();
+        let Some(to) = self.destination(pawn_index, direction) else {
+            return Err(MoveError::PawnBlocked);
+        };
+        self.pawns[pawn_index].position = to.clone();
+        let winner = self.winner();
+        self.next_player = match (&winner, &self.next_player) {
+            (Some(_), _) => None,
+            (None, Some(color)) => Some(color.other_color()),
+            (None, None) => None,
+        };
+        Ok(MoveOutcome { from, to, winner })
     }
 
-    pub fn move_pawn_until_blocked(&mut self, pawn_index: usize, direction: &Direction) -> bool {
-        let mut has_moved = false;
-        match &self.next_player {
-            None => return false,
-            Some(color) => {
-                if self.pawns[pawn_index].color != *color {
-                    return false;
-                }
-            }
+    // Takes back the last move applied, false if no pawn stands where that move ended
+    pub fn undo_move(&mut self, outcome: &MoveOutcome) -> bool {
+        let Some(pawn) = self.pawns.iter_mut().find(|pawn| pawn.position == outcome.to) else {
+            return false;
         };
-        let mut row_increment = 0;
-        let mut column_increment = 0;
-        match direction {
-            Direction::Up => row_increment = -1,
-            Direction::Down => row_increment = 1,
-            Direction::Left => column_increment = -1,
-            Direction::Right => column_increment = 1,
-            Direction::UpLeft => {
-                row_increment = -1;
-                column_increment = -1;
+        pawn.position = outcome.from.clone();
+        self.next_player = Some(pawn.color.clone());
+        true
+    }
+
+    // Every legal move of the player to move, without building the resulting boards
+    pub fn legal_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.pawns.iter()
+            .enumerate()
+            .filter(|(_, pawn)| self.next_player.as_ref() == Some(&pawn.color))
+            .flat_map(move |(pawn_index, _)| {
+                Direction::iter()
+                    .filter(move |direction| self.destination(pawn_index, direction).is_some())
+                    .map(move |direction| Move::new(pawn_index, direction))
+            })
+    }
+
+    // Moves that would win at once for this color, whether it is its turn or not.
+    // Pawns are slid in place and only the win condition is asked, this runs on every leaf of the MinMax tree
+    pub fn winning_moves(&self, color: &Color) -> Vec<Move> {
+        let mut threats = vec![];
+        if self.winner().is_some() {
+            return threats;
+        }
+        let mut board = self.clone();
+        for pawn_index in 0..board.pawns.len() {
+            if board.pawns[pawn_index].color != *color {
+                continue;
+            }
+            let from = board.pawns[pawn_index].position.clone();
+            for direction in Direction::iter() {
+                let Some(to) = board.destination(pawn_index, &direction) else {
+                    continue;
+                };
+                board.pawns[pawn_index].position = to;
+                if board.win_condition.0.outcome(&board).as_ref() == Some(color) {
+                    threats.push(Move::new(pawn_index, direction));
+                }
+                board.pawns[pawn_index].position = from.clone();
             }
-            Direction::UpRight => {
-                row_increment = -1;
-                column_increment = 1;
+        }
+        threats
+    }
+
+    // Moves of this color that win or set up a win, whether it is its turn or not. A few hundred times
+    // the work of winning_moves, as every reply to a threatening move is tried: not for the leaves of a search
+    pub fn threats(&self, color: &Color) -> Vec<Threat> {
+        let mut threats = vec![];
+        if self.winner().is_some() {
+            return threats;
+        }
+        let mut turn = self.clone();
+        turn.next_player = Some(color.clone());
+        for game_move in turn.legal_moves().collect::<Vec<_>>() {
+            let child = turn.child(&game_move);
+            if child.winner().as_ref() == Some(color) {
+                let squares = child.pawn_squares(color);
+                threats.push(Threat { kind: ThreatKind::Win, game_move, squares });
+                continue;
             }
-            Direction::DownLeft => {
-                row_increment = 1;
-                column_increment = -1;
+            let squares = child.threat_squares(color);
+            if squares.is_empty() {
+                continue;
             }
-            Direction::DownRight => {
-                row_increment = 1;
-                column_increment = 1;
+            let opponent = color.other_color();
+            // an opponent left without a move is a draw, not a forced win
+            let replies: Vec<Move> = child.legal_moves().collect();
+            let forced = !replies.is_empty() && replies.iter().all(|reply| {
+                let after_reply = child.child(reply);
+                after_reply.winner().is_none() && !after_reply.winning_moves(color).is_empty()
+            });
+            if forced {
+                threats.push(Threat { kind: ThreatKind::ForcedWin, game_move, squares });
+            } else if squares.len() > 1 && child.winning_moves(&opponent).is_empty() {
+                threats.push(Threat { kind: ThreatKind::DoubleThreat, game_move, squares });
             }
         }
-        loop {
-            if !self.move_pawn(pawn_index, row_increment, column_increment) {
-                break;
+        threats
+    }
+
+    // Squares the winning moves of this color land on, without repeats. Two squares make a double threat
+    pub fn threat_squares(&self, color: &Color) -> Vec<Position> {
+        let mut squares: Vec<Position> = vec![];
+        for threat in self.winning_moves(color) {
+            if let Some(square) = self.destination(threat.pawn_index, &threat.direction) {
+                if !squares.contains(&square) {
+                    squares.push(square);
+                }
             }
-            has_moved = true;
         }
-        if has_moved {
-            if self.winner().is_some() {
-                self.next_player = None;
-                return has_moved;
-            }
-            self.next_player = match &self.next_player {
-                Some(color) => Some(color.other_color()),
-                None => None,
+        squares
+    }
+
+    // Legal moves of the player to move after which the opponent cannot win at once
+    pub fn safe_moves(&self) -> Vec<Move> {
+        let Some(color) = &self.next_player else {
+            return vec![];
+        };
+        self.legal_moves()
+            .filter(|game_move| {
+                let child = self.child(game_move);
+                child.winner().is_some() || child.winning_moves(&color.other_color()).is_empty()
+            })
+            .collect()
+    }
+
+    // Squares of the pawns of this color, column by column, as a line is written
+    pub fn pawn_squares(&self, color: &Color) -> Vec<Position> {
+        let mut squares: Vec<Position> = self.pawns.iter()
+            .filter(|pawn| pawn.color == *color)
+            .map(|pawn| pawn.position.clone())
+            .collect();
+        squares.sort_by_key(|position| (position.column, position.row));
+        squares
+    }
+
+    // Board after a legal move, as given by legal_moves
+    pub fn child(&self, game_move: &Move) -> Board {
+        let mut child = self.clone();
+        child.apply_move(game_move.pawn_index, &game_move.direction).expect("child requires a legal move");
+        child
+    }
+
+    pub fn move_to_notation(&self, game_move: &Move) -> Option<String> {
+        let mut new_board = self.clone();
+        if !new_board.move_pawn_until_blocked(game_move.pawn_index, &game_move.direction) {
+            return None;
+        }
+        Some(format!(
+            "{}-{}",
+            self.pawns[game_move.pawn_index].position.to_notation(),
+            new_board.pawns[game_move.pawn_index].position.to_notation()
+        ))
+    }
+
+    // Notation of each move of a line played from this board, stopping at the first illegal move
+    pub fn line_to_notation(&self, line: &[(usize, Direction)]) -> Vec<String> {
+        let mut current_board = self.clone();
+        let mut notations = vec![];
+        for (pawn_index, direction) in line.iter() {
+            let Some(notation) = current_board.move_to_notation(&Move::new(*pawn_index, direction.clone())) else {
+                break;
             };
+            current_board.move_pawn_until_blocked(*pawn_index, direction);
+            notations.push(notation);
         }
-        has_moved
+        notations
+    }
+
+    pub fn move_from_notation(&self, notation: &str) -> Option<Move> {
+        let (from, to) = notation.trim().split_once('-')?;
+        let from = Position::from_notation(from)?;
+        let to = Position::from_notation(to)?;
+        let pawn_index = self.pawns.iter().position(|pawn| pawn.position == from)?;
+        self.get_valid_directions_and_resulting_boards(pawn_index)
+            .into_iter()
+            .find(|(_, new_board)| new_board.pawns[pawn_index].position == to)
+            .map(|(direction, _)| Move::new(pawn_index, direction))
+    }
+
+    // Legal move turning this board into the other one, compared by FEN so the order of the pawns does not matter.
+    // None if no legal move does, or if more than one does
+    pub fn diff(&self, other: &Board) -> Option<Move> {
+        let target = other.to_fen();
+        let mut found = self.legal_moves().filter(|game_move| self.child(game_move).to_fen() == target);
+        let game_move = found.next()?;
+        found.next().is_none().then_some(game_move)
     }
 
     pub fn get_valid_directions(&self, pawn_index: usize) -> Vec<Direction> {
-        let mut valid_directions = Vec::with_capacity(8);
-        let directions = Direction::iter();
-        for direction in directions {
-            let mut new_board = self.clone();
-            if new_board.move_pawn_until_blocked(pawn_index, &direction) {
-                valid_directions.push(direction);
-            }
+        if self.next_player.as_ref() != self.pawns.get(pawn_index).map(|pawn| &pawn.color) {
+            return vec![];
         }
-        valid_directions
+        Direction::iter()
+            .filter(|direction| self.destination(pawn_index, direction).is_some())
+            .collect()
     }
 
     pub fn get_valid_directions_and_resulting_boards(&self, pawn_index: usize) -> Vec<(Direction, Board)> {
-        let mut valid_directions = Vec::with_capacity(8);
-        let directions = Direction::iter();
-        for direction in directions {
-            let mut new_board = self.clone();
-            if new_board.move_pawn_until_blocked(pawn_index, &direction) {
-                valid_directions.push((direction, new_board));
-            }
-        }
-        valid_directions
+        self.get_valid_directions(pawn_index)
+            .into_iter()
+            .map(|direction| {
+                let new_board = self.child(&Move::new(pawn_index, direction.clone()));
+                (direction, new_board)
+            })
+            .collect()
     }
 
     pub fn get_all_valid_directions_and_resulting_boards(&self) -> Vec<(usize, Direction, Board)> {
-        let mut valid_directions = Vec::with_capacity(24);
-        for (pawn_index, pawn) in self.pawns.iter().enumerate() {
-            if Some(pawn.color.clone()) != self.next_player {
-                continue;
-            }
-            let directions = self.get_valid_directions_and_resulting_boards(pawn_index);
-            for (direction, new_board) in directions {
-                valid_directions.push((pawn_index, direction, new_board))
-            }
-        }
-        valid_directions
+        self.legal_moves()
+            .map(|game_move| {
+                let new_board = self.child(&game_move);
+                (game_move.pawn_index, game_move.direction, new_board)
+            })
+            .collect()
     }
 }
@@ -3,5 +3,7 @@ use neutreeko::ui::App;
 
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
+    #[cfg(feature = "tracing")]
+    neutreeko::profiling::init_tracing();
     yew::Renderer::<App>::new().render();
 }
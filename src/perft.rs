@@ -0,0 +1,55 @@
+use clap::Parser;
+use neutreeko::logic::{Board, Move};
+
+#[derive(Parser, Debug)]
+#[command(name = "neutreeko-perft", about = "Count move sequences to verify move generation")]
+struct Args {
+    /// Maximum depth, in plies
+    #[arg(short, long, default_value_t = 4)]
+    depth: usize,
+    /// Starting position, defaults to the standard start
+    #[arg(short, long)]
+    fen: Option<String>,
+    /// Print the node count below each root move at maximum depth
+    #[arg(long)]
+    divide: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let board = match &args.fen {
+        Some(fen) => Board::from_fen(fen).unwrap_or_else(|| {
+            eprintln!("Invalid FEN {}", fen);
+            std::process::exit(1);
+        }),
+        None => Board::default_new(),
+    };
+    println!("{}", board.str_rep());
+
+    let symmetries = board.symmetries();
+    let mut mismatches = 0;
+    for depth in 1..=args.depth {
+        let nodes = board.perft(depth);
+        println!("perft({}) = {}", depth, nodes);
+        for (index, symmetric_board) in symmetries.iter().enumerate().skip(1) {
+            let symmetric_nodes = symmetric_board.perft(depth);
+            if symmetric_nodes != nodes {
+                mismatches += 1;
+                println!("  mismatch for symmetry {}: {} nodes", index, symmetric_nodes);
+            }
+        }
+    }
+
+    if args.divide && args.depth > 0 {
+        for (pawn_index, direction, new_board) in board.get_all_valid_directions_and_resulting_boards() {
+            let notation = board.move_to_notation(&Move::new(pawn_index, direction)).unwrap_or_default();
+            println!("{}: {}", notation, new_board.perft(args.depth - 1));
+        }
+    }
+
+    if mismatches > 0 {
+        println!("{} symmetry mismatches", mismatches);
+        std::process::exit(1);
+    }
+    println!("All symmetries agree");
+}
@@ -1,18 +1,222 @@
+use std::collections::VecDeque;
+use std::future::{Future, poll_fn};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::task::{Context, Poll, Wake, Waker};
+#[cfg(feature = "web")]
+use std::time::Duration;
+
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "web")]
 use log::info;
+#[cfg(feature = "web")]
+use gloo_timers::future::sleep;
+#[cfg(feature = "web-threads")]
+use wasm_bindgen_futures::JsFuture;
+
+// Long searches hand control back to the platform at least this often
+pub const YIELD_INTERVAL_MS: f64 = 50.0;
+// Approximate memory a search may take in the browser, tree and network weights, under what a tab can get
+pub const WASM_MAX_SEARCH_MEMORY: usize = 160 * 1024 * 1024;
+
+// Detail of the output of verbose searches, from the least to the most
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Info, // evaluation of the position and move chosen
+    Debug, // every candidate move with its score
+    Trace, // network priors and engine setup
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 3] = [LogLevel::Info, LogLevel::Debug, LogLevel::Trace];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "Moves played",
+            LogLevel::Debug => "Candidate moves",
+            LogLevel::Trace => "Everything",
+        }
+    }
+}
 
-pub trait Platform: Clone {
+// 'static so engines can be handed to worker threads
+pub trait Platform: Clone + 'static {
     fn now() -> f64;
     fn random() -> f32;
     fn print(str_print: &str);
+    // Output of the engines, printed whatever its level unless the platform routes it elsewhere
+    fn log(_level: LogLevel, message: &str) {
+        Self::print(message);
+    }
+    fn yield_now() -> impl Future<Output = ()>;
+    // Hard cap on the approximate memory of a search in bytes: MinMax stops there and returns its best result so far,
+    // MCTS prunes its tree
+    fn max_search_memory() -> usize {
+        usize::MAX
+    }
     fn random_int(number_values: usize) -> usize {
-        (Self::random() * number_values as f32).floor() as usize
+        // f32 rounding can give number_values itself for a random value just below 1
+        ((Self::random() * number_values as f32).floor() as usize).min(number_values.saturating_sub(1))
+    }
+    // Key-value storage kept across runs: the browser storage on the web, files natively.
+    // A platform without storage finds nothing and refuses to write
+    fn storage_get(_key: &str) -> impl Future<Output = Option<Vec<u8>>> {
+        async { None }
+    }
+    fn storage_set(_key: &str, _value: &[u8]) -> impl Future<Output = Result<(), String>> {
+        async { Err("No storage on this platform".to_string()) }
+    }
+    fn storage_delete(_key: &str) -> impl Future<Output = Result<(), String>> {
+        async { Err("No storage on this platform".to_string()) }
+    }
+    // Runs a task in the background, its results come back through a channel. The task is built where it runs,
+    // so only the closure has to cross threads and the future may hold engines that cannot.
+    // A platform without threads runs it to the end before returning
+    fn spawn<F, T>(task: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Future<Output = ()> + 'static,
+    {
+        block_on(task());
+    }
+}
+
+// Stored UTF-8 text, the formats of the game records, statistics and settings
+pub async fn load_text<O: Platform>(key: &str) -> Option<String> {
+    String::from_utf8(O::storage_get(key).await?).ok()
+}
+
+pub async fn save_text<O: Platform>(key: &str, text: &str) -> bool {
+    O::storage_set(key, text.as_bytes()).await.is_ok()
+}
+
+// Spawns a task computing a value, received once it is done. None if the task stops without sending it
+pub fn spawn_with_result<O, F, T, R>(task: F) -> Receiver<R>
+where
+    O: Platform,
+    F: FnOnce() -> T + Send + 'static,
+    T: Future<Output = R> + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = channel();
+    O::spawn(move || async move {
+        sender.send(task().await);
+    });
+    receiver
+}
+
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Polls the future on this thread until it is ready, parking in between. Not on the browser main thread, which cannot block
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+struct ChannelState<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receiver_alive: bool,
+    waker: Option<Waker>,
+}
+
+// Unbounded channel between tasks, on any platform: sending never waits and receiving is async, so the page can wait
+// for a background task without blocking. Senders may cross threads with the values they carry
+pub struct Sender<T> {
+    state: Arc<Mutex<ChannelState<T>>>,
+}
+
+pub struct Receiver<T> {
+    state: Arc<Mutex<ChannelState<T>>>,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let state = Arc::new(Mutex::new(ChannelState { queue: VecDeque::new(), senders: 1, receiver_alive: true, waker: None }));
+    (Sender { state: state.clone() }, Receiver { state })
+}
+
+impl<T> Sender<T> {
+    // False when the receiver is gone, the value is dropped
+    pub fn send(&self, value: T) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.receiver_alive {
+            return false;
+        }
+        state.queue.push_back(value);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.state.lock().unwrap().senders += 1;
+        Self { state: self.state.clone() }
+    }
+}
+
+// The last sender gone wakes the receiver, which then ends
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&self) -> Option<T> {
+        self.state.lock().unwrap().queue.pop_front()
+    }
+
+    // Next value in the order sent, None once every sender is gone and the values taken
+    pub async fn recv(&self) -> Option<T> {
+        poll_fn(|context| {
+            let mut state = self.state.lock().unwrap();
+            if let Some(value) = state.queue.pop_front() {
+                return Poll::Ready(Some(value));
+            }
+            if state.senders == 0 {
+                return Poll::Ready(None);
+            }
+            state.waker = Some(context.waker().clone());
+            Poll::Pending
+        }).await
     }
 }
 
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.receiver_alive = false;
+        state.queue.clear();
+    }
+}
+
+#[cfg(feature = "web")]
 #[derive(Clone)]
 pub struct WasmPlatform;
 
+#[cfg(feature = "web")]
 impl Platform for WasmPlatform {
     fn now() -> f64 {
         #[wasm_bindgen(js_namespace = performance)]
@@ -33,6 +237,94 @@ impl Platform for WasmPlatform {
     fn print(str_print: &str) {
         info!("{}", str_print);
     }
+
+    fn yield_now() -> impl Future<Output = ()> {
+        // a zero-delay timeout lets the browser render and process events
+        sleep(Duration::from_millis(0))
+    }
+
+    fn max_search_memory() -> usize {
+        WASM_MAX_SEARCH_MEMORY
+    }
+
+    fn storage_get(key: &str) -> impl Future<Output = Option<Vec<u8>>> {
+        crate::webstorage::get(key.to_string())
+    }
+
+    fn storage_set(key: &str, value: &[u8]) -> impl Future<Output = Result<(), String>> {
+        crate::webstorage::set(key.to_string(), value.to_vec())
+    }
+
+    fn storage_delete(key: &str) -> impl Future<Output = Result<(), String>> {
+        crate::webstorage::delete(key.to_string())
+    }
+
+    // On the event loop of the page, the task yields to it like any search. Blocking on a web worker would stop the
+    // timers yield_now waits for, the parallel part of the searches runs on the workers of web-threads instead
+    fn spawn<F, T>(task: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Future<Output = ()> + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(task());
+    }
+
+    // Kept for the engine log of the page, searches on worker threads included. The main thread must not wait
+    // on the lock, a line logged while a worker holds it is dropped
+    fn log(level: LogLevel, message: &str) {
+        let Ok(mut engine_log) = ENGINE_LOG.try_lock() else {
+            return;
+        };
+        if engine_log.len() >= MAX_ENGINE_LOG_LINES {
+            engine_log.remove(0);
+        }
+        engine_log.push((level, message.to_string()));
+    }
+}
+
+// Lines the page has not taken yet, the oldest are dropped past this
+#[cfg(feature = "web")]
+const MAX_ENGINE_LOG_LINES: usize = 1000;
+
+#[cfg(feature = "web")]
+static ENGINE_LOG: Mutex<Vec<(LogLevel, String)>> = Mutex::new(Vec::new());
+
+// Lines logged by the engines since the last call, oldest first. Empty while a worker is logging, its lines wait for the next call
+#[cfg(feature = "web")]
+pub fn take_engine_log() -> Vec<(LogLevel, String)> {
+    ENGINE_LOG.try_lock().map(|mut engine_log| std::mem::take(&mut *engine_log)).unwrap_or_default()
+}
+
+// Set once the rayon pool of web workers runs, until then searches stay on the main thread
+#[cfg(feature = "web-threads")]
+static THREADS_READY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "web-threads")]
+pub fn threads_ready() -> bool {
+    THREADS_READY.load(Ordering::Relaxed)
+}
+
+// Starts one web worker per core for the rayon searches. Wasm threads share memory through a SharedArrayBuffer,
+// which browsers only give to cross-origin isolated pages: false elsewhere, and the page keeps a single thread
+#[cfg(feature = "web-threads")]
+pub async fn init_threads() -> bool {
+    let global = js_sys::global();
+    let isolated = js_sys::Reflect::get(&global, &JsValue::from_str("crossOriginIsolated"))
+        .is_ok_and(|isolated| isolated.is_truthy());
+    if !isolated {
+        return false;
+    }
+    let cores = js_sys::Reflect::get(&global, &JsValue::from_str("navigator"))
+        .and_then(|navigator| js_sys::Reflect::get(&navigator, &JsValue::from_str("hardwareConcurrency")))
+        .ok()
+        .and_then(|cores| cores.as_f64())
+        .unwrap_or(1.0) as usize;
+    if cores < 2 || JsFuture::from(wasm_bindgen_rayon::init_thread_pool(cores)).await.is_err() {
+        return false;
+    }
+    THREADS_READY.store(true, Ordering::Relaxed);
+    info!("Searching on {} threads", cores);
+    true
 }
 
 #[derive(Clone)]
@@ -55,4 +347,137 @@ impl Platform for NativePlatform {
     fn print(str_print: &str) {
         println!("{}", str_print);
     }
+
+    fn yield_now() -> impl Future<Output = ()> {
+        async {
+            std::thread::yield_now();
+        }
+    }
+
+    fn storage_get(key: &str) -> impl Future<Output = Option<Vec<u8>>> {
+        let path = storage_path(key);
+        async move { std::fs::read(path).ok() }
+    }
+
+    fn storage_set(key: &str, value: &[u8]) -> impl Future<Output = Result<(), String>> {
+        let path = storage_path(key);
+        let value = value.to_vec();
+        async move {
+            std::fs::create_dir_all(storage_dir()).map_err(|error| error.to_string())?;
+            std::fs::write(path, value).map_err(|error| error.to_string())
+        }
+    }
+
+    // Deleting a missing entry is not an error
+    fn storage_delete(key: &str) -> impl Future<Output = Result<(), String>> {
+        let path = storage_path(key);
+        async move {
+            match std::fs::remove_file(path) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.to_string()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn spawn<F, T>(task: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Future<Output = ()> + 'static,
+    {
+        std::thread::spawn(move || block_on(task()));
+    }
+}
+
+// Directory of the native storage: NEUTREEKO_DATA_DIR, otherwise neutreeko in the XDG data directory
+pub fn storage_dir() -> std::path::PathBuf {
+    if let Some(dir) = std::env::var_os("NEUTREEKO_DATA_DIR") {
+        return dir.into();
+    }
+    let data_home = std::env::var_os("XDG_DATA_HOME").map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".local").join("share")))
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    data_home.join("neutreeko")
+}
+
+// One file per key, the characters a file name may not hold written as %XX
+fn storage_path(key: &str) -> std::path::PathBuf {
+    let file_name: String = key.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect();
+    storage_dir().join(file_name)
+}
+
+// Seed of SeededPlatform until reseed is called
+pub const DEFAULT_SEED: u64 = 42;
+
+thread_local! {
+    static SEEDED_RNG: std::cell::RefCell<rand::rngs::StdRng> = std::cell::RefCell::new(rand::SeedableRng::seed_from_u64(DEFAULT_SEED));
+    // storage of SeededPlatform, forgotten with the thread so tests never see each other's entries
+    static SEEDED_STORAGE: std::cell::RefCell<std::collections::HashMap<String, Vec<u8>>> = std::cell::RefCell::new(std::collections::HashMap::new());
+}
+
+// Native platform with a reproducible random sequence per thread and no output, for tests and regressions.
+// Spawned tasks run inline, drawing from the sequence of the thread that spawns them
+#[derive(Clone)]
+pub struct SeededPlatform;
+
+impl SeededPlatform {
+    pub fn reseed(seed: u64) {
+        SEEDED_RNG.with(|rng| *rng.borrow_mut() = rand::SeedableRng::seed_from_u64(seed));
+    }
+}
+
+impl Platform for SeededPlatform {
+    fn now() -> f64 {
+        NativePlatform::now()
+    }
+
+    fn random() -> f32 {
+        use rand::Rng;
+        SEEDED_RNG.with(|rng| rng.borrow_mut().random())
+    }
+
+    fn print(_str_print: &str) {}
+
+    fn yield_now() -> impl Future<Output = ()> {
+        NativePlatform::yield_now()
+    }
+
+    fn storage_get(key: &str) -> impl Future<Output = Option<Vec<u8>>> {
+        let value = SEEDED_STORAGE.with(|storage| storage.borrow().get(key).cloned());
+        async move { value }
+    }
+
+    fn storage_set(key: &str, value: &[u8]) -> impl Future<Output = Result<(), String>> {
+        SEEDED_STORAGE.with(|storage| storage.borrow_mut().insert(key.to_string(), value.to_vec()));
+        async { Ok(()) }
+    }
+
+    fn storage_delete(key: &str) -> impl Future<Output = Result<(), String>> {
+        SEEDED_STORAGE.with(|storage| storage.borrow_mut().remove(key));
+        async { Ok(()) }
+    }
+}
+
+// Shared flag used to stop a running search, which then returns its best result so far
+#[derive(Clone, Default, Debug)]
+pub struct SearchHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
 }
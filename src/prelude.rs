@@ -0,0 +1,17 @@
+// What most users of the library need, `use neutreeko::prelude::*;` brings it in scope
+
+pub use crate::game::{Game, Player};
+pub use crate::logic::{Board, BoardError, Color, Direction, Move, MoveError, MoveOutcome, Pawn, Position, StartPosition, Symmetry, Threat, ThreatKind};
+pub use crate::platform::{NativePlatform, Platform, SearchHandle, SeededPlatform};
+#[cfg(feature = "web")]
+pub use crate::platform::WasmPlatform;
+
+#[cfg(feature = "engines")]
+pub use crate::ai::{
+    AI, ScoredMove, SearchObserver, SearchProgress, SearchResult,
+    alphazeutreeko::AlphaZeutreeko, any::AnyAI, mcts::MCTS, minmax::MinMax,
+};
+#[cfg(feature = "engines")]
+pub use crate::analysis::{Analysis, analyze};
+#[cfg(feature = "engines")]
+pub use crate::gamerecord::GameRecord;
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    gamerecord::GameRecord,
+    gamestore::human_color,
+    platform::{Platform, load_text, save_text},
+    puzzle::PuzzleStreak,
+};
+
+// Finished games of the local player against one engine at one strength level
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpponentStats {
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub plies: usize, // of all the games, for the average length
+}
+
+impl OpponentStats {
+    // Share of the points scored, a draw counting half. None before the first game
+    pub fn win_rate(&self) -> Option<f32> {
+        (self.games > 0).then(|| (self.wins as f32 + self.draws as f32 / 2.0) / self.games as f32)
+    }
+
+    pub fn average_plies(&self) -> Option<f32> {
+        (self.games > 0).then(|| self.plies as f32 / self.games as f32)
+    }
+
+    fn add(&mut self, other: &OpponentStats) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.plies += other.plies;
+    }
+}
+
+// Progress of the local player, kept on the device like their games: results by engine and strength level.
+// The daily puzzle streak has its own entry, the report shows both
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UserProfile {
+    pub opponents: BTreeMap<(String, String), OpponentStats>, // by engine and strength level names
+}
+
+impl UserProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.opponents.is_empty()
+    }
+
+    // Counts a finished game between the human player and the engine, returns whether it was counted
+    pub fn record_game(&mut self, record: &GameRecord, engine: &str, level: &str) -> bool {
+        let Some(human) = human_color(record) else {
+            return false;
+        };
+        let Some(result) = &record.result else {
+            return false;
+        };
+        let stats = self.opponents.entry((engine.to_string(), level.to_string())).or_default();
+        stats.games += 1;
+        stats.plies += record.moves.len();
+        match result.winner() {
+            Some(winner) if winner == human => stats.wins += 1,
+            Some(_) => stats.losses += 1,
+            None => stats.draws += 1,
+        }
+        true
+    }
+
+    // Every opponent together
+    pub fn total(&self) -> OpponentStats {
+        let mut total = OpponentStats::default();
+        for stats in self.opponents.values() {
+            total.add(stats);
+        }
+        total
+    }
+
+    // Statistics to keep outside the browser: one line per opponent, the totals and the puzzle streak on this day
+    pub fn to_report(&self, streak: &PuzzleStreak, day: i64) -> String {
+        let line = |name: &str, stats: &OpponentStats| format!(
+            "{:<32} {:>5} games  +{} ={} -{}  {:>5.1}%  {:>5.1} plies\n",
+            name, stats.games, stats.wins, stats.draws, stats.losses,
+            100.0 * stats.win_rate().unwrap_or(0.0), stats.average_plies().unwrap_or(0.0),
+        );
+        let mut text = String::from("Neutreeko statistics\n\n");
+        for ((engine, level), stats) in self.opponents.iter() {
+            text.push_str(&line(&format!("{} ({})", engine, level), stats));
+        }
+        text.push_str(&line("All games", &self.total()));
+        text.push_str(&format!("\nDaily puzzle streak: {} days, best {}\n", streak.current_on(day), streak.best));
+        text
+    }
+
+    // One line per opponent: engine;level;games;wins;draws;losses;plies
+    pub fn to_text(&self) -> String {
+        self.opponents.iter()
+            .map(|((engine, level), stats)| format!(
+                "{};{};{};{};{};{};{}\n",
+                engine, level, stats.games, stats.wins, stats.draws, stats.losses, stats.plies,
+            ))
+            .collect()
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut profile = Self::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.trim().split(';');
+            let engine = fields.next()?.to_string();
+            let level = fields.next()?.to_string();
+            let stats = OpponentStats {
+                games: fields.next()?.parse().ok()?,
+                wins: fields.next()?.parse().ok()?,
+                draws: fields.next()?.parse().ok()?,
+                losses: fields.next()?.parse().ok()?,
+                plies: fields.next()?.parse().ok()?,
+            };
+            profile.opponents.insert((engine, level), stats);
+        }
+        Some(profile)
+    }
+
+    pub async fn load_storage<O: Platform>(key: &str) -> Option<Self> {
+        Self::from_text(&load_text::<O>(key).await?)
+    }
+
+    pub async fn save_storage<O: Platform>(&self, key: &str) -> bool {
+        save_text::<O>(key, &self.to_text()).await
+    }
+
+    pub async fn remove_storage<O: Platform>(key: &str) -> bool {
+        O::storage_delete(key).await.is_ok()
+    }
+}
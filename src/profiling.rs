@@ -0,0 +1,62 @@
+// Spans for performance analysis with the tracing feature: searches at INFO, network forward passes and MinMax layers
+// at DEBUG, MCTS iterations at TRACE. Without the feature the macros expand to nothing
+
+// Enters a span until the end of the enclosing block, e.g. span!(DEBUG, "forward").
+// Not for blocks holding an .await, the span would stay entered while other tasks run: see instrument
+macro_rules! span {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($arg)*).entered();
+    };
+}
+pub(crate) use span;
+
+// Runs a future in a span, entered again at every poll
+#[cfg(feature = "tracing")]
+macro_rules! instrument {
+    ($future:expr, $level:ident, $($arg:tt)*) => {
+        {
+            // the span is made first, its fields may borrow what the future borrows mutably
+            let span = tracing::span!(tracing::Level::$level, $($arg)*);
+            tracing::Instrument::instrument($future, span)
+        }
+    };
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! instrument {
+    ($future:expr, $level:ident, $($arg:tt)*) => {
+        $future
+    };
+}
+pub(crate) use instrument;
+
+// Flushes the folded stacks when dropped, to keep until the end of main
+#[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+pub type FlameGuard = tracing_flame::FlushGuard<std::io::BufWriter<std::fs::File>>;
+
+// Spans printed on stderr as they close, filtered by RUST_LOG (neutreeko=info by default).
+// With NEUTREEKO_FLAME=file they are also written as folded stacks: inferno-flamegraph < file > flamegraph.svg
+#[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+pub fn init_tracing() -> Option<FlameGuard> {
+    use tracing_subscriber::{EnvFilter, fmt, fmt::format::FmtSpan, prelude::*};
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("neutreeko=info"));
+    let fmt_layer = fmt::layer().with_writer(std::io::stderr).with_span_events(FmtSpan::CLOSE);
+    let (flame_layer, guard) = match std::env::var("NEUTREEKO_FLAME") {
+        Ok(path) => match tracing_flame::FlameLayer::with_file(&path) {
+            Ok((layer, guard)) => (Some(layer), Some(guard)),
+            Err(error) => {
+                eprintln!("Could not write the folded stacks to {}: {}", path, error);
+                (None, None)
+            }
+        },
+        Err(_) => (None, None),
+    };
+    tracing_subscriber::registry().with(filter).with(fmt_layer).with(flame_layer).init();
+    guard
+}
+
+// Spans become performance marks and measures of the browser, in the timeline of the developer tools
+#[cfg(all(feature = "tracing", target_arch = "wasm32"))]
+pub fn init_tracing() {
+    tracing_wasm::set_as_global_default();
+}
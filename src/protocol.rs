@@ -0,0 +1,244 @@
+use burn::backend::NdArray;
+
+use crate::{
+    ai::{AI, any::AnyAI, ann::weights::{DEFAULT_MODEL_ID, set_model_bytes}, mcts::MCTSConfig, oracle::ProvenResult},
+    logic::{Board, Color, Move},
+    platform::Platform,
+};
+
+// Largest depth option, MinMax takes minutes beyond
+const MAX_DEPTH: usize = 20;
+
+// Registered under this prefix and the path, the model option loads the weights once per file
+const MODEL_FILE_PREFIX: &str = "file:";
+
+// Line-based text protocol, in the spirit of UCI/GTP, to drive the engines from external tools.
+//   neutreeko                                     -> id name ... / option name ... / neutreekook
+//   isready                                       -> readyok
+//   newgame                                       reset to the default position
+//   position startpos|fen <fen> [moves b1-c1 ...]  positions the moves go through count for repetitions
+//   setoption name <name> value <value>           options listed in the handshake, case insensitive:
+//                                                    depth <plies>       MinMax depth when go gives none, 0 unset
+//                                                    movetime <ms>       MCTS time per move when go gives none, 0 unset
+//                                                    exploration <c>     exploration constant of the MCTS searches
+//                                                    model <path>        weights of the network engines, <empty> the default ones
+//                                                    contempt <x>        draws worth -x to the engine, from -1 to 1
+//   go [movetime <ms>] [depth <plies>]            -> info score <eval> [mate <plies>] depth <d> nodes <n> time <ms> [pv ...]
+//                                                    [/ info string <color> wins in <plies>: <line>] / bestmove <move>|none
+//                                                    mate is negative when the side to move loses
+//   d                                             print the current board
+//   quit
+pub struct ProtocolHandler<O: Platform> {
+    board: Board,
+    history: Vec<Board>, // boards before the current one, given again to a rebuilt engine
+    engine: AnyAI<NdArray<f32>, O>,
+    difficulty: usize, // the engine was created with, to create it again with other weights
+    depth: Option<usize>,
+    movetime: Option<f64>,
+    exploration: Option<f32>,
+    contempt: f32,
+    running: bool,
+}
+
+impl<O: Platform> ProtocolHandler<O> {
+    pub fn new(engine: AnyAI<NdArray<f32>, O>, difficulty: usize) -> Self {
+        Self {
+            board: Board::default_new(),
+            history: vec![],
+            engine,
+            difficulty,
+            depth: None,
+            movetime: None,
+            exploration: None,
+            contempt: 0.0,
+            running: true,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn handle_line(&mut self, line: &str) -> Vec<String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(command) = tokens.first() else {
+            return vec![];
+        };
+        match *command {
+            "neutreeko" => vec![
+                format!("id name neutreeko {}", env!("CARGO_PKG_VERSION")),
+                format!("option name depth type spin default 0 min 0 max {}", MAX_DEPTH),
+                "option name movetime type spin default 0 min 0 max 3600000".to_string(),
+                format!("option name exploration type float default {} min 0 max 10", MCTSConfig::default().exploration),
+                "option name model type string default <empty>".to_string(),
+                "option name contempt type float default 0 min -1 max 1".to_string(),
+                "neutreekook".to_string(),
+            ],
+            "isready" => vec!["readyok".to_string()],
+            "newgame" => {
+                self.board = Board::default_new();
+                self.history.clear();
+                self.engine.set_game_history(&[]);
+                vec![]
+            }
+            "position" => self.position(&tokens[1..]),
+            "setoption" => self.set_option(&tokens[1..]),
+            "go" => self.go(&tokens[1..]),
+            "d" => {
+                let mut lines: Vec<String> = self.board.str_rep().lines().map(String::from).collect();
+                lines.push(format!("fen {}", self.board.to_fen()));
+                lines
+            }
+            "quit" => {
+                self.running = false;
+                vec![]
+            }
+            _ => vec![format!("info string unknown command {}", command)],
+        }
+    }
+
+    fn position(&mut self, args: &[&str]) -> Vec<String> {
+        let moves_index = args.iter().position(|arg| *arg == "moves").unwrap_or(args.len());
+        let board = match args.first() {
+            Some(&"startpos") => Some(Board::default_new()),
+            Some(&"fen") => Board::from_fen(&args[1..moves_index].join(" ")),
+            _ => None,
+        };
+        let Some(mut board) = board else {
+            return vec!["info string invalid position".to_string()];
+        };
+        let mut history = vec![];
+        for notation in args.iter().skip(moves_index + 1) {
+            let Some(game_move) = board.move_from_notation(notation) else {
+                return vec![format!("info string invalid move {}", notation)];
+            };
+            history.push(board.clone());
+            if let Err(error) = board.apply_move(game_move.pawn_index, &game_move.direction) {
+                return vec![format!("info string invalid move {}: {}", notation, error)];
+            }
+        }
+        self.board = board;
+        self.engine.set_game_history(&history);
+        self.history = history;
+        vec![]
+    }
+
+    // setoption name <name> value <value>, names are case insensitive as in UCI. Paths may contain spaces
+    fn set_option(&mut self, args: &[&str]) -> Vec<String> {
+        let (Some(&"name"), Some(name), Some(&"value")) = (args.first(), args.get(1), args.get(2)) else {
+            return vec!["info string usage: setoption name <name> value <value>".to_string()];
+        };
+        let value = args[3..].join(" ");
+        if value.is_empty() {
+            return vec!["info string usage: setoption name <name> value <value>".to_string()];
+        }
+        match name.to_lowercase().as_str() {
+            "depth" => match value.parse::<usize>() {
+                Ok(depth) if depth <= MAX_DEPTH => {
+                    self.depth = (depth > 0).then_some(depth);
+                    vec![]
+                }
+                _ => vec![format!("info string invalid depth {}, from 0 to {}", value, MAX_DEPTH)],
+            },
+            "movetime" => match value.parse::<f64>() {
+                Ok(movetime) if movetime >= 0.0 => {
+                    self.movetime = (movetime > 0.0).then_some(movetime);
+                    vec![]
+                }
+                _ => vec![format!("info string invalid movetime {}", value)],
+            },
+            "exploration" => match value.parse::<f32>() {
+                Ok(exploration) if (0.0..=10.0).contains(&exploration) => {
+                    self.exploration = Some(exploration);
+                    if self.engine.set_exploration(exploration) {
+                        vec![]
+                    } else {
+                        vec![format!("info string {} has no exploration constant", self.engine.name())]
+                    }
+                }
+                _ => vec![format!("info string invalid exploration {}, from 0 to 10", value)],
+            },
+            "model" => self.load_model(&value),
+            "contempt" => match value.parse::<f32>() {
+                Ok(contempt) if (-1.0..=1.0).contains(&contempt) => {
+                    self.contempt = contempt;
+                    self.engine.set_contempt(contempt);
+                    vec![]
+                }
+                _ => vec![format!("info string invalid contempt {}, from -1 to 1", value)],
+            },
+            _ => vec![format!("info string unknown option {}", name)],
+        }
+    }
+
+    // Creates the network engine again with the weights of the file, <empty> goes back to the default model
+    fn load_model(&mut self, path: &str) -> Vec<String> {
+        let name = self.engine.name();
+        if !AnyAI::<NdArray<f32>, O>::uses_network(name) {
+            return vec![format!("info string {} has no network", name)];
+        }
+        let model_id = if path == "<empty>" {
+            DEFAULT_MODEL_ID.to_string()
+        } else {
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    let model_id = format!("{}{}", MODEL_FILE_PREFIX, path);
+                    set_model_bytes(&model_id, bytes);
+                    model_id
+                }
+                Err(error) => return vec![format!("info string could not read {}: {}", path, error)],
+            }
+        };
+        let color = self.board.next_player.clone().unwrap_or(Color::Green);
+        let Some(mut engine) = AnyAI::from_name_with_model(name, color, self.difficulty, &model_id) else {
+            return vec![format!("info string could not create {}", name)];
+        };
+        engine.set_contempt(self.contempt);
+        if let Some(exploration) = self.exploration {
+            engine.set_exploration(exploration);
+        }
+        engine.set_game_history(&self.history);
+        self.engine = engine;
+        vec![]
+    }
+
+    fn go(&mut self, args: &[&str]) -> Vec<String> {
+        let mut movetime = None;
+        let mut depth = None;
+        let mut args_iter = args.iter();
+        while let Some(arg) = args_iter.next() {
+            match *arg {
+                "movetime" => movetime = args_iter.next().and_then(|value| value.parse().ok()),
+                "depth" => depth = args_iter.next().and_then(|value| value.parse().ok()),
+                _ => (),
+            }
+        }
+        if self.board.next_player.is_none() {
+            return vec!["bestmove none".to_string()];
+        }
+        self.engine.set_limits(movetime.or(self.movetime), depth.or(self.depth));
+        let (result, best_move) = self.engine.search(&self.board);
+        let notation = best_move
+            .and_then(|(pawn_index, direction)| self.board.move_to_notation(&Move::new(pawn_index, direction)))
+            .unwrap_or_else(|| "none".to_string());
+        let pv = self.board.line_to_notation(&result.pv);
+        let mate = match &result.proven {
+            Some(ProvenResult::Win(distance)) => format!(" mate {}", distance),
+            Some(ProvenResult::Loss(distance)) => format!(" mate -{}", distance),
+            None => String::new(),
+        };
+        let mut info = format!(
+            "info score {:.3}{} depth {} nodes {} time {:.0}",
+            result.value, mate, result.depth, result.nodes, result.time_ms
+        );
+        if !pv.is_empty() {
+            info.push_str(&format!(" pv {}", pv.join(" ")));
+        }
+        let mut lines = vec![info];
+        if let Some(text) = result.proven_text(&self.board) {
+            lines.push(format!("info string {}", text));
+        }
+        lines.push(format!("bestmove {}", notation));
+        lines
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    ai::{minmax::NODE_BYTES, oracle::ProvenResult},
+    endgame::{EndgameDrill, solve_endgame, solve_endgame_async},
+    logic::Board,
+    platform::{Platform, WASM_MAX_SEARCH_MEMORY, load_text, save_text},
+};
+
+// Days are counted in UTC from 1970-01-01, the puzzle changes at the same instant for every player
+pub const MS_PER_DAY: f64 = 86_400_000.0;
+
+// A daily puzzle is won in 2 or 3 moves of the attacker: one move wins are too easy, longer ones too rare to find fast
+const MIN_PUZZLE_PLIES: usize = 3;
+const MAX_PUZZLE_PLIES: usize = 5;
+
+pub fn day_number(ms_since_epoch: f64) -> i64 {
+    (ms_since_epoch / MS_PER_DAY).floor() as i64
+}
+
+// splitmix64 seeded with the day: the puzzle has to be the same on every platform and build,
+// which Platform::random does not promise
+struct DayRng(u64);
+
+impl DayRng {
+    fn new(day: i64) -> Self {
+        Self(day as u64 ^ 0x6e65_7574_7265_656b)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Next code of Board::decode_u64 giving a position still to play, without a win in one
+    fn next_candidate(&mut self) -> Board {
+        loop {
+            let Some(board) = Board::decode_u64(self.next() & 0x7fff_ffff) else {
+                continue;
+            };
+            let Some(mover) = &board.next_player else {
+                continue;
+            };
+            if board.winner().is_none() && board.winning_moves(mover).is_empty() {
+                return board;
+            }
+        }
+    }
+}
+
+// The proof has to fit in the memory cap of the browser, so that every build accepts the same candidate
+fn accept(board: Board, nodes: usize, proven: Option<ProvenResult>) -> Option<EndgameDrill> {
+    match proven {
+        Some(ProvenResult::Win(plies)) if (MIN_PUZZLE_PLIES..=MAX_PUZZLE_PLIES).contains(&plies) && nodes < WASM_MAX_SEARCH_MEMORY / NODE_BYTES => {
+            Some(EndgameDrill { board, plies })
+        }
+        _ => None,
+    }
+}
+
+// First candidate of the day that MinMax proves won, the same for every player
+pub fn daily_puzzle<O: Platform>(day: i64) -> EndgameDrill {
+    let mut rng = DayRng::new(day);
+    loop {
+        let board = rng.next_candidate();
+        let result = solve_endgame::<O>(&board);
+        if let Some(drill) = accept(board, result.nodes, result.proven) {
+            return drill;
+        }
+    }
+}
+
+pub async fn daily_puzzle_async<O: Platform>(day: i64) -> EndgameDrill {
+    let mut rng = DayRng::new(day);
+    loop {
+        let board = rng.next_candidate();
+        let result = solve_endgame_async::<O>(&board).await;
+        if let Some(drill) = accept(board, result.nodes, result.proven) {
+            return drill;
+        }
+    }
+}
+
+// Days in a row the daily puzzle was solved, kept by the player
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PuzzleStreak {
+    pub last_solved: Option<i64>,
+    pub current: usize,
+    pub best: usize,
+}
+
+impl PuzzleStreak {
+    // Solving again the same day changes nothing
+    pub fn record_solve(&mut self, day: i64) {
+        if self.last_solved == Some(day) {
+            return;
+        }
+        self.current = if self.last_solved == Some(day - 1) { self.current + 1 } else { 1 };
+        self.best = self.best.max(self.current);
+        self.last_solved = Some(day);
+    }
+
+    // The streak is lost once a whole day passes without a solve
+    pub fn current_on(&self, day: i64) -> usize {
+        match self.last_solved {
+            Some(last_solved) if day - last_solved <= 1 => self.current,
+            _ => 0,
+        }
+    }
+
+    pub fn is_solved(&self, day: i64) -> bool {
+        self.last_solved == Some(day)
+    }
+
+    // last solved day (- if none);current;best
+    pub fn to_text(&self) -> String {
+        let last_solved = self.last_solved.map_or("-".to_string(), |day| day.to_string());
+        format!("{};{};{}", last_solved, self.current, self.best)
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut fields = text.trim().split(';');
+        let last_solved = match fields.next()? {
+            "-" => None,
+            day => Some(day.parse().ok()?),
+        };
+        Some(Self { last_solved, current: fields.next()?.parse().ok()?, best: fields.next()?.parse().ok()? })
+    }
+
+    pub async fn load_storage<O: Platform>(key: &str) -> Option<Self> {
+        Self::from_text(&load_text::<O>(key).await?)
+    }
+
+    pub async fn save_storage<O: Platform>(&self, key: &str) -> bool {
+        save_text::<O>(key, &self.to_text()).await
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    ai::{AI, SearchResult, minmax::MinMax, oracle::ProvenResult},
+    logic::{Board, Color},
+    platform::Platform,
+};
+
+// Random positions to start games from, which neither side wins by force soon. MinMax only scores finished games:
+// its value is 0 unless it proves a result, and close to 1 in absolute value when it does
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalancedStartConfig {
+    pub depth: usize, // of the MinMax evaluation, raised to see every win closer than min_moves_to_win
+    pub max_value: f32, // largest absolute value of the evaluation, below 0.5 any proven result is too much
+    pub min_moves_to_win: usize, // moves of the winner in the fastest forced win, when one is proven
+}
+
+impl Default for BalancedStartConfig {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            max_value: 0.5,
+            min_moves_to_win: 3,
+        }
+    }
+}
+
+impl BalancedStartConfig {
+    fn search_depth(&self) -> usize {
+        self.depth.max(2 * self.min_moves_to_win.saturating_sub(1))
+    }
+
+    fn accept(&self, result: &SearchResult) -> bool {
+        // a win in n moves takes 2n - 1 plies, a loss 2n
+        let far_enough = match &result.proven {
+            Some(ProvenResult::Win(plies)) | Some(ProvenResult::Loss(plies)) => (plies + 1) / 2 >= self.min_moves_to_win,
+            None => true,
+        };
+        far_enough && result.value.abs() <= self.max_value
+    }
+}
+
+// Either side to move, skipping at once the boards the mover wins in one or loses whatever they play
+fn random_candidate<O: Platform>() -> Board {
+    loop {
+        let mut board = Board::random_board::<O>();
+        if O::random_int(2) == 1 {
+            board.next_player = board.next_player.map(|color| color.other_color());
+        }
+        let Some(mover) = &board.next_player else {
+            continue;
+        };
+        if board.winning_moves(mover).is_empty() && !board.safe_moves().is_empty() {
+            return board;
+        }
+    }
+}
+
+fn evaluator<O: Platform>(board: &Board, config: &BalancedStartConfig) -> MinMax<O> {
+    MinMax::new(board.next_player.clone().unwrap_or(Color::Green), config.search_depth())
+}
+
+pub fn balanced_start<O: Platform>(config: &BalancedStartConfig) -> Board {
+    loop {
+        let board = random_candidate::<O>();
+        if config.accept(&evaluator::<O>(&board, config).give_all_options(&board, false)) {
+            return board;
+        }
+    }
+}
+
+pub async fn balanced_start_async<O: Platform>(config: &BalancedStartConfig) -> Board {
+    loop {
+        let board = random_candidate::<O>();
+        if config.accept(&evaluator::<O>(&board, config).give_all_options_async(&board, false, None).await) {
+            return board;
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    analysis::{Arrow, ArrowKind},
+    gamerecord::GameRecord,
+    logic::{Board, Color, Position},
+};
+
+// Board diagrams outside the browser page: a standalone SVG for sharing positions and for reports,
+// and with the image feature a PNG of it on native
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    pub square_size: u32, // in pixels, the page draws 80
+    pub coordinates: bool, // column letters and row numbers around the board
+    pub arrows: Vec<Arrow>,
+    pub highlighted: Vec<Position>, // squares tinted under the pawns, the last move for instance
+    pub caption: Option<String>, // written under the board
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            square_size: 60,
+            coordinates: true,
+            arrows: vec![],
+            highlighted: vec![],
+            caption: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    // Band holding the coordinates, or the caption, as a share of a square
+    fn margin(&self) -> u32 {
+        self.square_size / 3
+    }
+}
+
+fn pawn_colour(color: &Color) -> &'static str {
+    match color {
+        Color::Green => "green",
+        Color::Yellow => "yellow",
+    }
+}
+
+// The best move in blue as green is a side, shared with the page
+pub fn arrow_colour(kind: &ArrowKind) -> &'static str {
+    match kind {
+        ArrowKind::BestMove => "#2e86de",
+        ArrowKind::Threat => "#e74c3c",
+        ArrowKind::User => "#f39c12",
+    }
+}
+
+// Text written in the SVG, the caption may come from a user
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub fn board_to_svg(board: &Board, options: &RenderOptions) -> String {
+    let size = options.square_size as f64;
+    let margin = if options.coordinates { options.margin() as f64 } else { 0.0 };
+    let caption_height = if options.caption.is_some() { options.margin() as f64 * 1.5 } else { 0.0 };
+    let board_width = size * board.number_of_columns as f64;
+    let board_height = size * board.number_of_rows as f64;
+    let width = board_width + 2.0 * margin;
+    let height = board_height + 2.0 * margin + caption_height;
+    let centre = |position: &Position| (
+        margin + (position.column as f64 + 0.5) * size,
+        margin + (position.row as f64 + 0.5) * size,
+    );
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width, h = height,
+    );
+    svg.push_str(&format!("<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height));
+    // one marker per arrow kind, sized in strokes
+    svg.push_str("<defs>\n");
+    for kind in ArrowKind::ALL.iter() {
+        svg.push_str(&format!(
+            "<marker id=\"arrowhead-{:?}\" markerWidth=\"3\" markerHeight=\"3\" refX=\"0\" refY=\"1.5\" orient=\"auto\"><path d=\"M0,0 L3,1.5 L0,3 z\" fill=\"{}\" fill-opacity=\"0.75\"/></marker>\n",
+            kind, arrow_colour(kind),
+        ));
+    }
+    svg.push_str("</defs>\n");
+
+    for position in options.highlighted.iter() {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#f7dc6f\" fill-opacity=\"0.5\"/>\n",
+            margin + position.column as f64 * size, margin + position.row as f64 * size, size, size,
+        ));
+    }
+    for row in 0..=board.number_of_rows {
+        let y = margin + row as f64 * size;
+        svg.push_str(&format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#c0c0c0\"/>\n", margin, y, margin + board_width, y));
+    }
+    for column in 0..=board.number_of_columns {
+        let x = margin + column as f64 * size;
+        svg.push_str(&format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#c0c0c0\"/>\n", x, margin, x, margin + board_height));
+    }
+
+    if options.coordinates {
+        let font_size = margin * 0.7;
+        for column in 0..board.number_of_columns {
+            let x = margin + (column as f64 + 0.5) * size;
+            for y in [margin / 2.0, margin * 1.5 + board_height] {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#555\">{}</text>\n",
+                    x, y, font_size, Position::column_name(column),
+                ));
+            }
+        }
+        for row in 0..board.number_of_rows {
+            let y = margin + (row as f64 + 0.5) * size;
+            for x in [margin / 2.0, margin * 1.5 + board_width] {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#555\">{}</text>\n",
+                    x, y, font_size, Position::row_name(row),
+                ));
+            }
+        }
+    }
+
+    let radius = size / 2.0 - size / 16.0;
+    for pawn in board.pawns.iter() {
+        let (x, y) = centre(&pawn.position);
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"black\" stroke-width=\"{}\"/>\n",
+            x, y, radius, pawn_colour(&pawn.color), (size / 40.0).max(1.0),
+        ));
+    }
+
+    // the line stops short of the centre of the square so the tip of the head lands there
+    let stroke = size / 8.0;
+    for arrow in options.arrows.iter() {
+        let (x1, y1) = centre(&arrow.from);
+        let (x2, y2) = centre(&arrow.to);
+        let length = (x2 - x1).hypot(y2 - y1).max(1.0);
+        let shortened = (length - 3.0 * stroke).max(0.0) / length;
+        svg.push_str(&format!(
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"{}\" stroke-opacity=\"0.75\" marker-end=\"url(#arrowhead-{:?})\"/>\n",
+            x1, y1, x1 + (x2 - x1) * shortened, y1 + (y2 - y1) * shortened, arrow_colour(&arrow.kind), stroke, arrow.kind,
+        ));
+    }
+
+    if let Some(caption) = &options.caption {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"black\">{}</text>\n",
+            width / 2.0, height - caption_height / 2.0, options.margin() as f64 * 0.8, escape(caption),
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// One diagram per position of the game, from the start to the end. The squares of the last move are highlighted,
+// the arrows recorded for the next move drawn and the caption numbers the move, the result in the last one.
+// None if the record contains an illegal move
+pub fn game_frames(record: &GameRecord, options: &RenderOptions) -> Option<Vec<String>> {
+    let boards = record.boards()?;
+    let mut frames = vec![];
+    for (ply, board) in boards.iter().enumerate() {
+        let mut frame_options = options.clone();
+        frame_options.arrows = record.moves.get(ply).map(|move_record| move_record.arrows.clone()).unwrap_or_default();
+        let mut caption = String::new();
+        if let Some(last_move) = ply.checked_sub(1).map(|index| &record.moves[index]) {
+            frame_options.highlighted = last_move.notation.split('-').filter_map(Position::from_notation).collect();
+            caption = format!("{}. {}", ply.div_ceil(2), last_move.notation);
+        }
+        if ply + 1 == boards.len() {
+            if let Some(result) = &record.result {
+                caption = format!("{} {}", caption, result.to_pgn()).trim().to_string();
+            }
+        }
+        frame_options.caption = Some(caption);
+        frames.push(board_to_svg(board, &frame_options));
+    }
+    Some(frames)
+}
+
+// The SVG rasterized by resvg, coordinates and caption in a sans-serif font of the system
+#[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+fn rasterize(svg: &str) -> Result<resvg::tiny_skia::Pixmap, String> {
+    let mut svg_options = resvg::usvg::Options::default();
+    svg_options.fontdb_mut().load_system_fonts();
+    let tree = resvg::usvg::Tree::from_str(svg, &svg_options).map_err(|error| error.to_string())?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("Empty image")?;
+    resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+#[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+pub fn board_to_png(board: &Board, options: &RenderOptions) -> Result<Vec<u8>, String> {
+    rasterize(&board_to_svg(board, options))?.encode_png().map_err(|error| error.to_string())
+}
+
+// Animated GIF of the game looping forever, frame_ms per move and three times that on the final position
+#[cfg(all(feature = "image", not(target_arch = "wasm32")))]
+pub fn game_to_gif(record: &GameRecord, options: &RenderOptions, frame_ms: u32) -> Result<Vec<u8>, String> {
+    let frames = game_frames(record, options).ok_or("The game contains an illegal move")?;
+    let pixmaps = frames.iter().map(|frame| rasterize(frame)).collect::<Result<Vec<_>, String>>()?;
+    let (width, height) = (pixmaps[0].width() as u16, pixmaps[0].height() as u16);
+    let mut bytes = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut bytes, width, height, &[]).map_err(|error| error.to_string())?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|error| error.to_string())?;
+        for (index, pixmap) in pixmaps.iter().enumerate() {
+            // the background is opaque, premultiplied colours are the plain ones
+            let mut rgba = pixmap.data().to_vec();
+            let mut frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+            let hold = if index + 1 == pixmaps.len() { 3 } else { 1 };
+            // GIF delays count hundredths of a second
+            frame.delay = (frame_ms * hold / 10).min(u16::MAX as u32) as u16;
+            encoder.write_frame(&frame).map_err(|error| error.to_string())?;
+        }
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,139 @@
+use crate::{
+    ai::{any::AnyAI, oracle::{ExactOracle, ProvenResult}},
+    logic::{Board, Move},
+    platform::Platform,
+};
+
+use burn::tensor::backend::Backend;
+
+#[derive(Clone, Debug)]
+pub struct SelfCheckConfig {
+    pub positions: usize,
+    pub movetime_ms: Option<f64>,
+    pub depth: Option<usize>, // of MinMax engines, deeper than their difficulty to serve as a reference
+}
+
+impl Default for SelfCheckConfig {
+    fn default() -> Self {
+        Self {
+            positions: 50,
+            movetime_ms: None,
+            depth: None,
+        }
+    }
+}
+
+// Move an engine chose, and whether the oracle proves it loses in a position that could still be held
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineChoice {
+    pub engine: String,
+    pub played: Option<Move>,
+    pub losing: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckedPosition {
+    pub board: Board,
+    pub proven: Option<ProvenResult>,
+    pub choices: Vec<EngineChoice>,
+}
+
+impl CheckedPosition {
+    pub fn disagreement(&self) -> bool {
+        self.choices.iter().any(|choice| choice.played != self.choices[0].played)
+    }
+
+    pub fn flagged(&self) -> bool {
+        self.choices.iter().any(|choice| choice.losing)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelfCheckReport {
+    pub engines: Vec<String>,
+    pub positions: Vec<CheckedPosition>,
+}
+
+impl SelfCheckReport {
+    pub fn disagreements(&self) -> usize {
+        self.positions.iter().filter(|position| position.disagreement()).count()
+    }
+
+    // Provably losing moves of each engine, in the order of the engines
+    pub fn losing_moves(&self) -> Vec<(String, usize)> {
+        self.engines.iter().enumerate()
+            .map(|(index, engine)| (engine.clone(), self.positions.iter().filter(|position| position.choices[index].losing).count()))
+            .collect()
+    }
+
+    // Summary, then every position where the engines disagree or one loses, losing moves marked with !!
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Self-check of {} on {} positions\n", self.engines.join(", "), self.positions.len());
+        text.push_str(&format!("Disagreements: {}\n", self.disagreements()));
+        let losing_moves: Vec<String> = self.losing_moves().iter().map(|(engine, count)| format!("{} {}", engine, count)).collect();
+        text.push_str(&format!("Provably losing moves: {}\n", losing_moves.join(", ")));
+        for (index, position) in self.positions.iter().enumerate().filter(|(_, position)| position.disagreement() || position.flagged()) {
+            let proven = match (&position.proven, &position.board.next_player) {
+                (Some(proven), Some(mover)) => proven.to_text(mover),
+                _ => "not proven".to_string(),
+            };
+            text.push_str(&format!("\nPosition {} {}{} ({})\n", index + 1, position.board.to_fen(), if position.flagged() { " FLAGGED" } else { "" }, proven));
+            for choice in position.choices.iter() {
+                let notation = choice.played.as_ref()
+                    .and_then(|played| position.board.move_to_notation(played))
+                    .unwrap_or_else(|| "-".to_string());
+                text.push_str(&format!("  {:<20} {}{}\n", choice.engine, notation, if choice.losing { " !!" } else { "" }));
+            }
+        }
+        text
+    }
+}
+
+// Random position still to play, either side to move
+fn random_position<O: Platform>() -> Board {
+    loop {
+        let mut board = Board::random_board::<O>();
+        if O::random_int(2) == 1 {
+            board.next_player = board.next_player.map(|color| color.other_color());
+        }
+        if board.legal_moves().next().is_some() {
+            return board;
+        }
+    }
+}
+
+// A move loses when the oracle proves the position after it won for the opponent, while another move is not proven lost
+fn losing_moves(oracle: &dyn ExactOracle, board: &Board) -> Vec<Move> {
+    let (losing, holding): (Vec<Move>, Vec<Move>) = board.legal_moves()
+        .partition(|game_move| matches!(oracle.probe(&board.child(game_move)), Some(ProvenResult::Win(_))));
+    if holding.is_empty() { vec![] } else { losing }
+}
+
+// Every engine plays the same random positions, the oracle judges their moves
+pub fn run_self_check<B: Backend, O: Platform>(
+    engines: &mut [(String, AnyAI<B, O>)],
+    oracle: &dyn ExactOracle,
+    config: &SelfCheckConfig,
+) -> SelfCheckReport {
+    let mut report = SelfCheckReport { engines: engines.iter().map(|(name, _)| name.clone()).collect(), positions: vec![] };
+    for (_, engine) in engines.iter_mut() {
+        engine.set_limits(config.movetime_ms, config.depth);
+    }
+    for index in 0..config.positions {
+        let board = random_position::<O>();
+        let losing = losing_moves(oracle, &board);
+        let mut choices = vec![];
+        for (name, engine) in engines.iter_mut() {
+            let (_, best_move) = engine.search(&board);
+            let played = best_move.map(|(pawn_index, direction)| Move::new(pawn_index, direction));
+            let losing = played.as_ref().is_some_and(|played| losing.contains(played));
+            choices.push(EngineChoice { engine: name.clone(), played, losing });
+        }
+        let position = CheckedPosition { proven: oracle.probe(&board), board, choices };
+        if position.flagged() {
+            O::print(&format!("Position {}: a provably losing move was played", index + 1));
+        }
+        report.positions.push(position);
+    }
+    report
+}
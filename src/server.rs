@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use burn::backend::NdArray;
+use clap::Parser;
+use neutreeko::{
+    ai::any::{AnyAI, ENGINE_NAMES},
+    game::Game,
+    gamerecord::GameRecord,
+    ladder::{Ladder, ParticipantKind},
+    logic::{Board, Color, Move, StartPosition},
+    platform::{NativePlatform, Platform},
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "neutreeko-server", about = "Serve Neutreeko games and engine moves over HTTP")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    address: String,
+
+    /// File keeping the ladder accounts, their tokens and ratings across restarts
+    #[arg(long)]
+    ladder_accounts: Option<String>,
+
+    /// PGN file the finished ladder games are appended to
+    #[arg(long)]
+    ladder_games: Option<String>,
+}
+
+type Engine = AnyAI<NdArray<f32>, NativePlatform>;
+type SharedGames = Arc<Mutex<Games>>;
+type SharedLadder = Arc<Mutex<LadderState>>;
+
+// Strongest difficulty accepted, higher ones would hold a request for minutes
+const MAX_DIFFICULTY: usize = 6;
+
+#[derive(Default)]
+struct Games {
+    games: HashMap<usize, ServerGame>,
+    next_id: usize,
+}
+
+struct ServerGame {
+    game: Game,
+    notations: Vec<String>, // moves played so far, in algebraic notation
+}
+
+impl ServerGame {
+    fn to_json(&self, id: usize) -> String {
+        let board = &self.game.board;
+        let notations: Vec<String> = self.notations.iter().map(|notation| format!("\"{}\"", notation)).collect();
+        let legal_moves: Vec<String> = board.legal_moves()
+            .filter_map(|game_move| board.move_to_notation(&game_move))
+            .map(|notation| format!("\"{}\"", notation))
+            .collect();
+        format!(
+            "{{\"id\":{},\"fen\":\"{}\",\"board\":{},\"moves\":[{}],\"legal_moves\":[{}]}}",
+            id, board.to_fen(), board.to_json(), notations.join(","), legal_moves.join(",")
+        )
+    }
+
+    fn play(&mut self, game_move: &Move) -> Result<String, String> {
+        let notation = self.game.board.move_to_notation(game_move).ok_or("illegal move")?;
+        self.game.play(game_move).map_err(|error| error.to_string())?;
+        self.notations.push(notation.clone());
+        Ok(notation)
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+struct NewGameRequest {
+    start: Option<String>, // StartPosition name
+    fen: Option<String>,
+}
+
+// Either {"move": "b1-b4"}, {"pawn": 0, "direction": "Down"} or the position after the move, {"fen": "..."}
+#[derive(serde::Deserialize)]
+struct MoveRequest {
+    #[serde(rename = "move")]
+    notation: Option<String>,
+    pawn: Option<usize>,
+    direction: Option<String>,
+    fen: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct EngineMoveRequest {
+    engine: String,
+    difficulty: usize,
+    #[serde(default = "default_true")]
+    play: bool, // false only suggests the move
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn json_response(status: StatusCode, body: String) -> Response {
+    (status, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    json_response(status, format!("{{\"error\":\"{}\"}}", message.replace('\\', "\\\\").replace('"', "\\\"")))
+}
+
+fn not_found(id: usize) -> Response {
+    error_response(StatusCode::NOT_FOUND, &format!("no game {}", id))
+}
+
+async fn create_game(State(games): State<SharedGames>, request: Option<Json<NewGameRequest>>) -> Response {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let board = match (&request.fen, &request.start) {
+        (Some(fen), _) => match Board::from_fen(fen) {
+            Some(board) => board,
+            None => return error_response(StatusCode::BAD_REQUEST, &format!("invalid FEN {}", fen)),
+        },
+        (None, Some(start)) => match StartPosition::from_name(start) {
+            Some(start) => Board::from_start(&start),
+            None => return error_response(StatusCode::BAD_REQUEST, &format!("unknown start position {}", start)),
+        },
+        (None, None) => Board::default_new(),
+    };
+    let mut games = games.lock().unwrap();
+    let id = games.next_id;
+    games.next_id += 1;
+    let game = ServerGame { game: Game::new(&board), notations: vec![] };
+    let body = game.to_json(id);
+    games.games.insert(id, game);
+    json_response(StatusCode::CREATED, body)
+}
+
+async fn get_game(State(games): State<SharedGames>, Path(id): Path<usize>) -> Response {
+    match games.lock().unwrap().games.get(&id) {
+        Some(game) => json_response(StatusCode::OK, game.to_json(id)),
+        None => not_found(id),
+    }
+}
+
+async fn get_fen(State(games): State<SharedGames>, Path(id): Path<usize>) -> Response {
+    match games.lock().unwrap().games.get(&id) {
+        Some(game) => game.game.board.to_fen().into_response(),
+        None => not_found(id),
+    }
+}
+
+async fn delete_game(State(games): State<SharedGames>, Path(id): Path<usize>) -> Response {
+    match games.lock().unwrap().games.remove(&id) {
+        Some(_) => StatusCode::NO_CONTENT.into_response(),
+        None => not_found(id),
+    }
+}
+
+async fn post_move(State(games): State<SharedGames>, Path(id): Path<usize>, Json(request): Json<MoveRequest>) -> Response {
+    let mut games = games.lock().unwrap();
+    let Some(game) = games.games.get_mut(&id) else {
+        return not_found(id);
+    };
+    let game_move = match (&request.notation, request.pawn, &request.direction) {
+        (Some(notation), _, _) => game.game.board.move_from_notation(notation),
+        (None, Some(pawn), Some(direction)) => direction.parse().ok().map(|direction| Move::new(pawn, direction)),
+        _ if request.fen.is_some() => request.fen.as_deref().and_then(Board::from_fen).and_then(|after| game.game.board.diff(&after)),
+        _ => return error_response(StatusCode::BAD_REQUEST, "expected \"move\", \"pawn\" and \"direction\", or \"fen\""),
+    };
+    let Some(game_move) = game_move else {
+        return error_response(StatusCode::BAD_REQUEST, "illegal move");
+    };
+    match game.play(&game_move) {
+        Ok(_) => json_response(StatusCode::OK, game.to_json(id)),
+        Err(error) => error_response(StatusCode::BAD_REQUEST, &error),
+    }
+}
+
+async fn engine_move(State(games): State<SharedGames>, Path(id): Path<usize>, Json(request): Json<EngineMoveRequest>) -> Response {
+    if !ENGINE_NAMES.contains(&request.engine.as_str()) {
+        return error_response(StatusCode::BAD_REQUEST, &format!("unknown engine {}, expected one of {}", request.engine, ENGINE_NAMES.join(", ")));
+    }
+    if request.difficulty == 0 || request.difficulty > MAX_DIFFICULTY {
+        return error_response(StatusCode::BAD_REQUEST, &format!("difficulty must be from 1 to {}", MAX_DIFFICULTY));
+    }
+    let board = match games.lock().unwrap().games.get(&id) {
+        Some(game) => game.game.board.clone(),
+        None => return not_found(id),
+    };
+    let Some(color) = board.next_player.clone() else {
+        return error_response(StatusCode::CONFLICT, "the game is over");
+    };
+    let EngineMoveRequest { engine: engine_name, difficulty, play } = request;
+    // searches take seconds, they run outside the async workers
+    let searched_board = board.clone();
+    let search = tokio::task::spawn_blocking(move || {
+        let mut engine = Engine::from_name(&engine_name, color, difficulty)?;
+        Some(engine.search(&searched_board))
+    }).await;
+    let Ok(Some((result, Some((pawn_index, direction))))) = search else {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "the engine found no move");
+    };
+    let engine_move = Move::new(pawn_index, direction);
+    let mut games = games.lock().unwrap();
+    let Some(game) = games.games.get_mut(&id) else {
+        return not_found(id);
+    };
+    // a move was posted during the search
+    if game.game.board != board {
+        return error_response(StatusCode::CONFLICT, "the position changed during the search");
+    }
+    let notation = if play {
+        match game.play(&engine_move) {
+            Ok(notation) => notation,
+            Err(error) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &error),
+        }
+    } else {
+        board.move_to_notation(&engine_move).unwrap_or_default()
+    };
+    json_response(StatusCode::OK, format!(
+        "{{\"move\":{},\"notation\":\"{}\",\"evaluation\":{:.4},\"game\":{}}}",
+        engine_move.to_json(), notation, result.value, game.to_json(id)
+    ))
+}
+
+// Rated games between accounts, see src/ladder.rs. Requests act for the account whose token is sent as
+// "Authorization: Bearer <token>", participants poll /ladder/game for their pairing and their opponent's moves
+struct LadderState {
+    ladder: Ladder,
+    accounts_file: Option<String>,
+    games_file: Option<String>,
+}
+
+impl LadderState {
+    fn save_accounts(&self) {
+        if let Some(path) = &self.accounts_file {
+            if let Err(error) = std::fs::write(path, self.ladder.accounts_to_text()) {
+                eprintln!("Could not write {}: {}", path, error);
+            }
+        }
+    }
+
+    // Ratings change with every result, both files are written at once
+    fn record_finished(&self, record: &GameRecord) {
+        self.save_accounts();
+        if let Some(path) = &self.games_file {
+            use std::io::Write;
+            let written = std::fs::OpenOptions::new().create(true).append(true).open(path)
+                .and_then(|mut file| writeln!(file, "{}", record.to_pgn()));
+            if let Err(error) = written {
+                eprintln!("Could not write {}: {}", path, error);
+            }
+        }
+    }
+
+    // Games whose side to move ran out of time are lost by it, settled before a request reads or changes the ladder
+    fn forfeit_overdue(&mut self) {
+        for record in self.ladder.forfeit_overdue(NativePlatform::now()) {
+            self.record_finished(&record);
+        }
+    }
+
+    // Name of the account of the request
+    fn account(&self, headers: &HeaderMap) -> Result<String, Response> {
+        let token = headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "expected an Authorization: Bearer <token> header"))?;
+        self.ladder.authenticate(token.trim())
+            .map(str::to_string)
+            .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "unknown token"))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct AccountRequest {
+    name: String,
+    #[serde(default = "default_kind")]
+    kind: String, // "bot" or "human"
+}
+
+fn default_kind() -> String {
+    ParticipantKind::Bot.label().to_string()
+}
+
+#[derive(serde::Deserialize)]
+struct LadderMoveRequest {
+    #[serde(rename = "move")]
+    notation: String,
+}
+
+// Game as seen by the account, the result is null while it goes on. Other accounts than its players
+// watch it: their color is null and they get no legal moves
+fn ladder_game_json(id: usize, record: &GameRecord, name: &str) -> String {
+    let board = record.boards().and_then(|boards| boards.last().cloned()).unwrap_or_else(Board::default_new);
+    let color = if record.green == name {
+        Some(Color::Green)
+    } else if record.yellow == name {
+        Some(Color::Yellow)
+    } else {
+        None
+    };
+    let your_turn = record.result.is_none() && color.is_some() && board.next_player == color;
+    let notations: Vec<String> = record.moves.iter().map(|move_record| format!("\"{}\"", move_record.notation)).collect();
+    let legal_moves: Vec<String> = if your_turn {
+        board.legal_moves()
+            .filter_map(|game_move| board.move_to_notation(&game_move))
+            .map(|notation| format!("\"{}\"", notation))
+            .collect()
+    } else {
+        vec![]
+    };
+    let result = record.result.as_ref().map_or("null".to_string(), |result| format!("\"{}\"", result.to_pgn()));
+    let termination = record.termination.as_ref().map_or("null".to_string(), |termination| format!("\"{}\"", termination));
+    let color = color.map_or("null".to_string(), |color| format!("\"{:?}\"", color).to_lowercase());
+    format!(
+        "{{\"id\":{},\"green\":\"{}\",\"yellow\":\"{}\",\"color\":{},\"your_turn\":{},\"fen\":\"{}\",\"moves\":[{}],\"legal_moves\":[{}],\"result\":{},\"termination\":{}}}",
+        id, record.green, record.yellow, color, your_turn, board.to_fen(), notations.join(","), legal_moves.join(","), result, termination
+    )
+}
+
+async fn create_account(State(ladder): State<SharedLadder>, Json(request): Json<AccountRequest>) -> Response {
+    let Some(kind) = ParticipantKind::from_name(&request.kind) else {
+        return error_response(StatusCode::BAD_REQUEST, &format!("unknown kind {}, expected bot or human", request.kind));
+    };
+    let mut state = ladder.lock().unwrap();
+    match state.ladder.register::<NativePlatform>(&request.name, kind) {
+        Ok(token) => {
+            state.save_accounts();
+            json_response(StatusCode::CREATED, format!("{{\"name\":\"{}\",\"token\":\"{}\"}}", request.name, token))
+        }
+        Err(error) => error_response(StatusCode::CONFLICT, &error),
+    }
+}
+
+async fn get_standings(State(ladder): State<SharedLadder>) -> Response {
+    let mut state = ladder.lock().unwrap();
+    state.forfeit_overdue();
+    let participants: Vec<String> = state.ladder.standings().iter()
+        .map(|participant| format!(
+            "{{\"name\":\"{}\",\"kind\":\"{}\",\"rating\":{:.1},\"games\":{}}}",
+            participant.name, participant.kind.label(), participant.rating, participant.games
+        ))
+        .collect();
+    json_response(StatusCode::OK, format!("[{}]", participants.join(",")))
+}
+
+async fn seek(State(ladder): State<SharedLadder>, headers: HeaderMap) -> Response {
+    let mut state = ladder.lock().unwrap();
+    let name = match state.account(&headers) {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    state.forfeit_overdue();
+    if let Err(error) = state.ladder.seek(&name, NativePlatform::now()) {
+        return error_response(StatusCode::CONFLICT, &error);
+    }
+    state.ladder.pair::<NativePlatform>(NativePlatform::now());
+    current_ladder_game(&state.ladder, &name)
+}
+
+async fn cancel_seek(State(ladder): State<SharedLadder>, headers: HeaderMap) -> Response {
+    let mut state = ladder.lock().unwrap();
+    match state.account(&headers) {
+        Ok(name) => {
+            state.ladder.cancel_seek(&name);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+// The game of the account once paired, 202 while it waits for an opponent
+fn current_ladder_game(ladder: &Ladder, name: &str) -> Response {
+    match ladder.current_game(name) {
+        Some((id, game)) => json_response(StatusCode::OK, ladder_game_json(id, &game.record, name)),
+        None if ladder.is_seeking(name) => json_response(StatusCode::ACCEPTED, "{\"seeking\":true}".to_string()),
+        None => error_response(StatusCode::NOT_FOUND, "no game in progress, POST /ladder/seek to find one"),
+    }
+}
+
+async fn get_ladder_game(State(ladder): State<SharedLadder>, headers: HeaderMap) -> Response {
+    let mut state = ladder.lock().unwrap();
+    let name = match state.account(&headers) {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    state.forfeit_overdue();
+    state.ladder.pair::<NativePlatform>(NativePlatform::now());
+    current_ladder_game(&state.ladder, &name)
+}
+
+// Any game by id, finished ones included, so the loser of the last move sees the result
+async fn get_ladder_game_by_id(State(ladder): State<SharedLadder>, Path(id): Path<usize>, headers: HeaderMap) -> Response {
+    let mut state = ladder.lock().unwrap();
+    let name = match state.account(&headers) {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    state.forfeit_overdue();
+    match state.ladder.record(id) {
+        Some(record) => json_response(StatusCode::OK, ladder_game_json(id, record, &name)),
+        None => not_found(id),
+    }
+}
+
+async fn post_ladder_move(
+    State(ladder): State<SharedLadder>,
+    Path(id): Path<usize>,
+    headers: HeaderMap,
+    Json(request): Json<LadderMoveRequest>,
+) -> Response {
+    let mut state = ladder.lock().unwrap();
+    let name = match state.account(&headers) {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    state.forfeit_overdue();
+    match state.ladder.play(&name, id, &request.notation, NativePlatform::now()) {
+        Ok(Some(record)) => {
+            state.record_finished(&record);
+            json_response(StatusCode::OK, ladder_game_json(id, &record, &name))
+        }
+        Ok(None) => match state.ladder.record(id) {
+            Some(record) => json_response(StatusCode::OK, ladder_game_json(id, record, &name)),
+            None => not_found(id),
+        },
+        Err(error) => error_response(StatusCode::BAD_REQUEST, &error),
+    }
+}
+
+async fn resign_ladder_game(State(ladder): State<SharedLadder>, Path(id): Path<usize>, headers: HeaderMap) -> Response {
+    let mut state = ladder.lock().unwrap();
+    let name = match state.account(&headers) {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    state.forfeit_overdue();
+    match state.ladder.resign(&name, id) {
+        Ok(record) => {
+            state.record_finished(&record);
+            json_response(StatusCode::OK, ladder_game_json(id, &record, &name))
+        }
+        Err(error) => error_response(StatusCode::BAD_REQUEST, &error),
+    }
+}
+
+async fn get_ladder_pgn(State(ladder): State<SharedLadder>) -> Response {
+    let mut state = ladder.lock().unwrap();
+    state.forfeit_overdue();
+    state.ladder.finished_pgn().into_response()
+}
+
+fn router(games: SharedGames, ladder: SharedLadder) -> Router {
+    let ladder_routes = Router::new()
+        .route("/ladder/accounts", post(create_account))
+        .route("/ladder/standings", get(get_standings))
+        .route("/ladder/seek", post(seek).delete(cancel_seek))
+        .route("/ladder/game", get(get_ladder_game))
+        .route("/ladder/games/:id", get(get_ladder_game_by_id))
+        .route("/ladder/games/:id/moves", post(post_ladder_move))
+        .route("/ladder/games/:id/resign", post(resign_ladder_game))
+        .route("/ladder/games.pgn", get(get_ladder_pgn))
+        .with_state(ladder);
+    Router::new()
+        .route("/games", post(create_game))
+        .route("/games/:id", get(get_game).delete(delete_game))
+        .route("/games/:id/fen", get(get_fen))
+        .route("/games/:id/moves", post(post_move))
+        .route("/games/:id/engine-move", post(engine_move))
+        .with_state(games)
+        .merge(ladder_routes)
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let listener = tokio::net::TcpListener::bind(&args.address).await.unwrap_or_else(|error| {
+        eprintln!("Could not listen on {}: {}", args.address, error);
+        std::process::exit(1);
+    });
+    // a missing accounts file starts an empty ladder
+    let ladder = match args.ladder_accounts.as_deref().map(std::fs::read_to_string) {
+        Some(Ok(text)) => Ladder::from_accounts_text(&text).unwrap_or_else(|| {
+            eprintln!("Could not read the ladder accounts");
+            std::process::exit(1);
+        }),
+        _ => Ladder::new(),
+    };
+    let ladder = LadderState { ladder, accounts_file: args.ladder_accounts, games_file: args.ladder_games };
+    println!("Listening on http://{}", args.address);
+    if let Err(error) = axum::serve(listener, router(SharedGames::default(), Arc::new(Mutex::new(ladder)))).await {
+        eprintln!("Server stopped: {}", error);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    ai::{ScoredMove, oracle::{ExactOracle, ProvenResult}, value::Value},
+    logic::{Board, Move},
+    platform::Platform,
+};
+
+// Proven positions to follow the progress of the network from one checkpoint to the next, see generate_tactics
+pub const TACTICS: &str = include_str!("../assets/tactics.txt");
+
+// Position with the moves reaching its proven result fastest (slowest for a loss) and whether the side to move wins
+#[derive(Clone, Debug, PartialEq)]
+pub struct TacticalPosition {
+    pub board: Board,
+    pub best_moves: Vec<Move>,
+    pub winning: bool,
+}
+
+impl TacticalPosition {
+    // fen;best moves separated by spaces;win or loss
+    pub fn to_line(&self) -> String {
+        let best_moves: Vec<String> = self.best_moves.iter().filter_map(|game_move| self.board.move_to_notation(game_move)).collect();
+        format!("{};{};{}", self.board.to_fen(), best_moves.join(" "), if self.winning { "win" } else { "loss" })
+    }
+
+    pub fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split(';');
+        let board = Board::from_fen(fields.next()?.trim())?;
+        let best_moves = fields.next()?.split_whitespace()
+            .map(|notation| board.move_from_notation(notation))
+            .collect::<Option<Vec<Move>>>()?;
+        let winning = match fields.next()?.trim() {
+            "win" => true,
+            "loss" => false,
+            _ => return None,
+        };
+        Some(Self { board, best_moves, winning })
+    }
+}
+
+// Share of the positions a policy and a value get right
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TacticsReport {
+    pub positions: usize,
+    pub policy_positions: usize, // positions where some moves are worse than others, the policy is only scored on them
+    pub top1: f32, // the most likely move is a best move
+    pub top3: f32, // a best move is among the 3 most likely
+    pub value_sign: f32, // the value is positive for wins and negative for losses
+}
+
+impl TacticsReport {
+    pub fn to_text(&self) -> String {
+        format!("{} positions: top-1 {:.1}%, top-3 {:.1}% of {} with a best move, value sign {:.1}%",
+            self.positions, 100.0 * self.top1, 100.0 * self.top3, self.policy_positions, 100.0 * self.value_sign)
+    }
+}
+
+// Scores the value and the move probabilities given by predict for every position, the policy of a network or the moves of an engine
+pub fn score_tactics(positions: &[TacticalPosition], mut predict: impl FnMut(&Board) -> (Value, Vec<ScoredMove>)) -> TacticsReport {
+    let mut report = TacticsReport { positions: positions.len(), ..Default::default() };
+    for position in positions.iter() {
+        let (value, mut moves) = predict(&position.board);
+        if (value > Value::DRAW) == position.winning {
+            report.value_sign += 1.0;
+        }
+        if position.best_moves.len() == position.board.legal_moves().count() {
+            continue;
+        }
+        report.policy_positions += 1;
+        moves.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let best_rank = moves.iter().position(|scored_move| position.best_moves.contains(&scored_move.to_move()));
+        if best_rank == Some(0) {
+            report.top1 += 1.0;
+        }
+        if best_rank.is_some_and(|rank| rank < 3) {
+            report.top3 += 1.0;
+        }
+    }
+    report.value_sign /= report.positions.max(1) as f32;
+    report.top1 /= report.policy_positions.max(1) as f32;
+    report.top3 /= report.policy_positions.max(1) as f32;
+    report
+}
+
+// Lines starting with # are comments. None if a line cannot be read
+pub fn tactics_from_text(text: &str) -> Option<Vec<TacticalPosition>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(TacticalPosition::from_line)
+        .collect()
+}
+
+pub fn tactical_positions() -> Vec<TacticalPosition> {
+    tactics_from_text(TACTICS).unwrap_or_default()
+}
+
+// Random positions the oracle proves, with every move keeping the proven result. Wins in one are skipped,
+// the network would be tested on seeing a line rather than on tactics
+pub fn generate_tactics<P: Platform>(oracle: &dyn ExactOracle, count: usize) -> Vec<TacticalPosition> {
+    let mut positions: Vec<TacticalPosition> = vec![];
+    while positions.len() < count {
+        let mut board = Board::random_board::<P>();
+        if P::random_int(2) == 1 {
+            board.next_player = board.next_player.map(|color| color.other_color());
+        }
+        let Some(result) = oracle.probe(&board) else {
+            continue;
+        };
+        if result == ProvenResult::Win(1) || positions.iter().any(|position| position.board.canonical_fen() == board.canonical_fen()) {
+            continue;
+        }
+        let best_moves: Vec<Move> = board.legal_moves()
+            .filter(|game_move| oracle.probe(&board.child(game_move)).is_some_and(|child_result| child_result.for_previous_player() == result))
+            .collect();
+        if best_moves.is_empty() {
+            continue;
+        }
+        let winning = matches!(result, ProvenResult::Win(_));
+        positions.push(TacticalPosition { board, best_moves, winning });
+    }
+    positions
+}
@@ -3,18 +3,83 @@ use neutreeko::{
     platform::NativePlatform,
     ai::{
         AI,
-        ann::train::ANNTrainer,
+        ann::{ANNConfig, calibration::{calibration_report, fit_temperature, value_samples}, train::{ANNTrainer, coverage::PositionVisits, human::{HumanMoveTrainer, human_examples}}},
+        // ann::{ANNConfig, train::distill::Distiller},
         minmax::MinMax,
-    }
+    },
+    gamerecord::games_from_pgn,
+    gamestore::{GameStore, HUMAN_PLAYER},
 };
 use burn::backend::{Autodiff, NdArray};
 
+// Passes over the games of the player, they are few so each one is seen many times
+const HUMAN_EPOCHS: usize = 30;
+// Positions reached by the previous self-play runs, new games start from the rarest
+const POSITION_VISITS_FILE: &str = "assets/models/position_visits.txt";
+// One example in this many is kept aside to measure how well the model predicts unseen moves
+const HUMAN_VALIDATION_EVERY: usize = 10;
+
 #[cfg(feature = "train")]
 fn main() {
+    #[cfg(feature = "tracing")]
+    let _flame = neutreeko::profiling::init_tracing();
+    // neutreeko-train human games.pgn: fits the "plays like you" model on the games exported from the web page
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("human") {
+        train_human(args.get(2).map_or("assets/models/human_games.pgn", String::as_str));
+        return;
+    }
+    // neutreeko-train calibrate games.pgn [model]: compares the values of the network with the results of the games
+    if args.get(1).map(String::as_str) == Some("calibrate") {
+        calibrate(
+            args.get(2).map_or("assets/models/13_200_itself.pgn", String::as_str),
+            args.get(3).map_or("assets/models/14_3_opening", String::as_str),
+        );
+        return;
+    }
     train();
     evaluate();
 }
 
+fn train_human(games_filepath: &str) {
+    let Some(store) = std::fs::read_to_string(games_filepath).ok().and_then(|pgn| GameStore::from_pgn(&pgn)) else {
+        println!("Could not read the games of {}", games_filepath);
+        return;
+    };
+    let examples = human_examples(store.games(), HUMAN_PLAYER);
+    println!("{} moves played by the human in {} games", examples.len(), store.len());
+    let (validation, training): (Vec<_>, Vec<_>) = examples.into_iter().enumerate()
+        .partition(|(index, _)| index % HUMAN_VALIDATION_EVERY == 0);
+    let validation: Vec<_> = validation.into_iter().map(|(_, example)| example).collect();
+    let training: Vec<_> = training.into_iter().map(|(_, example)| example).collect();
+
+    let mut trainer: HumanMoveTrainer<Autodiff<NdArray<f32>>> = HumanMoveTrainer::new(ANNConfig::human());
+    trainer.fit(&training, HUMAN_EPOCHS);
+    println!("Moves predicted: {:.1}% of training moves, {:.1}% of unseen moves",
+        100.0 * trainer.accuracy(&training), 100.0 * trainer.accuracy(&validation));
+    let _ = trainer.save("assets/models/human");
+    // to load in the web page with "Load my model"
+    trainer.save_for_web("assets/models/web/human");
+}
+
+fn calibrate(games_filepath: &str, model_filepath: &str) {
+    let Some(games) = std::fs::read_to_string(games_filepath).ok().and_then(|pgn| games_from_pgn(&pgn)) else {
+        println!("Could not read the games of {}", games_filepath);
+        return;
+    };
+    let mut trainer: ANNTrainer<Autodiff<NdArray<f32>>, MinMax<NativePlatform>> = ANNTrainer::new();
+    if trainer.load(model_filepath).is_err() {
+        println!("Could not load model {}", model_filepath);
+        return;
+    }
+    let samples = value_samples(trainer.ann(), &games);
+    println!("Raw values of {}", model_filepath);
+    print!("{}", calibration_report(&samples, 1.0).to_text());
+    let temperature = fit_temperature(&samples);
+    println!("With temperature {:.2}, to set as VALUE_TEMPERATURE", temperature);
+    print!("{}", calibration_report(&samples, temperature).to_text());
+}
+
 fn train() {
     let mut trainer: ANNTrainer<Autodiff<NdArray<f32>>, MinMax<NativePlatform>> = ANNTrainer::new();
     let result = trainer.load("assets/models/12_3_opening");
@@ -24,37 +89,55 @@ fn train() {
     // let _ = trainer.save("assets/models/7_3_opening");
 
     // trainer.opponent = Some(MinMax::new(Color::Yellow, 4));
-    // trainer.training_loop(10);
+    // let _ = trainer.training_loop(10);
     // let _ = trainer.save("assets/models/8_10_MinMax4");
 
-    // trainer.training_loop(10);
+    // let _ = trainer.training_loop(10);
     // let _ = trainer.save("assets/models/9_10_MinMax4");
 
-    // trainer.training_loop(10);
+    // let _ = trainer.training_loop(10);
     // let _ = trainer.save("assets/models/10_10_MinMax4");
 
     // trainer.opponent = None;
-    trainer.training_loop(200);
+    trainer.position_visits = PositionVisits::load_file(POSITION_VISITS_FILE);
+    // trainer.balanced_starts = Some(neutreeko::randomstart::BalancedStartConfig::default());
+    // trainer.divergence_threshold = Some(0.05);
+    // trainer.replay_file = Some("assets/models/replay.bin".to_string());
+    // let _ = trainer.train_from_replay("assets/models/replay.bin", 10000);
+    let result = trainer.training_loop(200);
+    if let Err(error) = result {println!("Training stopped, invalid move: {}", error);}
+    if let Some(position_visits) = &trainer.position_visits {
+        let _ = position_visits.save_file(POSITION_VISITS_FILE);
+    }
     let _ = trainer.save("assets/models/13_200_itself");
+    let _ = trainer.save_games("assets/models/13_200_itself.pgn");
+    // let _ = trainer.export_selfplay("assets/models/13_selfplay.jsonl", 100);
 
     trainer.train_opening(3);
     let _ = trainer.save("assets/models/14_3_opening");
     trainer.save_for_web();
+
+    // let mut distiller: Distiller<Autodiff<NdArray<f32>>> = Distiller::from_file("assets/models/14_3_opening", ANNConfig::new(16).with_blocks(2)).unwrap();
+    // distiller.distill(10000);
+    // let _ = distiller.save("assets/models/15_distilled_16_2");
+    // let _ = distiller.save_for_web_half_precision("assets/models/web/model_16_2_half");
 }
 
 fn evaluate(){
     let mut trainer: ANNTrainer<Autodiff<NdArray<f32>>, MinMax<NativePlatform>> = ANNTrainer::new();
     trainer.opponent = Some(MinMax::new(Color::Yellow, 4));
     // let _ = trainer.load("assets/models/7_3_opening");
-    // trainer.evaluate(2);
+    // let _ = trainer.evaluate(2);
     // let _ = trainer.load("assets/models/8_10_MinMax4");
-    // trainer.evaluate(2);
+    // let _ = trainer.evaluate(2);
     // let _ = trainer.load("assets/models/9_10_MinMax4");
-    // trainer.evaluate(2);
+    // let _ = trainer.evaluate(2);
     // let _ = trainer.load("assets/models/10_10_MinMax4");
-    // trainer.evaluate(2);
+    // let _ = trainer.evaluate(2);
     let _ = trainer.load("assets/models/13_200_itself");
-    trainer.evaluate(2);
+    println!("Tactics: {}", trainer.eval_tactics().to_text());
+    let _ = trainer.evaluate(2);
     let _ = trainer.load("assets/models/14_3_opening");
-    trainer.evaluate(2);
+    println!("Tactics: {}", trainer.eval_tactics().to_text());
+    let _ = trainer.evaluate(2);
 }
@@ -0,0 +1,154 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use burn::backend::{Autodiff, ndarray::NdArray};
+use gloo_timers::future::sleep;
+use yew::prelude::*;
+use yew::{html, Component, Context, Html};
+
+use crate::ai::{ann::{ANNConfig, train::{ANNTrainer, Augmentation}}, minmax::MinMax};
+use crate::platform::WasmPlatform;
+
+type DemoTrainer = ANNTrainer<Autodiff<NdArray<f32, i32>>, MinMax<WasmPlatform>, WasmPlatform>;
+
+// Small enough to learn a self-play game in a few seconds on the main thread
+const DEMO_CHANNELS: usize = 8;
+const DEMO_BLOCKS: usize = 1;
+const DEMO_SYMMETRIES: usize = 2;
+const DEMO_NODE_LIMIT: usize = 50;
+
+// Games played by one click on Train
+const DEMO_GAMES: usize = 5;
+
+const CHART_WIDTH: f32 = 300.0;
+const CHART_HEIGHT: f32 = 120.0;
+
+pub enum Msg {
+    Train,
+    Stop,
+    Reset,
+    GameLearned(Vec<f32>),
+    Finished(Option<String>),
+}
+
+// Self-play training of a tiny network in the page, the chart shows the average loss of every game learned from
+pub struct TrainingDemo {
+    trainer: Rc<RefCell<DemoTrainer>>,
+    stop: Rc<Cell<bool>>,
+    training: bool,
+    losses: Vec<f32>,
+    error: Option<String>,
+}
+
+fn new_trainer() -> DemoTrainer {
+    let mut trainer = DemoTrainer::with_network(&ANNConfig::new(DEMO_CHANNELS).with_blocks(DEMO_BLOCKS), 1);
+    trainer.augmentation = Augmentation::Sampled(DEMO_SYMMETRIES);
+    trainer.set_node_limit(Some(DEMO_NODE_LIMIT));
+    trainer
+}
+
+impl Component for TrainingDemo {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            trainer: Rc::new(RefCell::new(new_trainer())),
+            stop: Rc::new(Cell::new(false)),
+            training: false,
+            losses: vec![],
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Train => {
+                if self.training {
+                    return false;
+                }
+                self.training = true;
+                self.error = None;
+                self.stop.set(false);
+                let trainer = self.trainer.clone();
+                let stop = self.stop.clone();
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    for _ in 0..DEMO_GAMES {
+                        if stop.get() {
+                            break;
+                        }
+                        // one game blocks the page, the pause lets it redraw the chart in between
+                        let result = trainer.borrow_mut().training_loop(1);
+                        if let Err(error) = result {
+                            link.send_message(Msg::Finished(Some(format!("Training stopped: {:?}", error))));
+                            return;
+                        }
+                        link.send_message(Msg::GameLearned(trainer.borrow().losses.clone()));
+                        sleep(Duration::from_millis(0)).await;
+                    }
+                    link.send_message(Msg::Finished(None));
+                });
+            }
+            Msg::Stop => {
+                self.stop.set(true);
+                return false;
+            }
+            Msg::Reset => {
+                if self.training {
+                    return false;
+                }
+                self.trainer = Rc::new(RefCell::new(new_trainer()));
+                self.losses.clear();
+                self.error = None;
+            }
+            Msg::GameLearned(losses) => {
+                self.losses = losses;
+            }
+            Msg::Finished(error) => {
+                self.training = false;
+                self.error = error;
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let status = match self.losses.last() {
+            Some(loss) => format!("{} games learned, last loss {:.3}", self.losses.len(), loss),
+            None => "No game learned yet".to_string(),
+        };
+        html! {
+            <div class="training-demo">
+                <h3>{ "Training demo" }</h3>
+                <p>{ format!("A network with {} channels learns from its own games, {} at a time", DEMO_CHANNELS, DEMO_GAMES) }</p>
+                <button onclick={ctx.link().callback(|_| Msg::Train)} disabled={self.training}>{ "Train" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::Stop)} disabled={!self.training}>{ "Stop" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::Reset)} disabled={self.training}>{ "Reset" }</button>
+                <p>{ status }</p>
+                { for self.error.iter().map(|error| html! { <p class="error-message">{ error }</p> }) }
+                {loss_chart(&self.losses)}
+            </div>
+        }
+    }
+}
+
+// Line of the losses scaled to the chart, the first game on the left
+fn loss_chart(losses: &[f32]) -> Html {
+    if losses.len() < 2 {
+        return html! {};
+    }
+    let max_loss = losses.iter().cloned().fold(f32::EPSILON, f32::max);
+    let step = CHART_WIDTH / (losses.len() - 1) as f32;
+    let points: Vec<String> = losses.iter()
+        .enumerate()
+        .map(|(index, loss)| format!("{:.1},{:.1}", index as f32 * step, CHART_HEIGHT * (1.0 - loss / max_loss)))
+        .collect();
+    html! {
+        <svg class="loss-chart" width={CHART_WIDTH.to_string()} height={CHART_HEIGHT.to_string()}
+            viewBox={format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)} role="img" aria-label="Loss per game">
+            <polyline points={points.join(" ")} fill="none" stroke="currentColor" stroke-width="2" />
+        </svg>
+    }
+}
@@ -0,0 +1,423 @@
+use std::io;
+use std::time::Duration;
+
+use burn::backend::NdArray;
+use clap::{Parser, ValueEnum};
+use neutreeko::{
+    ai::{SearchResult, any::AnyAI, ann::weights::{DEFAULT_MODEL_ID, set_model_bytes}},
+    analysis::Analysis,
+    gamerecord::{GameRecord, MoveRecord, games_to_pgn},
+    logic::{Board, Color, Direction, Move, Position, StartPosition},
+    platform::{NativePlatform, Platform, Receiver, Sender, channel},
+};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color as TermColor, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PlayerColor {
+    Green,
+    Yellow,
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "neutreeko-tui", about = "Play Neutreeko in a terminal interface")]
+struct Args {
+    /// Engine to play against, as <engine>:<difficulty>
+    #[arg(short, long, default_value = "mcts:4")]
+    engine: String,
+    /// Color played by the human
+    #[arg(short, long, value_enum, default_value_t = PlayerColor::Green)]
+    color: PlayerColor,
+    /// Checkpoint (.bpk) used by the network engines instead of the bundled weights
+    #[arg(short, long)]
+    model: Option<String>,
+    /// Start position: standard, yellow-first, green-center or yellow-center
+    #[arg(long, default_value = "standard")]
+    start: String,
+    /// File where the games are written as PGN when leaving
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+type Engine = AnyAI<NdArray<f32>, NativePlatform>;
+
+// Model identifier of the checkpoint given with --model
+const CHECKPOINT_MODEL_ID: &str = "checkpoint";
+
+// Results sent back by the search thread, with the board they were computed for
+enum Reply {
+    Move(Engine, Board, SearchResult, Option<(usize, Direction)>),
+    Analysis(Engine, Board, Analysis),
+}
+
+struct TuiApp {
+    start: Board,
+    board: Board,
+    history: Vec<Board>,
+    record: GameRecord,
+    games: Vec<GameRecord>, // finished or abandoned games, written to --output
+    human_color: Color,
+    engine_name: String,
+    engine: Option<Engine>, // None while it searches
+    cursor: Position,
+    selected_pawn: Option<usize>,
+    evaluation: Option<f32>, // last engine evaluation, for Green
+    analysis: Option<(Board, Analysis)>,
+    message: String,
+    replies: Receiver<Reply>,
+    reply_sender: Sender<Reply>,
+}
+
+impl TuiApp {
+    fn new(start: Board, human_color: Color, engine_name: String, engine: Engine) -> Self {
+        let (reply_sender, replies) = channel();
+        let mut record = GameRecord::new(&start);
+        Self::name_players(&mut record, &human_color, &engine_name);
+        Self {
+            board: start.clone(),
+            history: vec![],
+            record,
+            games: vec![],
+            human_color,
+            engine_name,
+            engine: Some(engine),
+            cursor: Position { row: 2, column: 2 },
+            selected_pawn: None,
+            evaluation: None,
+            analysis: None,
+            message: "Arrows move the cursor, Enter selects a pawn then its destination".to_string(),
+            replies,
+            reply_sender,
+            start,
+        }
+    }
+
+    fn name_players(record: &mut GameRecord, human_color: &Color, engine_name: &str) {
+        let (green, yellow) = match human_color {
+            Color::Green => ("Human", engine_name),
+            Color::Yellow => (engine_name, "Human"),
+        };
+        record.green = green.to_string();
+        record.yellow = yellow.to_string();
+    }
+
+    fn searching(&self) -> bool {
+        self.engine.is_none()
+    }
+
+    fn new_game(&mut self) {
+        if !self.record.moves.is_empty() {
+            self.games.push(self.record.clone());
+        }
+        self.board = self.start.clone();
+        self.history.clear();
+        self.record = GameRecord::new(&self.start);
+        Self::name_players(&mut self.record, &self.human_color, &self.engine_name);
+        self.selected_pawn = None;
+        self.evaluation = None;
+        self.analysis = None;
+        self.message = "New game".to_string();
+    }
+
+    fn play(&mut self, game_move: &Move, move_record: MoveRecord) {
+        self.history.push(self.board.clone());
+        if let Err(error) = self.board.apply_move(game_move.pawn_index, &game_move.direction) {
+            self.history.pop();
+            self.message = format!("Illegal move: {}", error);
+            return;
+        }
+        self.record.moves.push(move_record);
+        self.record.finish(&self.board);
+        self.selected_pawn = None;
+        self.analysis = None;
+    }
+
+    // Takes back the last human move and the engine answer
+    fn undo(&mut self) {
+        while let Some(previous_board) = self.history.pop() {
+            self.board = previous_board;
+            self.record.moves.pop();
+            if self.board.next_player.as_ref() == Some(&self.human_color) {
+                break;
+            }
+        }
+        self.record.result = None;
+        self.selected_pawn = None;
+        self.analysis = None;
+    }
+
+    fn start_engine_move(&mut self) {
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        let board = self.board.clone();
+        let sender = self.reply_sender.clone();
+        NativePlatform::spawn(move || async move {
+            let (result, best_move) = engine.search(&board);
+            sender.send(Reply::Move(engine, board, result, best_move));
+        });
+    }
+
+    fn start_analysis(&mut self) {
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        let board = self.board.clone();
+        let sender = self.reply_sender.clone();
+        self.message = "Analyzing...".to_string();
+        NativePlatform::spawn(move || async move {
+            let analysis = engine.analyze(&board);
+            sender.send(Reply::Analysis(engine, board, analysis));
+        });
+    }
+
+    fn poll_replies(&mut self) {
+        while let Some(reply) = self.replies.try_recv() {
+            match reply {
+                Reply::Move(engine, board, result, best_move) => {
+                    self.engine = Some(engine);
+                    // the game was restarted or taken back during the search
+                    if board != self.board {
+                        continue;
+                    }
+                    let Some((pawn_index, direction)) = best_move else {
+                        continue;
+                    };
+                    let engine_move = Move::new(pawn_index, direction);
+                    let notation = board.move_to_notation(&engine_move).unwrap_or_default();
+                    self.evaluation = board.next_player.as_ref().map(|color| match color {
+                        Color::Green => result.value,
+                        Color::Yellow => -result.value,
+                    });
+                    self.message = format!("Engine plays {}", notation);
+                    self.play(&engine_move, MoveRecord::from_search(notation, &result));
+                }
+                Reply::Analysis(engine, board, analysis) => {
+                    self.engine = Some(engine);
+                    self.message = "Analysis done".to_string();
+                    self.analysis = Some((board, analysis));
+                }
+            }
+        }
+    }
+
+    fn move_cursor(&mut self, rows: isize, columns: isize) {
+        let row = self.cursor.row as isize + rows;
+        let column = self.cursor.column as isize + columns;
+        if row >= 0 && column >= 0 && (row as usize) < self.board.number_of_rows && (column as usize) < self.board.number_of_columns {
+            self.cursor = Position { row: row as usize, column: column as usize };
+        }
+    }
+
+    // Selects the pawn under the cursor, or moves the selected pawn to the cursor
+    fn confirm(&mut self) {
+        if self.board.next_player.as_ref() != Some(&self.human_color) {
+            return;
+        }
+        if let Some(pawn_index) = self.board.pawn_at(&self.cursor) {
+            if self.board.pawns[pawn_index].color == self.human_color {
+                self.selected_pawn = Some(pawn_index);
+            }
+            return;
+        }
+        let Some(pawn_index) = self.selected_pawn else {
+            return;
+        };
+        let destination = self.board.get_valid_directions_and_resulting_boards(pawn_index)
+            .into_iter()
+            .find(|(_, new_board)| new_board.pawns[pawn_index].position == self.cursor);
+        match destination {
+            Some((direction, _)) => {
+                let human_move = Move::new(pawn_index, direction);
+                let notation = self.board.move_to_notation(&human_move).unwrap_or_default();
+                self.message = format!("You play {}", notation);
+                self.play(&human_move, MoveRecord::new(notation));
+            }
+            None => self.message = "The pawn cannot stop there".to_string(),
+        }
+    }
+
+    // Returns false when the user quits
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1, 0),
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(0, 1),
+            KeyCode::Enter | KeyCode::Char(' ') if !self.searching() => self.confirm(),
+            KeyCode::Char('u') if !self.searching() => self.undo(),
+            KeyCode::Char('n') if !self.searching() => self.new_game(),
+            KeyCode::Char('a') if !self.searching() => self.start_analysis(),
+            _ => (),
+        }
+        true
+    }
+
+    fn status(&self) -> String {
+        if let Some(winner) = self.board.winner() {
+            return format!("{:?} wins", winner);
+        }
+        match &self.board.next_player {
+            Some(_) if self.searching() => "Engine is thinking...".to_string(),
+            Some(color) => format!("{:?} to move", color),
+            None => "Game over".to_string(),
+        }
+    }
+
+    fn board_lines(&self) -> Vec<Line<'static>> {
+        let mut destinations = vec![];
+        if let Some(pawn_index) = self.selected_pawn {
+            for (_, new_board) in self.board.get_valid_directions_and_resulting_boards(pawn_index) {
+                destinations.push(new_board.pawns[pawn_index].position.clone());
+            }
+        }
+        let mut lines = vec![];
+        for row in 0..self.board.number_of_rows {
+            let mut spans = vec![Span::raw(format!("{} ", row + 1))];
+            for column in 0..self.board.number_of_columns {
+                let position = Position { row, column };
+                let (symbol, mut style) = match self.board.pawn_at(&position) {
+                    Some(pawn_index) => match self.board.pawns[pawn_index].color {
+                        Color::Green => (" G ", Style::default().fg(TermColor::Green).add_modifier(Modifier::BOLD)),
+                        Color::Yellow => (" Y ", Style::default().fg(TermColor::Yellow).add_modifier(Modifier::BOLD)),
+                    },
+                    None if destinations.contains(&position) => (" * ", Style::default().fg(TermColor::DarkGray)),
+                    None => (" . ", Style::default().fg(TermColor::DarkGray)),
+                };
+                if self.selected_pawn.is_some_and(|pawn_index| self.board.pawns[pawn_index].position == position) {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                if position == self.cursor {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                spans.push(Span::styled(symbol, style));
+            }
+            lines.push(Line::from(spans));
+        }
+        let columns: String = (0..self.board.number_of_columns)
+            .map(|column| format!(" {} ", (b'a' + column as u8) as char))
+            .collect();
+        lines.push(Line::from(format!("  {}", columns)));
+        lines
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main_area, message_area] = Layout::vertical([Constraint::Min(10), Constraint::Length(3)]).areas(frame.area());
+        let [board_area, moves_area, analysis_area] = Layout::horizontal([
+            Constraint::Length(22),
+            Constraint::Length(28),
+            Constraint::Min(30),
+        ]).areas(main_area);
+
+        let mut board_lines = self.board_lines();
+        board_lines.push(Line::from(""));
+        board_lines.push(Line::from(self.status()));
+        if let Some(evaluation) = self.evaluation {
+            board_lines.push(Line::from(format!("Eval (Green): {:+.2}", evaluation)));
+        }
+        frame.render_widget(Paragraph::new(board_lines).block(Block::default().borders(Borders::ALL).title("Board")), board_area);
+
+        let moves: Vec<ListItem> = self.record.moves.chunks(2).enumerate()
+            .map(|(index, pair)| {
+                let played: Vec<String> = pair.iter()
+                    .map(|move_record| match move_record.evaluation {
+                        Some(evaluation) => format!("{} ({:+.2})", move_record.notation, evaluation),
+                        None => move_record.notation.clone(),
+                    })
+                    .collect();
+                ListItem::new(format!("{}. {}", index + 1, played.join(" ")))
+            })
+            .collect();
+        frame.render_widget(List::new(moves).block(Block::default().borders(Borders::ALL).title("Moves")), moves_area);
+
+        let analysis_text = match &self.analysis {
+            Some((board, analysis)) => analysis.to_text(board),
+            None => "Press a to analyze the position".to_string(),
+        };
+        frame.render_widget(Paragraph::new(analysis_text).block(Block::default().borders(Borders::ALL).title("Analysis")), analysis_area);
+
+        let help = "Enter select/move  u undo  a analyze  n new game  q quit";
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(self.message.clone()), Line::from(help)]).block(Block::default().borders(Borders::TOP)),
+            message_area,
+        );
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            self.poll_replies();
+            let engine_to_move = self.board.winner().is_none()
+                && self.board.next_player.as_ref().is_some_and(|color| *color != self.human_color);
+            if engine_to_move && !self.searching() {
+                self.start_engine_move();
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn parse_engine(spec: &str, model_id: &str) -> (String, Engine) {
+    let (name, difficulty) = spec.split_once(':').unwrap_or((spec, "4"));
+    let difficulty = difficulty.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid difficulty in {}", spec);
+        std::process::exit(1);
+    });
+    let engine = Engine::from_name_with_model(name, Color::Green, difficulty, model_id).unwrap_or_else(|| {
+        eprintln!("Unknown engine {}", name);
+        std::process::exit(1);
+    });
+    (spec.to_string(), engine)
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let model_id = match &args.model {
+        Some(path) => {
+            let bytes = std::fs::read(path).unwrap_or_else(|error| {
+                eprintln!("Could not read {}: {}", path, error);
+                std::process::exit(1);
+            });
+            set_model_bytes(CHECKPOINT_MODEL_ID, bytes);
+            CHECKPOINT_MODEL_ID
+        }
+        None => DEFAULT_MODEL_ID,
+    };
+    let (engine_name, engine) = parse_engine(&args.engine, model_id);
+    let start = StartPosition::from_name(&args.start).unwrap_or_else(|| {
+        eprintln!("Unknown start position {}", args.start);
+        std::process::exit(1);
+    });
+    let human_color = match args.color {
+        PlayerColor::Green => Color::Green,
+        PlayerColor::Yellow => Color::Yellow,
+    };
+    let mut app = TuiApp::new(Board::from_start(&start), human_color, engine_name, engine);
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    if let Some(path) = &args.output {
+        // files the game in progress with the finished ones
+        app.new_game();
+        if let Err(error) = std::fs::write(path, games_to_pgn(&app.games)) {
+            eprintln!("Could not write {}: {}", path, error);
+        }
+    }
+    result
+}
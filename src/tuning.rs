@@ -0,0 +1,273 @@
+use crate::{
+    ai::{any::AnyAI, mcts::MCTSConfig, timemanager::{DEFAULT_EXPECTED_MOVES, TimeManager}},
+    arena::{MatchConfig, play_game},
+    gamerecord::GameResult,
+    logic::{Board, Color},
+    platform::Platform,
+    randomstart::{BalancedStartConfig, balanced_start},
+};
+
+use burn::tensor::backend::Backend;
+
+// Search parameters SPSA can tune. MinMax only scores proven results, it has no evaluation weights to tune:
+// these are the knobs of the MCTS engines and of their time manager
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TunableParameter {
+    Exploration,
+    FirstPlayUrgency,
+    PriorTemperature,
+    ProgressiveBias,
+    ExpectedMoves, // only used with a game time
+}
+
+impl TunableParameter {
+    pub const ALL: [TunableParameter; 5] = [
+        TunableParameter::Exploration,
+        TunableParameter::FirstPlayUrgency,
+        TunableParameter::PriorTemperature,
+        TunableParameter::ProgressiveBias,
+        TunableParameter::ExpectedMoves,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TunableParameter::Exploration => "exploration",
+            TunableParameter::FirstPlayUrgency => "fpu",
+            TunableParameter::PriorTemperature => "prior-temperature",
+            TunableParameter::ProgressiveBias => "progressive-bias",
+            TunableParameter::ExpectedMoves => "expected-moves",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|parameter| parameter.label() == name).cloned()
+    }
+
+    // Value the engines start from, the first play urgency of the default configuration is none, a draw
+    pub fn default_value(&self) -> f64 {
+        let config = MCTSConfig::default();
+        match self {
+            TunableParameter::Exploration => config.exploration as f64,
+            TunableParameter::FirstPlayUrgency => config.first_play_urgency.unwrap_or(0.0) as f64,
+            TunableParameter::PriorTemperature => config.prior_temperature as f64,
+            TunableParameter::ProgressiveBias => config.progressive_bias as f64,
+            TunableParameter::ExpectedMoves => DEFAULT_EXPECTED_MOVES as f64,
+        }
+    }
+
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            TunableParameter::Exploration => (0.05, 5.0),
+            TunableParameter::FirstPlayUrgency => (-1.0, 1.0),
+            TunableParameter::PriorTemperature => (0.2, 5.0),
+            TunableParameter::ProgressiveBias => (0.0, 5.0),
+            TunableParameter::ExpectedMoves => (5.0, 100.0),
+        }
+    }
+
+    // Perturbation of the first iteration, large enough for a few games to tell the two sides apart
+    pub fn step(&self) -> f64 {
+        match self {
+            TunableParameter::Exploration => 0.3,
+            TunableParameter::FirstPlayUrgency => 0.2,
+            TunableParameter::PriorTemperature => 0.3,
+            TunableParameter::ProgressiveBias => 0.3,
+            TunableParameter::ExpectedMoves => 5.0,
+        }
+    }
+
+    fn clamp(&self, value: f64) -> f64 {
+        let (min, max) = self.range();
+        value.clamp(min, max)
+    }
+}
+
+// Simultaneous perturbation stochastic approximation: every iteration plays the engine with all the parameters
+// moved one way against the engine with them moved the other way, and steps each parameter towards the winning side.
+// Gains follow Spall's schedule, a_k = a / (k + 1 + A)^alpha and c_k = c / (k + 1)^gamma
+#[derive(Clone, Debug)]
+pub struct TuningConfig {
+    pub engine: String, // mcts or alphazeutreeko
+    pub difficulty: usize,
+    pub parameters: Vec<TunableParameter>,
+    pub iterations: usize,
+    pub games_per_iteration: usize, // rounded up to pairs, both sides play each start with both colours
+    pub movetime_ms: Option<f64>,
+    pub game_time_ms: Option<f64>, // needed for ExpectedMoves to matter
+    pub max_moves: usize,
+    pub random_starts: bool, // balanced random starts instead of the standard one
+    pub learning_rate: f64, // a of each parameter, in units of its step squared
+    pub alpha: f64,
+    pub gamma: f64,
+    pub stability: Option<f64>, // A, a tenth of the iterations when None
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            engine: "mcts".to_string(),
+            difficulty: 2,
+            parameters: vec![TunableParameter::Exploration],
+            iterations: 100,
+            games_per_iteration: 2,
+            movetime_ms: Some(100.0),
+            game_time_ms: None,
+            max_moves: 200,
+            random_starts: true,
+            learning_rate: 4.0,
+            alpha: 0.602,
+            gamma: 0.101,
+            stability: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningIteration {
+    pub values: Vec<f64>, // after the update, in the order of the parameters
+    pub score: f64, // of the plus side, from -1 (lost every game) to 1
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningReport {
+    pub parameters: Vec<TunableParameter>,
+    pub initial: Vec<f64>,
+    pub tuned: Vec<f64>,
+    pub iterations: Vec<TuningIteration>,
+    pub games: usize,
+}
+
+impl TuningReport {
+    // Tuned values first, then one line per iteration for plotting the trajectory
+    pub fn to_text(&self) -> String {
+        let mut text = format!("SPSA tuning, {} iterations, {} games\n", self.iterations.len(), self.games);
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            text.push_str(&format!("{:<18} {:>8.3} -> {:>8.3}\n", parameter.label(), self.initial[index], self.tuned[index]));
+        }
+        text.push_str("\niteration score ");
+        text.push_str(&self.parameters.iter().map(|parameter| parameter.label()).collect::<Vec<_>>().join(" "));
+        text.push('\n');
+        for (index, iteration) in self.iterations.iter().enumerate() {
+            let values: Vec<String> = iteration.values.iter().map(|value| format!("{:.3}", value)).collect();
+            text.push_str(&format!("{} {:.2} {}\n", index + 1, iteration.score, values.join(" ")));
+        }
+        text
+    }
+}
+
+// Engine of the configuration with these parameter values, None if the engine is unknown
+fn tuned_engine<B: Backend, O: Platform>(config: &TuningConfig, parameters: &[TunableParameter], values: &[f64]) -> Option<AnyAI<B, O>> {
+    let mut engine = AnyAI::from_name(&config.engine, Color::Green, config.difficulty)?;
+    let mut expected_moves = DEFAULT_EXPECTED_MOVES;
+    let mut set = |mcts_config: &mut MCTSConfig| {
+        for (parameter, value) in parameters.iter().zip(values.iter()) {
+            match parameter {
+                TunableParameter::Exploration => mcts_config.exploration = *value as f32,
+                TunableParameter::FirstPlayUrgency => mcts_config.first_play_urgency = Some(*value as f32),
+                TunableParameter::PriorTemperature => mcts_config.prior_temperature = *value as f32,
+                TunableParameter::ProgressiveBias => mcts_config.progressive_bias = *value as f32,
+                TunableParameter::ExpectedMoves => expected_moves = value.round() as usize,
+            }
+        }
+    };
+    match &mut engine {
+        AnyAI::MCTS(ai) => set(&mut ai.config),
+        AnyAI::AlphaZeutreeko(ai) => set(&mut ai.config),
+        _ => return None,
+    }
+    // the arena would start the time manager over with the default expected moves, it is set here instead
+    let time_manager = config.game_time_ms.map(|game_time| {
+        let mut time_manager = TimeManager::new(game_time);
+        time_manager.expected_moves = expected_moves;
+        time_manager
+    });
+    match &mut engine {
+        AnyAI::MCTS(ai) => ai.set_time_manager(time_manager),
+        AnyAI::AlphaZeutreeko(ai) => ai.set_time_manager(time_manager),
+        _ => return None,
+    }
+    Some(engine)
+}
+
+// Points of the plus engine over a pair of games from the start, one with each colour, minus those of the other
+fn play_pair<B: Backend, O: Platform>(
+    config: &TuningConfig,
+    plus: &[f64],
+    minus: &[f64],
+    start: &Board,
+    match_config: &MatchConfig,
+) -> Option<f64> {
+    let mut score = 0.0;
+    for plus_color in [Color::Green, Color::Yellow] {
+        let mut plus_engine: AnyAI<B, O> = tuned_engine(config, &config.parameters, plus)?;
+        let mut minus_engine: AnyAI<B, O> = tuned_engine(config, &config.parameters, minus)?;
+        let record = match plus_color {
+            Color::Green => play_game(&mut plus_engine, &mut minus_engine, ("plus", "minus"), start, match_config),
+            Color::Yellow => play_game(&mut minus_engine, &mut plus_engine, ("minus", "plus"), start, match_config),
+        };
+        score += match record.result {
+            Some(GameResult::Draw) | None => 0.0,
+            Some(result) if result.winner() == Some(plus_color) => 1.0,
+            Some(_) => -1.0,
+        };
+    }
+    Some(score)
+}
+
+// Errs when the engine has no parameter to tune
+pub fn run_tuning<B: Backend, O: Platform>(config: &TuningConfig) -> Result<TuningReport, String> {
+    if !matches!(config.engine.as_str(), "mcts" | "alphazeutreeko") {
+        return Err(format!("{} has no tunable parameter, use mcts or alphazeutreeko", config.engine));
+    }
+    if config.parameters.is_empty() {
+        return Err("no parameter to tune".to_string());
+    }
+    // without a game time there is no time manager, the games would not depend on the value
+    if config.parameters.contains(&TunableParameter::ExpectedMoves) && config.game_time_ms.is_none() {
+        return Err("expected-moves needs a game time".to_string());
+    }
+    let parameters = &config.parameters;
+    let initial: Vec<f64> = parameters.iter().map(TunableParameter::default_value).collect();
+    let mut values = initial.clone();
+    let stability = config.stability.unwrap_or(config.iterations as f64 / 10.0);
+    let pairs = config.games_per_iteration.div_ceil(2).max(1);
+    // the tuned engines keep their own time manager, see tuned_engine
+    let match_config = MatchConfig { movetime_ms: config.movetime_ms, game_time_ms: None, max_moves: config.max_moves, ..MatchConfig::default() };
+    let mut iterations = vec![];
+    for iteration in 0..config.iterations {
+        let k = iteration as f64 + 1.0;
+        let a_k = config.learning_rate / (k + stability).powf(config.alpha);
+        let c_k = 1.0 / k.powf(config.gamma);
+        // Bernoulli perturbation, each parameter moved by its own step
+        let delta: Vec<f64> = parameters.iter().map(|_| if O::random_int(2) == 0 { -1.0 } else { 1.0 }).collect();
+        let shifted = |sign: f64| -> Vec<f64> {
+            parameters.iter().zip(values.iter()).zip(delta.iter())
+                .map(|((parameter, value), delta)| parameter.clamp(value + sign * c_k * parameter.step() * delta))
+                .collect()
+        };
+        let (plus, minus) = (shifted(1.0), shifted(-1.0));
+        let mut score = 0.0;
+        for _ in 0..pairs {
+            let start = if config.random_starts {
+                balanced_start::<O>(&BalancedStartConfig::default())
+            } else {
+                Board::default_new()
+            };
+            score += play_pair::<B, O>(config, &plus, &minus, &start, &match_config).ok_or("the engine could not be created")?;
+        }
+        score /= 2.0 * pairs as f64;
+        // gradient estimate (y+ - y-) / (2 c_k step delta), with a gain of learning_rate step squared
+        for (index, parameter) in parameters.iter().enumerate() {
+            let step = parameter.step();
+            let gradient = score / (2.0 * c_k * step * delta[index]);
+            values[index] = parameter.clamp(values[index] + a_k * step * step * gradient);
+        }
+        O::print(&format!(
+            "Iteration {}: score {:+.2}, {}",
+            iteration + 1, score,
+            parameters.iter().zip(values.iter()).map(|(parameter, value)| format!("{} {:.3}", parameter.label(), value)).collect::<Vec<_>>().join(", "),
+        ));
+        iterations.push(TuningIteration { values: values.clone(), score });
+    }
+    Ok(TuningReport { parameters: parameters.clone(), initial, tuned: values, iterations, games: config.iterations * pairs * 2 })
+}
@@ -1,72 +1,1021 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use yew::prelude::*;
 use yew::{html, Component, Context, Html};
-use web_sys::HtmlSelectElement;
+use web_sys::{File, HtmlInputElement, HtmlSelectElement};
 use gloo_timers::future::sleep;
 use std::time::Duration;
 use burn::backend::ndarray::NdArray;
+use burn::tensor::backend::Backend;
 
-use crate::platform::{Platform, WasmPlatform};
-use crate::ai::{AI, minmax::MinMax, mcts::MCTS, ann::ANNSolo, alphazeutreeko::AlphaZeutreeko};
-use crate::logic::{Board, Direction, Pawn, Position, Color};
+use crate::platform::{LogLevel, Platform, SearchHandle, WasmPlatform, load_text, save_text, spawn_with_result, take_engine_log};
+use crate::ai::{AI, SearchObserver, SearchProgress, SearchResult, Telemetry, any::{AnyAI, ENGINE_NAMES}, book::{OpeningBook, WithBook}};
+#[cfg(feature = "webgpu")]
+use crate::ai::ann::webgpu::{WebGpuBackend, init_webgpu};
+use crate::ai::ann::{ANNSolo, weights::{DEFAULT_MODEL_ID, HUMAN_MODEL_ID, available_models, fetch_model, is_model_loaded, restore_uploaded_models, upload_model, upload_model_as}};
+use crate::ai::statscache::{SharedStatsCache, StatsCache};
+use crate::ai::searchtree::SearchTree;
+use crate::ai::{minmax::MinMax, strength::{Calibrated, STRENGTH_LEVELS, strength_level}};
+use crate::explain::explain_move;
+use crate::endgame::{DrillProgress, EndgameDrill, drill_progress, endgame_drills, solution_line, solve_endgame_async};
+use crate::puzzle::{PuzzleStreak, daily_puzzle_async, day_number};
+use crate::randomstart::{BalancedStartConfig, balanced_start_async};
+use crate::render::{RenderOptions, arrow_colour, board_to_svg};
+use crate::analysis::{Analysis, Arrow, ArrowKind, DEFAULT_BLUNDER_THRESHOLD, GameReview, analyze_async, landing_heat_map, review_game_async};
+use crate::logic::{Board, Direction, Move, MoveError, MoveOutcome, Pawn, Position, Color, StartPosition};
+use crate::game::Game;
+use crate::gamerecord::{GameRecord, GameResult, MoveRecord, SWAP_COMMENT};
+use crate::gamestore::{GameStore, HUMAN_PLAYER, SavedGames};
+use crate::profile::{OpponentStats, UserProfile};
+use crate::achievements::{Achievement, Achievements};
 
 const SCALING: u32 = 80;
 
 const MARGIN: u32 = 5;
+
+// Width of the band holding the coordinates around the board
+const COORDINATE_SIZE: u32 = 20;
+
+// Shorter touches are taps, they select the pawn as a click does
+const MIN_SWIPE_DISTANCE: f64 = 30.0;
+
+// MinMax depth used to review finished games
+const REVIEW_DEPTH: usize = 4;
+
+// Browser storage entry keeping the MCTS statistics across visits
+const STATS_CACHE_KEY: &str = "neutreeko-mcts-stats";
+
+// Browser storage entry keeping the games of the player, only written once they opted in
+const HUMAN_GAMES_KEY: &str = "neutreeko-human-games";
+
+// Browser storage entry keeping the games saved by name
+const SAVED_GAMES_KEY: &str = "neutreeko-saved-games";
+
+// Browser storage entry keeping the streak of daily puzzles solved
+const PUZZLE_STREAK_KEY: &str = "neutreeko-puzzle-streak";
+
+// Browser storage entry keeping the results of the player against the engines
+const PROFILE_KEY: &str = "neutreeko-profile";
+
+// Browser storage entry keeping the achievements unlocked
+const ACHIEVEMENTS_KEY: &str = "neutreeko-achievements";
+
+// Browser storage entry keeping the choices of the player
+const SETTINGS_KEY: &str = "neutreeko-settings";
+
+// MinMax depth of the heat map, shallow so it follows the game without delay
+const HEAT_MAP_DEPTH: usize = 3;
+
+// MCTS difficulty of the best move and threat arrows, MinMax alone scores every unproven move the same
+const ARROW_DEPTH: usize = 3;
+
+// MinMax depth of the evaluation graph, every ply is searched once in the background
+const EVALUATION_DEPTH: usize = 3;
+
+// Replay speeds of a finished game, with the time each move stays on the board in milliseconds
+const REPLAY_SPEEDS: [(&str, u64); 4] = [("Slow", 2000), ("Normal", 1000), ("Fast", 500), ("Very fast", 250)];
+// Contempt of the AI for draws by repetition, as it plays on or repeats against a stronger player
+const CONTEMPT_LEVELS: [(&str, f32); 3] = [("Seeks draws", -0.3), ("Neutral", 0.0), ("Avoids draws", 0.3)];
+
+// Lines of the engine log panel, the oldest are dropped past this
+const ENGINE_LOG_LINES: usize = 300;
+
+// Size of the evaluation graph, in pixels
+const GRAPH_WIDTH: f64 = 400.0;
+const GRAPH_HEIGHT: f64 = 100.0;
+
+// AI types are 0 for none, then the engines of ENGINE_NAMES, then the automatic choice and the model of the player
+const AUTOMATIC_AI_TYPE: usize = ENGINE_NAMES.len() + 1;
+const HUMAN_LIKE_AI_TYPE: usize = AUTOMATIC_AI_TYPE + 1;
+
+// Strength level selected at start, Club
+const DEFAULT_STRENGTH_LEVEL: usize = 6;
+
+// What colours the squares under the pawns
+#[derive(Clone, Copy, PartialEq)]
+enum HeatMapSource {
+    Off,
+    Policy, // network policy mass of the moves landing on the square
+    MinMax, // MinMax scores of the moves landing on the square
+}
+
+impl HeatMapSource {
+    const ALL: [HeatMapSource; 3] = [HeatMapSource::Off, HeatMapSource::Policy, HeatMapSource::MinMax];
+
+    fn label(&self) -> &'static str {
+        match self {
+            HeatMapSource::Off => "Off",
+            HeatMapSource::Policy => "Network policy",
+            HeatMapSource::MinMax => "MinMax scores",
+        }
+    }
+
+    fn engine_name(&self) -> Option<&'static str> {
+        match self {
+            HeatMapSource::Off => None,
+            HeatMapSource::Policy => Some("ann"),
+            HeatMapSource::MinMax => Some("minmax"),
+        }
+    }
+}
+
+// How the next games start
+#[derive(Clone, Copy, PartialEq)]
+enum GameMode {
+    Standard, // from the start position selected
+    RandomStart, // from a random position no side wins soon
+    MirrorMatch, // a random start played twice against the AI, once with each colour
+}
+
+impl GameMode {
+    const ALL: [GameMode; 3] = [GameMode::Standard, GameMode::RandomStart, GameMode::MirrorMatch];
+
+    fn label(&self) -> &'static str {
+        match self {
+            GameMode::Standard => "Standard start",
+            GameMode::RandomStart => "Random balanced start",
+            GameMode::MirrorMatch => "Mirror match (both sides of a random start)",
+        }
+    }
+}
+
+// Choices of the player kept across visits, one name=value line each. Unknown names are skipped and missing ones keep
+// their default, so older and newer pages read each other's settings
+#[derive(Clone, Debug, PartialEq)]
+struct Settings {
+    difficulty: usize,
+    ai_type: usize,
+    use_book: bool,
+    use_gpu: bool,
+    model: String,
+    swap_rule: bool,
+    show_coordinates: bool,
+    show_arrows: bool,
+    replay_speed: usize,
+    contempt: usize,
+}
+
+impl Settings {
+    fn to_text(&self) -> String {
+        [
+            format!("difficulty={}", self.difficulty),
+            format!("ai_type={}", self.ai_type),
+            format!("use_book={}", self.use_book),
+            format!("use_gpu={}", self.use_gpu),
+            format!("model={}", self.model),
+            format!("swap_rule={}", self.swap_rule),
+            format!("show_coordinates={}", self.show_coordinates),
+            format!("show_arrows={}", self.show_arrows),
+            format!("replay_speed={}", self.replay_speed),
+            format!("contempt={}", self.contempt),
+        ].join("\n")
+    }
+
+    // Values that do not parse keep the current ones
+    fn read_text(&mut self, text: &str) {
+        for (name, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            match name.trim() {
+                "difficulty" => self.difficulty = value.parse().unwrap_or(self.difficulty),
+                "ai_type" => self.ai_type = value.parse().unwrap_or(self.ai_type),
+                "use_book" => self.use_book = value.parse().unwrap_or(self.use_book),
+                "use_gpu" => self.use_gpu = value.parse().unwrap_or(self.use_gpu),
+                "model" => self.model = value.to_string(),
+                "swap_rule" => self.swap_rule = value.parse().unwrap_or(self.swap_rule),
+                "show_coordinates" => self.show_coordinates = value.parse().unwrap_or(self.show_coordinates),
+                "show_arrows" => self.show_arrows = value.parse().unwrap_or(self.show_arrows),
+                "replay_speed" => self.replay_speed = value.parse().unwrap_or(self.replay_speed),
+                "contempt" => self.contempt = value.parse().unwrap_or(self.contempt),
+                _ => (),
+            }
+        }
+    }
+}
+
+// What earlier visits kept in the browser storage, read once when the page opens
+pub struct StoredData {
+    settings: Option<String>,
+    stats_cache: Option<StatsCache>,
+    human_games: Option<GameStore>, // None unless the player opted in
+    saved_games: Option<SavedGames>,
+    puzzle_streak: Option<PuzzleStreak>,
+    profile: Option<UserProfile>,
+    achievements: Option<Achievements>,
+    uploaded_models: Vec<String>,
+}
+
+impl StoredData {
+    async fn load() -> Self {
+        Self {
+            settings: load_text::<WasmPlatform>(SETTINGS_KEY).await,
+            stats_cache: StatsCache::load_storage::<WasmPlatform>(STATS_CACHE_KEY).await,
+            human_games: GameStore::load_storage::<WasmPlatform>(HUMAN_GAMES_KEY).await,
+            saved_games: SavedGames::load_storage::<WasmPlatform>(SAVED_GAMES_KEY).await,
+            puzzle_streak: PuzzleStreak::load_storage::<WasmPlatform>(PUZZLE_STREAK_KEY).await,
+            profile: UserProfile::load_storage::<WasmPlatform>(PROFILE_KEY).await,
+            achievements: Achievements::load_storage::<WasmPlatform>(ACHIEVEMENTS_KEY).await,
+            uploaded_models: restore_uploaded_models::<WasmPlatform>().await,
+        }
+    }
+}
+
 pub enum Msg {
+    StorageLoaded(Box<StoredData>),
+    SavedGamesStored(bool),
     PawnClick(usize),
     DirectionClick(Direction),
+    KeyDown(String, bool), // key name and whether Shift was held, on the focused board
+    PawnSwiped(usize, Direction),
     Restart,
     CreateAi(Color),
     AiShouldPlay,
-    AiMoveReady(Option<(usize, Direction)>),
+    AiMoveReady(Option<(usize, Direction)>, Option<String>, Option<String>), // with the reasons for the move and the proven result after it
+    AiProgress(SearchProgress),
+    SearchTreeReady(Option<SearchTree>),
+    TelemetryReady(Telemetry),
+    ToggleSearchTree,
+    SetHeatMap(usize),
+    HeatMapReady(Board, Vec<f32>),
+    EvaluationReady(usize, Board, f32), // ply, board after it and evaluation for the player who moved
+    ViewPly(Option<usize>), // plies played on the board shown, None for the game being played
+    StartReplay,
+    ReplayStep(usize), // replay it belongs to, a step of a stopped replay is dropped
+    StopReplay,
+    SetReplaySpeed(usize),
+    SetContempt(usize),
     SetDifficulty(usize),
     SetAiType(usize),
+    SetUseBook(bool),
+    SetUseGpu(bool),
+    GpuDetected(bool),
+    SetStartPosition(usize),
+    SetGameMode(usize),
+    RandomStartReady(Board),
+    MirrorSecondGame,
+    SetSwapRule(bool),
+    SetShowCoordinates(bool),
+    SetShowArrows(bool),
+    ArrowAnalysisReady(Board, Analysis),
+    ToggleDrawArrows,
+    ArrowSquareClick(Position), // first click the start of the arrow, second its end
+    ClearArrows,
+    SetLogLevel(usize), // 0 for no log, then the levels of LogLevel::ALL
+    ToggleEngineLog,
+    SwapColors,
+    AiSwapped,
+    StopThinking,
+    ToggleExport,
+    ToggleSavedGames,
+    SetSaveName(String),
+    SaveGame,
+    LoadSavedGame(String),
+    DeleteSavedGame(String),
+    ToggleSavedExport,
+    ReviewGame,
+    ReviewReady(Option<GameReview>),
+    ModelLoaded(Color, Result<(), String>),
+    SetModel(String),
+    UploadModel(File),
+    ModelUploaded(Result<String, String>),
+    SetKeepGames(bool),
+    ToggleHumanGames,
+    ToggleProfile,
+    ResetProfile,
+    UploadHumanModel(File),
+    HumanModelUploaded(Result<(), String>),
+    ToggleEditor,
+    EditorSquareClick(Position),
+    EditorSetNextPlayer(Color),
+    EditorClear,
+    EditorStartGame,
+    EditorAnalyze,
+    PositionAnalyzed(Board, Analysis),
+    StartEndgame,
+    RetryEndgame,
+    LeaveEndgame,
+    ShowEndgameSolution,
+    EndgameSolved(Board, Vec<String>), // best line from the drill position
+    EndgameCheck,
+    EndgameDefence(Board, SearchResult), // search of the defender after the move of the player
+    StartDailyPuzzle,
+    DailyPuzzleReady(i64, EndgameDrill), // day and its puzzle
+}
+
+type CpuBackend = NdArray<f32, i32>;
+
+type UiEngine<B> = Calibrated<WithBook<AnyAI<B, WasmPlatform>, WasmPlatform>, WasmPlatform>;
+
+// The engine lives in a slot so it keeps its tree between moves, the slot is empty while a task searches or ponders with it
+type EngineSlot<B> = Rc<RefCell<Option<UiEngine<B>>>>;
+
+// Backend running the network of the engine, the search itself stays on the CPU
+enum BackendSlot {
+    Cpu(EngineSlot<CpuBackend>),
+    #[cfg(feature = "webgpu")]
+    Gpu(EngineSlot<WebGpuBackend>),
+}
+
+struct AiPlayer {
+    color: Color,
+    slot: BackendSlot,
+    opponent: (String, String), // engine and strength level names, the statistics count the game under them
+}
+
+// Waits until a pondering task has handed the engine back
+async fn take_engine<B: Backend>(slot: &EngineSlot<B>) -> UiEngine<B> {
+    loop {
+        if let Some(ai) = slot.borrow_mut().take() {
+            return ai;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
 }
 
-enum AiType {
-    None,
-    MinMax(Color),
-    MCTS(Color),
-    ANNSolo(Color),
-    AlphaZeutreeko(Color),
+// Move of the AI, or its swap, then pondering on the reply until the player moves
+async fn play_ai_turn<B: Backend>(slot: EngineSlot<B>, board: Board, history: Vec<Board>, color: Color, consider_swap: bool,
+    contempt: f32, verbose: bool, observer: UiSearchObserver, ponder_handle: SearchHandle, link: yew::html::Scope<App>) {
+    // Small delay to allow browser to render player's move first
+    sleep(Duration::from_millis(50)).await;
+    let mut ai = take_engine(&slot).await;
+    // the colors may have been swapped since the engine was created
+    if *ai.color() != color {
+        ai.set_color(color.clone());
+    }
+    ai.set_contempt(contempt);
+    ai.set_game_history(&history);
+    if consider_swap && ai.wants_swap_async(&board, Some(&observer)).await {
+        ai.set_color(color.other_color());
+        slot.replace(Some(ai));
+        link.send_message(Msg::AiSwapped);
+        return;
+    }
+    let result = ai.give_all_options_async(&board, verbose, Some(&observer)).await;
+    let ai_move = ai.select_move(&result.moves, verbose);
+    let explanation = ai_move.as_ref()
+        .and_then(|(pawn_index, direction)| explain_move(&board, &Move::new(*pawn_index, direction.clone()), &result))
+        .map(|explanation| explanation.reasons.join(", "));
+    let proven_text = ai_move.as_ref().and_then(|chosen| proven_after_move(&board, chosen, &result));
+    link.send_message(Msg::SearchTreeReady(ai.search_tree(1)));
+    link.send_message(Msg::TelemetryReady(ai.telemetry()));
+    link.send_message(Msg::AiMoveReady(ai_move.clone(), explanation, proven_text));
+    if let Some((pawn_index, direction)) = ai_move {
+        let mut next_board = board.clone();
+        next_board.move_pawn_until_blocked(pawn_index, &direction);
+        ai.ponder_async(&next_board, &ponder_handle).await;
+    }
+    slot.replace(Some(ai));
+}
+
+// "Green wins in 6: ..." for the position after the AI move, when the search proved its result and the move follows the line
+fn proven_after_move(board: &Board, chosen: &(usize, Direction), result: &SearchResult) -> Option<String> {
+    let proven = result.proven.as_ref()?;
+    if result.pv.first() != Some(chosen) {
+        return None;
+    }
+    let after = SearchResult {
+        pv: result.pv[1..].to_vec(),
+        proven: Some(proven.after_best_move()),
+        ..Default::default()
+    };
+    after.proven_text(&board.child(&Move::new(chosen.0, chosen.1.clone())))
+}
+
+// Pair of games of a mirror match, the second from the same start with the colours exchanged
+struct MirrorMatch {
+    start: Board,
+    results: Vec<(Color, GameResult)>, // colour of the player and result of each finished game
+}
+
+impl MirrorMatch {
+    // Points of the player, a draw counting half
+    fn player_score(&self) -> f32 {
+        self.results.iter()
+            .map(|(color, result)| match result.winner() {
+                Some(winner) if winner == *color => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            })
+            .sum()
+    }
+}
+
+// Drill of the endgame trainer: the player attacks and MinMax defends, the win has to come as fast as the optimal line
+struct EndgameSession {
+    drill: EndgameDrill,
+    attacker: Color,
+    checking: bool, // the defence is being searched
+    progress: Option<DrillProgress>, // after the last move of the player
+    solution: Vec<String>,
+    show_solution: bool,
+    daily: Option<i64>, // day of the daily puzzle, None for a drill of the trainer
 }
 
 pub struct App {
-    board: Board,
+    game: Game,
     state: Rc<AppState>,
-    ai: AiType,
+    ai: Option<AiPlayer>,
     ai_thinking: bool,
+    search_handle: Option<SearchHandle>,
+    ponder_handle: Option<SearchHandle>,
+    ai_progress: Option<SearchProgress>,
     selected_pawn: Option<usize>,
+    keyboard_direction: Option<Direction>, // chosen with Shift and the arrows, played with Enter
+    announcement: String, // read by screen readers when it changes
     difficulty_selected: usize,
     ai_type_selected: usize,
+    use_book: bool,
+    use_gpu: bool, // network on the GPU when the browser has WebGPU, see the webgpu feature
+    gpu_available: bool, // stays false in builds without the webgpu feature
+    book: Arc<OpeningBook>,
+    record: GameRecord,
+    move_explanations: HashMap<usize, String>, // reasons for the AI moves, by ply, shown on the move list
+    proven_outcome: Option<String>, // distance to the end proven by the last AI search, until the next move
+    show_export: bool,
+    reviewing: bool,
+    review: Option<GameReview>,
+    model_selected: String,
+    model_loading: bool,
+    model_error: Option<String>,
+    move_error: Option<String>,
+    stats_cache: SharedStatsCache,
+    human_games: Option<GameStore>, // None unless the player opted in to keep their games
+    show_human_games: bool,
+    saved_games: SavedGames,
+    show_saved_games: bool,
+    save_name: String,
+    saved_games_error: Option<String>,
+    show_saved_export: bool,
+    start_position: StartPosition,
+    game_mode: GameMode,
+    random_start_loading: bool,
+    mirror_match: Option<MirrorMatch>, // pair being played in the mirror match mode
+    swap_rule: bool,
+    show_coordinates: bool, // column letters and row numbers around the board, as in the notation
+    show_arrows: bool, // best move and threats of the engine drawn on the board
+    arrow_analysis: Option<(Board, Analysis)>, // of the board the arrows were requested for, None until the search ends
+    arrow_board: Option<Board>,
+    drawing_arrows: bool, // square clicks draw arrows instead of moving pawns
+    arrow_start: Option<Position>,
+    user_arrows: Vec<Arrow>, // drawn on the current board, written to the record with the next move
+    log_level: Option<LogLevel>, // None when searches are not verbose
+    engine_log: Vec<(LogLevel, String)>,
+    show_engine_log: bool,
+    show_search_tree: bool,
+    search_tree: Option<SearchTree>, // root children of the last AI search
+    telemetry: Option<Telemetry>, // of the AI engine, since the start of the game
+    heat_map_source: HeatMapSource,
+    heat_map_board: Option<Board>, // board the heat map was requested for
+    heat_map: Vec<f32>,
+    evaluating: Option<Board>, // board searched for the evaluation graph
+    viewed_ply: Option<usize>, // set while a past position of the game is shown
+    replaying: bool, // the finished game is played back through viewed_ply
+    replay_number: usize, // counts the replays started, so the steps of an earlier one are dropped
+    replay_speed: usize, // index in REPLAY_SPEEDS
+    contempt: usize, // index in CONTEMPT_LEVELS
+    editor: Option<Board>, // position being set up, None outside the editor
+    editor_error: Option<String>,
+    analyzing: bool,
+    position_analysis: Option<(Board, Analysis)>,
+    endgame: Option<EndgameSession>, // None outside the endgame trainer
+    puzzle_streak: PuzzleStreak,
+    profile: UserProfile,
+    show_profile: bool,
+    achievements: Achievements,
+    new_achievements: Vec<Achievement>, // unlocked by the last game or puzzle, shown until the next one starts
+    daily_puzzle_loading: bool,
+    stored_settings: Option<Settings>, // last settings written, None until the stored ones are read
 }
 
 impl App {
+    fn selected_engine_name(&self) -> Option<&'static str> {
+        match self.ai_type_selected {
+            0 => None,
+            // automatic: the strength level picks the engine
+            AUTOMATIC_AI_TYPE => Some(strength_level(self.difficulty_selected).engine.engine_name()),
+            HUMAN_LIKE_AI_TYPE => Some("ann"),
+            ai_type => match ENGINE_NAMES.get(ai_type - 1) {
+                Some(engine_name) => Some(engine_name),
+                None => panic!("AI Type not implemented!"),
+            },
+        }
+    }
+
     fn create_ai(&mut self, color:Color) {
-        if self.ai_type_selected == 0 {
-            ()
-        } else if self.ai_type_selected == 1 {
-            self.ai = AiType::MinMax(color);
-        } else if self.ai_type_selected == 2 {
-            self.ai = AiType::MCTS(color);
+        let level = strength_level(self.difficulty_selected);
+        let Some(engine_name) = self.selected_engine_name() else {
+            return;
+        };
+        let human_like = self.ai_type_selected == HUMAN_LIKE_AI_TYPE;
+        let engine_label = if human_like {
+            "ann (plays like you)".to_string()
+        } else if AnyAI::<CpuBackend, WasmPlatform>::uses_network(engine_name) {
+            format!("{} ({}, model {})", engine_name, level.name, self.model_selected)
+        } else {
+            format!("{} ({})", engine_name, level.name)
+        };
+        let opponent = (if human_like { "human-like" } else { engine_name }.to_string(), level.name.to_string());
+        match color {
+            Color::Green => (self.record.green, self.record.yellow) = (engine_label, HUMAN_PLAYER.to_string()),
+            Color::Yellow => (self.record.green, self.record.yellow) = (HUMAN_PLAYER.to_string(), engine_label),
+        }
+        #[cfg(feature = "webgpu")]
+        if self.use_gpu && self.gpu_available {
+            let slot = BackendSlot::Gpu(Rc::new(RefCell::new(Some(self.build_engine(color.clone(), engine_name, human_like)))));
+            self.ai = Some(AiPlayer { color, slot, opponent });
+            return;
         }
-        else if self.ai_type_selected == 3 {
-            self.ai = AiType::ANNSolo(color);
+        let slot = BackendSlot::Cpu(Rc::new(RefCell::new(Some(self.build_engine(color.clone(), engine_name, human_like)))));
+        self.ai = Some(AiPlayer { color, slot, opponent });
+    }
+
+    fn build_engine<B: Backend>(&self, color: Color, engine_name: &str, human_like: bool) -> UiEngine<B> {
+        let level = strength_level(self.difficulty_selected);
+        let engine = if human_like {
+            AnyAI::ANNSolo(ANNSolo::human_like(color))
+        } else {
+            AnyAI::from_name_with_model(engine_name, color, level.difficulty, &self.model_selected).unwrap()
+        };
+        let mut engine = WithBook::wrap(engine, self.book.clone());
+        // book moves are not the player's, the model alone should choose
+        engine.enabled = self.use_book && !human_like;
+        engine.set_stats_cache(Some(self.stats_cache.clone()));
+        Calibrated::wrap(engine, level)
+    }
+
+    // Plays a move on the board and writes it to the game record
+    fn play_move(&mut self, pawn_index: usize, direction: &Direction) -> Result<MoveOutcome, MoveError> {
+        let color = self.game.board.pawns.get(pawn_index).map(|pawn| pawn.color.clone());
+        let played_from = self.game.board.clone();
+        let outcome = self.game.play(&Move { pawn_index, direction: direction.clone() })?;
+        let notation = format!("{}-{}", outcome.from.to_notation(), outcome.to.to_notation());
+        self.announcement = format!("{:?} pawn {} moved to {}", color.unwrap_or(Color::Green), outcome.from.to_notation(), outcome.to.to_notation());
+        if let Some(winner) = &outcome.winner {
+            self.announcement.push_str(&format!(". {:?} wins", winner));
+        }
+        self.keyboard_direction = None;
+        self.proven_outcome = None;
+        self.viewed_ply = None;
+        let mut move_record = MoveRecord::new(notation);
+        move_record.arrows = self.board_arrows(&played_from);
+        self.user_arrows.clear();
+        self.arrow_start = None;
+        self.record.moves.push(move_record);
+        self.record.finish(&self.game.board);
+        if outcome.winner.is_some() {
+            if let (Some(mirror_match), Some(ai), Some(result)) = (&mut self.mirror_match, &self.ai, &self.record.result) {
+                if mirror_match.results.len() < 2 {
+                    mirror_match.results.push((ai.color.other_color(), result.clone()));
+                }
+            }
+            let stats_cache = self.stats_cache.lock().unwrap().clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                stats_cache.save_storage::<WasmPlatform>(STATS_CACHE_KEY).await;
+            });
+            if let Some(ai) = &self.ai {
+                if self.profile.record_game(&self.record, &ai.opponent.0, &ai.opponent.1) {
+                    let profile = self.profile.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        profile.save_storage::<WasmPlatform>(PROFILE_KEY).await;
+                    });
+                }
+            }
+            let level = self.ai.as_ref().and_then(|ai| STRENGTH_LEVELS.iter().find(|level| level.name == ai.opponent.1));
+            if let Some(level) = level {
+                let unlocked = self.achievements.record_game(&self.record, level, day_number(js_sys::Date::now()));
+                self.unlock_achievements(unlocked);
+            }
+            if let Some(human_games) = &mut self.human_games {
+                if human_games.add(self.record.clone()) {
+                    let human_games = human_games.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        human_games.save_storage::<WasmPlatform>(HUMAN_GAMES_KEY).await;
+                    });
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    // Announces the achievements just unlocked and stores them
+    fn unlock_achievements(&mut self, unlocked: Vec<Achievement>) {
+        for achievement in unlocked.iter() {
+            self.announcement.push_str(&format!(". Achievement unlocked: {}", achievement.label()));
+        }
+        self.new_achievements.extend(unlocked);
+        let achievements = self.achievements.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            achievements.save_storage::<WasmPlatform>(ACHIEVEMENTS_KEY).await;
+        });
+    }
+
+    // Stops the AI and starts over from this position
+    fn new_game(&mut self, start: Board) {
+        if let Some(handle) = self.search_handle.take() {
+            handle.cancel();
+        }
+        if let Some(ponder_handle) = self.ponder_handle.take() {
+            ponder_handle.cancel();
+        }
+        self.game = Game::new(&start);
+        self.game.swap_rule = self.swap_rule;
+        self.record = GameRecord::new(&self.game.board);
+        self.move_explanations.clear();
+        self.user_arrows.clear();
+        self.arrow_start = None;
+        self.evaluating = None;
+        self.viewed_ply = None;
+        self.proven_outcome = None;
+        self.review = None;
+        self.replaying = false;
+        self.search_tree = None;
+        self.telemetry = None;
+        self.move_error = None;
+        self.selected_pawn = None;
+        self.keyboard_direction = None;
+        self.announcement = String::new();
+        self.ai = None;
+        self.ai_thinking = false;
+        self.ai_progress = None;
+        self.endgame = None;
+        self.mirror_match = None;
+        self.new_achievements.clear();
+    }
+
+    // New game from the chosen start, a random start is searched first and the game begins once it is found
+    fn restart(&mut self, ctx: &Context<Self>) {
+        if self.game_mode == GameMode::Standard {
+            return self.new_game(Board::from_start(&self.start_position));
+        }
+        if self.random_start_loading {
+            return;
+        }
+        self.random_start_loading = true;
+        Self::run_in_background(ctx, || async {
+            balanced_start_async::<WasmPlatform>(&BalancedStartConfig::default()).await
+        }, Msg::RandomStartReady);
+    }
+
+    // Work spawned on the platform, its result comes back as a message
+    fn run_in_background<F, T, R>(ctx: &Context<Self>, task: F, message: impl FnOnce(R) -> Msg + 'static)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Future<Output = R> + 'static,
+        R: Send + 'static,
+    {
+        let result = spawn_with_result::<WasmPlatform, _, _, _>(task);
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(result) = result.recv().await {
+                link.send_message(message(result));
+            }
+        });
+    }
+
+    // Sets up the drill as a new game without AI, its solution is searched in the background
+    fn start_endgame(&mut self, ctx: &Context<Self>, drill: EndgameDrill) {
+        self.editor = None;
+        self.new_game(drill.board.clone());
+        self.endgame = Some(EndgameSession {
+            attacker: drill.board.next_player.clone().unwrap_or(Color::Green),
+            drill: drill.clone(),
+            checking: false,
+            progress: None,
+            solution: vec![],
+            show_solution: false,
+            daily: None,
+        });
+        Self::run_in_background(ctx, move || async move {
+            let result = solve_endgame_async::<WasmPlatform>(&drill.board).await;
+            (drill.board.clone(), solution_line(&drill, &result))
+        }, |(board, line)| Msg::EndgameSolved(board, line));
+    }
+
+    // The player only moves the attacking pawns, and not while their last move is checked or once the drill is over
+    fn endgame_blocks_input(&self) -> bool {
+        self.endgame.as_ref().is_some_and(|session| {
+            session.checking
+                || self.game.board.next_player.as_ref() != Some(&session.attacker)
+                || session.progress.as_ref().is_some_and(|progress| *progress != DrillProgress::OnTrack)
+        })
+    }
+
+    // Position of the editor with green pawns first, as in boards read from FEN; None with the reason shown if it cannot be played
+    fn validated_setup(&mut self) -> Option<Board> {
+        let mut board = self.editor.clone()?;
+        let (mut pawns, mut yellow_pawns): (Vec<Pawn>, Vec<Pawn>) = board.pawns.into_iter().partition(|pawn| pawn.color == Color::Green);
+        pawns.append(&mut yellow_pawns);
+        board.pawns = pawns;
+        match board.validate_setup() {
+            Ok(()) => Some(board),
+            Err(error) => {
+                self.editor_error = Some(format!("Invalid position: {}", error));
+                None
+            }
+        }
+    }
+
+    // Computes the heat map of the current board in the background, HeatMapReady brings it back
+    fn request_heat_map(&mut self, ctx: &Context<Self>) {
+        let board = self.game.board.clone();
+        self.heat_map_board = Some(board.clone());
+        self.heat_map.clear();
+        let (Some(engine_name), Some(color)) = (self.heat_map_source.engine_name(), board.next_player.clone()) else {
+            return;
+        };
+        let model_id = self.model_selected.clone();
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if AnyAI::<CpuBackend, WasmPlatform>::uses_network(engine_name) && !is_model_loaded(&model_id)
+                && fetch_model(&model_id).await.is_err() {
+                return;
+            }
+            let Some(mut engine) = AnyAI::<CpuBackend, WasmPlatform>::from_name_with_model(engine_name, color, HEAT_MAP_DEPTH, &model_id) else {
+                return;
+            };
+            let result = engine.give_all_options_async(&board, false, None).await;
+            let heat_map = landing_heat_map(&board, &result.moves);
+            link.send_message(Msg::HeatMapReady(board, heat_map));
+        });
+    }
+
+    // Searches the current board for the arrows in the background, ArrowAnalysisReady brings it back
+    fn request_arrow_analysis(&mut self, ctx: &Context<Self>) {
+        let board = self.game.board.clone();
+        self.arrow_board = Some(board.clone());
+        self.arrow_analysis = None;
+        let Some(color) = board.next_player.clone() else {
+            return;
+        };
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let Some(mut engine) = AnyAI::<CpuBackend, WasmPlatform>::from_name("mcts", color, ARROW_DEPTH) else {
+                return;
+            };
+            let analysis = analyze_async(&mut engine, &board).await;
+            link.send_message(Msg::ArrowAnalysisReady(board, analysis));
+        });
+    }
+
+    // Arrows of the board shown: those kept in the record for a past position, otherwise the drawn ones
+    // and, when they are on, those of the engine
+    fn board_arrows(&self, board: &Board) -> Vec<Arrow> {
+        if let Some(ply) = self.viewed_ply {
+            return self.record.moves.get(ply).map(|move_record| move_record.arrows.clone()).unwrap_or_default();
+        }
+        let mut arrows = self.user_arrows.clone();
+        if self.show_arrows {
+            let analysis = self.arrow_analysis.iter().chain(self.position_analysis.iter())
+                .find(|(analyzed, _)| analyzed == board);
+            if let Some((analyzed, analysis)) = analysis {
+                arrows.extend(analysis.arrows(analyzed));
+            }
+        }
+        arrows
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            difficulty: self.difficulty_selected,
+            ai_type: self.ai_type_selected,
+            use_book: self.use_book,
+            use_gpu: self.use_gpu,
+            model: self.model_selected.clone(),
+            swap_rule: self.swap_rule,
+            show_coordinates: self.show_coordinates,
+            show_arrows: self.show_arrows,
+            replay_speed: self.replay_speed,
+            contempt: self.contempt,
+        }
+    }
+
+    fn apply_settings(&mut self, settings: &Settings) {
+        self.difficulty_selected = settings.difficulty.clamp(1, STRENGTH_LEVELS.len());
+        self.ai_type_selected = settings.ai_type.min(HUMAN_LIKE_AI_TYPE);
+        self.use_book = settings.use_book;
+        self.use_gpu = settings.use_gpu;
+        self.model_selected = settings.model.clone();
+        self.swap_rule = settings.swap_rule;
+        self.game.swap_rule = settings.swap_rule;
+        self.show_coordinates = settings.show_coordinates;
+        self.show_arrows = settings.show_arrows;
+        self.replay_speed = settings.replay_speed.min(REPLAY_SPEEDS.len() - 1);
+        self.contempt = settings.contempt.min(CONTEMPT_LEVELS.len() - 1);
+    }
+
+    // Writes the settings in the background when they changed, never before the stored ones were read
+    fn store_settings(&mut self) {
+        let settings = self.settings();
+        if self.stored_settings.as_ref().is_none_or(|stored| *stored == settings) {
+            return;
+        }
+        let text = settings.to_text();
+        self.stored_settings = Some(settings);
+        wasm_bindgen_futures::spawn_local(async move {
+            save_text::<WasmPlatform>(SETTINGS_KEY, &text).await;
+        });
+    }
+
+    // SavedGamesStored reports whether the write went through
+    fn store_saved_games(&self, ctx: &Context<Self>) {
+        let saved_games = self.saved_games.clone();
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            link.send_message(Msg::SavedGamesStored(saved_games.save_storage::<WasmPlatform>(SAVED_GAMES_KEY).await));
+        });
+    }
+
+    // Next position of the replay after the delay of the chosen speed
+    fn schedule_replay_step(&self, ctx: &Context<Self>) {
+        let replay_number = self.replay_number;
+        let delay = REPLAY_SPEEDS[self.replay_speed].1;
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            sleep(Duration::from_millis(delay)).await;
+            link.send_message(Msg::ReplayStep(replay_number));
+        });
+    }
+
+    // Board shown with its arrows as a standalone SVG, in a data URL the share link downloads
+    fn board_image_url(&self) -> String {
+        let (board, arrows) = match (&self.editor, self.viewed_board()) {
+            (Some(board), _) => (board.clone(), vec![]),
+            (None, Some(board)) => {
+                let arrows = self.board_arrows(&board);
+                (board, arrows)
+            }
+            (None, None) => (self.game.board.clone(), self.board_arrows(&self.game.board)),
+        };
+        let options = RenderOptions { coordinates: self.show_coordinates, arrows, caption: Some(board.to_fen()), ..RenderOptions::default() };
+        let svg = board_to_svg(&board, &options);
+        format!("data:image/svg+xml;charset=utf-8,{}", String::from(js_sys::encode_uri_component(&svg)))
+    }
+
+    // Searches the first ply of the record without an evaluation in the background, EvaluationReady brings it back.
+    // Plies ending the game need no search
+    fn request_evaluation(&mut self, ctx: &Context<Self>) {
+        let Some(boards) = self.record.boards() else {
+            return;
+        };
+        let Some(ply) = self.record.moves.iter().position(|move_record| move_record.evaluation.is_none()) else {
+            return;
+        };
+        let board = boards[ply + 1].clone();
+        if board.winner().is_some() {
+            self.record.moves[ply].evaluation = Some(1.0);
+            return;
         }
-        else if self.ai_type_selected == 4 {
-            self.ai = AiType::AlphaZeutreeko(color);
+        let Some(color) = board.next_player.clone() else {
+            return;
+        };
+        if self.evaluating.as_ref() == Some(&board) {
+            return;
         }
-        else {
-            panic!("AI Type not implemented!")
+        self.evaluating = Some(board.clone());
+        Self::run_in_background(ctx, move || async move {
+            let mut engine: MinMax<WasmPlatform> = MinMax::new(color, EVALUATION_DEPTH);
+            let result = engine.give_all_options_async(&board, false, None).await;
+            (board, -result.value)
+        }, move |(board, value)| Msg::EvaluationReady(ply, board, value));
+    }
+
+    // Lines the engines logged since the last update, above the chosen level they are dropped
+    fn collect_engine_log(&mut self) {
+        let lines = take_engine_log();
+        let Some(log_level) = self.log_level else {
+            return;
+        };
+        self.engine_log.extend(lines.into_iter().filter(|(level, _)| *level <= log_level));
+        if self.engine_log.len() > ENGINE_LOG_LINES {
+            self.engine_log.drain(..self.engine_log.len() - ENGINE_LOG_LINES);
         }
     }
+
+    // Past position of the game being looked at from the evaluation graph
+    fn viewed_board(&self) -> Option<Board> {
+        self.record.boards()?.get(self.viewed_ply?).cloned()
+    }
+
+    // Swap rule offered to the player when the AI made the first move
+    fn player_may_swap(&self) -> bool {
+        self.game.can_swap() && !self.ai_thinking
+            && self.ai.as_ref().is_some_and(|ai_player| Some(ai_player.color.clone()) != self.game.board.next_player)
+    }
+
+    // Arrows go through the pawns of the player to move in reading order, Shift and an arrow choose a direction
+    // (two of them make a diagonal, e.g. Shift+Up then Shift+Left), Enter plays the move and Escape cancels
+    fn handle_key(&mut self, ctx: &Context<Self>, key: &str, shift: bool) {
+        let Some(color) = self.game.board.next_player.clone() else {
+            return;
+        };
+        let ai_turn = self.ai.as_ref().is_some_and(|ai_player| ai_player.color == color);
+        if self.editor.is_some() || self.ai_thinking || ai_turn || self.endgame_blocks_input() || self.game.board.winner().is_some() {
+            return;
+        }
+        let arrow = match key {
+            "ArrowUp" => Some(Direction::Up),
+            "ArrowDown" => Some(Direction::Down),
+            "ArrowLeft" => Some(Direction::Left),
+            "ArrowRight" => Some(Direction::Right),
+            _ => None,
+        };
+        match (key, arrow) {
+            (_, Some(arrow)) if shift => {
+                let Some(pawn_index) = self.selected_pawn else {
+                    self.announcement = "Select a pawn with the arrow keys first".to_string();
+                    return;
+                };
+                let direction = combined_direction(self.keyboard_direction.as_ref(), arrow);
+                // kept even when blocked, the next arrow may make it a diagonal
+                self.announcement = match self.game.board.move_to_notation(&Move::new(pawn_index, direction.clone())) {
+                    Some(notation) => format!("{}, Enter to play", notation.replace('-', " to ")),
+                    None => format!("Pawn {} cannot move {:?}", self.game.board.pawns[pawn_index].position.to_notation(), direction),
+                };
+                self.keyboard_direction = Some(direction);
+            }
+            (_, Some(arrow)) => {
+                let mut own_pawns: Vec<usize> = (0..self.game.board.pawns.len())
+                    .filter(|pawn_index| self.game.board.pawns[*pawn_index].color == color)
+                    .collect();
+                own_pawns.sort_by_key(|pawn_index| {
+                    let position = &self.game.board.pawns[*pawn_index].position;
+                    (position.row, position.column)
+                });
+                if own_pawns.is_empty() {
+                    return;
+                }
+                let current = self.selected_pawn.and_then(|selected| own_pawns.iter().position(|pawn_index| *pawn_index == selected));
+                let next = match (current, arrow) {
+                    (None, _) => 0,
+                    (Some(current), Direction::Right | Direction::Down) => (current + 1) % own_pawns.len(),
+                    (Some(current), _) => (current + own_pawns.len() - 1) % own_pawns.len(),
+                };
+                let pawn_index = own_pawns[next];
+                self.selected_pawn = Some(pawn_index);
+                self.keyboard_direction = None;
+                self.announcement = format!("{:?} pawn {} selected", color, self.game.board.pawns[pawn_index].position.to_notation());
+            }
+            ("Enter", None) => match (self.selected_pawn, self.keyboard_direction.clone()) {
+                (Some(_), Some(direction)) => ctx.link().send_message(Msg::DirectionClick(direction)),
+                _ => self.announcement = "Choose a direction with Shift and the arrow keys".to_string(),
+            },
+            ("Escape", None) => {
+                self.selected_pawn = None;
+                self.keyboard_direction = None;
+                self.announcement = "Selection cleared".to_string();
+            }
+            _ => (),
+        }
+    }
+
+    // The board stays as it is, the players exchange their colors
+    fn swap_colors(&mut self) {
+        if !self.game.swap() {
+            return;
+        }
+        if let Some(ai_player) = &mut self.ai {
+            ai_player.color = ai_player.color.other_color();
+        }
+        std::mem::swap(&mut self.record.green, &mut self.record.yellow);
+        if let Some(first_move) = self.record.moves.first_mut() {
+            first_move.comment = Some(SWAP_COMMENT.to_string());
+        }
+    }
+}
+
+// Diagonal made of a vertical and a horizontal arrow pressed one after the other, otherwise the last arrow
+fn combined_direction(previous: Option<&Direction>, arrow: Direction) -> Direction {
+    match (previous, &arrow) {
+        (Some(Direction::Up), Direction::Left) | (Some(Direction::Left), Direction::Up) => Direction::UpLeft,
+        (Some(Direction::Up), Direction::Right) | (Some(Direction::Right), Direction::Up) => Direction::UpRight,
+        (Some(Direction::Down), Direction::Left) | (Some(Direction::Left), Direction::Down) => Direction::DownLeft,
+        (Some(Direction::Down), Direction::Right) | (Some(Direction::Right), Direction::Down) => Direction::DownRight,
+        _ => arrow,
+    }
+}
+
+// Forwards search progress to the component and lets the stop button cancel the search
+struct UiSearchObserver {
+    handle: SearchHandle,
+    link: yew::html::Scope<App>,
+}
+
+impl SearchObserver for UiSearchObserver {
+    fn on_progress(&self, progress: &SearchProgress) {
+        self.link.send_message(Msg::AiProgress(progress.clone()));
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.handle.is_cancelled()
+    }
 }
 
 #[derive(Clone, PartialEq)]
 struct AppState {
     pawn_clicked: Callback<usize>,
+    pawn_swiped: Callback<(usize, Direction)>,
     direction_clicked: Callback<Direction>,
 }
 
@@ -76,159 +1025,791 @@ impl Component for App {
 
     fn create(ctx: &Context<Self>) -> Self {
         let pawn_clicked = ctx.link().callback(Msg::PawnClick);
+        let pawn_swiped = ctx.link().callback(|(pawn_index, direction)| Msg::PawnSwiped(pawn_index, direction));
         let direction_clicked = ctx.link().callback(Msg::DirectionClick);
         let state = Rc::new(AppState {
             pawn_clicked,
+            pawn_swiped,
             direction_clicked,
         });
 
-        let board = Board::default_new();
+        // searches before the workers are up stay on the main thread
+        #[cfg(feature = "web-threads")]
+        wasm_bindgen_futures::spawn_local(async {
+            crate::platform::init_threads().await;
+        });
+        // engines created before the answer use NdArray
+        #[cfg(feature = "webgpu")]
+        {
+            let link = ctx.link().clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                link.send_message(Msg::GpuDetected(init_webgpu().await));
+            });
+        }
+
+        {
+            let link = ctx.link().clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                link.send_message(Msg::StorageLoaded(Box::new(StoredData::load().await)));
+            });
+        }
+
+        let game = Game::new(&Board::default_new());
+        let record = GameRecord::new(&game.board);
         Self {
-            board,
+            game,
             state,
-            ai: AiType::None,
+            ai: None,
             ai_thinking: false,
+            search_handle: None,
+            ponder_handle: None,
+            ai_progress: None,
             selected_pawn: None,
-            difficulty_selected: 4,
+            keyboard_direction: None,
+            announcement: String::new(),
+            difficulty_selected: DEFAULT_STRENGTH_LEVEL,
             ai_type_selected: 0,
+            use_book: true,
+            use_gpu: true,
+            gpu_available: false,
+            book: Arc::new(OpeningBook::default_book()),
+            record,
+            move_explanations: HashMap::new(),
+            proven_outcome: None,
+            show_export: false,
+            reviewing: false,
+            review: None,
+            model_selected: DEFAULT_MODEL_ID.to_string(),
+            model_loading: false,
+            model_error: None,
+            move_error: None,
+            stats_cache: StatsCache::default().shared(),
+            human_games: None,
+            show_human_games: false,
+            saved_games: SavedGames::default(),
+            show_saved_games: false,
+            save_name: String::new(),
+            saved_games_error: None,
+            show_saved_export: false,
+            start_position: StartPosition::Standard,
+            game_mode: GameMode::Standard,
+            random_start_loading: false,
+            mirror_match: None,
+            swap_rule: false,
+            show_coordinates: true,
+            show_arrows: false,
+            arrow_analysis: None,
+            arrow_board: None,
+            drawing_arrows: false,
+            arrow_start: None,
+            user_arrows: vec![],
+            log_level: None,
+            engine_log: vec![],
+            show_engine_log: false,
+            show_search_tree: false,
+            search_tree: None,
+            telemetry: None,
+            heat_map_source: HeatMapSource::Off,
+            heat_map_board: None,
+            heat_map: vec![],
+            evaluating: None,
+            viewed_ply: None,
+            replaying: false,
+            replay_number: 0,
+            replay_speed: 1,
+            contempt: 1,
+            editor: None,
+            editor_error: None,
+            analyzing: false,
+            position_analysis: None,
+            endgame: None,
+            puzzle_streak: PuzzleStreak::default(),
+            profile: UserProfile::new(),
+            show_profile: false,
+            achievements: Achievements::new(),
+            new_achievements: vec![],
+            daily_puzzle_loading: false,
+            stored_settings: None,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
+            Msg::StorageLoaded(stored) => {
+                let mut settings = self.settings();
+                if let Some(text) = &stored.settings {
+                    settings.read_text(text);
+                }
+                // an upload the storage lost falls back to the served model
+                if settings.model != DEFAULT_MODEL_ID && !stored.uploaded_models.contains(&settings.model) {
+                    settings.model = DEFAULT_MODEL_ID.to_string();
+                }
+                self.apply_settings(&settings);
+                self.stored_settings = Some(settings);
+                // engines created meanwhile share the cache, its content is replaced in place
+                if let Some(stats_cache) = stored.stats_cache {
+                    *self.stats_cache.lock().unwrap() = stats_cache;
+                }
+                self.human_games = stored.human_games;
+                self.saved_games = stored.saved_games.unwrap_or_default();
+                self.puzzle_streak = stored.puzzle_streak.unwrap_or_default();
+                self.profile = stored.profile.unwrap_or_default();
+                self.achievements = stored.achievements.unwrap_or_default();
+            }
+            Msg::SavedGamesStored(stored) => {
+                if !stored {
+                    self.saved_games_error = Some("The browser storage is full or unavailable, the game is only kept until the page closes".to_string());
+                }
+            }
+            Msg::ToggleEditor => {
+                self.editor = match self.editor {
+                    Some(_) => None,
+                    None => Some(self.game.board.clone()),
+                };
+                self.editor_error = None;
+                self.selected_pawn = None;
+            }
+            Msg::EditorSquareClick(position) => {
+                if let Some(board) = &mut self.editor {
+                    // empty, then Green, then Yellow, then empty again
+                    match board.pawn_at(&position) {
+                        None => board.pawns.push(Pawn::new(Color::Green, position)),
+                        Some(pawn_index) if board.pawns[pawn_index].color == Color::Green => board.pawns[pawn_index].color = Color::Yellow,
+                        Some(pawn_index) => {
+                            board.pawns.remove(pawn_index);
+                        }
+                    }
+                    self.editor_error = None;
+                }
+            }
+            Msg::EditorSetNextPlayer(color) => {
+                if let Some(board) = &mut self.editor {
+                    board.next_player = Some(color);
+                }
+            }
+            Msg::EditorClear => {
+                if let Some(board) = &mut self.editor {
+                    board.pawns.clear();
+                }
+            }
+            Msg::EditorStartGame => {
+                let Some(board) = self.validated_setup() else {
+                    return true;
+                };
+                self.editor = None;
+                self.new_game(board);
+            }
+            Msg::EditorAnalyze => {
+                let Some(board) = self.validated_setup() else {
+                    return true;
+                };
+                self.analyzing = true;
+                Self::run_in_background(ctx, move || async move {
+                    let mut analyzer: MinMax<WasmPlatform> = MinMax::new(Color::Green, REVIEW_DEPTH);
+                    let analysis = analyze_async(&mut analyzer, &board).await;
+                    (board, analysis)
+                }, |(board, analysis)| Msg::PositionAnalyzed(board, analysis));
+            }
+            Msg::PositionAnalyzed(board, analysis) => {
+                self.analyzing = false;
+                self.position_analysis = Some((board, analysis));
+            }
+            Msg::StartEndgame => {
+                let drills = endgame_drills();
+                if drills.is_empty() {
+                    return true;
+                }
+                let drill = drills[WasmPlatform::random_int(drills.len())].clone();
+                self.start_endgame(ctx, drill);
+            }
+            Msg::RetryEndgame => {
+                if let Some(session) = self.endgame.take() {
+                    let solution = session.solution;
+                    self.start_endgame(ctx, session.drill);
+                    // the line is known already, the search under way gives it again
+                    if let Some(retried) = &mut self.endgame {
+                        retried.solution = solution;
+                        retried.daily = session.daily;
+                        // a daily puzzle stays spoiled once its solution was shown
+                        retried.show_solution = session.daily.is_some() && session.show_solution;
+                    }
+                }
+            }
+            Msg::LeaveEndgame => {
+                self.restart(ctx);
+            }
+            Msg::ShowEndgameSolution => {
+                if let Some(session) = &mut self.endgame {
+                    session.show_solution = true;
+                }
+            }
+            Msg::EndgameSolved(board, solution) => {
+                if let Some(session) = self.endgame.as_mut().filter(|session| session.drill.board == board) {
+                    session.solution = solution;
+                }
+            }
+            Msg::EndgameCheck => {
+                let Some(session) = &mut self.endgame else {
+                    return true;
+                };
+                if self.game.board.winner().is_some() {
+                    session.progress = Some(DrillProgress::Won);
+                    // a puzzle only counts when solved without looking at the solution
+                    if session.show_solution {
+                        return true;
+                    }
+                    if let Some(day) = session.daily {
+                        self.puzzle_streak.record_solve(day);
+                        let puzzle_streak = self.puzzle_streak.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            puzzle_streak.save_storage::<WasmPlatform>(PUZZLE_STREAK_KEY).await;
+                        });
+                    }
+                    let unlocked = self.achievements.record_puzzle(day_number(js_sys::Date::now()));
+                    self.unlock_achievements(unlocked);
+                    return true;
+                }
+                session.checking = true;
+                let board = self.game.board.clone();
+                Self::run_in_background(ctx, move || async move {
+                    let result = solve_endgame_async::<WasmPlatform>(&board).await;
+                    (board, result)
+                }, |(board, result)| Msg::EndgameDefence(board, result));
+            }
+            Msg::EndgameDefence(board, result) => {
+                // a check for an abandoned drill
+                if self.endgame.is_none() || self.game.board != board {
+                    return true;
+                }
+                let plies_left = self.endgame.as_ref().map_or(0, |session| session.drill.plies.saturating_sub(self.record.moves.len()));
+                let progress = drill_progress(&board, &result, plies_left);
+                if let Some(session) = &mut self.endgame {
+                    session.checking = false;
+                    session.progress = Some(progress.clone());
+                }
+                if progress == DrillProgress::OnTrack {
+                    if let Some((pawn_index, direction)) = result.pv.first().cloned() {
+                        if let Err(error) = self.play_move(pawn_index, &direction) {
+                            self.move_error = Some(format!("Defence rejected: {}", error));
+                        }
+                    }
+                }
+            }
+            Msg::StartDailyPuzzle => {
+                if self.daily_puzzle_loading {
+                    return false;
+                }
+                self.daily_puzzle_loading = true;
+                let day = day_number(js_sys::Date::now());
+                Self::run_in_background(ctx, move || async move {
+                    daily_puzzle_async::<WasmPlatform>(day).await
+                }, move |drill| Msg::DailyPuzzleReady(day, drill));
+            }
+            Msg::DailyPuzzleReady(day, drill) => {
+                self.daily_puzzle_loading = false;
+                self.start_endgame(ctx, drill);
+                if let Some(session) = &mut self.endgame {
+                    session.daily = Some(day);
+                }
+            }
             Msg::SetAiType(ai_type) => {
                 self.ai_type_selected = ai_type;
             }
             Msg::SetDifficulty(difficulty) => {
                 self.difficulty_selected = difficulty;
             }
+            Msg::SetUseBook(use_book) => {
+                self.use_book = use_book;
+            }
+            Msg::SetUseGpu(use_gpu) => {
+                self.use_gpu = use_gpu;
+            }
+            Msg::GpuDetected(gpu_available) => {
+                self.gpu_available = gpu_available;
+            }
+            Msg::SetStartPosition(index) => {
+                // a new start position begins a new game
+                self.start_position = StartPosition::all().get(index).cloned().unwrap_or_default();
+                self.game_mode = GameMode::Standard;
+                ctx.link().send_message(Msg::Restart);
+            }
+            Msg::SetGameMode(index) => {
+                self.game_mode = GameMode::ALL.get(index).copied().unwrap_or(GameMode::Standard);
+                ctx.link().send_message(Msg::Restart);
+            }
+            Msg::RandomStartReady(board) => {
+                self.random_start_loading = false;
+                // the mode may have been changed during the search
+                if self.game_mode != GameMode::Standard {
+                    self.new_game(board.clone());
+                }
+                if self.game_mode == GameMode::MirrorMatch {
+                    self.mirror_match = Some(MirrorMatch { start: board, results: vec![] });
+                    self.record.event = "Mirror match, game 1".to_string();
+                }
+            }
+            Msg::MirrorSecondGame => {
+                let Some(mirror_match) = self.mirror_match.take() else {
+                    return false;
+                };
+                let Some((player_color, _)) = mirror_match.results.first().cloned() else {
+                    self.mirror_match = Some(mirror_match);
+                    return false;
+                };
+                self.new_game(mirror_match.start.clone());
+                self.mirror_match = Some(mirror_match);
+                self.record.event = "Mirror match, game 2".to_string();
+                // the AI takes the colour the player had
+                ctx.link().send_message(Msg::CreateAi(player_color));
+            }
+            Msg::SetSwapRule(swap_rule) => {
+                // the rule applies from the first move, so it begins a new game
+                self.swap_rule = swap_rule;
+                ctx.link().send_message(Msg::Restart);
+            }
+            Msg::SetShowCoordinates(show_coordinates) => {
+                self.show_coordinates = show_coordinates;
+            }
+            Msg::SetShowArrows(show_arrows) => {
+                self.show_arrows = show_arrows;
+                self.arrow_board = None;
+                self.arrow_analysis = None;
+            }
+            Msg::ArrowAnalysisReady(board, analysis) => {
+                // a search of a board left since is dropped
+                if self.arrow_board.as_ref() == Some(&board) {
+                    self.arrow_analysis = Some((board, analysis));
+                }
+            }
+            Msg::ToggleDrawArrows => {
+                self.drawing_arrows = !self.drawing_arrows;
+                self.arrow_start = None;
+            }
+            Msg::ArrowSquareClick(position) => {
+                match self.arrow_start.take() {
+                    None => self.arrow_start = Some(position),
+                    Some(from) if from == position => (),
+                    Some(from) => {
+                        let arrow = Arrow { from, to: position, kind: ArrowKind::User };
+                        // drawing an arrow again erases it
+                        if let Some(index) = self.user_arrows.iter().position(|drawn| *drawn == arrow) {
+                            self.user_arrows.remove(index);
+                        } else {
+                            self.user_arrows.push(arrow);
+                        }
+                    }
+                }
+            }
+            Msg::ClearArrows => {
+                self.user_arrows.clear();
+                self.arrow_start = None;
+            }
+            Msg::SetLogLevel(index) => {
+                self.log_level = index.checked_sub(1).and_then(|index| LogLevel::ALL.get(index)).copied();
+            }
+            Msg::ToggleEngineLog => {
+                self.show_engine_log = !self.show_engine_log;
+            }
+            Msg::SwapColors => {
+                // the AI played first, the player takes its color and the AI replies
+                self.swap_colors();
+                ctx.link().send_message(Msg::AiShouldPlay);
+            }
+            Msg::AiSwapped => {
+                if !self.ai_thinking {
+                    // decision abandoned by a restart
+                    return true;
+                }
+                self.ai_thinking = false;
+                self.search_handle = None;
+                self.ai_progress = None;
+                self.swap_colors();
+            }
             Msg::PawnClick(pawn_index) => {
-                if self.board.next_player == Some(self.board.pawns[pawn_index].color.clone()){
+                if self.editor.is_none() && self.viewed_ply.is_none() && !self.endgame_blocks_input() && self.game.board.next_player == Some(self.game.board.pawns[pawn_index].color.clone()){
                     self.selected_pawn = Some(pawn_index);
+                    self.keyboard_direction = None;
+                }
+            }
+            Msg::PawnSwiped(pawn_index, direction) => {
+                // a swipe plays the selected pawn, other pawns are selected by a tap first
+                if self.selected_pawn == Some(pawn_index) {
+                    ctx.link().send_message(Msg::DirectionClick(direction));
                 }
             }
+            Msg::KeyDown(key, shift) => {
+                self.handle_key(ctx, &key, shift);
+            }
             Msg::DirectionClick(direction) => {
                 if let Some(pawn_index) = self.selected_pawn {
-                    let mut new_board = self.board.clone();
-                    if new_board.move_pawn_until_blocked(pawn_index, &direction) {
-                        self.board = new_board;
-                        self.selected_pawn = None;
+                    match self.play_move(pawn_index, &direction) {
+                        Ok(_) => {
+                            self.selected_pawn = None;
+                            self.move_error = None;
 
-                        ctx.link().send_message(Msg::AiShouldPlay);
+                            if self.endgame.is_some() {
+                                ctx.link().send_message(Msg::EndgameCheck);
+                            } else {
+                                ctx.link().send_message(Msg::AiShouldPlay);
+                            }
+                        }
+                        Err(error) => self.move_error = Some(format!("Move rejected: {}", error)),
                     }
                 }
             }
             Msg::AiShouldPlay => {
-                match &self.ai {
-                    AiType::None => (),                    
-                    AiType::MinMax(color) => {
-                        if Some(color.clone()) != self.board.next_player {
-                            return true;
-                        }
-                        // Set AI thinking state
-                        self.ai_thinking = true;
-                        
-                        // Spawn async task to calculate AI move
-                        let board = self.board.clone();
-                        let link = ctx.link().clone();
-                        let mut ai: MinMax<WasmPlatform> = MinMax::new(color.clone(), self.difficulty_selected);
-                        wasm_bindgen_futures::spawn_local(async move {
-                            // Small delay to allow browser to render player's move first
-                            sleep(Duration::from_millis(50)).await;
-                            let ai_move = ai.ai_play(&board, true);
-                            link.send_message(Msg::AiMoveReady(ai_move));
-                        });
+                if let Some(ponder_handle) = self.ponder_handle.take() {
+                    ponder_handle.cancel();
+                }
+                let Some(ai_player) = &self.ai else {
+                    return true;
+                };
+                if Some(ai_player.color.clone()) != self.game.board.next_player {
+                    return true;
+                }
+                // Set AI thinking state
+                self.ai_thinking = true;
+
+                // Spawn async task to calculate AI move, then ponder until the player moves
+                let board = self.game.board.clone();
+                // the boards of the record up to the current one, which is the last
+                let mut history = self.record.boards().unwrap_or_default();
+                history.pop();
+                let color = ai_player.color.clone();
+                let consider_swap = self.game.can_swap();
+                let contempt = CONTEMPT_LEVELS[self.contempt].1;
+                let verbose = self.log_level.is_some();
+                let link = ctx.link().clone();
+                let handle = SearchHandle::new();
+                self.search_handle = Some(handle.clone());
+                let observer = UiSearchObserver { handle, link: link.clone() };
+                let ponder_handle = SearchHandle::new();
+                self.ponder_handle = Some(ponder_handle.clone());
+                match &ai_player.slot {
+                    BackendSlot::Cpu(slot) => wasm_bindgen_futures::spawn_local(
+                        play_ai_turn(slot.clone(), board, history, color, consider_swap, contempt, verbose, observer, ponder_handle, link)),
+                    #[cfg(feature = "webgpu")]
+                    BackendSlot::Gpu(slot) => wasm_bindgen_futures::spawn_local(
+                        play_ai_turn(slot.clone(), board, history, color, consider_swap, contempt, verbose, observer, ponder_handle, link)),
+                }
+            }
+            Msg::SearchTreeReady(search_tree) => {
+                self.search_tree = search_tree;
+            }
+            Msg::TelemetryReady(telemetry) => {
+                self.telemetry = Some(telemetry);
+            }
+            Msg::ToggleSearchTree => {
+                self.show_search_tree = !self.show_search_tree;
+            }
+            Msg::SetHeatMap(index) => {
+                self.heat_map_source = HeatMapSource::ALL.get(index).copied().unwrap_or(HeatMapSource::Off);
+                self.heat_map_board = None;
+                self.heat_map.clear();
+            }
+            Msg::EvaluationReady(ply, board, evaluation) => {
+                self.evaluating = None;
+                // an evaluation for another game is dropped
+                if self.record.boards().and_then(|boards| boards.get(ply + 1).cloned()) == Some(board) {
+                    self.record.moves[ply].evaluation = Some(evaluation);
+                }
+            }
+            Msg::ViewPly(ply) => {
+                self.replaying = false;
+                self.viewed_ply = ply.filter(|ply| *ply < self.record.moves.len());
+                self.selected_pawn = None;
+                self.keyboard_direction = None;
+            }
+            Msg::StartReplay => {
+                if self.record.moves.is_empty() {
+                    return false;
+                }
+                self.replaying = true;
+                self.replay_number += 1;
+                self.viewed_ply = Some(0);
+                self.selected_pawn = None;
+                self.keyboard_direction = None;
+                self.schedule_replay_step(ctx);
+            }
+            Msg::ReplayStep(replay_number) => {
+                if !self.replaying || replay_number != self.replay_number {
+                    return false;
+                }
+                // the final position is the game board itself
+                match self.viewed_ply {
+                    Some(ply) if ply + 1 < self.record.moves.len() => {
+                        self.viewed_ply = Some(ply + 1);
+                        self.schedule_replay_step(ctx);
                     }
-                    AiType::MCTS(color) => {
-                        if Some(color.clone()) != self.board.next_player {
-                            return true;
-                        }
-                        // Set AI thinking state
-                        self.ai_thinking = true;
-                        
-                        // Spawn async task to calculate AI move
-                        let board = self.board.clone();
-                        let link = ctx.link().clone();
-                        WasmPlatform::print(&format!("AI color {:?}", color));
-                        let mut ai: MCTS<WasmPlatform> = MCTS::new(color.clone(), self.difficulty_selected);
-                        wasm_bindgen_futures::spawn_local(async move {
-                            // Small delay to allow browser to render player's move first
-                            sleep(Duration::from_millis(50)).await;
-                            let ai_move = ai.ai_play(&board, true);
-                            link.send_message(Msg::AiMoveReady(ai_move));
-                        });
+                    _ => {
+                        self.viewed_ply = None;
+                        self.replaying = false;
                     }
-                    AiType::ANNSolo(color) => {
-                        if Some(color.clone()) != self.board.next_player {
-                            return true;
+                }
+            }
+            Msg::StopReplay => {
+                self.replaying = false;
+            }
+            Msg::SetContempt(index) => {
+                // the AI takes it from its next move
+                self.contempt = index.min(CONTEMPT_LEVELS.len() - 1);
+            }
+            Msg::SetReplaySpeed(index) => {
+                self.replay_speed = index.min(REPLAY_SPEEDS.len() - 1);
+            }
+            Msg::HeatMapReady(board, heat_map) => {
+                // a map computed for an earlier position is dropped
+                if self.heat_map_board.as_ref() == Some(&board) {
+                    self.heat_map = heat_map;
+                }
+            }
+            Msg::StopThinking => {
+                if let Some(handle) = &self.search_handle {
+                    handle.cancel();
+                }
+            }
+            Msg::AiProgress(progress) => {
+                if self.ai_thinking {
+                    self.ai_progress = Some(progress);
+                }
+            }
+            Msg::AiMoveReady(ai_move, explanation, proven_text) => {
+                if !self.ai_thinking {
+                    // search abandoned by a restart
+                    return true;
+                }
+                self.ai_thinking = false;
+                self.search_handle = None;
+                self.ai_progress = None;
+                if let Some((ai_pawn_index, ai_direction)) = ai_move {
+                    match self.play_move(ai_pawn_index, &ai_direction) {
+                        Ok(_) => {
+                            if let Some(explanation) = explanation {
+                                self.move_explanations.insert(self.record.moves.len() - 1, explanation);
+                            }
+                            self.proven_outcome = proven_text;
                         }
-                        // Set AI thinking state
-                        self.ai_thinking = true;
-                        
-                        // Spawn async task to calculate AI move
-                        let board = self.board.clone();
+                        Err(error) => self.move_error = Some(format!("AI move rejected: {}", error)),
+                    }
+                }
+            }
+            Msg::Restart => {
+                self.restart(ctx);
+            }
+            Msg::ToggleExport => {
+                self.show_export = !self.show_export;
+            }
+            Msg::ToggleSavedGames => {
+                self.show_saved_games = !self.show_saved_games;
+                self.saved_games_error = None;
+            }
+            Msg::SetSaveName(name) => {
+                self.save_name = name;
+            }
+            Msg::SaveGame => {
+                if !self.saved_games.save(&self.save_name, self.record.clone(), js_sys::Date::now()) {
+                    self.saved_games_error = Some("Give the game a name, or delete a saved game to make room".to_string());
+                } else {
+                    self.saved_games_error = None;
+                    self.store_saved_games(ctx);
+                }
+            }
+            Msg::LoadSavedGame(name) => {
+                let Some(saved) = self.saved_games.get(&name).cloned() else {
+                    return true;
+                };
+                let (Some(start), Some(game)) = (saved.record.start_board(), Game::from_record(&saved.record)) else {
+                    self.saved_games_error = Some(format!("{} has an illegal move and cannot be loaded", name));
+                    return true;
+                };
+                self.editor = None;
+                self.new_game(start);
+                self.game = game;
+                self.game.swap_rule = self.swap_rule;
+                self.record = saved.record;
+                self.save_name = name;
+                self.saved_games_error = None;
+            }
+            Msg::DeleteSavedGame(name) => {
+                if self.saved_games.remove(&name) {
+                    self.store_saved_games(ctx);
+                }
+            }
+            Msg::ToggleSavedExport => {
+                self.show_saved_export = !self.show_saved_export;
+            }
+            Msg::ReviewGame => {
+                if self.reviewing || self.record.moves.is_empty() {
+                    return true;
+                }
+                self.reviewing = true;
+                let record = self.record.clone();
+                Self::run_in_background(ctx, move || async move {
+                    let mut reviewer: MinMax<WasmPlatform> = MinMax::new(Color::Green, REVIEW_DEPTH);
+                    review_game_async(&mut reviewer, &record, DEFAULT_BLUNDER_THRESHOLD).await
+                }, Msg::ReviewReady);
+            }
+            Msg::ReviewReady(review) => {
+                self.reviewing = false;
+                self.review = review;
+            }
+            Msg::CreateAi(color) => {
+                if self.ai_type_selected == HUMAN_LIKE_AI_TYPE && !is_model_loaded(HUMAN_MODEL_ID) {
+                    self.model_error = Some("Load your model first, fitted by neutreeko-train human on your exported games".to_string());
+                    return true;
+                }
+                let needs_model = self.ai_type_selected != HUMAN_LIKE_AI_TYPE && self.selected_engine_name()
+                    .is_some_and(AnyAI::<CpuBackend, WasmPlatform>::uses_network);
+                if needs_model && !is_model_loaded(&self.model_selected) {
+                    // the weights are only downloaded by players who pick a network engine
+                    if !self.model_loading {
+                        self.model_loading = true;
+                        self.model_error = None;
                         let link = ctx.link().clone();
-                        let mut ai: ANNSolo<NdArray<f32, i32>, WasmPlatform> = ANNSolo::new(color.clone(), self.difficulty_selected);
+                        let model_id = self.model_selected.clone();
                         wasm_bindgen_futures::spawn_local(async move {
-                            // Small delay to allow browser to render player's move first
-                            sleep(Duration::from_millis(50)).await;
-                            let ai_move = ai.ai_play(&board, true);
-                            link.send_message(Msg::AiMoveReady(ai_move));
+                            let result = fetch_model(&model_id).await;
+                            link.send_message(Msg::ModelLoaded(color, result));
                         });
                     }
-                    AiType::AlphaZeutreeko(color) => {
-                        if Some(color.clone()) != self.board.next_player {
-                            return true;
-                        }
-                        // Set AI thinking state
-                        self.ai_thinking = true;
-                        
-                        // Spawn async task to calculate AI move
-                        let board = self.board.clone();
-                        let link = ctx.link().clone();
-                        let mut ai: AlphaZeutreeko<NdArray<f32, i32>, WasmPlatform> = AlphaZeutreeko::new(color.clone(), self.difficulty_selected);
-                        wasm_bindgen_futures::spawn_local(async move {
-                            // Small delay to allow browser to render player's move first
-                            sleep(Duration::from_millis(50)).await;
-                            let ai_move = ai.ai_play(&board, true);
-                            link.send_message(Msg::AiMoveReady(ai_move));
-                        });
+                    return true;
+                }
+                // the drill defender is not an AI player, playing one ends the drill
+                self.endgame = None;
+                self.create_ai(color);
+                ctx.link().send_message(Msg::AiShouldPlay);
+            }
+            Msg::ModelLoaded(color, result) => {
+                self.model_loading = false;
+                match result {
+                    Ok(()) => ctx.link().send_message(Msg::CreateAi(color)),
+                    Err(error) => self.model_error = Some(error),
+                }
+            }
+            Msg::SetModel(model_id) => {
+                self.model_selected = model_id;
+            }
+            Msg::UploadModel(file) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = upload_model(&file).await;
+                    link.send_message(Msg::ModelUploaded(result));
+                });
+            }
+            Msg::ModelUploaded(result) => {
+                match result {
+                    Ok(model_id) => {
+                        self.model_selected = model_id;
+                        self.model_error = None;
                     }
+                    Err(error) => self.model_error = Some(error),
                 }
             }
-            Msg::AiMoveReady(ai_move) => {
-                self.ai_thinking = false;
-                if let Some((ai_pawn_index, ai_direction)) = ai_move {
-                    self.board.move_pawn_until_blocked(ai_pawn_index, &ai_direction);
+            Msg::SetKeepGames(keep_games) => {
+                // opting in writes an empty store, opting out deletes every kept game
+                if keep_games {
+                    let human_games = GameStore::new();
+                    let stored = human_games.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        stored.save_storage::<WasmPlatform>(HUMAN_GAMES_KEY).await;
+                    });
+                    self.human_games = Some(human_games);
+                } else {
+                    wasm_bindgen_futures::spawn_local(async {
+                        GameStore::remove_storage::<WasmPlatform>(HUMAN_GAMES_KEY).await;
+                    });
+                    self.human_games = None;
+                    self.show_human_games = false;
                 }
             }
-            Msg::Restart => {
-                self.board = Board::default_new();
-                self.selected_pawn = None;
-                self.ai = AiType::None;
-                self.ai_thinking = false;
+            Msg::ToggleHumanGames => {
+                self.show_human_games = !self.show_human_games;
             }
-            Msg::CreateAi(color) => {
-                self.create_ai(color);
-                ctx.link().send_message(Msg::AiShouldPlay);
+            Msg::ToggleProfile => {
+                self.show_profile = !self.show_profile;
+            }
+            Msg::ResetProfile => {
+                self.profile = UserProfile::new();
+                wasm_bindgen_futures::spawn_local(async {
+                    UserProfile::remove_storage::<WasmPlatform>(PROFILE_KEY).await;
+                });
+            }
+            Msg::UploadHumanModel(file) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = upload_model_as(&file, HUMAN_MODEL_ID).await;
+                    link.send_message(Msg::HumanModelUploaded(result));
+                });
+            }
+            Msg::HumanModelUploaded(result) => {
+                match result {
+                    Ok(()) => {
+                        self.ai_type_selected = HUMAN_LIKE_AI_TYPE;
+                        self.model_error = None;
+                    }
+                    Err(error) => self.model_error = Some(error),
+                }
             }
         }
+        if self.heat_map_source != HeatMapSource::Off && self.heat_map_board.as_ref() != Some(&self.game.board) {
+            self.request_heat_map(ctx);
+        }
+        if self.show_arrows && self.arrow_board.as_ref() != Some(&self.game.board) {
+            self.request_arrow_analysis(ctx);
+        }
+        self.request_evaluation(ctx);
+        self.collect_engine_log();
+        self.store_settings();
         true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        // only offered when the browser gave a WebGPU adapter
+        let gpu_view = if self.gpu_available {
+            html! {
+                <div class="difficulty-selector">
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={self.use_gpu}
+                            onchange={ctx.link().callback(|e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::SetUseGpu(input.checked())
+                            })}
+                        />
+                        { " Network on the GPU (WebGPU)" }
+                    </label>
+                </div>
+            }
+        } else {
+            html! {}
+        };
         let app_state = self.state.clone();
-        let next_player_text = match self.board.winner() {
+        let next_player_text = match self.game.board.winner() {
             Some(Color::Green) => "Green wins!".to_string(),
             Some(Color::Yellow) => "Yellow wins!".to_string(),
             None => {
-                if self.ai_thinking {
-                    "🤔 AI is thinking...".to_string()
+                if self.model_loading {
+                    "⏳ Loading the neural network...".to_string()
+                } else if self.ai_thinking {
+                    match &self.ai_progress {
+                        Some(progress) => {
+                            let best_move = progress.best_move.as_ref()
+                                .and_then(|(pawn_index, direction)| self.game.board.move_to_notation(&Move::new(*pawn_index, direction.clone())))
+                                .map_or(String::new(), |notation| format!(", considering {}", notation));
+                            format!("🤔 AI is thinking... {:.0}% ({} nodes{})", progress.fraction * 100.0, progress.nodes, best_move)
+                        }
+                        None => "🤔 AI is thinking...".to_string(),
+                    }
                 } else {
-                    match self.board.next_player {
+                    let turn = match self.game.board.next_player {
                         Some(Color::Green) => "Green's turn".to_string(),
                         Some(Color::Yellow) => "Yellow's turn".to_string(),
                         None => String::new(),
+                    };
+                    match &self.proven_outcome {
+                        Some(outcome) => format!("{} · {}", turn, outcome),
+                        None => turn,
                     }
                 }
             }
@@ -237,7 +1818,16 @@ impl Component for App {
         // Configuration controls (AI selection, restart)
         let config_view = html! {
             <div class="config-controls">
-                <button onclick={ctx.link().callback(|_| Msg::Restart)}>{ "Restart Game" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::Restart)} disabled={self.random_start_loading}>
+                    { if self.random_start_loading { "Finding a random start..." } else { "Restart Game" } }
+                </button>
+                <button onclick={ctx.link().callback(|_| Msg::ToggleEditor)}>
+                    { if self.editor.is_some() { "Leave editor" } else { "Edit position" } }
+                </button>
+                <button onclick={ctx.link().callback(|_| Msg::StartEndgame)}>{ "Endgame trainer" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::StartDailyPuzzle)} disabled={self.daily_puzzle_loading}>
+                    { if self.daily_puzzle_loading { "Finding today's puzzle..." } else { "Daily puzzle" } }
+                </button>
                 <div class="difficulty-selector">
                     <label>{ "AI Type: " }</label>
                     <select
@@ -251,37 +1841,303 @@ impl Component for App {
                     <option value="2" selected={self.ai_type_selected == 2}>{ "MCTS" }</option>
                     <option value="3" selected={self.ai_type_selected == 3}>{ "ANN" }</option>
                     <option value="4" selected={self.ai_type_selected == 4}>{ "AlphaZeutreeko" }</option>
+                    <option value="5" selected={self.ai_type_selected == 5}>{ "Committee (MinMax + AlphaZeutreeko)" }</option>
+                    <option value="6" selected={self.ai_type_selected == 6}>{ "Random" }</option>
+                    <option value="7" selected={self.ai_type_selected == 7}>{ "Greedy (one move ahead)" }</option>
+                    <option value={AUTOMATIC_AI_TYPE.to_string()} selected={self.ai_type_selected == AUTOMATIC_AI_TYPE}>{ "Automatic" }</option>
+                    <option value={HUMAN_LIKE_AI_TYPE.to_string()} selected={self.ai_type_selected == HUMAN_LIKE_AI_TYPE}>{ "Plays like you" }</option>
+                    </select>
+                </div>
+                <div class="difficulty-selector">
+                    <label>{ "AI Strength: " }</label>
+                    <select
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlSelectElement = e.target_unchecked_into();
+                            Msg::SetDifficulty(input.value().parse().unwrap_or(DEFAULT_STRENGTH_LEVEL))
+                        })}
+                    >
+                    { for STRENGTH_LEVELS.iter().enumerate().map(|(index, level)| html! {
+                        <option value={(index + 1).to_string()} selected={self.difficulty_selected == index + 1}>
+                            { format!("{} (~{} Elo)", level.name, level.estimated_elo) }
+                        </option>
+                    }) }
+                    </select>
+                </div>
+                <div class="difficulty-selector">
+                    <label>{ "Start position: " }</label>
+                    <select
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlSelectElement = e.target_unchecked_into();
+                            Msg::SetStartPosition(input.value().parse().unwrap_or(0))
+                        })}
+                    >
+                    { for StartPosition::all().into_iter().enumerate().map(|(index, start)| html! {
+                        <option value={index.to_string()} selected={self.start_position == start}>{ start.description() }</option>
+                    }) }
+                    </select>
+                </div>
+                <div class="difficulty-selector">
+                    <label>{ "Game mode: " }</label>
+                    <select
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlSelectElement = e.target_unchecked_into();
+                            Msg::SetGameMode(input.value().parse().unwrap_or(0))
+                        })}
+                    >
+                    { for GameMode::ALL.iter().enumerate().map(|(index, mode)| html! {
+                        <option value={index.to_string()} selected={self.game_mode == *mode}>{ mode.label() }</option>
+                    }) }
+                    </select>
+                </div>
+                <div class="difficulty-selector">
+                    <label>{ "Model: " }</label>
+                    <select
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlSelectElement = e.target_unchecked_into();
+                            Msg::SetModel(input.value())
+                        })}
+                    >
+                    { for available_models().into_iter().map(|(model_id, label)| html! {
+                        <option value={model_id.clone()} selected={self.model_selected == model_id}>{ label }</option>
+                    }) }
+                    </select>
+                    <input
+                        type="file"
+                        accept=".bpk"
+                        onchange={ctx.link().batch_callback(|e: Event| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            input.files().and_then(|files| files.get(0)).map(Msg::UploadModel)
+                        })}
+                    />
+                </div>
+                <div class="difficulty-selector">
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={self.use_book}
+                            onchange={ctx.link().callback(|e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::SetUseBook(input.checked())
+                            })}
+                        />
+                        { " Opening book" }
+                    </label>
+                </div>
+                <div class="difficulty-selector">
+                    <label>{ "Draws: " }</label>
+                    <select
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlSelectElement = e.target_unchecked_into();
+                            Msg::SetContempt(input.value().parse().unwrap_or(1))
+                        })}
+                    >
+                    { for CONTEMPT_LEVELS.iter().enumerate().map(|(index, (label, _))| html! {
+                        <option value={index.to_string()} selected={self.contempt == index}>{ *label }</option>
+                    }) }
+                    </select>
+                </div>
+                {gpu_view}
+                <div class="difficulty-selector">
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={self.swap_rule}
+                            onchange={ctx.link().callback(|e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::SetSwapRule(input.checked())
+                            })}
+                        />
+                        { " Swap rule" }
+                    </label>
+                </div>
+                <div class="difficulty-selector">
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={self.show_coordinates}
+                            onchange={ctx.link().callback(|e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::SetShowCoordinates(input.checked())
+                            })}
+                        />
+                        { " Coordinates" }
+                    </label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={self.show_arrows}
+                            onchange={ctx.link().callback(|e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::SetShowArrows(input.checked())
+                            })}
+                        />
+                        { " Engine arrows" }
+                    </label>
+                    <button onclick={ctx.link().callback(|_| Msg::ToggleDrawArrows)}>
+                        { if self.drawing_arrows { "Stop drawing arrows" } else { "Draw arrows" } }
+                    </button>
+                    <button hidden={self.user_arrows.is_empty()} onclick={ctx.link().callback(|_| Msg::ClearArrows)}>{ "Clear arrows" }</button>
+                </div>
+                <div class="difficulty-selector">
+                    <label>{ "Engine log: " }</label>
+                    <select
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlSelectElement = e.target_unchecked_into();
+                            Msg::SetLogLevel(input.value().parse().unwrap_or(0))
+                        })}
+                    >
+                        <option value="0" selected={self.log_level.is_none()}>{ "Off" }</option>
+                        { for LogLevel::ALL.iter().enumerate().map(|(index, level)| html! {
+                            <option value={(index + 1).to_string()} selected={self.log_level == Some(*level)}>{ level.label() }</option>
+                        }) }
                     </select>
+                    <button hidden={self.log_level.is_none() && self.engine_log.is_empty()} onclick={ctx.link().callback(|_| Msg::ToggleEngineLog)}>
+                        { if self.show_engine_log { "Hide engine log" } else { "Show engine log" } }
+                    </button>
                 </div>
                 <div class="difficulty-selector">
-                    <label>{ "AI Difficulty: " }</label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={self.human_games.is_some()}
+                            onchange={ctx.link().callback(|e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::SetKeepGames(input.checked())
+                            })}
+                        />
+                        { " Keep my games in this browser, to train an AI playing like me" }
+                    </label>
+                    <button hidden={self.human_games.is_none()} onclick={ctx.link().callback(|_| Msg::ToggleHumanGames)}>
+                        { format!("Export my games ({})", self.human_games.as_ref().map_or(0, GameStore::len)) }
+                    </button>
+                    <label>{ " My model: " }</label>
+                    <input
+                        type="file"
+                        accept=".bpk"
+                        onchange={ctx.link().batch_callback(|e: Event| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            input.files().and_then(|files| files.get(0)).map(Msg::UploadHumanModel)
+                        })}
+                    />
+                </div>
+                <button disabled={self.model_loading} onclick={ctx.link().callback(|_| Msg::CreateAi(Color::Yellow))}>{ "Play against AI as Green" }</button>
+                <button disabled={self.model_loading} onclick={ctx.link().callback(|_| Msg::CreateAi(Color::Green))}>{ "Play against AI as Yellow" }</button>
+                <button hidden={!self.player_may_swap()} onclick={ctx.link().callback(|_| Msg::SwapColors)}>{ "Swap colours" }</button>
+                <button disabled={!self.ai_thinking} onclick={ctx.link().callback(|_| Msg::StopThinking)}>{ "Stop thinking" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::ToggleExport)}>{ "Export game" }</button>
+                <a class="share-link" download="neutreeko-position.svg" href={self.board_image_url()}>{ "Position image" }</a>
+                <button onclick={ctx.link().callback(|_| Msg::ToggleSavedGames)}>
+                    { format!("Saved games ({})", self.saved_games.len()) }
+                </button>
+                <button onclick={ctx.link().callback(|_| Msg::ToggleProfile)}>
+                    { if self.show_profile { "Hide my statistics" } else { "My statistics" } }
+                </button>
+                <div class="difficulty-selector">
+                    <label>{ "Heat map: " }</label>
                     <select
                         onchange={ctx.link().callback(|e: Event| {
                             let input: HtmlSelectElement = e.target_unchecked_into();
-                            Msg::SetDifficulty(input.value().parse().unwrap_or(4))
+                            Msg::SetHeatMap(input.value().parse().unwrap_or(0))
                         })}
                     >
-                    <option value="1" selected={self.difficulty_selected == 1}>{ "Very Easy" }</option>
-                    <option value="2" selected={self.difficulty_selected == 2}>{ "Easy" }</option>
-                    <option value="3" selected={self.difficulty_selected == 3}>{ "Medium" }</option>
-                    <option value="4" selected={self.difficulty_selected == 4}>{ "Hard" }</option>
-                    <option value="5" selected={self.difficulty_selected == 5}>{ "Very Hard" }</option>
-                    <option value="6" selected={self.difficulty_selected == 6}>{ "Expert" }</option>
+                    { for HeatMapSource::ALL.iter().enumerate().map(|(index, source)| html! {
+                        <option value={index.to_string()} selected={self.heat_map_source == *source}>{ source.label() }</option>
+                    }) }
                     </select>
                 </div>
-                <button onclick={ctx.link().callback(|_| Msg::CreateAi(Color::Yellow))}>{ "Play against AI as Green" }</button>
-                <button onclick={ctx.link().callback(|_| Msg::CreateAi(Color::Green))}>{ "Play against AI as Yellow" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::ToggleSearchTree)}>
+                    { if self.show_search_tree { "Hide search statistics" } else { "Show search statistics" } }
+                </button>
+                <button disabled={self.reviewing || self.ai_thinking || self.record.moves.is_empty()} onclick={ctx.link().callback(|_| Msg::ReviewGame)}>
+                    { if self.reviewing { "Reviewing..." } else { "Game review" } }
+                </button>
             </div>
         };
 
+        let swap_view = if self.game.is_swapped() {
+            html! {
+                <p>{ "Colours were swapped after the first move" }</p>
+            }
+        } else {
+            html! {}
+        };
+
+        // Game record, to copy and paste in analysis tools
+        let export_view = if self.show_export {
+            html! {
+                <textarea class="game-export" readonly=true rows="12" cols="60" value={self.record.to_pgn()} />
+            }
+        } else {
+            html! {}
+        };
+
+        // Kept games, to save in a file for neutreeko-train human
+        let human_games_view = match &self.human_games {
+            Some(human_games) if self.show_human_games => html! {
+                <textarea class="game-export" readonly=true rows="12" cols="60" value={human_games.to_pgn()} />
+            },
+            _ => html! {},
+        };
+
         // Game board and pawns
-        let game_view = html! {
-            <div class="game-container">
-                <BoardView board={self.board.clone()} selected_pawn={self.selected_pawn} />
+        let game_view = if let Some(board) = &self.editor {
+            html! {
+                <div class="game-container">
+                    <BoardView
+                        board={board.clone()}
+                        selected_pawn={None}
+                        heat_map={vec![]}
+                        coordinates={self.show_coordinates}
+                        square_clicked={ctx.link().callback(Msg::EditorSquareClick)}
+                        arrows={self.position_analysis.iter()
+                            .filter(|(analyzed, _)| self.show_arrows && analyzed == board)
+                            .flat_map(|(analyzed, analysis)| analysis.arrows(analyzed))
+                            .collect::<Vec<Arrow>>()}
+                    />
+                </div>
+            }
+        } else if let Some(board) = self.viewed_board() {
+            // a past position is only looked at, the pawns do not move
+            html! {
+                <div class="game-container">
+                    <BoardView board={board.clone()} selected_pawn={None} heat_map={vec![]} coordinates={self.show_coordinates} arrows={self.board_arrows(&board)} />
+                </div>
+            }
+        } else {
+            html! {
+                <div
+                    class="game-container"
+                    tabindex="0"
+                    role="application"
+                    aria-label="Board. Arrow keys select a pawn, Shift with the arrow keys chooses a direction, Enter plays the move"
+                    onkeydown={ctx.link().batch_callback(|e: KeyboardEvent| {
+                        // keys pressed on the direction buttons are theirs
+                        if e.target() != e.current_target() {
+                            return None;
+                        }
+                        let key = e.key();
+                        if !(key.starts_with("Arrow") || key == "Enter" || key == "Escape") {
+                            return None;
+                        }
+                        // arrows would scroll the page
+                        e.prevent_default();
+                        Some(Msg::KeyDown(key, e.shift_key()))
+                    })}
+                >
+                    <BoardView
+                        board={self.game.board.clone()}
+                        selected_pawn={self.selected_pawn}
+                        heat_map={self.heat_map.clone()}
+                        coordinates={self.show_coordinates}
+                        arrows={self.board_arrows(&self.game.board)}
+                        square_clicked={self.drawing_arrows.then(|| ctx.link().callback(Msg::ArrowSquareClick))}
+                        marked_square={self.arrow_start.clone()}
+                    />
 
-                // Direction buttons positioned around selected pawn
-                {self.render_direction_buttons(ctx)}
-            </div>
+                    // Direction buttons positioned around selected pawn
+                    {self.render_direction_buttons(ctx)}
+                </div>
+            }
         };
 
         html! {
@@ -289,7 +2145,29 @@ impl Component for App {
                 <div class="app-container">
                     {config_view}
                     <h2>{ next_player_text }</h2>
+                    <p class="screen-reader-only" aria-live="polite">{ &self.announcement }</p>
+                    { for self.new_achievements.iter().map(|achievement| html! {
+                        <p class="achievement-unlocked">{ format!("Achievement unlocked: {}, {}", achievement.label(), achievement.description().to_lowercase()) }</p>
+                    }) }
+                    {swap_view}
+                    { for self.model_error.iter().chain(self.move_error.iter()).chain(self.editor_error.iter()).map(|error| html! {
+                        <p class="error-message">{ error }</p>
+                    }) }
+                    {self.render_editor(ctx)}
+                    {self.render_endgame(ctx)}
+                    {self.render_mirror_match(ctx)}
+                    {self.render_move_list()}
+                    {self.render_replay(ctx)}
+                    {self.render_evaluation_graph(ctx)}
                     {game_view}
+                    {export_view}
+                    {human_games_view}
+                    {self.render_saved_games(ctx)}
+                    {self.render_profile(ctx)}
+                    {self.render_search_tree()}
+                    {self.render_engine_log()}
+                    {self.render_review()}
+                    {self.render_training_demo()}
                 </div>
             </ContextProvider<Rc<AppState>>>
         }
@@ -299,14 +2177,14 @@ impl Component for App {
 impl App {
     fn render_direction_buttons(&self, ctx: &Context<Self>) -> Html {
         if let Some(pawn_index) = self.selected_pawn {
-            let pawn = &self.board.pawns[pawn_index];
-            let valid_directions = self.board.get_valid_directions(pawn_index);
+            let pawn = &self.game.board.pawns[pawn_index];
+            let valid_directions = self.game.board.get_valid_directions(pawn_index);
 
             let scaling_i32 = SCALING as i32;
             let controls_size = 180;
 
             let top = 50 + i32::try_from(pawn.position.row).unwrap() * scaling_i32 - (controls_size - scaling_i32) / 2;
-            let left = ((f64::from(pawn.position.column as u8) + 0.5 - f64::from(self.board.number_of_columns as u8) / 2.0 ) * f64::from(SCALING)) as i32;
+            let left = ((f64::from(pawn.position.column as u8) + 0.5 - f64::from(self.game.board.number_of_columns as u8) / 2.0 ) * f64::from(SCALING)) as i32;
 
             html! {
                 <div class="direction-controls" style={format!("position: relative; top: {}px; left: {}px;", top, left)}>
@@ -332,12 +2210,422 @@ impl App {
         }
     }
 
+    // Play back of a finished game move by move, at the speed chosen
+    fn render_replay(&self, ctx: &Context<Self>) -> Html {
+        if self.record.result.is_none() || self.record.moves.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class="difficulty-selector">
+                if self.replaying {
+                    <button onclick={ctx.link().callback(|_| Msg::StopReplay)}>{ "Stop replay" }</button>
+                } else {
+                    <button onclick={ctx.link().callback(|_| Msg::StartReplay)}>{ "Replay game" }</button>
+                }
+                <label>{ " Speed: " }</label>
+                <select
+                    onchange={ctx.link().callback(|e: Event| {
+                        let input: HtmlSelectElement = e.target_unchecked_into();
+                        Msg::SetReplaySpeed(input.value().parse().unwrap_or(1))
+                    })}
+                >
+                { for REPLAY_SPEEDS.iter().enumerate().map(|(index, (label, _))| html! {
+                    <option value={index.to_string()} selected={self.replay_speed == index}>{ *label }</option>
+                }) }
+                </select>
+            </div>
+        }
+    }
+
+    // Moves of the game in the notation of the exported record, numbered by pairs
+    fn render_move_list(&self) -> Html {
+        if self.record.moves.is_empty() {
+            return html! {};
+        }
+        html! {
+            <p class="move-list">
+                { for self.record.moves.iter().enumerate().map(|(ply, move_record)| {
+                    let number = if ply % 2 == 0 { format!("{}. ", ply / 2 + 1) } else { String::new() };
+                    // AI moves give their reasons on hover
+                    let explanation = self.move_explanations.get(&ply).cloned();
+                    html! {
+                        <>
+                            { number }
+                            <span class={classes!(explanation.is_some().then_some("explained-move"))} title={explanation}>{ &move_record.notation }</span>
+                            { " " }
+                        </>
+                    }
+                }) }
+            </p>
+        }
+    }
+
+    // What the engines considered, newest last
+    fn render_engine_log(&self) -> Html {
+        if !self.show_engine_log {
+            return html! {};
+        }
+        html! {
+            <div class="engine-log">
+                <h3>{ "Engine log" }</h3>
+                <pre>
+                    { for self.engine_log.iter().map(|(_, line)| html! { <>{ line }{ "\n" }</> }) }
+                </pre>
+            </div>
+        }
+    }
+
+    // Evaluation for green after every ply as inline SVG, a point shows the board after its ply
+    fn render_evaluation_graph(&self, ctx: &Context<Self>) -> Html {
+        let Some(boards) = self.record.boards().filter(|_| !self.record.moves.is_empty()) else {
+            return html! {};
+        };
+        let x = |plies: usize| GRAPH_WIDTH * plies as f64 / self.record.moves.len() as f64;
+        let y = |green_evaluation: f32| GRAPH_HEIGHT * (1.0 - green_evaluation.clamp(-1.0, 1.0) as f64) / 2.0;
+        // the line stops at the first ply still being searched
+        let points: Vec<(usize, f32)> = self.record.moves.iter()
+            .zip(boards.iter())
+            .map_while(|(move_record, board)| {
+                let evaluation = move_record.evaluation?;
+                Some(if board.next_player == Some(Color::Yellow) { -evaluation } else { evaluation })
+            })
+            .enumerate()
+            .map(|(ply, green_evaluation)| (ply + 1, green_evaluation))
+            .collect();
+        let line = std::iter::once((0, 0.0)).chain(points.iter().cloned())
+            .map(|(plies, green_evaluation)| format!("{:.1},{:.1}", x(plies), y(green_evaluation)))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let viewed_view = match self.viewed_ply {
+            Some(plies) => html! {
+                <p>
+                    { format!("Position after {} plies ", plies) }
+                    <button onclick={ctx.link().callback(|_| Msg::ViewPly(None))}>{ "Back to the game" }</button>
+                </p>
+            },
+            None => html! {},
+        };
+        html! {
+            <div class="evaluation-graph">
+                <svg width={GRAPH_WIDTH.to_string()} height={GRAPH_HEIGHT.to_string()} viewBox={format!("-4 -4 {} {}", GRAPH_WIDTH + 8.0, GRAPH_HEIGHT + 8.0)}>
+                    <rect x="0" y="0" width={GRAPH_WIDTH.to_string()} height={(GRAPH_HEIGHT / 2.0).to_string()} fill="#eef7ee" />
+                    <rect x="0" y={(GRAPH_HEIGHT / 2.0).to_string()} width={GRAPH_WIDTH.to_string()} height={(GRAPH_HEIGHT / 2.0).to_string()} fill="#fdf8e4" />
+                    <polyline points={line} fill="none" stroke="#555" stroke-width="1.5" />
+                    { for points.iter().map(|(plies, green_evaluation)| {
+                        let plies = *plies;
+                        let notation = &self.record.moves[plies - 1].notation;
+                        html! {
+                            <circle
+                                cx={format!("{:.1}", x(plies))}
+                                cy={format!("{:.1}", y(*green_evaluation))}
+                                r={if self.viewed_ply == Some(plies) { "5" } else { "3" }}
+                                fill="#555"
+                                style="cursor: pointer;"
+                                onclick={ctx.link().callback(move |_| Msg::ViewPly(Some(plies)))}
+                            >
+                                <title>{ format!("{}. {} {:+.2}", plies.div_ceil(2), notation, green_evaluation) }</title>
+                            </circle>
+                        }
+                    }) }
+                </svg>
+                {viewed_view}
+            </div>
+        }
+    }
+
+    #[cfg(feature = "web-train")]
+    fn render_training_demo(&self) -> Html {
+        html! { <crate::traindemo::TrainingDemo /> }
+    }
+
+    // Pages built without the web-train feature have no trainer
+    #[cfg(not(feature = "web-train"))]
+    fn render_training_demo(&self) -> Html {
+        html! {}
+    }
+
+    fn render_review(&self) -> Html {
+        let Some(review) = &self.review else {
+            return html! {};
+        };
+        html! {
+            <div class="game-review">
+                <h3>{ "Game review" }</h3>
+                <p>{ format!("Blunders: Green {}, Yellow {}", review.blunder_count(&Color::Green), review.blunder_count(&Color::Yellow)) }</p>
+                <ol>
+                    { for review.moves.iter().map(|move_review| html! {
+                        <li class={if move_review.is_blunder { "blunder" } else { "" }}>
+                            { format!("{:?} {} ({:+.2})", move_review.color, move_review.notation, move_review.played_evaluation) }
+                            { if move_review.is_blunder {
+                                format!(" ?? better {}", move_review.best_move.clone().unwrap_or_default())
+                            } else {
+                                String::new()
+                            } }
+                        </li>
+                    }) }
+                </ol>
+            </div>
+        }
+    }
+
+    // Setup controls shown while editing a position, with the analysis of the last analyzed one
+    fn render_editor(&self, ctx: &Context<Self>) -> Html {
+        let analysis_view = match &self.position_analysis {
+            Some((board, analysis)) => html! { <pre class="position-analysis">{ analysis.to_text(board) }</pre> },
+            None => html! {},
+        };
+        let Some(board) = &self.editor else {
+            return analysis_view;
+        };
+        html! {
+            <div class="board-editor">
+                <p>{ "Click a square to cycle it between empty, Green and Yellow." }</p>
+                <label>
+                    <input
+                        type="radio"
+                        name="editor-next-player"
+                        checked={board.next_player == Some(Color::Green)}
+                        onchange={ctx.link().callback(|_| Msg::EditorSetNextPlayer(Color::Green))}
+                    />
+                    { " Green to move " }
+                </label>
+                <label>
+                    <input
+                        type="radio"
+                        name="editor-next-player"
+                        checked={board.next_player == Some(Color::Yellow)}
+                        onchange={ctx.link().callback(|_| Msg::EditorSetNextPlayer(Color::Yellow))}
+                    />
+                    { " Yellow to move " }
+                </label>
+                <button onclick={ctx.link().callback(|_| Msg::EditorClear)}>{ "Clear board" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::EditorStartGame)}>{ "Play from here" }</button>
+                <button disabled={self.analyzing} onclick={ctx.link().callback(|_| Msg::EditorAnalyze)}>
+                    { if self.analyzing { "Analyzing..." } else { "Analyze" } }
+                </button>
+                {analysis_view}
+            </div>
+        }
+    }
+
+    // Games saved by name, to load, delete or export together, and the current game to save
+    fn render_saved_games(&self, ctx: &Context<Self>) -> Html {
+        if !self.show_saved_games {
+            return html! {};
+        }
+        let saved_at = |saved_ms: f64| -> String {
+            js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(saved_ms))
+                .to_locale_string("default", &wasm_bindgen::JsValue::UNDEFINED)
+                .into()
+        };
+        let export_view = if self.show_saved_export {
+            html! { <textarea class="game-export" readonly=true rows="12" cols="60" value={self.saved_games.to_pgn()} /> }
+        } else {
+            html! {}
+        };
+        html! {
+            <div class="saved-games">
+                <h3>{ "Saved games" }</h3>
+                <input
+                    type="text"
+                    placeholder="Name"
+                    value={self.save_name.clone()}
+                    oninput={ctx.link().callback(|e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        Msg::SetSaveName(input.value())
+                    })}
+                />
+                <button onclick={ctx.link().callback(|_| Msg::SaveGame)}>
+                    { if self.saved_games.get(self.save_name.trim()).is_some() { "Overwrite" } else { "Save current game" } }
+                </button>
+                { for self.saved_games_error.iter().map(|error| html! {
+                    <p class="error-message">{ error }</p>
+                }) }
+                <table>
+                    { for self.saved_games.games().iter().map(|saved| {
+                        let load_name = saved.name.clone();
+                        let delete_name = saved.name.clone();
+                        html! {
+                            <tr>
+                                <td>{ &saved.name }</td>
+                                <td>{ saved_at(saved.saved_ms) }</td>
+                                <td>{ format!("{} plies, {}", saved.record.moves.len(), saved.record.result.as_ref().map_or("unfinished", GameResult::to_pgn)) }</td>
+                                <td><button onclick={ctx.link().callback(move |_| Msg::LoadSavedGame(load_name.clone()))}>{ "Load" }</button></td>
+                                <td><button onclick={ctx.link().callback(move |_| Msg::DeleteSavedGame(delete_name.clone()))}>{ "Delete" }</button></td>
+                            </tr>
+                        }
+                    }) }
+                </table>
+                <button disabled={self.saved_games.is_empty()} onclick={ctx.link().callback(|_| Msg::ToggleSavedExport)}>{ "Export all" }</button>
+                {export_view}
+            </div>
+        }
+    }
+
+    // Results against each engine and level, the puzzle streak and achievements, and the report to download
+    fn render_profile(&self, ctx: &Context<Self>) -> Html {
+        if !self.show_profile {
+            return html! {};
+        }
+        let day = day_number(js_sys::Date::now());
+        let percent = |rate: Option<f32>| rate.map_or("-".to_string(), |rate| format!("{:.0}%", 100.0 * rate));
+        let length = |plies: Option<f32>| plies.map_or("-".to_string(), |plies| format!("{:.1}", plies));
+        let row = |name: String, stats: &OpponentStats| html! {
+            <tr>
+                <td>{ name }</td>
+                <td>{ stats.games }</td>
+                <td>{ format!("+{} ={} -{}", stats.wins, stats.draws, stats.losses) }</td>
+                <td>{ percent(stats.win_rate()) }</td>
+                <td>{ length(stats.average_plies()) }</td>
+            </tr>
+        };
+        let report = self.profile.to_report(&self.puzzle_streak, day);
+        let report_url = format!("data:text/plain;charset=utf-8,{}", String::from(js_sys::encode_uri_component(&report)));
+        html! {
+            <div class="profile">
+                <h3>{ "My statistics" }</h3>
+                <table>
+                    <tr><th>{ "Opponent" }</th><th>{ "Games" }</th><th>{ "Results" }</th><th>{ "Score" }</th><th>{ "Average plies" }</th></tr>
+                    { for self.profile.opponents.iter().map(|((engine, level), stats)| row(format!("{} ({})", engine, level), stats)) }
+                    { row("All games".to_string(), &self.profile.total()) }
+                </table>
+                <p class="puzzle-streak">{ format!("Daily puzzle streak: {} days in a row, best {}", self.puzzle_streak.current_on(day), self.puzzle_streak.best) }</p>
+                <h4>{ "Achievements" }</h4>
+                <ul class="achievements">
+                    { for Achievement::ALL.iter().map(|achievement| {
+                        let status = if self.achievements.is_unlocked(*achievement) {
+                            "unlocked".to_string()
+                        } else {
+                            self.achievements.progress(*achievement).unwrap_or_else(|| "locked".to_string())
+                        };
+                        html! {
+                            <li class={if self.achievements.is_unlocked(*achievement) { "unlocked" } else { "locked" }}>
+                                { format!("{}: {} ({})", achievement.label(), achievement.description(), status) }
+                            </li>
+                        }
+                    }) }
+                </ul>
+                <a class="share-link" download="neutreeko-statistics.txt" href={report_url}>{ "Export statistics" }</a>
+                <button disabled={self.profile.is_empty()} onclick={ctx.link().callback(|_| Msg::ResetProfile)}>{ "Reset statistics" }</button>
+            </div>
+        }
+    }
+
+    // Goal and state of the drill, with retry and the solution on demand
+    fn render_endgame(&self, ctx: &Context<Self>) -> Html {
+        let Some(session) = &self.endgame else {
+            return html! {};
+        };
+        let moves_played = self.record.moves.len().div_ceil(2);
+        let status = match (&session.progress, session.checking) {
+            (_, true) => "Checking your move...".to_string(),
+            (Some(DrillProgress::Won), _) => format!("Solved, won in {} moves!", moves_played),
+            (Some(DrillProgress::TooSlow(plies)), _) => format!("Still winning, but the defence now lasts {} more plies: a faster win was missed", plies),
+            (Some(DrillProgress::Lost), _) => "The win slipped away".to_string(),
+            (Some(DrillProgress::OnTrack) | None, _) => format!("Move {} of {}", moves_played + 1, session.drill.moves_to_win()),
+        };
+        let solution_view = if session.show_solution {
+            let solution = if session.solution.is_empty() { "searching...".to_string() } else { session.solution.join(" ") };
+            html! { <p class="endgame-solution">{ format!("Solution: {}", solution) }</p> }
+        } else {
+            html! {}
+        };
+        let (title, streak_view, next_view) = match session.daily {
+            Some(day) => {
+                let streak = format!("Streak: {} days in a row, best {}", self.puzzle_streak.current_on(day), self.puzzle_streak.best);
+                ("Daily puzzle", html! { <p class="puzzle-streak">{ streak }</p> }, html! {})
+            }
+            None => ("Endgame trainer", html! {}, html! {
+                <button onclick={ctx.link().callback(|_| Msg::StartEndgame)}>{ "Next endgame" }</button>
+            }),
+        };
+        html! {
+            <div class="endgame-trainer">
+                <h3>{ title }</h3>
+                <p>{ format!("{:?} to move wins in {} moves against the best defence, find the fastest win.", session.attacker, session.drill.moves_to_win()) }</p>
+                <p>{ status }</p>
+                {streak_view}
+                <button onclick={ctx.link().callback(|_| Msg::RetryEndgame)}>{ "Retry" }</button>
+                <button onclick={ctx.link().callback(|_| Msg::ShowEndgameSolution)}>{ "Show solution" }</button>
+                {next_view}
+                <button onclick={ctx.link().callback(|_| Msg::LeaveEndgame)}>{ "Leave trainer" }</button>
+                {solution_view}
+            </div>
+        }
+    }
+
+    fn render_mirror_match(&self, ctx: &Context<Self>) -> Html {
+        let Some(mirror_match) = &self.mirror_match else {
+            return html! {};
+        };
+        let games = mirror_match.results.iter().enumerate().map(|(index, (color, result))| html! {
+            <li>{ format!("Game {}: you played {:?}, {}", index + 1, color, result.to_pgn()) }</li>
+        });
+        let status = match mirror_match.results.len() {
+            0 => html! { <p>{ "Play this start against the AI with the colour of your choice, then the other side." }</p> },
+            1 => html! {
+                <button onclick={ctx.link().callback(|_| Msg::MirrorSecondGame)}>{ "Play the other side" }</button>
+            },
+            _ => html! { <p>{ format!("You scored {} of 2 on this start", mirror_match.player_score()) }</p> },
+        };
+        html! {
+            <div class="mirror-match">
+                <h3>{ "Mirror match" }</h3>
+                <ul>{ for games }</ul>
+                {status}
+            </div>
+        }
+    }
+
+    // Root children of the last AI search, to see why it preferred its move, then the engine telemetry
+    fn render_search_tree(&self) -> Html {
+        if !self.show_search_tree {
+            return html! {};
+        }
+        let telemetry_view = match &self.telemetry {
+            Some(telemetry) => html! { <pre class="telemetry">{ telemetry.to_text() }</pre> },
+            None => html! {},
+        };
+        let Some(search_tree) = &self.search_tree else {
+            return html! {
+                <>
+                    <p>{ "No search statistics, the last AI move did not come from a search tree" }</p>
+                    {telemetry_view}
+                </>
+            };
+        };
+        html! {
+            <div class="search-tree">
+                <h3>{ format!("Last search: {} visits, value {:+.2}", search_tree.visits, search_tree.q) }</h3>
+                <table>
+                    <tr><th>{ "Move" }</th><th>{ "Visits" }</th><th>{ "Q" }</th><th>{ "Prior" }</th></tr>
+                    { for search_tree.children.iter().map(|child| html! {
+                        <tr>
+                            <td>{ child.notation.clone().unwrap_or_default() }</td>
+                            <td>{ child.visits }</td>
+                            <td>{ format!("{:+.3}", child.q) }</td>
+                            <td>{ format!("{:.3}", child.prior) }</td>
+                        </tr>
+                    }) }
+                </table>
+                {telemetry_view}
+            </div>
+        }
+    }
+
     fn render_direction_button(&self, ctx: &Context<Self>, direction: Direction, valid_directions: &Vec<Direction>, symbol: &str) -> Html {
         let is_valid = valid_directions.contains(&direction);
         if is_valid {
+            // the move the button plays, shown on hover
+            let notation = self.selected_pawn
+                .and_then(|pawn_index| self.game.board.move_to_notation(&Move::new(pawn_index, direction.clone())))
+                .unwrap_or_default();
+            let class = if self.keyboard_direction.as_ref() == Some(&direction) { "dir-btn keyboard-choice" } else { "dir-btn" };
             html! {
                 <button
-                    class="dir-btn"
+                    class={class}
+                    title={notation.clone()}
+                    aria-label={format!("Play {}", notation)}
                     onclick={ctx.link().callback(move |_| Msg::DirectionClick(direction.clone()))}
                 >
                     {symbol}
@@ -353,7 +2641,8 @@ impl App {
 
 struct PawnView{
     state: Rc<AppState>,
-    _listener: ContextHandle<Rc<AppState>>
+    _listener: ContextHandle<Rc<AppState>>,
+    touch_start: Option<(f64, f64)>, // screen point where the finger went down
 }
 
 #[derive(Clone, Properties, PartialEq)]
@@ -366,6 +2655,33 @@ struct PawnComponent {
 
 enum PawnMsg {
     ContextChanged(Rc<AppState>),
+    TouchStart(f64, f64),
+    TouchEnd(f64, f64),
+}
+
+// First finger of a touch event, in screen coordinates
+fn touch_point(e: &TouchEvent) -> Option<(f64, f64)> {
+    let touch = e.changed_touches().get(0)?;
+    Some((f64::from(touch.client_x()), f64::from(touch.client_y())))
+}
+
+// Direction of a swipe snapped to the nearest of the 8 directions, None for a tap.
+// Screen y grows downwards, as the rows do
+fn swipe_direction(dx: f64, dy: f64) -> Option<Direction> {
+    if dx.hypot(dy) < MIN_SWIPE_DISTANCE {
+        return None;
+    }
+    let octant = (dy.atan2(dx) / std::f64::consts::FRAC_PI_4).round() as i32;
+    Some(match octant.rem_euclid(8) {
+        0 => Direction::Right,
+        1 => Direction::DownRight,
+        2 => Direction::Down,
+        3 => Direction::DownLeft,
+        4 => Direction::Left,
+        5 => Direction::UpLeft,
+        6 => Direction::Up,
+        _ => Direction::UpRight,
+    })
 }
 
 impl Component for PawnView {
@@ -378,15 +2694,28 @@ impl Component for PawnView {
             .context::<Rc<AppState>>(ctx.link().callback(PawnMsg::ContextChanged))
             .expect("context to be set");
 
-        Self { state, _listener }
+        Self { state, _listener, touch_start: None }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             PawnMsg::ContextChanged(state) => {
                 self.state = state;
                 true
             }
+            PawnMsg::TouchStart(x, y) => {
+                self.touch_start = Some((x, y));
+                false
+            }
+            PawnMsg::TouchEnd(x, y) => {
+                let Some((start_x, start_y)) = self.touch_start.take() else {
+                    return false;
+                };
+                if let Some(direction) = swipe_direction(x - start_x, y - start_y) {
+                    self.state.pawn_swiped.emit((ctx.props().index, direction));
+                }
+                false
+            }
         }
     }
  
@@ -401,14 +2730,25 @@ impl Component for PawnView {
             "border: 2px solid black;"
         };
 
+        let color = &ctx.props().pawn.color;
+        let ontouchstart = ctx.link().batch_callback(|e: TouchEvent| touch_point(&e).map(|(x, y)| PawnMsg::TouchStart(x, y)));
+        let ontouchend = ctx.link().batch_callback(|e: TouchEvent| {
+            let (x, y) = touch_point(&e)?;
+            Some(PawnMsg::TouchEnd(x, y))
+        });
         html! {
             <div
                 onclick={onclick}
+                ontouchstart={ontouchstart}
+                ontouchend={ontouchend}
+                role="button"
+                aria-label={format!("{:?} pawn {}", color, ctx.props().position.to_notation())}
+                aria-pressed={ctx.props().selected.to_string()}
                 style={format!(
-                    "width: {}px; height: {}px; background-color: {}; position: absolute; top: {}px; left: {}px; border-radius: 50%; {}; cursor: pointer; display: flex; align-items: center; justify-content: center; font-size: 18px; font-weight: bold; color: black;",
+                    "width: {}px; height: {}px; background-color: {}; position: absolute; top: {}px; left: {}px; border-radius: 50%; {}; cursor: pointer; touch-action: none; display: flex; align-items: center; justify-content: center; font-size: 18px; font-weight: bold; color: black;",
                     SCALING - MARGIN * 2,
                     SCALING - MARGIN * 2,
-                    match ctx.props().pawn.color {
+                    match color {
                         Color::Green => "green",
                         Color::Yellow => "yellow",
                     },
@@ -429,6 +2769,63 @@ struct BoardView;
 struct BoardComponent {
     board: Board,
     selected_pawn: Option<usize>,
+    heat_map: Vec<f32>, // one value from 0 to 1 per square, row by row, empty when off
+    #[prop_or_default]
+    coordinates: bool, // column letters above and below the board, row numbers on its sides
+    #[prop_or_default]
+    square_clicked: Option<Callback<Position>>, // set in the editor and while drawing arrows, squares then take the clicks instead of the pawns
+    #[prop_or_default]
+    arrows: Vec<Arrow>,
+    #[prop_or_default]
+    marked_square: Option<Position>, // start of the arrow being drawn
+}
+
+// Arrows from the centre of a square to the centre of another, drawn over the pawns and letting the clicks through
+fn render_arrows(arrows: &[Arrow], width: u32, height: u32) -> Html {
+    const STROKE: f64 = 10.0;
+    // the head is 3 strokes long, the line stops short of the centre so its tip lands there
+    const HEAD: f64 = 3.0 * STROKE;
+    let centre = |position: &Position| (
+        f64::from(position.column as u32 * SCALING + SCALING / 2),
+        f64::from(position.row as u32 * SCALING + SCALING / 2),
+    );
+    let lines = arrows.iter().map(|arrow| {
+        let (x1, y1) = centre(&arrow.from);
+        let (x2, y2) = centre(&arrow.to);
+        let length = (x2 - x1).hypot(y2 - y1).max(1.0);
+        let shortened = (length - HEAD).max(0.0) / length;
+        html! {
+            <line
+                x1={format!("{:.1}", x1)}
+                y1={format!("{:.1}", y1)}
+                x2={format!("{:.1}", x1 + (x2 - x1) * shortened)}
+                y2={format!("{:.1}", y1 + (y2 - y1) * shortened)}
+                stroke={arrow_colour(&arrow.kind)}
+                stroke-width={STROKE.to_string()}
+                stroke-opacity="0.75"
+                marker-end={format!("url(#arrowhead-{:?})", arrow.kind)}
+            >
+                <title>{ format!("{} {}-{}", arrow.kind.label(), arrow.from.to_notation(), arrow.to.to_notation()) }</title>
+            </line>
+        }
+    });
+    html! {
+        <svg
+            width={width.to_string()}
+            height={height.to_string()}
+            style="position: absolute; top: 0; left: 0; pointer-events: none; z-index: 2;"
+            aria-hidden="true"
+        >
+            <defs>
+                { for ArrowKind::ALL.iter().map(|kind| html! {
+                    <marker id={format!("arrowhead-{:?}", kind)} markerWidth="3" markerHeight="3" refX="0" refY="1.5" orient="auto">
+                        <path d="M0,0 L3,1.5 L0,3 z" fill={arrow_colour(kind)} fill-opacity="0.75" />
+                    </marker>
+                }) }
+            </defs>
+            { for lines }
+        </svg>
+    }
 }
  
 impl Component for BoardView {
@@ -440,6 +2837,21 @@ impl Component for BoardView {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        // translucent squares drawn under the pawns
+        let number_of_columns = ctx.props().board.number_of_columns;
+        let heat_squares: Vec<Html> = ctx.props().heat_map.iter()
+            .enumerate()
+            .filter(|(_, heat)| **heat > 0.0)
+            .map(|(square, heat)| html! {
+                <div style={format!(
+                    "position: absolute; top: {}px; left: {}px; width: {}px; height: {}px; background-color: rgba(231, 76, 60, {:.2}); pointer-events: none;",
+                    (square / number_of_columns) as u32 * SCALING,
+                    (square % number_of_columns) as u32 * SCALING,
+                    SCALING, SCALING,
+                    0.6 * heat,
+                )} />
+            })
+            .collect();
         let mut pawns = Vec::new();
         for (index, pawn) in ctx.props().board.pawns.iter().enumerate() {
             pawns.push(html! {
@@ -451,6 +2863,56 @@ impl Component for BoardView {
                 />
             });
         }
+        // clickable squares drawn over the pawns in the editor
+        let mut edit_squares = Vec::new();
+        if let Some(square_clicked) = &ctx.props().square_clicked {
+            for row in 0..ctx.props().board.number_of_rows {
+                for column in 0..number_of_columns {
+                    let onclick = square_clicked.reform(move |_| Position { row, column });
+                    edit_squares.push(html! {
+                        <div onclick={onclick} style={format!(
+                            "position: absolute; top: {}px; left: {}px; width: {}px; height: {}px; cursor: pointer; z-index: 1;",
+                            row as u32 * SCALING, column as u32 * SCALING, SCALING, SCALING,
+                        )} />
+                    });
+                }
+            }
+        }
+        let marked_square = match &ctx.props().marked_square {
+            Some(position) => html! {
+                <div style={format!(
+                    "position: absolute; top: {}px; left: {}px; width: {}px; height: {}px; box-sizing: border-box; border: 3px dashed {}; pointer-events: none;",
+                    position.row as u32 * SCALING, position.column as u32 * SCALING, SCALING, SCALING, arrow_colour(&ArrowKind::User),
+                )} />
+            },
+            None => html! {},
+        };
+        // labels outside the board, a-e and 1-5 as in the notation
+        let mut labels = Vec::new();
+        if ctx.props().coordinates {
+            let width = SCALING * number_of_columns as u32;
+            let height = SCALING * ctx.props().board.number_of_rows as u32;
+            for column in 0..number_of_columns {
+                for top in [-(COORDINATE_SIZE as i32), height as i32] {
+                    labels.push(html! {
+                        <div class="board-label" style={format!(
+                            "top: {}px; left: {}px; width: {}px; height: {}px;",
+                            top, column as u32 * SCALING, SCALING, COORDINATE_SIZE,
+                        )}>{ Position::column_name(column) }</div>
+                    });
+                }
+            }
+            for row in 0..ctx.props().board.number_of_rows {
+                for left in [-(COORDINATE_SIZE as i32), width as i32] {
+                    labels.push(html! {
+                        <div class="board-label" style={format!(
+                            "top: {}px; left: {}px; width: {}px; height: {}px;",
+                            row as u32 * SCALING, left, COORDINATE_SIZE, SCALING,
+                        )}>{ Position::row_name(row) }</div>
+                    });
+                }
+            }
+        }
         html! {
             <div style={format!(
                 "position: absolute; top: {}px; width: {}px; height: {}px; background-image: linear-gradient(0deg, #e0e0e0 1px, transparent 1px), linear-gradient(90deg, #e0e0e0 1px, transparent 1px); background-size: {}px {}px; background-position: 0 0; border: 1px solid #e0e0e0;",
@@ -459,7 +2921,12 @@ impl Component for BoardView {
                 SCALING * ctx.props().board.number_of_rows as u32,
                 SCALING, SCALING
             )}>
+                {labels}
+                {heat_squares}
                 {pawns}
+                {edit_squares}
+                {marked_square}
+                {render_arrows(&ctx.props().arrows, SCALING * number_of_columns as u32, SCALING * ctx.props().board.number_of_rows as u32)}
             </div>
         }
     }
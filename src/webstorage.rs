@@ -0,0 +1,130 @@
+// Storage of WasmPlatform. Text up to LOCAL_STORAGE_MAX_BYTES goes to the local storage, where the settings, games and
+// statistics always were, anything larger or binary, the model weights, to an IndexedDB object store
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbRequest, IdbTransactionMode, Storage};
+
+// Browsers give a site about 5 MB of local storage in all
+const LOCAL_STORAGE_MAX_BYTES: usize = 1024 * 1024;
+
+const DATABASE_NAME: &str = "neutreeko";
+const OBJECT_STORE: &str = "storage";
+
+thread_local! {
+    // opened on the first use and kept, so storage calls do not open it again each time. A failed attempt is not kept,
+    // the next call tries again
+    static DATABASE: std::cell::RefCell<Option<IdbDatabase>> = const { std::cell::RefCell::new(None) };
+}
+
+fn js_error(error: JsValue) -> String {
+    format!("{:?}", error)
+}
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+// Resolves with the result of the request once it succeeds
+async fn request_result(request: &IdbRequest) -> Result<JsValue, String> {
+    let promise = js_sys::Promise::new(&mut |resolve: js_sys::Function, reject: js_sys::Function| {
+        let succeeded = request.clone();
+        let on_success = Closure::once_into_js(move || {
+            let _ = resolve.call1(&JsValue::NULL, &succeeded.result().unwrap_or(JsValue::UNDEFINED));
+        });
+        let on_error = Closure::once_into_js(move || {
+            let _ = reject.call1(&JsValue::NULL, &JsValue::from_str("IndexedDB request failed"));
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await.map_err(js_error)
+}
+
+async fn open_database() -> Result<IdbDatabase, String> {
+    if let Some(database) = DATABASE.with(|database| database.borrow().clone()) {
+        return Ok(database);
+    }
+    let database = connect_database().await?;
+    DATABASE.with(|cached| *cached.borrow_mut() = Some(database.clone()));
+    Ok(database)
+}
+
+fn database_is_open() -> bool {
+    DATABASE.with(|database| database.borrow().is_some())
+}
+
+// The object store is created on the first visit, before the database opens
+async fn connect_database() -> Result<IdbDatabase, String> {
+    let factory = web_sys::window().ok_or("No browser window")?
+        .indexed_db().map_err(js_error)?
+        .ok_or("No IndexedDB in this browser")?;
+    let request = factory.open_with_u32(DATABASE_NAME, 1).map_err(js_error)?;
+    let upgraded = request.clone();
+    let on_upgrade = Closure::once_into_js(move || {
+        if let Ok(database) = upgraded.result() {
+            let _ = database.unchecked_into::<IdbDatabase>().create_object_store(OBJECT_STORE);
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+    Ok(request_result(&request).await?.unchecked_into())
+}
+
+async fn database_get(key: &str) -> Result<Option<Vec<u8>>, String> {
+    let database = open_database().await?;
+    let store = database.transaction_with_str(OBJECT_STORE).map_err(js_error)?
+        .object_store(OBJECT_STORE).map_err(js_error)?;
+    let value = request_result(&store.get(&JsValue::from_str(key)).map_err(js_error)?).await?;
+    Ok(if value.is_undefined() { None } else { Some(js_sys::Uint8Array::new(&value).to_vec()) })
+}
+
+// Writes the value, or deletes the key for None
+async fn database_put(key: &str, value: Option<&[u8]>) -> Result<(), String> {
+    let database = open_database().await?;
+    let store = database.transaction_with_str_and_mode(OBJECT_STORE, IdbTransactionMode::Readwrite).map_err(js_error)?
+        .object_store(OBJECT_STORE).map_err(js_error)?;
+    let request = match value {
+        Some(value) => store.put_with_key(&js_sys::Uint8Array::from(value), &JsValue::from_str(key)),
+        None => store.delete(&JsValue::from_str(key)),
+    };
+    request_result(&request.map_err(js_error)?).await.map(|_| ())
+}
+
+pub async fn get(key: String) -> Option<Vec<u8>> {
+    if let Some(text) = local_storage().and_then(|storage| storage.get_item(&key).ok().flatten()) {
+        return Some(text.into_bytes());
+    }
+    database_get(&key).await.ok().flatten()
+}
+
+// Small text goes to the local storage, the rest, and text past its quota, to the database
+pub async fn set(key: String, value: Vec<u8>) -> Result<(), String> {
+    let storage = local_storage();
+    if let (Ok(text), Some(storage)) = (std::str::from_utf8(&value), &storage) {
+        if value.len() <= LOCAL_STORAGE_MAX_BYTES && storage.set_item(&key, text).is_ok() {
+            // get reads the local storage first, an older copy in the database is never read. It is removed when
+            // the database is open anyway, a small write does not open it
+            if database_is_open() {
+                let _ = database_put(&key, None).await;
+            }
+            return Ok(());
+        }
+    }
+    database_put(&key, Some(&value)).await.map_err(|error| format!("{} was not saved: {}", key, error))?;
+    if let Some(storage) = storage {
+        let _ = storage.remove_item(&key);
+    }
+    Ok(())
+}
+
+// Without a local storage the database has the only copy. With one, the key may never have been in the database,
+// which may not even open, e.g. in a private window, so its error is ignored
+pub async fn delete(key: String) -> Result<(), String> {
+    match local_storage() {
+        Some(storage) => {
+            storage.remove_item(&key).map_err(js_error)?;
+            let _ = database_put(&key, None).await;
+            Ok(())
+        }
+        None => database_put(&key, None).await,
+    }
+}
@@ -0,0 +1,113 @@
+use neutreeko::logic::{Board, Move, Symmetry, THREE_ON_A_LINE, ThreatKind};
+use proptest::prelude::*;
+
+// Positions reached by playing the chosen legal moves from the start, stopping when the game ends
+fn board_after(choices: &[usize]) -> Board {
+    let mut board = Board::default_new();
+    for choice in choices {
+        let moves: Vec<Move> = board.legal_moves().collect();
+        if moves.is_empty() {
+            break;
+        }
+        board = board.child(&moves[choice % moves.len()]);
+    }
+    board
+}
+
+fn boards() -> impl Strategy<Value = Board> {
+    prop::collection::vec(any::<usize>(), 0..40).prop_map(|choices| board_after(&choices))
+}
+
+fn symmetries() -> impl Strategy<Value = Symmetry> {
+    (0..8usize).prop_map(|index| Symmetry::all().nth(index).unwrap())
+}
+
+proptest! {
+    #[test]
+    fn legal_moves_keep_pawn_count_and_validity(board in boards(), choice in any::<usize>()) {
+        let moves: Vec<Move> = board.legal_moves().collect();
+        prop_assume!(!moves.is_empty());
+        let child = board.child(&moves[choice % moves.len()]);
+        prop_assert_eq!(child.pawns.len(), board.pawns.len());
+        prop_assert_eq!(child.pawns.iter().filter(|pawn| pawn.color == board.pawns[0].color).count(),
+            board.pawns.iter().filter(|pawn| pawn.color == board.pawns[0].color).count());
+        prop_assert!(Board::from_fen(&child.to_fen()).is_some());
+    }
+
+    #[test]
+    fn undo_restores_the_board(board in boards(), choice in any::<usize>()) {
+        let moves: Vec<Move> = board.legal_moves().collect();
+        prop_assume!(!moves.is_empty());
+        let game_move = &moves[choice % moves.len()];
+        let mut played = board.clone();
+        let outcome = played.apply_move(game_move.pawn_index, &game_move.direction).unwrap();
+        prop_assert!(played.undo_move(&outcome));
+        prop_assert_eq!(played, board);
+    }
+
+    #[test]
+    fn diff_finds_the_move_played(board in boards(), choice in any::<usize>()) {
+        let moves: Vec<Move> = board.legal_moves().collect();
+        prop_assume!(!moves.is_empty());
+        let game_move = &moves[choice % moves.len()];
+        prop_assert_eq!(board.diff(&board.child(game_move)).as_ref(), Some(game_move));
+        prop_assert_eq!(board.diff(&board), None);
+    }
+
+    #[test]
+    fn u64_encoding_round_trips(board in boards()) {
+        let code = board.encode_u64().unwrap();
+        let decoded = Board::decode_u64(code).unwrap();
+        prop_assert_eq!(decoded.to_fen(), board.to_fen());
+        prop_assert_eq!(decoded.encode_u64(), Some(code));
+        // the code has no room for the rules, boards under other ones are not encoded
+        prop_assert_eq!(board.with_win_condition(&THREE_ON_A_LINE).encode_u64(), None);
+    }
+
+    #[test]
+    fn symmetries_commute_with_moves(board in boards(), symmetry in symmetries(), choice in any::<usize>()) {
+        let moves: Vec<Move> = board.legal_moves().collect();
+        prop_assume!(!moves.is_empty());
+        let game_move = &moves[choice % moves.len()];
+        let transformed_move = Move::new(game_move.pawn_index, symmetry.apply_direction(&game_move.direction));
+        prop_assert_eq!(
+            symmetry.apply_board(&board.child(game_move)),
+            symmetry.apply_board(&board).child(&transformed_move)
+        );
+    }
+
+    #[test]
+    fn legal_moves_are_the_same_up_to_symmetry(board in boards(), symmetry in symmetries()) {
+        prop_assert_eq!(board.legal_moves().count(), symmetry.apply_board(&board).legal_moves().count());
+    }
+
+    #[test]
+    fn fen_round_trips(board in boards()) {
+        let fen = board.to_fen();
+        let parsed = Board::from_fen(&fen).unwrap();
+        prop_assert_eq!(parsed.to_fen(), fen);
+        prop_assert_eq!(parsed.next_player, board.next_player);
+        prop_assert_eq!(parsed.winner(), board.winner());
+    }
+
+    #[test]
+    fn threats_win_as_described(board in boards()) {
+        let Some(color) = board.next_player.clone() else {
+            return Ok(());
+        };
+        for threat in board.threats(&color) {
+            let child = board.child(&threat.game_move);
+            match threat.kind {
+                ThreatKind::Win => prop_assert_eq!(child.winner(), Some(color.clone())),
+                // there is a reply, and every reply leaves a winning move
+                ThreatKind::ForcedWin => {
+                    prop_assert!(child.legal_moves().next().is_some());
+                    for reply in child.legal_moves() {
+                        prop_assert!(!child.child(&reply).winning_moves(&color).is_empty());
+                    }
+                }
+                ThreatKind::DoubleThreat => prop_assert!(child.threat_squares(&color).len() > 1),
+            }
+        }
+    }
+}
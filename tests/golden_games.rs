@@ -0,0 +1,117 @@
+use neutreeko::{
+    ai::{AI, mcts::MCTS, minmax::MinMax},
+    game::Game,
+    gamerecord::{GameRecord, MoveRecord},
+    logic::{Board, Move},
+    platform::SeededPlatform,
+};
+
+// Position, then the moves that solve it, as proven by the ShallowSolver
+struct Fixture {
+    fen: &'static str,
+    best_moves: &'static [&'static str],
+}
+
+const MATE_IN_ONE: [Fixture; 3] = [
+    Fixture { fen: "1yg1g/2g2/y4/y4/5 y", best_moves: &["b1-a2"] },
+    Fixture { fen: "2y2/4y/3g1/4g/y1g2 g", best_moves: &["c5-c2"] },
+    Fixture { fen: "g1gg1/2y2/5/5/3yy g", best_moves: &["a1-b1"] },
+];
+
+const MATE_IN_TWO: [Fixture; 3] = [
+    Fixture { fen: "1g3/2y2/2g2/3g1/y2y1 g", best_moves: &["b1-a1"] },
+    Fixture { fen: "3yg/5/5/y3y/1g1g1 y", best_moves: &["d1-d4"] },
+    Fixture { fen: "4y/3yy/g3g/5/4g y", best_moves: &["d2-c1"] },
+];
+
+// Draws only come from repetition, so the drawing fixtures are positions where every move
+// but one loses at once and the saving move holds for at least four more plies
+const ONLY_DEFENCE: [Fixture; 2] = [
+    Fixture { fen: "3y1/5/y4/4y/g2gg y", best_moves: &["a3-c5"] },
+    Fixture { fen: "2g2/5/5/yy3/yg2g g", best_moves: &["c1-a3"] },
+];
+
+const MINMAX_DEPTH: usize = 4;
+const MCTS_ITERATIONS: usize = 20000;
+
+fn best_move_notation<A: AI<SeededPlatform>>(ai: &mut A, board: &Board) -> String {
+    let (pawn_index, direction) = ai.ai_play(board, false).expect("the position has legal moves");
+    board.move_to_notation(&Move::new(pawn_index, direction)).unwrap()
+}
+
+fn assert_minmax_solves(fixtures: &[Fixture]) {
+    for fixture in fixtures {
+        let board = Board::from_fen(fixture.fen).unwrap();
+        let mut ai: MinMax<SeededPlatform> = MinMax::new(board.next_player.clone().unwrap(), MINMAX_DEPTH);
+        let played = best_move_notation(&mut ai, &board);
+        assert!(fixture.best_moves.contains(&played.as_str()), "MinMax played {} in {}, expected {:?}", played, fixture.fen, fixture.best_moves);
+    }
+}
+
+fn assert_mcts_solves(fixtures: &[Fixture]) {
+    for fixture in fixtures {
+        SeededPlatform::reseed(0);
+        let board = Board::from_fen(fixture.fen).unwrap();
+        let mut ai: MCTS<SeededPlatform> = MCTS::new(board.next_player.clone().unwrap(), 100);
+        ai.set_node_limit(Some(MCTS_ITERATIONS));
+        let played = best_move_notation(&mut ai, &board);
+        assert!(fixture.best_moves.contains(&played.as_str()), "MCTS played {} in {}, expected {:?}", played, fixture.fen, fixture.best_moves);
+    }
+}
+
+#[test]
+fn minmax_finds_mate_in_one() {
+    assert_minmax_solves(&MATE_IN_ONE);
+}
+
+#[test]
+fn minmax_finds_mate_in_two() {
+    assert_minmax_solves(&MATE_IN_TWO);
+}
+
+#[test]
+fn minmax_finds_only_defence() {
+    assert_minmax_solves(&ONLY_DEFENCE);
+}
+
+#[test]
+fn mcts_finds_mate_in_one() {
+    assert_mcts_solves(&MATE_IN_ONE);
+}
+
+#[test]
+fn mcts_finds_mate_in_two() {
+    assert_mcts_solves(&MATE_IN_TWO);
+}
+
+#[test]
+fn mcts_finds_only_defence() {
+    assert_mcts_solves(&ONLY_DEFENCE);
+}
+
+// A short game, replayed twice by MinMax: the plies must not depend on the run
+const REPLAYED_GAME: [&str; 6] = ["b1-c1", "c2-c3", "c1-a3", "c3-a5", "c4-c5", "d5-e4"];
+
+#[test]
+fn minmax_replay_is_deterministic() {
+    let mut record = GameRecord::new(&Board::default_new());
+    let mut board = Board::default_new();
+    for notation in REPLAYED_GAME {
+        let game_move = board.move_from_notation(notation).expect("the replayed game is legal");
+        board.move_pawn_until_blocked(game_move.pawn_index, &game_move.direction);
+        record.moves.push(MoveRecord::new(notation.to_string()));
+    }
+    let game = Game::from_record(&record).unwrap();
+    let replay = |game: &Game| {
+        let mut ai: MinMax<SeededPlatform> = MinMax::new(board.next_player.clone().unwrap(), MINMAX_DEPTH);
+        game.replay_with_engine(&mut ai).unwrap()
+    };
+    let (first, second) = (replay(&game), replay(&game));
+    assert_eq!(first.plies.len(), REPLAYED_GAME.len());
+    for (first_ply, second_ply) in first.plies.iter().zip(second.plies.iter()) {
+        assert_eq!(first_ply.engine_move, second_ply.engine_move);
+        assert_eq!(first_ply.search.value, second_ply.search.value);
+    }
+    let notations: Vec<&str> = first.record.moves.iter().map(|move_record| move_record.notation.as_str()).collect();
+    assert_eq!(notations, REPLAYED_GAME);
+}
@@ -0,0 +1,24 @@
+use neutreeko::logic::Board;
+
+// Move sequences from the standard start, checked against an implementation written from the rules alone
+const START_PERFT: [(usize, usize); 4] = [(1, 14), (2, 207), (3, 2723), (4, 36677)];
+
+#[test]
+fn perft_of_the_standard_start() {
+    let board = Board::default_new();
+    for (depth, nodes) in START_PERFT {
+        assert_eq!(board.perft(depth), nodes, "perft({})", depth);
+    }
+}
+
+#[test]
+fn perft_agrees_on_all_symmetries() {
+    let board = Board::default_new();
+    let symmetries = board.symmetries();
+    assert_eq!(symmetries.len(), 8);
+    for (depth, nodes) in START_PERFT {
+        for (index, symmetric_board) in symmetries.iter().enumerate() {
+            assert_eq!(symmetric_board.perft(depth), nodes, "perft({}) of symmetry {}", depth, index);
+        }
+    }
+}